@@ -0,0 +1,203 @@
+// Hardware-counter view of the transfer workloads in `ring_buffer.rs`.
+// Wall-clock numbers hide where the time goes; for an SPSC queue that is
+// almost entirely cache-line traffic between the producer and consumer
+// cores, so each side gets its own set of counters.
+//
+// Run with `cargo bench --bench perf_counters --features bench-perf`.
+
+#[cfg(target_os = "linux")]
+mod perf {
+    use std::{io, thread};
+
+    use perf_event::{
+        events::{Cache, CacheOp, CacheResult, Hardware, WhichCache},
+        Builder, Counter, Group,
+    };
+    use spsc::ring_buffer::{BufferReader, BufferWriter, RingBuffer};
+
+    const BUFFER_SIZE: usize = 4096;
+    const ELEMENTS: usize = 1_000_000;
+    const BURSTS: [usize; 4] = [1, 16, 256, 1024];
+
+    struct Counters {
+        group: Group,
+        cycles: Counter,
+        instructions: Counter,
+        l1d_misses: Counter,
+        llc_misses: Counter,
+    }
+
+    #[derive(Clone, Copy)]
+    struct Sample {
+        cycles: u64,
+        instructions: u64,
+        l1d_misses: u64,
+        llc_misses: u64,
+    }
+
+    impl Counters {
+        // Counters opened without a pid observe the calling thread only, so
+        // this has to run on the thread being measured.
+        fn new() -> io::Result<Self> {
+            let mut group = Group::new()?;
+            let cycles = Builder::new()
+                .group(&mut group)
+                .kind(Hardware::CPU_CYCLES)
+                .build()?;
+            let instructions = Builder::new()
+                .group(&mut group)
+                .kind(Hardware::INSTRUCTIONS)
+                .build()?;
+            let l1d_misses = Builder::new()
+                .group(&mut group)
+                .kind(Cache {
+                    which: WhichCache::L1D,
+                    operation: CacheOp::READ,
+                    result: CacheResult::MISS,
+                })
+                .build()?;
+            let llc_misses = Builder::new()
+                .group(&mut group)
+                .kind(Cache {
+                    which: WhichCache::LL,
+                    operation: CacheOp::READ,
+                    result: CacheResult::MISS,
+                })
+                .build()?;
+
+            Ok(Self {
+                group,
+                cycles,
+                instructions,
+                l1d_misses,
+                llc_misses,
+            })
+        }
+
+        fn measure(mut self, f: impl FnOnce()) -> io::Result<Sample> {
+            self.group.enable()?;
+            f();
+            self.group.disable()?;
+            let counts = self.group.read()?;
+
+            Ok(Sample {
+                cycles: counts[&self.cycles],
+                instructions: counts[&self.instructions],
+                l1d_misses: counts[&self.l1d_misses],
+                llc_misses: counts[&self.llc_misses],
+            })
+        }
+    }
+
+    fn measure_transfer(
+        producer: impl FnOnce() + Send + 'static,
+        consumer: impl FnOnce() + Send + 'static,
+    ) -> io::Result<(Sample, Sample)> {
+        let consumer = thread::spawn(move || Counters::new()?.measure(consumer));
+        let producer = thread::spawn(move || Counters::new()?.measure(producer));
+
+        let producer = producer.join().unwrap()?;
+        let consumer = consumer.join().unwrap()?;
+
+        Ok((producer, consumer))
+    }
+
+    fn ring_buffer(burst: usize) -> io::Result<(Sample, Sample)> {
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(BUFFER_SIZE).unwrap();
+
+        measure_transfer(
+            move || {
+                let data: Vec<u64> = (0..ELEMENTS as u64).collect();
+                let mut index = 0;
+                while index != ELEMENTS {
+                    let end = (index + burst).min(ELEMENTS);
+                    index += writer.write(&data[index..end]);
+                }
+            },
+            move || {
+                let mut data = vec![0u64; ELEMENTS];
+                let mut index = 0;
+                while index != ELEMENTS {
+                    let end = (index + burst).min(ELEMENTS);
+                    index += reader.read(&mut data[index..end]);
+                }
+                assert!(data.iter().enumerate().all(|(i, &v)| v == i as u64));
+            },
+        )
+    }
+
+    fn vecdeque(burst: usize) -> io::Result<(Sample, Sample)> {
+        let writer = spsc::vecdeque::RingBuffer::<u64>::new(BUFFER_SIZE);
+        let reader = writer.clone();
+
+        measure_transfer(
+            move || {
+                let data: Vec<u64> = (0..ELEMENTS as u64).collect();
+                let mut index = 0;
+                while index != ELEMENTS {
+                    let end = (index + burst).min(ELEMENTS);
+                    index += writer.write(&data[index..end]);
+                }
+            },
+            move || {
+                let mut data = vec![0u64; ELEMENTS];
+                let mut index = 0;
+                while index != ELEMENTS {
+                    let end = (index + burst).min(ELEMENTS);
+                    index += reader.read(&mut data[index..end]);
+                }
+                assert!(data.iter().enumerate().all(|(i, &v)| v == i as u64));
+            },
+        )
+    }
+
+    fn print_table(name: &str, burst: usize, (producer, consumer): (Sample, Sample)) {
+        println!("{name}, burst {burst}, {ELEMENTS} elements");
+        println!(
+            "  {:<10} {:>14} {:>14} {:>6} {:>12} {:>12}",
+            "side", "cycles", "instructions", "ipc", "L1d misses", "LLC misses"
+        );
+        for (side, sample) in [("producer", producer), ("consumer", consumer)] {
+            println!(
+                "  {:<10} {:>14} {:>14} {:>6.2} {:>12} {:>12}",
+                side,
+                sample.cycles,
+                sample.instructions,
+                sample.instructions as f64 / sample.cycles.max(1) as f64,
+                sample.l1d_misses,
+                sample.llc_misses,
+            );
+        }
+        println!();
+    }
+
+    pub fn run() {
+        // Probe once up front so an unsupported machine gets one clear message.
+        if let Err(error) = Counters::new() {
+            println!("skipping perf counter benchmarks: hardware counters unavailable ({error})");
+            println!("check /proc/sys/kernel/perf_event_paranoid or run on bare metal");
+            return;
+        }
+
+        type Workload = fn(usize) -> io::Result<(Sample, Sample)>;
+        let workloads: [(&str, Workload); 2] =
+            [("Ring Buffer 1", ring_buffer), ("Ring Buffer 2", vecdeque)];
+
+        for (name, workload) in workloads {
+            for burst in BURSTS {
+                match workload(burst) {
+                    Ok(samples) => print_table(name, burst, samples),
+                    Err(error) => println!("{name}, burst {burst}: skipped ({error})\n"),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    #[cfg(target_os = "linux")]
+    perf::run();
+
+    #[cfg(not(target_os = "linux"))]
+    println!("skipping perf counter benchmarks: only supported on Linux");
+}
@@ -1,101 +1,471 @@
-use std::hint::black_box;
-
-use criterion::{criterion_group, criterion_main, Criterion};
-use spsc::ring_buffer::{BufferReader, BufferWriter};
-
-const BUFFER_SIZE: u32 = 4096;
-
-fn ring_buffer_1(
-    v1: usize,
-    v2: usize,
-    mut writer: spsc::ring_buffer::Writer<u64>,
-    mut reader: spsc::ring_buffer::Reader<u64>,
-) {
-    let reader_thread = std::thread::spawn(move || {
-        let mut read_buffer: Vec<u64> = (0..v1 as u64).map(|_| 0).collect();
-        let mut index = 0;
-
-        while index != v1 {
-            let n = reader.read(&mut read_buffer[index..index + v2]);
-            index += n as usize;
-        }
+use std::{
+    hint::black_box,
+    sync::{mpsc, Arc},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-        read_buffer
-    });
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use spsc::{
+    ring_buffer::{BufferReader, BufferWriter},
+    testing::run_transfer,
+};
+
+const BUFFER_SIZE: usize = 4096;
+/// Elements moved per measured iteration of the throughput matrix.
+const ELEMENTS: usize = 16_384;
+const BURSTS: [usize; 4] = [1, 16, 256, 4096];
+
+/// A 64-byte element, one cache line on most targets.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Line([u64; 8]);
+
+trait Element: Copy + Default + PartialEq + Send + Sync + 'static {
+    const NAME: &'static str;
+
+    fn from_index(index: usize) -> Self;
+}
+
+impl Element for u8 {
+    const NAME: &'static str = "u8";
+
+    fn from_index(index: usize) -> Self {
+        index as u8
+    }
+}
+
+impl Element for u64 {
+    const NAME: &'static str = "u64";
+
+    fn from_index(index: usize) -> Self {
+        index as u64
+    }
+}
+
+impl Element for Line {
+    const NAME: &'static str = "64 bytes";
+
+    fn from_index(index: usize) -> Self {
+        Self([index as u64; 8])
+    }
+}
+
+/// Sending half of a queue under test. Sends as much of `burst` as fits
+/// without blocking and returns how many elements went in.
+trait Producer<T>: Send + 'static {
+    fn send(&mut self, burst: &[T]) -> usize;
+}
+
+/// Receiving half of a queue under test, the counterpart of [`Producer`].
+trait Consumer<T>: Send + 'static {
+    fn recv(&mut self, burst: &mut [T]) -> usize;
+}
 
-    let writer_thread = std::thread::spawn(move || {
-        let write_buffer: Vec<u64> = (0..v1 as u64).map(|value| value).collect();
-        let mut index = 0;
+/// Either half of a ring from this crate. Every implementation is driven
+/// through [`BufferWriter`] and [`BufferReader`], so one pair of impls
+/// covers them all.
+struct Spsc<H>(H);
 
-        while index != v1 {
-            let n = writer.write(&write_buffer[index..index + v2]);
-            index += n as usize;
+impl<T: Copy, W: BufferWriter<T> + Send + 'static> Producer<T> for Spsc<W> {
+    fn send(&mut self, burst: &[T]) -> usize {
+        self.0.write(burst)
+    }
+}
+
+impl<T: Copy, R: BufferReader<T> + Send + 'static> Consumer<T> for Spsc<R> {
+    fn recv(&mut self, burst: &mut [T]) -> usize {
+        self.0.read(burst)
+    }
+}
+
+impl<T: Copy + Send + 'static> Producer<T> for mpsc::SyncSender<T> {
+    fn send(&mut self, burst: &[T]) -> usize {
+        burst
+            .iter()
+            .take_while(|&&value| self.try_send(value).is_ok())
+            .count()
+    }
+}
+
+impl<T: Copy + Send + 'static> Consumer<T> for mpsc::Receiver<T> {
+    fn recv(&mut self, burst: &mut [T]) -> usize {
+        fill(burst, || self.try_recv().ok())
+    }
+}
+
+impl<T: Copy + Send + 'static> Producer<T> for crossbeam_channel::Sender<T> {
+    fn send(&mut self, burst: &[T]) -> usize {
+        burst
+            .iter()
+            .take_while(|&&value| self.try_send(value).is_ok())
+            .count()
+    }
+}
+
+impl<T: Copy + Send + 'static> Consumer<T> for crossbeam_channel::Receiver<T> {
+    fn recv(&mut self, burst: &mut [T]) -> usize {
+        fill(burst, || self.try_recv().ok())
+    }
+}
+
+fn fill<T>(burst: &mut [T], mut next: impl FnMut() -> Option<T>) -> usize {
+    let mut n = 0;
+    while let Some(slot) = burst.get_mut(n) {
+        match next() {
+            Some(value) => *slot = value,
+            None => break,
         }
+        n += 1;
+    }
 
-        write_buffer
-    });
+    n
+}
 
-    let write_buffer = writer_thread.join().unwrap();
-    let read_buffer = reader_thread.join().unwrap();
-    assert!(write_buffer == read_buffer);
+/// A writer and a reader thread that stay alive across measured
+/// iterations, so thread start-up is kept out of the timings. Each
+/// iteration moves [`ELEMENTS`] elements in bursts.
+struct Workers {
+    writer_commands: mpsc::Sender<u64>,
+    reader_commands: mpsc::Sender<u64>,
+    // The reader reports once after its last iteration and then whether
+    // everything it received matched what was written.
+    finished: mpsc::Receiver<()>,
+    verified: mpsc::Receiver<bool>,
+    threads: [JoinHandle<()>; 2],
 }
 
-fn ring_buffer_2(
-    v1: usize,
-    v2: usize,
-    reader: spsc::vecdeque::RingBuffer<u64>,
-    writer: spsc::vecdeque::RingBuffer<u64>,
-) {
-    let reader_thread = std::thread::spawn(move || {
-        let mut read_buffer: Vec<u64> = (0..v1 as u64).map(|_| 0).collect();
-        let mut index = 0;
+impl Workers {
+    fn spawn<T: Element>(
+        mut producer: impl Producer<T>,
+        mut consumer: impl Consumer<T>,
+        burst: usize,
+    ) -> Self {
+        let write_buffer: Arc<[T]> = (0..ELEMENTS).map(T::from_index).collect();
+        let (writer_commands, writer_rounds) = mpsc::channel::<u64>();
+        let (reader_commands, reader_rounds) = mpsc::channel::<u64>();
+        let (finished_sender, finished) = mpsc::channel();
+        let (verified_sender, verified) = mpsc::channel();
+
+        let writer_thread = thread::spawn({
+            let write_buffer = write_buffer.clone();
+            move || {
+                for rounds in writer_rounds {
+                    for _ in 0..rounds {
+                        let mut index = 0;
+                        // Transfers may be partial, so a burst can end
+                        // anywhere.
+                        while index != ELEMENTS {
+                            let end = (index + burst).min(ELEMENTS);
+                            match producer.send(&write_buffer[index..end]) {
+                                0 => thread::yield_now(),
+                                n => index += n,
+                            }
+                        }
+                    }
+                }
+            }
+        });
 
-        while index != v1 {
-            let n = reader.read(&mut read_buffer[index..index + v2]);
-            index += n;
+        let reader_thread = thread::spawn(move || {
+            let mut read_buffer = vec![T::default(); ELEMENTS];
+
+            for rounds in reader_rounds {
+                let mut matched = true;
+                for _ in 0..rounds {
+                    let mut index = 0;
+                    while index != ELEMENTS {
+                        let end = (index + burst).min(ELEMENTS);
+                        match consumer.recv(&mut read_buffer[index..end]) {
+                            0 => thread::yield_now(),
+                            n => index += n,
+                        }
+                    }
+                    matched &= black_box(&read_buffer)[..] == write_buffer[..];
+                }
+                let _ = finished_sender.send(());
+                let _ = verified_sender.send(matched);
+            }
+        });
+
+        Self {
+            writer_commands,
+            reader_commands,
+            finished,
+            verified,
+            threads: [writer_thread, reader_thread],
         }
+    }
 
-        read_buffer
-    });
+    fn run(&self, rounds: u64) -> Duration {
+        let start = Instant::now();
+        self.writer_commands.send(rounds).unwrap();
+        self.reader_commands.send(rounds).unwrap();
+        self.finished.recv().unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(
+            self.verified.recv().unwrap(),
+            "read buffer differs from write buffer"
+        );
 
-    let writer_thread = std::thread::spawn(move || {
-        let write_buffer: Vec<u64> = (0..v1 as u64).map(|value| value).collect();
-        let mut index = 0;
+        elapsed
+    }
 
-        while index != v1 {
-            let n = writer.write(&write_buffer[index..index + v2]);
-            index += n;
+    fn join(self) {
+        drop(self.writer_commands);
+        drop(self.reader_commands);
+        for thread in self.threads {
+            thread.join().unwrap();
         }
+    }
+}
 
-        write_buffer
-    });
+fn benchmark_throughput_of<T: Element>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("Throughput / {}", T::NAME));
+    group.throughput(Throughput::Elements(ELEMENTS as u64));
+
+    for burst in BURSTS {
+        let mut bench = |name: &str, workers: Workers| {
+            group.bench_with_input(BenchmarkId::new(name, burst), &burst, |b, _| {
+                b.iter_custom(|rounds| workers.run(rounds))
+            });
+            workers.join();
+        };
+
+        let (writer, reader) = spsc::ring_buffer::RingBuffer::<T>::new(BUFFER_SIZE).unwrap();
+        bench(
+            "Ring Buffer",
+            Workers::spawn(Spsc(writer), Spsc(reader), burst),
+        );
+
+        let (writer, reader) = spsc::vecdeque::ring_buffer::<T>(BUFFER_SIZE);
+        bench(
+            "VecDeque",
+            Workers::spawn(Spsc(writer), Spsc(reader), burst),
+        );
 
-    let write_buffer = writer_thread.join().unwrap();
-    let read_buffer = reader_thread.join().unwrap();
-    assert!(write_buffer == read_buffer);
+        let (sender, receiver) = mpsc::sync_channel::<T>(BUFFER_SIZE);
+        bench("std mpsc", Workers::spawn(sender, receiver, burst));
+
+        let (sender, receiver) = crossbeam_channel::bounded::<T>(BUFFER_SIZE);
+        bench("crossbeam", Workers::spawn(sender, receiver, burst));
+    }
+    group.finish();
+}
+
+fn benchmark_throughput(c: &mut Criterion) {
+    benchmark_throughput_of::<u8>(c);
+    benchmark_throughput_of::<u64>(c);
+    benchmark_throughput_of::<Line>(c);
 }
 
-fn benchmark_ring_buffer_1(c: &mut Criterion) {
-    let (writer, reader) = spsc::ring_buffer::RingBuffer::<u64>::new(BUFFER_SIZE as usize).unwrap();
+fn benchmark_capacity_1(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Capacity 1");
 
-    c.bench_function("Ring Buffer 1", |b| {
+    group.bench_function("Ring Buffer", |b| {
         b.iter_with_setup(
-            || (writer.clone(), reader.clone()),
-            |(writer, reader)| ring_buffer_1(black_box(100_000), black_box(1), writer, reader),
+            || spsc::ring_buffer::RingBuffer::<u64>::new(1).unwrap(),
+            |(writer, reader)| {
+                let data = (0..black_box(10_000)).collect();
+                run_transfer(writer, reader, data, 1)
+            },
         )
     });
+    group.bench_function("Mailbox", |b| {
+        b.iter_with_setup(spsc::mailbox::<u64>, |(writer, reader)| {
+            let data = (0..black_box(10_000)).collect();
+            run_transfer(writer, reader, data, 1)
+        })
+    });
+    group.finish();
+}
+
+fn benchmark_slot_stamps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Slot Stamps");
+
+    for burst in [1, 64] {
+        group.bench_function(format!("Index Pair / Burst {burst}"), |b| {
+            b.iter_with_setup(
+                || spsc::ring_buffer::RingBuffer::<u64>::new(BUFFER_SIZE).unwrap(),
+                |(writer, reader)| {
+                    let data = (0..black_box(100_000)).collect();
+                    run_transfer(writer, reader, data, burst)
+                },
+            )
+        });
+        group.bench_function(format!("Slot Stamps / Burst {burst}"), |b| {
+            b.iter_with_setup(
+                || spsc::seqring::ring_buffer::<u64>(BUFFER_SIZE).unwrap(),
+                |(writer, reader)| {
+                    let data = (0..black_box(100_000)).collect();
+                    run_transfer(writer, reader, data, burst)
+                },
+            )
+        });
+    }
+    group.finish();
 }
 
-fn benchmark_ring_buffer_2(c: &mut Criterion) {
-    let ring_buffer = spsc::vecdeque::RingBuffer::<u64>::new(BUFFER_SIZE as usize);
+fn benchmark_single_element(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Single Element");
 
-    c.bench_function("Ring Buffer 2", |b| {
+    group.bench_function("Slice", |b| {
+        b.iter_with_setup(
+            || spsc::ring_buffer::RingBuffer::<u64>::new(BUFFER_SIZE).unwrap(),
+            |(writer, reader)| {
+                let data = (0..black_box(100_000)).collect();
+                run_transfer(writer, reader, data, 1)
+            },
+        )
+    });
+    group.bench_function("Push / Pop", |b| {
         b.iter_with_setup(
-            || (ring_buffer.clone(), ring_buffer.clone()),
-            |(writer, reader)| ring_buffer_2(black_box(100_000), black_box(1), writer, reader),
+            || spsc::ring_buffer::RingBuffer::<u64>::new(BUFFER_SIZE).unwrap(),
+            |(mut writer, mut reader)| {
+                let n = black_box(100_000u64);
+
+                let reader_thread = std::thread::spawn(move || {
+                    let mut sum = 0;
+                    let mut count = 0;
+                    while count != n {
+                        match reader.try_pop() {
+                            Some(value) => {
+                                sum += value;
+                                count += 1;
+                            }
+                            None => std::thread::yield_now(),
+                        }
+                    }
+                    sum
+                });
+
+                let writer_thread = std::thread::spawn(move || {
+                    for mut value in 0..n {
+                        while let Err(rejected) = writer.try_push(value) {
+                            value = rejected;
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+
+                writer_thread.join().unwrap();
+                assert_eq!(reader_thread.join().unwrap(), n * (n - 1) / 2);
+            },
         )
     });
+    group.finish();
+}
+
+/// How a side retries a single-element transfer that found no room or no
+/// data.
+#[derive(Clone, Copy)]
+enum Retry {
+    /// Yield between plain non-blocking calls, as in [`run_transfer`].
+    Loop,
+    /// [`Writer::write_busy`] and [`Reader::read_busy`] without a spin
+    /// limit.
+    ///
+    /// [`Writer::write_busy`]: spsc::ring_buffer::Writer::write_busy
+    /// [`Reader::read_busy`]: spsc::ring_buffer::Reader::read_busy
+    Busy,
+}
+
+impl Retry {
+    fn send(self, writer: &mut spsc::ring_buffer::Writer<u64>, value: u64) {
+        match self {
+            Self::Loop => {
+                while writer.write(&[value]) == 0 && !writer.is_disconnected() {
+                    thread::yield_now();
+                }
+            }
+            Self::Busy => {
+                writer.write_busy(&[value], usize::MAX);
+            }
+        }
+    }
+
+    fn recv(self, reader: &mut spsc::ring_buffer::Reader<u64>) -> Option<u64> {
+        let mut value = [0];
+        let read = match self {
+            Self::Loop => loop {
+                let disconnected = reader.is_disconnected();
+                match reader.read(&mut value) {
+                    0 if disconnected => break 0,
+                    0 => thread::yield_now(),
+                    n => break n,
+                }
+            },
+            Self::Busy => reader.read_busy(&mut value, usize::MAX),
+        };
+
+        (read == 1).then_some(value[0])
+    }
+}
+
+/// Round trips of one element through a pair of rings and an echo thread
+/// that stays alive across iterations.
+fn benchmark_busy_wait(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Busy Wait");
+
+    for (name, retry) in [("Retry Loop", Retry::Loop), ("Busy", Retry::Busy)] {
+        let (mut ping_writer, mut ping_reader) =
+            spsc::ring_buffer::RingBuffer::<u64>::new(BUFFER_SIZE).unwrap();
+        let (mut pong_writer, mut pong_reader) =
+            spsc::ring_buffer::RingBuffer::<u64>::new(BUFFER_SIZE).unwrap();
+
+        let echo_thread = thread::spawn(move || {
+            while let Some(value) = retry.recv(&mut ping_reader) {
+                retry.send(&mut pong_writer, value);
+            }
+        });
+
+        let mut value = 0;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                retry.send(&mut ping_writer, black_box(value));
+                assert_eq!(retry.recv(&mut pong_reader), Some(value));
+                value += 1;
+            })
+        });
+
+        drop(ping_writer);
+        echo_thread.join().unwrap();
+    }
+    group.finish();
+}
+
+/// Writes and reads one element at a time from a single thread, where
+/// the cost of the ring itself is all there is to measure.
+fn round_trip<W: BufferWriter<u64>, R: BufferReader<u64>>(writer: &mut W, reader: &mut R) {
+    let mut value = [0];
+    for index in 0..black_box(10_000u64) {
+        assert_eq!(writer.write(&[index]), 1);
+        assert_eq!(reader.read(&mut value), 1);
+    }
+    black_box(value);
+}
+
+fn benchmark_local(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Same Thread / Burst 1");
+
+    let (mut writer, mut reader) = spsc::ring_buffer::RingBuffer::<u64>::new(BUFFER_SIZE).unwrap();
+    group.bench_function("Atomic", |b| {
+        b.iter(|| round_trip(&mut writer, &mut reader))
+    });
+
+    let (mut writer, mut reader) = spsc::local::ring_buffer::<u64>(BUFFER_SIZE).unwrap();
+    group.bench_function("Local", |b| b.iter(|| round_trip(&mut writer, &mut reader)));
+
+    group.finish();
 }
 
-criterion_group!(benchmark, benchmark_ring_buffer_1, benchmark_ring_buffer_2);
+criterion_group!(
+    benchmark,
+    benchmark_throughput,
+    benchmark_capacity_1,
+    benchmark_slot_stamps,
+    benchmark_single_element,
+    benchmark_busy_wait,
+    benchmark_local
+);
 criterion_main!(benchmark);
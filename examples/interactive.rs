@@ -22,23 +22,24 @@ fn test_ring_buffer_1(v1: usize, v2: usize) {
                 println!("{:?}", &read_buffer[index..index + v2]);
             }
 
-            index += n as usize;
+            index += n;
         }
 
         read_buffer
     });
 
-    let write_buffer: Vec<u64> = (0..v1 as u64).map(|value| value).collect();
+    let write_buffer: Vec<u64> = (0..v1 as u64).collect();
     let mut write_index = 0;
     loop {
         let mut input = String::new();
         std::io::stdin().read_line(&mut input).unwrap();
 
         let n = writer.write(&write_buffer[write_index..write_index + v2]);
-        write_index += n as usize;
+        write_index += n;
     }
 }
 
+#[allow(unused)]
 fn test_ring_buffer_2(v1: usize, v2: usize) {
     let writer = spsc::vecdeque::RingBuffer::<u64>::new(BUFFER_SIZE);
     let reader = writer.clone();
@@ -60,7 +61,7 @@ fn test_ring_buffer_2(v1: usize, v2: usize) {
         read_buffer
     });
 
-    let write_buffer: Vec<u64> = (0..v1 as u64).map(|value| value).collect();
+    let write_buffer: Vec<u64> = (0..v1 as u64).collect();
     let mut write_index = 0;
     loop {
         let mut input = String::new();
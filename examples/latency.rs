@@ -0,0 +1,182 @@
+// Measures how long elements sit in the queue, from the moment they are
+// written to the moment they are read, and prints percentiles per
+// configuration:
+//
+//     cargo run --release --example latency
+//     cargo run --release --example latency -- --wait park --burst 64
+//
+// Without options every combination of wait strategy (spin, park), burst
+// (1, 64) and capacity (64, 65536) is run. `--messages` and `--interval-us`
+// control how many elements are sent and how long the writer pauses
+// between bursts.
+
+use std::{
+    env,
+    hint::spin_loop,
+    thread,
+    time::{Duration, Instant},
+};
+
+use hdrhistogram::Histogram;
+use spsc::{
+    cancel::CancelToken,
+    ring_buffer::{BufferReader, RingBuffer},
+    timed::Timed,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Wait {
+    // Retries in a busy loop until the ring has room or data.
+    Spin,
+    // Blocks in the ring's own wait policy, which parks after a short spin.
+    Park,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Config {
+    wait: Wait,
+    burst: usize,
+    capacity: usize,
+    messages: usize,
+    interval: Duration,
+}
+
+fn run(config: Config) -> Histogram<u64> {
+    let (mut writer, mut reader) = RingBuffer::<Timed<u64>>::new(config.capacity).unwrap();
+    let Config {
+        wait,
+        burst,
+        messages,
+        interval,
+        ..
+    } = config;
+
+    // Elements are stamped when the writer decides to send them, so time
+    // spent waiting for room counts towards their latency.
+    let producer = thread::spawn(move || {
+        let token = CancelToken::new();
+
+        for sent in (0..messages).step_by(burst) {
+            let stamp = Instant::now();
+            let stamped: Vec<_> = (sent..messages.min(sent + burst))
+                .map(|value| Timed {
+                    value: value as u64,
+                    stamp,
+                })
+                .collect();
+            match wait {
+                Wait::Spin => {
+                    let mut remainder = &stamped[..];
+                    while !remainder.is_empty() {
+                        remainder = writer.write_remainder(remainder);
+                        spin_loop();
+                    }
+                }
+                Wait::Park => writer.write_all_cancellable(&stamped, &token).unwrap(),
+            }
+            thread::sleep(interval);
+        }
+    });
+
+    // Nanoseconds, up to a minute at three significant digits.
+    let mut histogram = Histogram::<u64>::new_with_bounds(1, 60_000_000_000, 3).unwrap();
+    let token = CancelToken::new();
+    let mut buffer = vec![
+        Timed {
+            value: 0,
+            stamp: Instant::now(),
+        };
+        burst
+    ];
+    let mut received = 0;
+
+    while received < messages {
+        let buffer = &mut buffer[..burst.min(messages - received)];
+        match wait {
+            Wait::Spin => {
+                let mut read = 0;
+                while read < buffer.len() {
+                    read += reader.read(&mut buffer[read..]);
+                    spin_loop();
+                }
+            }
+            Wait::Park => reader.read_exact_cancellable(buffer, &token).unwrap(),
+        }
+
+        let now = Instant::now();
+        for timed in buffer.iter() {
+            assert_eq!(
+                timed.value, received as u64,
+                "elements arrived out of order"
+            );
+            histogram.saturating_record(timed.age(now).as_nanos() as u64);
+            received += 1;
+        }
+    }
+    producer.join().unwrap();
+
+    histogram
+}
+
+fn main() {
+    let mut waits = vec![Wait::Spin, Wait::Park];
+    let mut bursts = vec![1, 64];
+    let mut capacities = vec![64, 1 << 16];
+    let mut messages = 10_000;
+    let mut interval = Duration::from_micros(20);
+
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("{flag} needs a value"));
+        match flag.as_str() {
+            "--wait" => {
+                waits = vec![match value.as_str() {
+                    "spin" => Wait::Spin,
+                    "park" => Wait::Park,
+                    _ => panic!("--wait is spin or park"),
+                }]
+            }
+            "--burst" => bursts = vec![value.parse().expect("--burst is a number")],
+            "--capacity" => capacities = vec![value.parse().expect("--capacity is a number")],
+            "--messages" => messages = value.parse().expect("--messages is a number"),
+            "--interval-us" => {
+                interval = Duration::from_micros(value.parse().expect("--interval-us is a number"))
+            }
+            _ => panic!("unknown option {flag}"),
+        }
+    }
+
+    println!(
+        "{:<6} {:>6} {:>9} {:>10} {:>10} {:>10} {:>10}",
+        "wait", "burst", "capacity", "p50 ns", "p99 ns", "p99.9 ns", "max ns",
+    );
+    for &wait in &waits {
+        for &burst in &bursts {
+            for &capacity in &capacities {
+                if burst > capacity {
+                    continue;
+                }
+
+                let histogram = run(Config {
+                    wait,
+                    burst,
+                    capacity,
+                    messages,
+                    interval,
+                });
+                println!(
+                    "{:<6} {:>6} {:>9} {:>10} {:>10} {:>10} {:>10}",
+                    format!("{wait:?}").to_lowercase(),
+                    burst,
+                    capacity,
+                    histogram.value_at_quantile(0.5),
+                    histogram.value_at_quantile(0.99),
+                    histogram.value_at_quantile(0.999),
+                    histogram.max(),
+                );
+            }
+        }
+    }
+}
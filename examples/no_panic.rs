@@ -0,0 +1,27 @@
+// Instantiates the hot paths for a concrete element type so that the
+// `no-panic` feature can prove at link time that none of them can panic.
+// The check only works with optimizations enabled:
+//
+//     cargo build --release --example no_panic --features no-panic
+
+use std::hint::black_box;
+
+use spsc::ring_buffer::{BufferReader, BufferWriter, RawBufferReader, RawBufferWriter, RingBuffer};
+
+fn main() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(black_box(64)).unwrap();
+
+    let values = black_box([1, 2, 3]);
+    let (available, index) = writer.available(black_box(3));
+    // A fresh ring of 64 always has the 3 slots asked for.
+    *unsafe { writer.get_mut(index) } = values[0];
+    black_box(available);
+    black_box(writer.write(&values));
+
+    let (filled, index) = reader.filled(black_box(3));
+    // Three elements were just published.
+    black_box((filled, unsafe { *reader.get(index) }));
+    let mut buffer = [0; 3];
+    black_box(reader.read(&mut buffer));
+    black_box(buffer);
+}
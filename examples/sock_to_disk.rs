@@ -0,0 +1,113 @@
+// Accepts one TCP connection and copies everything it sends into a file
+// through a byte ring, with the disk side deliberately slowed down.
+//
+//     cargo run --example sock_to_disk -- [addr] [path] [delay-ms]
+//     head -c 50000000 /dev/urandom | nc 127.0.0.1 7878
+//
+// Once the ring is full the network thread stops reading the socket, the
+// kernel's receive buffer fills up and TCP flow control stalls the sender.
+// Nothing is dropped: when the sender closes the connection, the network
+// thread drops its writer and the disk thread drains what is left.
+
+use std::{
+    env,
+    fs::File,
+    io::{self, BufWriter, Write},
+    net::TcpListener,
+    thread,
+    time::Duration,
+};
+
+use spsc::ring_buffer::RingBuffer;
+
+const CAPACITY: usize = 1 << 20;
+
+// A sink that pretends to be a slow disk by sleeping before every write.
+struct SlowDisk<W> {
+    inner: W,
+    delay: Duration,
+}
+
+impl<W: Write> Write for SlowDisk<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        thread::sleep(self.delay);
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let addr = args.next().unwrap_or_else(|| "127.0.0.1:7878".into());
+    let path = args.next().unwrap_or_else(|| "sock_to_disk.out".into());
+    let delay = args
+        .next()
+        .map_or(5, |delay| delay.parse().expect("delay in ms"));
+
+    let listener = TcpListener::bind(&addr)?;
+    println!("listening on {addr}, writing to {path}");
+    let (mut stream, peer) = listener.accept()?;
+    println!("accepted {peer}");
+
+    let (mut writer, mut reader) = RingBuffer::<u8>::new(CAPACITY).unwrap();
+    let mut disk = SlowDisk {
+        inner: BufWriter::new(File::create(&path)?),
+        delay: Duration::from_millis(delay),
+    };
+
+    let network = thread::spawn(move || -> io::Result<u64> {
+        let mut received = 0;
+        let mut stalls = 0u64;
+
+        loop {
+            match writer.fill_from(&mut stream) {
+                Ok(0) => break,
+                Ok(n) => received += n as u64,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    // The ring is full, so the socket is left alone.
+                    stalls += 1;
+                    thread::sleep(Duration::from_micros(100));
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        println!("network: end of stream after {received} bytes, {stalls} stalls on a full ring");
+
+        // Dropping the writer is what tells the disk thread to finish up.
+        drop(writer);
+        Ok(received)
+    });
+
+    let storage = thread::spawn(move || -> io::Result<u64> {
+        let mut written = 0;
+
+        loop {
+            let disconnected = reader.is_disconnected();
+            match reader.drain_to(&mut disk)? {
+                0 if disconnected => break,
+                0 => thread::sleep(Duration::from_millis(1)),
+                n => {
+                    written += n as u64;
+                    println!(
+                        "disk: drained {n} bytes, ring was {:.0}% full",
+                        n as f64 * 100.0 / CAPACITY as f64,
+                    );
+                }
+            }
+        }
+        disk.flush()?;
+
+        Ok(written)
+    });
+
+    let received = network.join().expect("network thread panicked")?;
+    let written = storage.join().expect("disk thread panicked")?;
+    println!("received {received} bytes, wrote {written} bytes");
+    assert_eq!(received, written);
+
+    Ok(())
+}
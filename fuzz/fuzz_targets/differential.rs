@@ -0,0 +1,32 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spsc::testing::{run_differential, Op};
+
+// Input layout: one byte of capacity, then a stream of operations. The low
+// two bits of a tag byte pick a write of `tag >> 2` bytes taken from the
+// input, a read of `tag >> 2` bytes or a clear. `0xff` closes the writer,
+// which is kept rare since nothing more gets written after it.
+fuzz_target!(|data: &[u8]| {
+    let Some((&capacity, mut rest)) = data.split_first() else {
+        return;
+    };
+
+    let mut ops = Vec::new();
+    while let Some((&tag, tail)) = rest.split_first() {
+        let len = (tag >> 2) as usize;
+        rest = tail;
+        match tag & 3 {
+            _ if tag == 0xff => ops.push(Op::Close),
+            0 | 1 => {
+                let len = len.min(tail.len());
+                ops.push(Op::Write(tail[..len].to_vec()));
+                rest = &tail[len..];
+            }
+            2 => ops.push(Op::Read(len)),
+            _ => ops.push(Op::Clear),
+        }
+    }
+
+    run_differential(capacity as usize % 64 + 1, &ops);
+});
@@ -0,0 +1,88 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spsc::{
+    ring_buffer::{BufferReader, BufferWriter, RingBuffer},
+    testing::{run_fifo, run_transfer_bursts, Pump, Step, DEFAULT_TIMEOUT},
+};
+
+// Small capacities, so that almost every script wraps around.
+const CAPACITIES: [usize; 4] = [2, 3, 7, 8];
+
+// Input layout: one byte picking the capacity, then one byte per step. The
+// low two bits pick a write, a read or a skip, and the rest is its size.
+fuzz_target!(|data: &[u8]| {
+    let Some((&capacity, steps)) = data.split_first() else {
+        return;
+    };
+    let capacity = CAPACITIES[capacity as usize % CAPACITIES.len()];
+    let script: Vec<Step> = steps
+        .iter()
+        .map(|&tag| {
+            let n = (tag >> 2) as usize % (2 * capacity + 2);
+            match tag & 3 {
+                0 | 1 => Step::Write(n),
+                2 => Step::Read(n),
+                _ => Step::Skip(n),
+            }
+        })
+        .collect();
+
+    let (writer, reader) = RingBuffer::<u64>::new(capacity).unwrap();
+    Pump::new(writer, reader).run(&script);
+
+    let (mut writer, mut reader) = spsc::vecdeque::ring_buffer::<u64>(capacity);
+    run_fifo(&mut writer, &mut reader, &script);
+    let (mut writer, mut reader) = spsc::local::ring_buffer::<u64>(capacity).unwrap();
+    run_fifo(&mut writer, &mut reader, &script);
+    let (mut writer, mut reader) = spsc::seqring::ring_buffer::<u64>(capacity).unwrap();
+    run_fifo(&mut writer, &mut reader, &script);
+    let (mut writer, mut reader) = spsc::latency::instrumented::<u64>(capacity).unwrap();
+    run_fifo(&mut writer, &mut reader, &script);
+    let (mut writer, mut reader) = spsc::mailbox::<u64>();
+    run_fifo(&mut writer, &mut reader, &script);
+
+    // The same burst sizes again, with each side on a thread of its own.
+    let sizes = |write: bool| -> Vec<usize> {
+        script
+            .iter()
+            .filter_map(|step| match *step {
+                Step::Write(n) if write => Some(n),
+                Step::Read(n) | Step::Skip(n) if !write => Some(n),
+                _ => None,
+            })
+            .collect()
+    };
+    let (write_bursts, read_bursts) = (sizes(true), sizes(false));
+    if !write_bursts.iter().any(|&n| n > 0) || !read_bursts.iter().any(|&n| n > 0) {
+        return;
+    }
+    let elements: Vec<u64> = (0..write_bursts.iter().sum::<usize>() as u64).collect();
+
+    // Only rings that take partial writes, as the others would wait forever
+    // for room for a burst above their capacity.
+    let (writer, reader) = RingBuffer::<u64>::new(capacity).unwrap();
+    threaded(writer, reader, &elements, &write_bursts, &read_bursts);
+    let (writer, reader) = spsc::vecdeque::ring_buffer::<u64>(capacity);
+    threaded(writer, reader, &elements, &write_bursts, &read_bursts);
+});
+
+fn threaded<W, R>(
+    writer: W,
+    reader: R,
+    elements: &[u64],
+    write_bursts: &[usize],
+    read_bursts: &[usize],
+) where
+    W: BufferWriter<u64> + Send + 'static,
+    R: BufferReader<u64> + Send + 'static,
+{
+    run_transfer_bursts(
+        writer,
+        reader,
+        elements.to_vec(),
+        write_bursts,
+        read_bursts,
+        DEFAULT_TIMEOUT,
+    );
+}
@@ -0,0 +1,400 @@
+use std::{
+    alloc::{self, Layout},
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    ptr::{self, NonNull},
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    index,
+    ring_buffer::{
+        BufferReader, BufferWriter, RawBufferReader, RawBufferWriter, ReadBuf, RingBufferError,
+    },
+};
+
+// Ring storage starts on a cache line, which also bounds the alignment of
+// the element types an arena can hold.
+const SLOT_ALIGN: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArenaOccupancy {
+    pub rings_in_use: usize,
+    pub rings_total: usize,
+}
+
+/// A slab carved into a fixed number of equally sized rings, for programs
+/// that create and destroy many short-lived channels.
+///
+/// All storage and all ring headers are allocated once, up front. Creating
+/// a ring takes a free slot and the slot goes back to the arena when both
+/// of its handles are dropped, so a steady churn of channels does not touch
+/// the allocator. Handles keep the arena alive; it is freed with the last
+/// handle or clone.
+#[derive(Clone)]
+pub struct RingArena {
+    inner: Arc<ArenaInner>,
+}
+
+struct ArenaInner {
+    storage: NonNull<u8>,
+    layout: Layout,
+    slot_bytes: usize,
+    rings: Box<[RingHeader]>,
+    free: Mutex<Vec<u32>>,
+}
+
+// The storage is a plain allocation owned by the arena. Each slot belongs to
+// at most one ring at a time, handed out and taken back under `free`.
+unsafe impl Send for ArenaInner {}
+unsafe impl Sync for ArenaInner {}
+
+struct RingHeader {
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    handles: AtomicU8,
+}
+
+impl RingArena {
+    /// Reserves room for `rings` rings of `slot_bytes` bytes each. The
+    /// capacity of a ring is `slot_bytes / size_of::<T>()` elements for the
+    /// `T` it is created with.
+    pub fn new(rings: usize, slot_bytes: usize) -> Result<Self, RingBufferError> {
+        let ring_count = u32::try_from(rings)
+            .ok()
+            .filter(|&rings| rings > 0)
+            .ok_or(RingBufferError::Initialize)?;
+        let slot_bytes = slot_bytes
+            .checked_next_multiple_of(SLOT_ALIGN)
+            .filter(|&slot_bytes| slot_bytes > 0)
+            .ok_or(RingBufferError::Initialize)?;
+        let layout = slot_bytes
+            .checked_mul(rings)
+            .and_then(|size| Layout::from_size_align(size, SLOT_ALIGN).ok())
+            .ok_or(RingBufferError::Initialize)?;
+
+        let storage = NonNull::new(unsafe { alloc::alloc_zeroed(layout) })
+            .ok_or(RingBufferError::Initialize)?;
+        let headers = (0..rings)
+            .map(|_| RingHeader {
+                head: 0.into(),
+                tail: 0.into(),
+                handles: 0.into(),
+            })
+            .collect();
+
+        Ok(Self {
+            inner: Arc::new(ArenaInner {
+                storage,
+                layout,
+                slot_bytes,
+                rings: headers,
+                free: Mutex::new((0..ring_count).rev().collect()),
+            }),
+        })
+    }
+
+    /// Takes a free slot and returns the two halves of a ring over it, or
+    /// `None` if every slot is in use or a slot cannot hold even one `T`.
+    pub fn create<T: Copy>(&self) -> Option<(ArenaWriter<T>, ArenaReader<T>)> {
+        if size_of::<T>() == 0 || align_of::<T>() > SLOT_ALIGN {
+            return None;
+        }
+        let capacity =
+            Some(self.inner.slot_bytes / size_of::<T>()).filter(|&capacity| capacity > 0)?;
+        let wrap = index::index_wrap(capacity)?;
+
+        let slot = self.inner.free.lock().unwrap().pop()?;
+        let header = &self.inner.rings[slot as usize];
+        header.head.store(0, Ordering::Relaxed);
+        header.tail.store(0, Ordering::Relaxed);
+        header.handles.store(2, Ordering::Release);
+
+        let ring = ArenaRing {
+            inner: self.inner.clone(),
+            slot,
+            capacity,
+            wrap,
+            _marker: PhantomData,
+        };
+
+        Some((ArenaWriter { ring: ring.clone() }, ArenaReader { ring }))
+    }
+
+    pub fn occupancy(&self) -> ArenaOccupancy {
+        let rings_total = self.inner.rings.len();
+
+        ArenaOccupancy {
+            rings_in_use: rings_total - self.inner.free.lock().unwrap().len(),
+            rings_total,
+        }
+    }
+}
+
+impl Drop for ArenaInner {
+    fn drop(&mut self) {
+        unsafe { alloc::dealloc(self.storage.as_ptr(), self.layout) };
+    }
+}
+
+struct ArenaRing<T: Copy> {
+    inner: Arc<ArenaInner>,
+    slot: u32,
+    capacity: usize,
+    // The indices wrap like those of the lock-free ring, see
+    // `index::index_wrap`.
+    wrap: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy> Clone for ArenaRing<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            slot: self.slot,
+            capacity: self.capacity,
+            wrap: self.wrap,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// The raw storage pointer hides `T` from the auto traits; elements only
+// cross threads by value.
+unsafe impl<T: Copy + Send> Send for ArenaRing<T> {}
+
+impl<T: Copy> ArenaRing<T> {
+    #[inline(always)]
+    fn header(&self) -> &RingHeader {
+        &self.inner.rings[self.slot as usize]
+    }
+
+    #[inline(always)]
+    fn element(&self, index: usize) -> *mut T {
+        let offset = self.slot as usize * self.inner.slot_bytes;
+        let index = index % self.capacity;
+
+        unsafe {
+            self.inner
+                .storage
+                .as_ptr()
+                .add(offset)
+                .cast::<T>()
+                .add(index)
+        }
+    }
+
+    // Hands the slot back once both halves are gone. The free list was
+    // allocated for every slot up front, so this never allocates.
+    fn release(&self) {
+        if self.header().handles.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.inner.free.lock().unwrap().push(self.slot);
+        }
+    }
+}
+
+pub struct ArenaWriter<T: Copy> {
+    ring: ArenaRing<T>,
+}
+
+impl<T: Copy> ArenaWriter<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+}
+
+impl<T: Copy> Drop for ArenaWriter<T> {
+    fn drop(&mut self) {
+        self.ring.release();
+    }
+}
+
+impl<T: Copy> ArenaWriter<T> {
+    // Number of free slots and the index of the first.
+    #[inline(always)]
+    fn free(&self) -> (usize, usize) {
+        let head_index = self.ring.header().head.load(Ordering::Relaxed);
+        let tail_index = self.ring.header().tail.load(Ordering::Acquire);
+
+        let free = self.ring.capacity - index::distance(self.ring.wrap, tail_index, head_index);
+        (free, head_index)
+    }
+}
+
+impl<T: Copy> RawBufferWriter<T> for ArenaWriter<T> {
+    #[inline(always)]
+    fn available(&self, size: usize) -> (usize, usize) {
+        let (available, head_index) = self.free();
+        if available >= size {
+            (size, head_index)
+        } else {
+            (0, head_index)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get_mut(&mut self, index: usize) -> &mut T {
+        unsafe { &mut *self.ring.element(index) }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        let head = &self.ring.header().head;
+        head.store(
+            index::offset_index(self.ring.wrap, head.load(Ordering::Relaxed), offset),
+            Ordering::Release,
+        );
+    }
+}
+
+impl<T: Copy> BufferWriter<T> for ArenaWriter<T> {
+    /// Writes as much of `buffer` as currently fits and returns the number
+    /// of elements written, like the lock-free ring.
+    #[inline(always)]
+    fn write(&mut self, buffer: &[T]) -> usize {
+        let (free, index) = self.free();
+        let len = buffer.len().min(free);
+        let start = index % self.ring.capacity;
+        let first = len.min(self.ring.capacity - start);
+
+        // The `len` free slots run from `start` to at most the end of the
+        // ring's storage and carry on from its front, and they are
+        // published only once both runs are written.
+        unsafe {
+            ptr::copy_nonoverlapping(buffer.as_ptr(), self.ring.element(start), first);
+            ptr::copy_nonoverlapping(buffer[first..].as_ptr(), self.ring.element(0), len - first);
+            self.advance_index(len);
+        }
+
+        len
+    }
+}
+
+pub struct ArenaReader<T: Copy> {
+    ring: ArenaRing<T>,
+}
+
+impl<T: Copy> ArenaReader<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+}
+
+impl<T: Copy> Drop for ArenaReader<T> {
+    fn drop(&mut self) {
+        self.ring.release();
+    }
+}
+
+impl<T: Copy> ArenaReader<T> {
+    // Number of queued elements and the index of the oldest.
+    #[inline(always)]
+    fn queued(&self) -> (usize, usize) {
+        let head_index = self.ring.header().head.load(Ordering::Acquire);
+        let tail_index = self.ring.header().tail.load(Ordering::Relaxed);
+
+        (
+            index::distance(self.ring.wrap, tail_index, head_index),
+            tail_index,
+        )
+    }
+}
+
+impl<T: Copy> RawBufferReader<T> for ArenaReader<T> {
+    #[inline(always)]
+    fn filled(&self, size: usize) -> (usize, usize) {
+        let (filled, tail_index) = self.queued();
+        if filled >= size {
+            (size, tail_index)
+        } else {
+            (0, tail_index)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get(&self, index: usize) -> &T {
+        unsafe { &*self.ring.element(index) }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        let tail = &self.ring.header().tail;
+        tail.store(
+            index::offset_index(self.ring.wrap, tail.load(Ordering::Relaxed), offset),
+            Ordering::Release,
+        );
+    }
+}
+
+impl<T: Copy> BufferReader<T> for ArenaReader<T> {
+    /// Reads as many queued elements as fit in `buffer`.
+    #[inline(always)]
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        let (queued, index) = self.queued();
+        let len = buffer.remaining().min(queued);
+        let start = index % self.ring.capacity;
+        let first = len.min(self.ring.capacity - start);
+
+        // The `len` queued elements run from `start` to at most the end of
+        // the ring's storage and carry on from its front, and their slots
+        // are released only once both runs are copied out.
+        unsafe {
+            let out = buffer.unfilled_mut().as_mut_ptr().cast::<T>();
+            ptr::copy_nonoverlapping(self.ring.element(start), out, first);
+            ptr::copy_nonoverlapping(self.ring.element(0), out.add(first), len - first);
+            buffer.advance(len);
+            self.advance_index(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_wrap_at_a_multiple_of_an_odd_capacity() {
+        let arena = RingArena::new(1, 64).unwrap();
+        let (mut writer, mut reader) = arena.create::<[u8; 3]>().unwrap();
+        assert_eq!(writer.capacity(), 21);
+
+        // Start both indices just short of the wrap, where a plain
+        // remainder would jump to a different slot.
+        let wrap = writer.ring.wrap;
+        let header = writer.ring.header();
+        header.head.store(wrap - 2, Ordering::Relaxed);
+        header.tail.store(wrap - 2, Ordering::Relaxed);
+
+        for round in 0..10u8 {
+            let values = [[round; 3], [round + 1; 3], [round + 2; 3], [round + 3; 3]];
+            assert_eq!(writer.write(&values), 4);
+            let mut buffer = [[0; 3]; 4];
+            assert_eq!(reader.read(&mut buffer), 4);
+            assert_eq!(buffer, values);
+        }
+        assert!(writer.ring.header().head.load(Ordering::Relaxed) < wrap);
+    }
+
+    #[test]
+    fn transfers_take_what_fits_across_the_wrap() {
+        let arena = RingArena::new(1, 64).unwrap();
+        let (mut writer, mut reader) = arena.create::<[u8; 12]>().unwrap();
+        assert_eq!(writer.capacity(), 5);
+        let mut buffer = [[0; 12]; 8];
+
+        assert_eq!(writer.write(&[[1; 12], [2; 12], [3; 12]]), 3);
+        assert_eq!(reader.read(&mut buffer[..2]), 2);
+        let values: Vec<[u8; 12]> = (4..10).map(|value| [value; 12]).collect();
+        assert_eq!(writer.write(&values), 4);
+        assert_eq!(writer.write(&[[9; 12]]), 0);
+
+        assert_eq!(reader.read(&mut buffer), 5);
+        assert_eq!(buffer[..5], [[3; 12], [4; 12], [5; 12], [6; 12], [7; 12]]);
+        assert_eq!(reader.read(&mut buffer), 0);
+    }
+}
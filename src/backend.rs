@@ -0,0 +1,141 @@
+use std::marker::PhantomData;
+
+use crate::{
+    ring_buffer::{
+        BufferReader, BufferWriter, ReadBuf, Reader, RingBuffer, RingBufferError, Writer,
+    },
+    vecdeque,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// The lock-free ring in [`crate::ring_buffer`].
+    LockFree,
+    /// The mutex-guarded `VecDeque` ring in [`crate::vecdeque`].
+    Mutex,
+}
+
+/// Creates a ring whose backend is chosen at runtime, for example from a
+/// configuration file, without making the rest of the program generic.
+///
+/// The handles are enums rather than trait objects, so every call is a
+/// `match` the compiler can see through. Both halves implement
+/// [`BufferWriter`] and [`BufferReader`], and both backends follow the same
+/// rules for them, so code written against the traits runs on either.
+pub struct AnyRingBuffer<T: Copy> {
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy> AnyRingBuffer<T> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        capacity: usize,
+        backend: Backend,
+    ) -> Result<(AnyWriter<T>, AnyReader<T>), RingBufferError> {
+        match backend {
+            Backend::LockFree => {
+                let (writer, reader) = RingBuffer::new(capacity)?;
+
+                Ok((AnyWriter::LockFree(writer), AnyReader::LockFree(reader)))
+            }
+            Backend::Mutex => {
+                if capacity == 0 {
+                    return Err(RingBufferError::ZeroCapacity);
+                }
+                let (writer, reader) = vecdeque::ring_buffer(capacity);
+
+                Ok((AnyWriter::Mutex(writer), AnyReader::Mutex(reader)))
+            }
+        }
+    }
+}
+
+pub enum AnyWriter<T: Copy> {
+    LockFree(Writer<T>),
+    Mutex(vecdeque::DequeWriter<T>),
+}
+
+impl<T: Copy> AnyWriter<T> {
+    #[inline(always)]
+    pub fn backend(&self) -> Backend {
+        match self {
+            Self::LockFree(_) => Backend::LockFree,
+            Self::Mutex(_) => Backend::Mutex,
+        }
+    }
+
+    /// Returns the lock-free handle, or `self` if another backend is in use.
+    pub fn into_lock_free(self) -> Result<Writer<T>, Self> {
+        match self {
+            Self::LockFree(writer) => Ok(writer),
+            other => Err(other),
+        }
+    }
+
+    /// Returns the mutex handle, or `self` if another backend is in use.
+    pub fn into_mutex(self) -> Result<vecdeque::DequeWriter<T>, Self> {
+        match self {
+            Self::Mutex(writer) => Ok(writer),
+            other => Err(other),
+        }
+    }
+}
+
+impl<T: Copy> BufferWriter<T> for AnyWriter<T> {
+    #[inline(always)]
+    fn write(&mut self, buffer: &[T]) -> usize {
+        match self {
+            Self::LockFree(writer) => writer.write(buffer),
+            Self::Mutex(writer) => writer.write(buffer),
+        }
+    }
+}
+
+pub enum AnyReader<T: Copy> {
+    LockFree(Reader<T>),
+    Mutex(vecdeque::DequeReader<T>),
+}
+
+impl<T: Copy> AnyReader<T> {
+    #[inline(always)]
+    pub fn backend(&self) -> Backend {
+        match self {
+            Self::LockFree(_) => Backend::LockFree,
+            Self::Mutex(_) => Backend::Mutex,
+        }
+    }
+
+    /// Returns the lock-free handle, or `self` if another backend is in use.
+    pub fn into_lock_free(self) -> Result<Reader<T>, Self> {
+        match self {
+            Self::LockFree(reader) => Ok(reader),
+            other => Err(other),
+        }
+    }
+
+    /// Returns the mutex handle, or `self` if another backend is in use.
+    pub fn into_mutex(self) -> Result<vecdeque::DequeReader<T>, Self> {
+        match self {
+            Self::Mutex(reader) => Ok(reader),
+            other => Err(other),
+        }
+    }
+}
+
+impl<T: Copy> BufferReader<T> for AnyReader<T> {
+    #[inline(always)]
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        match self {
+            Self::LockFree(reader) => reader.read_buf(buffer),
+            Self::Mutex(reader) => reader.read_buf(buffer),
+        }
+    }
+
+    #[inline(always)]
+    fn read(&mut self, buffer: &mut [T]) -> usize {
+        match self {
+            Self::LockFree(reader) => reader.read(buffer),
+            Self::Mutex(reader) => reader.read(buffer),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::ring_buffer::RingBufferError;
+
+/// Creates a bip-buffer: a byte ring that hands out contiguous regions
+/// instead of splitting them at the end of the storage.
+///
+/// When a grant does not fit between the write index and the end of the
+/// storage, the writer wraps to the front early and records the point it
+/// left off at (the watermark), so the reader knows to skip the unused
+/// remainder. A grant therefore needs `n` contiguous free bytes, and may be
+/// refused even though the total free space is larger.
+pub fn bip_buffer(capacity: usize) -> Result<(BipWriter, BipReader), RingBufferError> {
+    if capacity == 0 {
+        return Err(RingBufferError::ZeroCapacity);
+    }
+
+    let shared = Arc::new(Shared {
+        buffer: UnsafeCell::new(vec![0; capacity].into_boxed_slice()),
+        write: 0.into(),
+        read: 0.into(),
+        watermark: 0.into(),
+    });
+
+    Ok((
+        BipWriter {
+            shared: shared.clone(),
+            reserve: 0,
+            granted: 0,
+        },
+        BipReader { shared },
+    ))
+}
+
+// `write` and `read` are the two sides' indices. `watermark` is where the
+// valid data ends while the write index has wrapped behind the read index.
+struct Shared {
+    buffer: UnsafeCell<Box<[u8]>>,
+    write: AtomicUsize,
+    read: AtomicUsize,
+    watermark: AtomicUsize,
+}
+
+// The bytes are only written in the region the write side has reserved and
+// only read in the region the read side was granted, and the atomics publish
+// each region before the other side may touch it.
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+impl Shared {
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        unsafe { (*self.buffer.get()).len() }
+    }
+
+    #[inline(always)]
+    fn region(&self, start: usize, len: usize) -> *mut u8 {
+        debug_assert!(start + len <= self.capacity());
+        unsafe { (*self.buffer.get()).as_mut_ptr().add(start) }
+    }
+}
+
+pub struct BipWriter {
+    shared: Arc<Shared>,
+    // End and length of the outstanding grant.
+    reserve: usize,
+    granted: usize,
+}
+
+impl BipWriter {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Returns exactly `n` contiguous bytes to fill, or `None` if that many
+    /// are not free in one piece. Nothing is visible to the reader until
+    /// [`BipWriter::commit`]; a new grant replaces an uncommitted one.
+    pub fn grant(&mut self, n: usize) -> Option<&mut [u8]> {
+        let write = self.shared.write.load(Ordering::Relaxed);
+        let read = self.shared.read.load(Ordering::Acquire);
+
+        let start = if write < read {
+            // Already wrapped: the free space ends just before `read`, and
+            // one byte stays unused so that full and empty differ.
+            if write + n < read {
+                write
+            } else {
+                return None;
+            }
+        } else if write + n <= self.capacity() {
+            write
+        } else if n < read {
+            0
+        } else {
+            return None;
+        };
+        self.reserve = start + n;
+        self.granted = n;
+
+        Some(unsafe { std::slice::from_raw_parts_mut(self.shared.region(start, n), n) })
+    }
+
+    /// Publishes the first `n` bytes of the last grant (at most its length).
+    pub fn commit(&mut self, n: usize) {
+        let used = n.min(self.granted);
+        if used == 0 {
+            return;
+        }
+
+        let write = self.shared.write.load(Ordering::Relaxed);
+        let new_write = self.reserve - (self.granted - used);
+        self.granted = 0;
+
+        if new_write < write && write != self.capacity() {
+            // Wrapped to the front early: the data ends at the old index.
+            self.shared.watermark.store(write, Ordering::Relaxed);
+        } else if new_write > self.shared.watermark.load(Ordering::Relaxed) {
+            self.shared
+                .watermark
+                .store(self.capacity(), Ordering::Relaxed);
+        }
+        self.shared.write.store(new_write, Ordering::Release);
+    }
+}
+
+pub struct BipReader {
+    shared: Arc<Shared>,
+}
+
+impl BipReader {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Returns the contiguous committed bytes at the read index, which is
+    /// empty when nothing is queued. Data the writer wrapped around is
+    /// returned by a later call once this region has been released.
+    pub fn read(&mut self) -> &[u8] {
+        let write = self.shared.write.load(Ordering::Acquire);
+        let watermark = self.shared.watermark.load(Ordering::Relaxed);
+        let mut read = self.shared.read.load(Ordering::Relaxed);
+
+        if read == watermark && write < read {
+            read = 0;
+            self.shared.read.store(0, Ordering::Release);
+        }
+
+        let end = if write < read { watermark } else { write };
+        let len = end - read;
+
+        unsafe { std::slice::from_raw_parts(self.shared.region(read, len), len) }
+    }
+
+    /// Consumes the first `n` bytes (at most the length of the region
+    /// returned by [`BipReader::read`]) and hands them back to the writer.
+    pub fn release(&mut self, n: usize) {
+        let len = self.read().len();
+        self.shared.read.fetch_add(n.min(len), Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        // Grants `n` bytes and commits the first `k` of them.
+        Grant(usize, usize),
+        Release(usize),
+    }
+
+    fn ops(capacity: usize) -> Vec<Op> {
+        let mut ops = Vec::new();
+        for n in 1..=capacity {
+            for k in 0..=n {
+                ops.push(Op::Grant(n, k));
+            }
+            ops.push(Op::Release(n));
+        }
+        ops
+    }
+
+    // Whether the documented rules leave `n` contiguous free bytes: after
+    // the write index up to the end, or from the front up to one byte short
+    // of the read index.
+    fn fits(shared: &Shared, n: usize) -> bool {
+        let write = shared.write.load(Ordering::Relaxed);
+        let read = shared.read.load(Ordering::Relaxed);
+
+        if write < read {
+            write + n < read
+        } else {
+            write + n <= shared.capacity() || n < read
+        }
+    }
+
+    // Runs `script` against a byte queue and checks every grant, region and
+    // byte on the way.
+    fn check(capacity: usize, script: &[Op]) {
+        let (mut writer, mut reader) = bip_buffer(capacity).unwrap();
+        let mut queued = VecDeque::new();
+        let mut next = 0u8;
+
+        for (step, &op) in script.iter().enumerate() {
+            let context = || format!("step {step} of {script:?} at capacity {capacity}");
+
+            match op {
+                Op::Grant(n, k) => {
+                    let expected = fits(&writer.shared, n);
+                    match writer.grant(n) {
+                        Some(region) => {
+                            assert!(expected, "granted {n} without room, {}", context());
+                            assert_eq!(region.len(), n, "{}", context());
+                            for byte in &mut region[..k] {
+                                *byte = next;
+                                queued.push_back(next);
+                                next = next.wrapping_add(1);
+                            }
+                            writer.commit(k);
+                        }
+                        None => assert!(!expected, "refused {n} with room, {}", context()),
+                    }
+                }
+                Op::Release(m) => {
+                    let len = reader.read().len();
+                    reader.release(m);
+                    queued.drain(..m.min(len));
+                }
+            }
+
+            // The readable region is the front of the queue, and is empty
+            // only when nothing is queued.
+            let region = reader.read();
+            assert_eq!(region.is_empty(), queued.is_empty(), "{}", context());
+            assert!(region.len() <= queued.len(), "{}", context());
+            assert!(
+                region.iter().eq(queued.iter().take(region.len())),
+                "{}",
+                context()
+            );
+        }
+    }
+
+    // Every script of up to `depth` operations, built up depth first.
+    fn exhaust(capacity: usize, depth: usize, script: &mut Vec<Op>) {
+        check(capacity, script);
+        if script.len() == depth {
+            return;
+        }
+
+        for op in ops(capacity) {
+            script.push(op);
+            exhaust(capacity, depth, script);
+            script.pop();
+        }
+    }
+
+    #[test]
+    fn every_short_script_keeps_fifo_order() {
+        for capacity in 1..=3 {
+            exhaust(capacity, 5, &mut Vec::new());
+        }
+        for capacity in 4..=6 {
+            exhaust(capacity, 3, &mut Vec::new());
+        }
+    }
+
+    #[test]
+    fn grant_that_exactly_fits_the_remainder_stays_in_place() {
+        let (mut writer, mut reader) = bip_buffer(8).unwrap();
+        writer.grant(5).unwrap().fill(1);
+        writer.commit(5);
+        reader.release(5);
+
+        // Three bytes are left before the end, and a grant of three takes
+        // them rather than wrapping.
+        writer.grant(3).unwrap().copy_from_slice(&[2, 3, 4]);
+        writer.commit(3);
+        assert_eq!(writer.shared.write.load(Ordering::Relaxed), 8);
+        assert_eq!(reader.read(), [2, 3, 4]);
+        reader.release(3);
+
+        // The next grant starts over at the front.
+        writer.grant(4).unwrap().copy_from_slice(&[5, 6, 7, 8]);
+        writer.commit(4);
+        assert_eq!(reader.read(), [5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn grant_past_the_remainder_wraps_and_sets_the_watermark() {
+        let (mut writer, mut reader) = bip_buffer(8).unwrap();
+        writer
+            .grant(6)
+            .unwrap()
+            .copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+        writer.commit(6);
+        reader.release(4);
+
+        // Two bytes are left at the end, so three go to the front.
+        writer.grant(3).unwrap().copy_from_slice(&[7, 8, 9]);
+        writer.commit(3);
+        assert_eq!(writer.shared.watermark.load(Ordering::Relaxed), 6);
+
+        // The reader finishes the old region, skips the unused two bytes
+        // and carries on at the front.
+        assert_eq!(reader.read(), [5, 6]);
+        reader.release(2);
+        assert_eq!(reader.read(), [7, 8, 9]);
+        reader.release(3);
+        assert!(reader.read().is_empty());
+    }
+
+    #[test]
+    fn wrapped_grant_needs_a_spare_byte_before_the_reader() {
+        let (mut writer, mut reader) = bip_buffer(8).unwrap();
+        writer.grant(8).unwrap();
+        writer.commit(8);
+        reader.release(3);
+
+        // Three bytes are free at the front, but one must stay unused.
+        assert!(writer.grant(3).is_none());
+        writer.grant(2).unwrap().copy_from_slice(&[9, 9]);
+        writer.commit(2);
+        assert!(writer.grant(1).is_none());
+    }
+
+    #[test]
+    fn partial_commit_of_a_wrapped_grant() {
+        let (mut writer, mut reader) = bip_buffer(8).unwrap();
+        writer.grant(7).unwrap();
+        writer.commit(7);
+        reader.release(6);
+
+        writer.grant(4).unwrap().copy_from_slice(&[1, 2, 3, 4]);
+        writer.commit(2);
+        reader.release(1);
+        assert_eq!(reader.read(), [1, 2]);
+        reader.release(2);
+        assert!(reader.read().is_empty());
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::{
+        atomic::{fence, AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    index::{distance, index_wrap, offset_index},
+    ring_buffer::RingBufferError,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlowReaderPolicy {
+    /// The writer waits for the slowest reader, so no reader ever misses an
+    /// element.
+    Block,
+    /// The writer never waits. A reader that falls more than `capacity`
+    /// elements behind skips ahead to the oldest retained element and counts
+    /// the skipped elements in [`BroadcastReader::lag`].
+    Skip,
+}
+
+/// Creates a ring with one writer and `readers` readers that each see every
+/// element, with the writer waiting for the slowest reader.
+///
+/// This is lossless, so a reader that stops reading eventually stalls the
+/// writer, and with it every other reader. Dropping a reader releases its
+/// hold: from then on the writer only waits for the readers still alive.
+/// Use [`SlowReaderPolicy::Skip`] for a writer that never waits.
+pub fn broadcast<T: Copy>(
+    capacity: usize,
+    readers: usize,
+) -> Result<(Writer<T>, Vec<BroadcastReader<T>>), RingBufferError> {
+    broadcast_with_policy(capacity, readers, SlowReaderPolicy::Block)
+}
+
+pub fn broadcast_with_policy<T: Copy>(
+    capacity: usize,
+    readers: usize,
+    policy: SlowReaderPolicy,
+) -> Result<(Writer<T>, Vec<BroadcastReader<T>>), RingBufferError> {
+    if capacity == 0 {
+        return Err(RingBufferError::ZeroCapacity);
+    }
+    let wrap = index_wrap(capacity).ok_or(RingBufferError::CapacityOverflow)?;
+
+    let shared = Arc::new(Shared {
+        buffer: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        capacity,
+        wrap,
+        policy,
+        head: 0.into(),
+        claim: 0.into(),
+        tails: (0..readers).map(|_| 0.into()).collect(),
+        live: (0..readers).map(|_| true.into()).collect(),
+    });
+
+    let writer = Writer {
+        shared: shared.clone(),
+    };
+    let readers = (0..readers)
+        .map(|id| BroadcastReader {
+            shared: shared.clone(),
+            id,
+            tail: 0,
+            lag: 0,
+        })
+        .collect();
+
+    Ok((writer, readers))
+}
+
+struct Shared<T: Copy> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    capacity: usize,
+    // Free-running indices wrap here, see `index::index_wrap`.
+    wrap: usize,
+    policy: SlowReaderPolicy,
+    head: AtomicUsize,
+    claim: AtomicUsize,
+    tails: Box<[AtomicUsize]>,
+    // Cleared when a reader is dropped, so its tail no longer holds the
+    // writer back.
+    live: Box<[AtomicBool]>,
+}
+
+// Readers only copy slots below `head`. The writer only overwrites slots all
+// live readers are past, or under skip policy announces them in `claim` first
+// so readers can throw away a torn copy.
+unsafe impl<T: Copy + Send> Sync for Shared<T> {}
+
+impl<T: Copy> Shared<T> {
+    #[inline(always)]
+    fn slot(&self, index: usize) -> *mut MaybeUninit<T> {
+        self.buffer[index % self.capacity].get()
+    }
+}
+
+pub struct Writer<T: Copy> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Copy> Writer<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    #[inline(always)]
+    pub fn readers(&self) -> usize {
+        self.shared.tails.len()
+    }
+
+    fn available(&self, size: usize) -> (usize, usize) {
+        let shared = &self.shared;
+        let head_index = shared.head.load(Ordering::Relaxed);
+
+        let available = match shared.policy {
+            SlowReaderPolicy::Block => {
+                let filled = shared
+                    .tails
+                    .iter()
+                    .zip(shared.live.iter())
+                    .filter(|(_, live)| live.load(Ordering::Acquire))
+                    .map(|(tail, _)| {
+                        distance(shared.wrap, tail.load(Ordering::Acquire), head_index)
+                    })
+                    .max()
+                    .unwrap_or(0);
+                shared.capacity - filled
+            }
+            SlowReaderPolicy::Skip => shared.capacity,
+        };
+
+        if available >= size {
+            (size, head_index)
+        } else {
+            (0, head_index)
+        }
+    }
+
+    pub fn write(&mut self, buffer: &[T]) -> usize {
+        let (available, index) = self.available(buffer.len());
+
+        if available > 0 {
+            let shared = &self.shared;
+
+            // Announce the slots about to be overwritten before touching them
+            // so that lagging readers in skip mode can discard torn copies.
+            shared.claim.store(
+                offset_index(shared.wrap, index, available),
+                Ordering::Relaxed,
+            );
+            fence(Ordering::Release);
+
+            for (offset, value) in buffer.iter().enumerate() {
+                unsafe { (*shared.slot(offset_index(shared.wrap, index, offset))).write(*value) };
+            }
+            shared.head.store(
+                offset_index(shared.wrap, index, available),
+                Ordering::Release,
+            );
+
+            available
+        } else {
+            0
+        }
+    }
+}
+
+pub struct BroadcastReader<T: Copy> {
+    shared: Arc<Shared<T>>,
+    id: usize,
+    tail: usize,
+    lag: u64,
+}
+
+impl<T: Copy> Drop for BroadcastReader<T> {
+    fn drop(&mut self) {
+        self.shared.live[self.id].store(false, Ordering::Release);
+    }
+}
+
+impl<T: Copy> BroadcastReader<T> {
+    #[inline(always)]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Total number of elements this reader has skipped because it fell
+    /// behind the writer. Always zero under [`SlowReaderPolicy::Block`].
+    #[inline(always)]
+    pub fn lag(&self) -> u64 {
+        self.lag
+    }
+
+    pub fn read(&mut self, buffer: &mut [T]) -> usize {
+        let size = buffer.len();
+        let shared = &self.shared;
+
+        'retry: loop {
+            let head_index = shared.head.load(Ordering::Acquire);
+            let mut filled = distance(shared.wrap, self.tail, head_index);
+
+            if filled > shared.capacity {
+                let skipped = filled - shared.capacity;
+                self.lag += skipped as u64;
+                self.tail = offset_index(shared.wrap, self.tail, skipped);
+                filled = shared.capacity;
+            }
+
+            if filled < size || size == 0 {
+                return 0;
+            }
+
+            for (offset, value) in buffer.iter_mut().enumerate() {
+                let position = offset_index(shared.wrap, self.tail, offset);
+                // Under skip policy the writer may be overwriting this slot,
+                // so the copy stays uninitialized until the claim shows it
+                // was not touched.
+                let copy = unsafe { shared.slot(position).read_volatile() };
+
+                if shared.policy == SlowReaderPolicy::Skip {
+                    fence(Ordering::Acquire);
+                    let claim = shared.claim.load(Ordering::Relaxed);
+                    if distance(shared.wrap, position, claim) > shared.capacity {
+                        // The writer lapped us while copying; start over from
+                        // the oldest element it has not touched.
+                        continue 'retry;
+                    }
+                }
+
+                // Safety: the slot is below `head`, so it was published, and
+                // under skip policy the claim check above shows the writer
+                // had not started overwriting it when it was copied.
+                *value = unsafe { copy.assume_init() };
+            }
+
+            self.tail = offset_index(shared.wrap, self.tail, size);
+            shared.tails[self.id].store(self.tail, Ordering::Release);
+
+            return size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    const TOTAL: u64 = 20_000;
+
+    // Each element repeats its sequence number, so a torn copy shows up as
+    // a mix of two numbers.
+    fn element(value: u64) -> [u64; 4] {
+        [value; 4]
+    }
+
+    fn write_all(writer: &mut Writer<[u64; 4]>) {
+        for value in 0..TOTAL {
+            while writer.write(&[element(value)]) == 0 {
+                thread::yield_now();
+            }
+        }
+    }
+
+    // Reads until the last element has been seen, pausing every `pause`
+    // elements to fall behind the writer.
+    fn read_to_end(mut reader: BroadcastReader<[u64; 4]>, pause: u64) -> (Vec<u64>, u64) {
+        let mut received = Vec::new();
+        let mut buffer = [[0; 4]; 1];
+
+        while received.last() != Some(&(TOTAL - 1)) {
+            if reader.read(&mut buffer) == 0 {
+                thread::yield_now();
+                continue;
+            }
+
+            let [value, rest @ ..] = buffer[0];
+            assert!(rest.iter().all(|&part| part == value), "torn element");
+            received.push(value);
+            if value % pause == 0 {
+                thread::sleep(Duration::from_micros(50));
+            }
+        }
+
+        (received, reader.lag())
+    }
+
+    #[test]
+    fn block_readers_at_different_speeds_see_everything() {
+        let (mut writer, readers) =
+            broadcast_with_policy::<[u64; 4]>(7, 3, SlowReaderPolicy::Block).unwrap();
+        let handles: Vec<_> = readers
+            .into_iter()
+            .zip([u64::MAX, 1_000, 97])
+            .map(|(reader, pause)| thread::spawn(move || read_to_end(reader, pause)))
+            .collect();
+
+        write_all(&mut writer);
+        for handle in handles {
+            let (received, lag) = handle.join().unwrap();
+            assert_eq!(received, (0..TOTAL).collect::<Vec<_>>());
+            assert_eq!(lag, 0);
+        }
+    }
+
+    #[test]
+    fn skip_readers_at_different_speeds_stay_in_order() {
+        let (mut writer, readers) =
+            broadcast_with_policy::<[u64; 4]>(7, 3, SlowReaderPolicy::Skip).unwrap();
+        let handles: Vec<_> = readers
+            .into_iter()
+            .zip([u64::MAX, 1_000, 97])
+            .map(|(reader, pause)| thread::spawn(move || read_to_end(reader, pause)))
+            .collect();
+
+        write_all(&mut writer);
+        for handle in handles {
+            let (received, lag) = handle.join().unwrap();
+            assert!(received.windows(2).all(|pair| pair[0] < pair[1]));
+            // Every element is either read or counted as skipped.
+            assert_eq!(received.len() as u64 + lag, TOTAL);
+        }
+    }
+
+    #[test]
+    fn indices_cross_the_wrap_point() {
+        let (mut writer, mut readers) =
+            broadcast_with_policy::<u32>(3, 1, SlowReaderPolicy::Block).unwrap();
+        let mut reader = readers.pop().unwrap();
+
+        // Start two elements before the wrap.
+        let shared = &writer.shared;
+        let start = shared.wrap - 2;
+        shared.head.store(start, Ordering::Relaxed);
+        shared.claim.store(start, Ordering::Relaxed);
+        shared.tails[0].store(start, Ordering::Relaxed);
+        reader.tail = start;
+
+        let mut buffer = [0; 2];
+        for round in 0..4 {
+            assert_eq!(writer.write(&[round, round + 1, round + 2]), 3);
+            assert_eq!(writer.write(&[0]), 0);
+            assert_eq!(reader.read(&mut buffer), 2);
+            assert_eq!(buffer, [round, round + 1]);
+            assert_eq!(reader.read(&mut buffer[..1]), 1);
+            assert_eq!(buffer[0], round + 2);
+        }
+        assert!(reader.tail < start);
+    }
+}
@@ -0,0 +1,65 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, Thread},
+};
+
+/// A shareable handle that interrupts blocking ring buffer calls.
+///
+/// Cancelling sets a sticky flag and unparks every thread currently blocked
+/// on a call that was given this token, so they return promptly instead of
+/// waiting for data or space.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<CancelInner>,
+}
+
+#[derive(Default)]
+struct CancelInner {
+    cancelled: AtomicBool,
+    waiters: Mutex<Vec<Thread>>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+
+        for waiter in self.inner.waiters.lock().unwrap().drain(..) {
+            waiter.unpark();
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Registers the current thread to be unparked on cancellation for as
+    /// long as the returned guard is alive.
+    pub(crate) fn register(&self) -> WaiterGuard<'_> {
+        self.inner.waiters.lock().unwrap().push(thread::current());
+
+        WaiterGuard { token: self }
+    }
+}
+
+pub(crate) struct WaiterGuard<'a> {
+    token: &'a CancelToken,
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        let id = thread::current().id();
+        let mut waiters = self.token.inner.waiters.lock().unwrap();
+
+        if let Some(position) = waiters.iter().position(|waiter| waiter.id() == id) {
+            waiters.swap_remove(position);
+        }
+    }
+}
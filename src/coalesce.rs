@@ -0,0 +1,191 @@
+use std::{
+    mem::ManuallyDrop,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    clock::{Clock, MonotonicClock},
+    ring_buffer::{RawBufferWriter, Writer},
+};
+
+/// A writer that stages elements in the ring's free slots and only
+/// publishes them to the reader once at least `min_batch` are staged, so the
+/// shared write index is touched once per batch instead of once per write.
+///
+/// With [`CoalescingWriter::max_publish_delay`] set, staged data is also
+/// published once the oldest staged element has waited that long. The
+/// deadline is checked on every [`CoalescingWriter::write`]; a producer that
+/// may go idle should call [`CoalescingWriter::poll`] periodically, as
+/// nothing else can publish on its behalf. Dropping the writer publishes
+/// whatever is still staged.
+pub struct CoalescingWriter<T: Copy, C: Clock = MonotonicClock> {
+    writer: Writer<T>,
+    clock: C,
+    min_batch: usize,
+    max_delay: Option<Duration>,
+    staged: usize,
+    staged_since: Option<Instant>,
+}
+
+impl<T: Copy> CoalescingWriter<T> {
+    pub fn new(writer: Writer<T>, min_batch: usize) -> Self {
+        Self::with_clock(writer, min_batch, MonotonicClock)
+    }
+}
+
+impl<T: Copy, C: Clock> CoalescingWriter<T, C> {
+    pub fn with_clock(writer: Writer<T>, min_batch: usize, clock: C) -> Self {
+        Self {
+            writer,
+            clock,
+            min_batch: min_batch.max(1),
+            max_delay: None,
+            staged: 0,
+            staged_since: None,
+        }
+    }
+
+    pub fn max_publish_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = Some(delay);
+        self
+    }
+
+    #[inline(always)]
+    pub fn staged_len(&self) -> usize {
+        self.staged
+    }
+
+    /// Stages all of `buffer` if it fits next to what is already staged and
+    /// returns its length, or returns 0 and stages nothing.
+    pub fn write(&mut self, buffer: &[T]) -> usize {
+        let (free, index) = self.writer.free();
+        let size = buffer.len();
+        if size == 0 || size > free - self.staged {
+            self.poll();
+            return 0;
+        }
+
+        // The staged slots and the `size` after them are all free, since
+        // `size` fits in what `free` reported beyond the staged count.
+        let start = index.wrapping_add(self.staged);
+        for (offset, value) in buffer.iter().enumerate() {
+            *unsafe { self.writer.get_mut(start.wrapping_add(offset)) } = *value;
+        }
+        self.staged += size;
+        if self.staged_since.is_none() {
+            self.staged_since = Some(self.clock.now());
+        }
+
+        if self.staged >= self.min_batch {
+            self.flush();
+        } else {
+            self.poll();
+        }
+
+        size
+    }
+
+    /// Publishes the staged data if it has waited longer than the configured
+    /// maximum delay. Returns `true` if anything was published.
+    pub fn poll(&mut self) -> bool {
+        match (self.max_delay, self.staged_since) {
+            (Some(delay), Some(since))
+                if self.clock.now().saturating_duration_since(since) >= delay =>
+            {
+                self.flush();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Publishes everything staged so far, regardless of batch size.
+    pub fn flush(&mut self) {
+        if self.staged > 0 {
+            // Every staged slot was written by `write`.
+            unsafe { self.writer.advance_index(self.staged) };
+            self.staged = 0;
+            self.staged_since = None;
+        }
+    }
+
+    /// Publishes anything staged and returns the inner writer.
+    pub fn into_inner(self) -> Writer<T> {
+        let mut this = ManuallyDrop::new(self);
+        this.flush();
+
+        unsafe {
+            std::ptr::drop_in_place(&mut this.clock);
+            std::ptr::read(&this.writer)
+        }
+    }
+}
+
+impl<T: Copy, C: Clock> Drop for CoalescingWriter<T, C> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{
+        clock::MockClock,
+        ring_buffer::{BufferReader, RingBuffer},
+    };
+
+    const DELAY: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn a_short_batch_is_published_once_the_delay_is_up() {
+        let (writer, mut reader) = RingBuffer::<u32>::new(16).unwrap();
+        let clock = MockClock::new();
+        let mut writer =
+            CoalescingWriter::with_clock(writer, 4, clock.clone()).max_publish_delay(DELAY);
+        let mut buffer = [0; 4];
+
+        assert_eq!(writer.write(&[7]), 1);
+        assert_eq!(reader.read(&mut buffer), 0);
+
+        clock.advance(DELAY - Duration::from_millis(1));
+        assert!(!writer.poll());
+        assert_eq!(reader.read(&mut buffer), 0);
+
+        clock.advance(Duration::from_millis(1));
+        assert!(writer.poll());
+        assert_eq!(reader.read(&mut buffer), 1);
+        assert_eq!(buffer[0], 7);
+        assert_eq!(writer.staged_len(), 0);
+    }
+
+    #[test]
+    fn a_polling_producer_delivers_a_short_batch_in_time() {
+        let (writer, mut reader) = RingBuffer::<u32>::new(16).unwrap();
+        let mut writer = CoalescingWriter::new(writer, 4).max_publish_delay(DELAY);
+
+        let written = Instant::now();
+        assert_eq!(writer.write(&[7]), 1);
+        let producer = thread::spawn(move || {
+            // An idle producer that keeps polling, as the type asks for.
+            while writer.staged_len() > 0 {
+                writer.poll();
+                thread::sleep(Duration::from_millis(1));
+            }
+        });
+
+        let mut buffer = [0; 1];
+        while reader.read(&mut buffer) == 0 {
+            thread::yield_now();
+        }
+        let waited = written.elapsed();
+        producer.join().unwrap();
+
+        assert_eq!(buffer[0], 7);
+        assert!(waited >= DELAY, "published after {waited:?}");
+        // Scheduling slack on top of the delay, far below a second batch.
+        assert!(waited < DELAY * 20, "published after {waited:?}");
+    }
+}
@@ -0,0 +1,226 @@
+use crate::ring_buffer::{RawBufferReader, RawBufferWriter, Reader, Writer};
+
+// Frame layout: stored length (u32 LE), uncompressed length (u32 LE), flags,
+// then the stored bytes.
+const HEADER_SIZE: usize = 9;
+const PASSTHROUGH: u8 = 1;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The stored frame is larger than the ring and can never be sent.
+    FrameTooLarge,
+    /// The ring does not have room for the frame right now.
+    Full,
+    /// The frame's payload is not valid LZ4 data. The frame is consumed.
+    Decompress,
+}
+
+/// Writes length-prefixed frames whose payloads are LZ4 block-compressed.
+/// Payloads that do not shrink are stored as-is.
+pub struct CompressedFrameWriter {
+    writer: Writer<u8>,
+}
+
+impl CompressedFrameWriter {
+    pub fn new(writer: Writer<u8>) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> Writer<u8> {
+        self.writer
+    }
+
+    /// Compresses `payload` and publishes it as one frame. A frame refused
+    /// with [`FrameError::Full`] is compressed again on retry.
+    pub fn send(&mut self, payload: &[u8]) -> Result<(), FrameError> {
+        let raw_len = u32::try_from(payload.len()).map_err(|_| FrameError::FrameTooLarge)?;
+        let compressed = lz4_flex::block::compress(payload);
+        let (stored, flags) = if compressed.len() < payload.len() {
+            (compressed.as_slice(), 0)
+        } else {
+            (payload, PASSTHROUGH)
+        };
+
+        let stored_len = stored.len() as u32;
+        let frame_size = stored
+            .len()
+            .checked_add(HEADER_SIZE)
+            .filter(|&frame_size| frame_size <= self.writer.capacity())
+            .ok_or(FrameError::FrameTooLarge)?;
+
+        let (available, index) = self.writer.available(frame_size);
+        if available == 0 {
+            return Err(FrameError::Full);
+        }
+
+        let mut header = [0; HEADER_SIZE];
+        header[..4].copy_from_slice(&stored_len.to_le_bytes());
+        header[4..8].copy_from_slice(&raw_len.to_le_bytes());
+        header[8] = flags;
+        // `available` reported `frame_size` free slots from `index`, and the
+        // frame is published only once it has been written whole.
+        unsafe {
+            for (offset, byte) in header.iter().chain(stored).enumerate() {
+                *self.writer.get_mut(index.wrapping_add(offset)) = *byte;
+            }
+            self.writer.advance_index(frame_size);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct CompressedFrameReader {
+    reader: Reader<u8>,
+}
+
+impl CompressedFrameReader {
+    pub fn new(reader: Reader<u8>) -> Self {
+        Self { reader }
+    }
+
+    pub fn into_inner(self) -> Reader<u8> {
+        self.reader
+    }
+
+    /// Returns the next decompressed payload, or `Ok(None)` when no complete
+    /// frame has been published yet.
+    pub fn recv(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        let (filled, index) = self.reader.filled(HEADER_SIZE);
+        if filled == 0 {
+            return Ok(None);
+        }
+
+        let header: [u8; HEADER_SIZE] =
+            std::array::from_fn(|offset| unsafe { *self.reader.get(index.wrapping_add(offset)) });
+        let stored_len = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let raw_len = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let frame_size = stored_len as usize + HEADER_SIZE;
+        let (filled, _) = self.reader.filled(frame_size);
+        if filled == 0 {
+            return Ok(None);
+        }
+
+        // The whole frame is published, and released only after it has been
+        // copied out.
+        let stored: Vec<u8> = (HEADER_SIZE..frame_size)
+            .map(|offset| unsafe { *self.reader.get(index.wrapping_add(offset)) })
+            .collect();
+        unsafe { self.reader.advance_index(frame_size) };
+
+        if header[8] & PASSTHROUGH != 0 {
+            Ok(Some(stored))
+        } else {
+            lz4_flex::block::decompress(&stored, raw_len as usize)
+                .map(Some)
+                .map_err(|_| FrameError::Decompress)
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use crate::ring_buffer::{BufferWriter, RingBuffer};
+
+    fn frames(capacity: usize) -> (CompressedFrameWriter, CompressedFrameReader) {
+        let (writer, reader) = RingBuffer::new(capacity).unwrap();
+        (
+            CompressedFrameWriter::new(writer),
+            CompressedFrameReader::new(reader),
+        )
+    }
+
+    // Bytes that LZ4 cannot shrink, from a xorshift generator so every run
+    // sees the same ones.
+    fn random(state: &mut u64, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                *state ^= *state << 13;
+                *state ^= *state >> 7;
+                *state ^= *state << 17;
+                *state as u8
+            })
+            .collect()
+    }
+
+    // Bytes published to the ring and not yet read.
+    fn queued(writer: &CompressedFrameWriter) -> usize {
+        writer.writer.capacity() - writer.writer.free().0
+    }
+
+    fn telemetry(sequence: usize) -> Vec<u8> {
+        format!(r#"{{"sensor":"temperature","sequence":{sequence},"unit":"celsius"}}"#)
+            .repeat(4)
+            .into_bytes()
+    }
+
+    #[test]
+    fn compressible_payloads_take_less_room_than_they_hold() {
+        let (mut writer, mut reader) = frames(1024);
+        let payload = telemetry(0);
+
+        writer.send(&payload).unwrap();
+        assert!(queued(&writer) < payload.len() + HEADER_SIZE);
+        assert_eq!(reader.recv(), Ok(Some(payload)));
+        assert_eq!(reader.recv(), Ok(None));
+    }
+
+    #[test]
+    fn random_payloads_are_stored_as_they_are() {
+        let (mut writer, mut reader) = frames(1024);
+        let payload = random(&mut 0x2545_f491_4f6c_dd1d, 300);
+
+        writer.send(&payload).unwrap();
+        assert_eq!(queued(&writer), payload.len() + HEADER_SIZE);
+        assert_eq!(reader.recv(), Ok(Some(payload)));
+    }
+
+    #[test]
+    fn frames_round_trip_across_the_wrap() {
+        // Neither kind of frame divides the ring evenly, so the frames keep
+        // landing at new offsets and many of them straddle the end.
+        let (mut writer, mut reader) = frames(256);
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+
+        for round in 0..200 {
+            let payload = match round % 2 {
+                0 => telemetry(round),
+                _ => random(&mut state, 37 + round % 50),
+            };
+            writer.send(&payload).unwrap();
+            assert_eq!(reader.recv(), Ok(Some(payload)), "round {round}");
+        }
+    }
+
+    #[test]
+    fn a_frame_too_large_for_the_ring_is_refused() {
+        let (mut writer, _reader) = frames(64);
+        let payload = random(&mut 1, 64);
+
+        assert_eq!(writer.send(&payload), Err(FrameError::FrameTooLarge));
+        assert_eq!(queued(&writer), 0);
+    }
+
+    #[test]
+    fn a_corrupted_frame_fails_to_decompress_and_is_consumed() {
+        let (mut writer, reader) = RingBuffer::new(256).unwrap();
+        let mut reader = CompressedFrameReader::new(reader);
+
+        // A frame that claims to be compressed, with bytes that are not
+        // valid LZ4 for the length it promises.
+        let stored = [0xff; 8];
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&100u32.to_le_bytes());
+        frame.push(0);
+        frame.extend_from_slice(&stored);
+        assert_eq!(writer.write(&frame), frame.len());
+
+        let mut writer = CompressedFrameWriter::new(writer);
+        writer.send(b"after").unwrap();
+
+        assert_eq!(reader.recv(), Err(FrameError::Decompress));
+        assert_eq!(reader.recv(), Ok(Some(b"after".to_vec())));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::ring_buffer::{BufferWriter, Reader, RingBuffer, RingBufferError, Writer};
+
+pub type Endpoints<A, B> = (Endpoint<A, B>, Endpoint<B, A>);
+
+pub fn duplex<A: Copy, B: Copy>(capacity: usize) -> Result<Endpoints<A, B>, RingBufferError> {
+    let (a_writer, b_reader) = RingBuffer::new(capacity)?;
+    let (b_writer, a_reader) = RingBuffer::new(capacity)?;
+
+    Ok((
+        Endpoint::new(a_writer, a_reader),
+        Endpoint::new(b_writer, b_reader),
+    ))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Disconnected;
+
+/// One side of a duplex channel: a writer for the outbound direction and a
+/// reader for the inbound one.
+///
+/// Each direction is closed independently. [`Endpoint::close_send`] closes
+/// the outbound direction only; dropping an endpoint closes both.
+pub struct Endpoint<Out: Copy, In: Copy> {
+    writer: Writer<Out>,
+    reader: Reader<In>,
+}
+
+impl<Out: Copy, In: Copy> Endpoint<Out, In> {
+    fn new(writer: Writer<Out>, reader: Reader<In>) -> Self {
+        Self { writer, reader }
+    }
+
+    pub fn try_send(&mut self, message: Out) -> Result<bool, Disconnected> {
+        if self.writer.is_closed() || self.writer.is_disconnected() {
+            Err(Disconnected)
+        } else {
+            Ok(self.writer.write(&[message]) == 1)
+        }
+    }
+
+    /// Sends `message`, parking while the outbound ring is full. Fails once
+    /// the peer has dropped its endpoint or this side has closed sending.
+    pub fn send(&mut self, message: Out) -> Result<(), Disconnected> {
+        self.writer.write_all(&[message]).map_err(|_| Disconnected)
+    }
+
+    /// Returns `Ok(None)` when nothing is queued yet. Messages sent before the
+    /// inbound direction was closed are still delivered before
+    /// `Err(Disconnected)`.
+    pub fn try_recv(&mut self) -> Result<Option<In>, Disconnected> {
+        // Checked first, so a message published right before the close is
+        // not missed.
+        let finished = self.reader.is_finished();
+
+        match self.reader.read_array() {
+            Some([message]) => Ok(Some(message)),
+            None if finished => Err(Disconnected),
+            None => Ok(None),
+        }
+    }
+
+    /// Receives the next message, parking while none is queued, with the
+    /// same end of stream as [`Endpoint::try_recv`].
+    pub fn recv(&mut self) -> Result<In, Disconnected> {
+        self.reader.iter().next().ok_or(Disconnected)
+    }
+
+    pub fn call(&mut self, request: Out) -> Result<In, Disconnected> {
+        self.send(request)?;
+        self.recv()
+    }
+
+    /// Closes the outbound direction. The peer still receives what was sent
+    /// before, then `Err(Disconnected)`, and is woken if it is parked in
+    /// [`Endpoint::recv`].
+    pub fn close_send(&mut self) {
+        self.writer.close();
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    // Answers every request with its double until the client goes away.
+    fn echo(mut endpoint: Endpoint<u64, u64>) -> usize {
+        let mut served = 0;
+        while let Ok(request) = endpoint.recv() {
+            if endpoint.send(request * 2).is_err() {
+                break;
+            }
+            served += 1;
+        }
+        served
+    }
+
+    #[test]
+    fn echo_worker_answers_every_call() {
+        let (mut client, server) = duplex::<u64, u64>(4).unwrap();
+        let worker = thread::spawn(move || echo(server));
+
+        for request in 0..10_000 {
+            assert_eq!(client.call(request), Ok(request * 2));
+        }
+
+        // Pipelined: several requests in flight before the replies are read.
+        for request in 0..3 {
+            client.send(request).unwrap();
+        }
+        for request in 0..3 {
+            assert_eq!(client.recv(), Ok(request * 2));
+        }
+
+        drop(client);
+        assert_eq!(worker.join().unwrap(), 10_003);
+    }
+
+    #[test]
+    fn worker_dropping_mid_call_wakes_the_caller() {
+        let (mut client, mut server) = duplex::<u64, u64>(4).unwrap();
+        let worker = thread::spawn(move || {
+            // Takes the request and goes away without replying.
+            server.recv().unwrap();
+        });
+
+        assert_eq!(client.call(1), Err(Disconnected));
+        worker.join().unwrap();
+        assert_eq!(client.send(2), Err(Disconnected));
+    }
+
+    #[test]
+    fn close_send_delivers_queued_messages_then_ends() {
+        let (mut client, mut server) = duplex::<u64, u64>(4).unwrap();
+        let worker = thread::spawn(move || {
+            let request = server.recv().unwrap();
+            server.send(request + 1).unwrap();
+            server.close_send();
+            assert_eq!(server.send(0), Err(Disconnected));
+
+            // The other direction stays open.
+            server.recv()
+        });
+
+        assert_eq!(client.call(1), Ok(2));
+        // Parks until the close wakes it.
+        assert_eq!(client.recv(), Err(Disconnected));
+        assert_eq!(client.try_recv(), Err(Disconnected));
+
+        client.send(3).unwrap();
+        assert_eq!(worker.join().unwrap(), Ok(3));
+    }
+}
@@ -0,0 +1,503 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    os::fd::AsRawFd,
+    path::Path,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+const MAGIC: u32 = u32::from_le_bytes(*b"SPSD");
+const VERSION: u32 = 1;
+
+/// When the durable ring forces its contents to disk with `msync(2)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// After every successful write and read.
+    EveryPublish,
+    /// After every `n` successful writes or reads on each side.
+    EveryN(u32),
+    /// Only when [`DurableWriter::flush`] / [`DurableReader::flush`] is
+    /// called.
+    Manual,
+}
+
+#[derive(Debug)]
+pub enum DurableError {
+    Io(io::Error),
+    InvalidFile,
+}
+
+impl From<io::Error> for DurableError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+// `head`/`tail` are the live indices the two sides use to hand data over.
+// The committed copies only move at flush points, after everything they
+// cover has been synced, and are what a reopened ring resumes from.
+#[repr(C)]
+struct Header {
+    magic: AtomicU32,
+    version: AtomicU32,
+    capacity: AtomicU32,
+    element_size: AtomicU32,
+    head: AtomicU32,
+    tail: AtomicU32,
+    committed_head: AtomicU32,
+    committed_tail: AtomicU32,
+}
+
+struct Mapping<T: Copy> {
+    ptr: NonNull<u8>,
+    len: usize,
+    slots_offset: usize,
+    capacity: u32,
+    _file: File,
+    _marker: PhantomData<T>,
+}
+
+// The mapping is owned and unmapped on drop. The two halves only touch
+// slots their indices hand them, and the header fields are atomics.
+unsafe impl<T: Copy + Send> Send for Mapping<T> {}
+unsafe impl<T: Copy + Send> Sync for Mapping<T> {}
+
+impl<T: Copy> Mapping<T> {
+    fn slots_offset() -> usize {
+        size_of::<Header>().next_multiple_of(align_of::<T>())
+    }
+
+    fn map(file: File, len: usize) -> io::Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        let slots_offset = Self::slots_offset();
+        Ok(Self {
+            ptr: NonNull::new(ptr.cast()).unwrap(),
+            len,
+            slots_offset,
+            capacity: ((len - slots_offset) / size_of::<T>()) as u32,
+            _file: file,
+            _marker: PhantomData,
+        })
+    }
+
+    #[inline(always)]
+    fn header(&self) -> &Header {
+        unsafe { self.ptr.cast::<Header>().as_ref() }
+    }
+
+    #[inline(always)]
+    fn slot(&self, index: u32) -> *mut T {
+        unsafe {
+            self.ptr
+                .as_ptr()
+                .add(self.slots_offset)
+                .cast::<T>()
+                .add((index % self.capacity) as usize)
+        }
+    }
+
+    fn sync(&self, len: usize) -> io::Result<()> {
+        if unsafe { libc::msync(self.ptr.as_ptr().cast(), len, libc::MS_SYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn sync_header(&self) -> io::Result<()> {
+        self.sync(size_of::<Header>())
+    }
+}
+
+impl<T: Copy> Drop for Mapping<T> {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr.as_ptr().cast(), self.len) };
+    }
+}
+
+/// A ring whose storage is a memory-mapped file, so that queued data
+/// survives a crash of either side.
+///
+/// Each side keeps a committed copy of its index in the file header, moved
+/// forward only at flush points (see [`FlushPolicy`]) after the data it
+/// covers has been synced. Reopening a ring resumes from the committed
+/// indices. Anything written after the writer's last flush is discarded,
+/// and anything read after the reader's last flush is delivered again.
+/// With [`FlushPolicy::EveryPublish`] on both sides, data is lost only if
+/// the writer crashes inside `write`. It is duplicated only if the reader
+/// crashes between copying data out and committing the read. Dropping a
+/// handle does not flush.
+///
+/// Elements are stored as raw bytes, so `T` should be plain data without
+/// pointers or references.
+pub struct DurableRing<T: Copy> {
+    mapping: Arc<Mapping<T>>,
+    policy: FlushPolicy,
+}
+
+impl<T: Copy> DurableRing<T> {
+    /// Creates (or truncates) the file at `path` and initializes an empty
+    /// ring with room for `capacity` elements.
+    pub fn create(path: impl AsRef<Path>, capacity: usize) -> Result<Self, DurableError> {
+        let capacity = u32::try_from(capacity).map_err(|_| DurableError::InvalidFile)?;
+        if capacity == 0 || size_of::<T>() == 0 {
+            return Err(DurableError::InvalidFile);
+        }
+
+        let len = Mapping::<T>::slots_offset() + capacity as usize * size_of::<T>();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(len as u64)?;
+
+        let mapping = Mapping::<T>::map(file, len)?;
+        let header = mapping.header();
+        header.version.store(VERSION, Ordering::Relaxed);
+        header.capacity.store(capacity, Ordering::Relaxed);
+        header
+            .element_size
+            .store(size_of::<T>() as u32, Ordering::Relaxed);
+        header.magic.store(MAGIC, Ordering::Release);
+        mapping.sync_header()?;
+
+        Ok(Self {
+            mapping: Arc::new(mapping),
+            policy: FlushPolicy::EveryPublish,
+        })
+    }
+
+    /// Maps an existing ring file and rolls both sides back to their last
+    /// committed indices.
+    ///
+    /// # Safety
+    ///
+    /// The file must have been created by [`DurableRing::create`] for the
+    /// same `T`, and no other process may have it open while it is
+    /// recovered.
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, DurableError> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len <= Mapping::<T>::slots_offset() || size_of::<T>() == 0 {
+            return Err(DurableError::InvalidFile);
+        }
+
+        let mapping = Mapping::<T>::map(file, len)?;
+        let header = mapping.header();
+        if header.magic.load(Ordering::Acquire) != MAGIC
+            || header.version.load(Ordering::Relaxed) != VERSION
+            || header.capacity.load(Ordering::Relaxed) != mapping.capacity
+            || header.element_size.load(Ordering::Relaxed) != size_of::<T>() as u32
+        {
+            return Err(DurableError::InvalidFile);
+        }
+
+        let head = header.committed_head.load(Ordering::Relaxed);
+        let mut tail = header.committed_tail.load(Ordering::Relaxed);
+        // The reader may have committed past data the writer never did.
+        if head.wrapping_sub(tail) > mapping.capacity {
+            tail = head;
+            header.committed_tail.store(tail, Ordering::Relaxed);
+        }
+        header.head.store(head, Ordering::Relaxed);
+        header.tail.store(tail, Ordering::Release);
+        mapping.sync_header()?;
+
+        Ok(Self {
+            mapping: Arc::new(mapping),
+            policy: FlushPolicy::EveryPublish,
+        })
+    }
+
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> u32 {
+        self.mapping.capacity
+    }
+
+    /// Splits the ring into its two halves. A process that only plays one
+    /// role simply drops the other half.
+    pub fn split(self) -> (DurableWriter<T>, DurableReader<T>) {
+        (
+            DurableWriter {
+                mapping: self.mapping.clone(),
+                policy: self.policy,
+                unflushed: 0,
+            },
+            DurableReader {
+                mapping: self.mapping,
+                policy: self.policy,
+                unflushed: 0,
+            },
+        )
+    }
+}
+
+fn flush_due(policy: FlushPolicy, unflushed: &mut u32) -> bool {
+    *unflushed += 1;
+    match policy {
+        FlushPolicy::EveryPublish => true,
+        FlushPolicy::EveryN(n) => *unflushed >= n,
+        FlushPolicy::Manual => false,
+    }
+}
+
+pub struct DurableWriter<T: Copy> {
+    mapping: Arc<Mapping<T>>,
+    policy: FlushPolicy,
+    unflushed: u32,
+}
+
+impl<T: Copy> DurableWriter<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> u32 {
+        self.mapping.capacity
+    }
+
+    /// Writes all of `buffer` if it fits and returns its length, or returns
+    /// 0 and writes nothing. Data is visible to the reader immediately but
+    /// only survives a reopen once flushed.
+    pub fn write(&mut self, buffer: &[T]) -> io::Result<u32> {
+        let header = self.mapping.header();
+        let head_index = header.head.load(Ordering::Relaxed);
+        let tail_index = header.tail.load(Ordering::Acquire);
+
+        let available = self.mapping.capacity - head_index.wrapping_sub(tail_index);
+        let size = match u32::try_from(buffer.len()) {
+            Ok(size) if size > 0 && size <= available => size,
+            _ => return Ok(0),
+        };
+
+        for (offset, value) in buffer.iter().enumerate() {
+            unsafe {
+                self.mapping
+                    .slot(head_index.wrapping_add(offset as u32))
+                    .write(*value)
+            };
+        }
+        header
+            .head
+            .store(head_index.wrapping_add(size), Ordering::Release);
+
+        if flush_due(self.policy, &mut self.unflushed) {
+            self.flush()?;
+        }
+
+        Ok(size)
+    }
+
+    /// Syncs the slots and then commits the current write index.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let header = self.mapping.header();
+        let head_index = header.head.load(Ordering::Relaxed);
+
+        self.mapping.sync(self.mapping.len)?;
+        header.committed_head.store(head_index, Ordering::Release);
+        self.mapping.sync_header()?;
+        self.unflushed = 0;
+
+        Ok(())
+    }
+}
+
+pub struct DurableReader<T: Copy> {
+    mapping: Arc<Mapping<T>>,
+    policy: FlushPolicy,
+    unflushed: u32,
+}
+
+impl<T: Copy> DurableReader<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> u32 {
+        self.mapping.capacity
+    }
+
+    /// Fills all of `buffer` if enough data is queued and returns its
+    /// length, or returns 0 and consumes nothing.
+    pub fn read(&mut self, buffer: &mut [T]) -> io::Result<u32> {
+        let header = self.mapping.header();
+        let head_index = header.head.load(Ordering::Acquire);
+        let tail_index = header.tail.load(Ordering::Relaxed);
+
+        let filled = head_index.wrapping_sub(tail_index);
+        let size = match u32::try_from(buffer.len()) {
+            Ok(size) if size > 0 && size <= filled => size,
+            _ => return Ok(0),
+        };
+
+        for (offset, value) in buffer.iter_mut().enumerate() {
+            *value = unsafe {
+                self.mapping
+                    .slot(tail_index.wrapping_add(offset as u32))
+                    .read()
+            };
+        }
+        header
+            .tail
+            .store(tail_index.wrapping_add(size), Ordering::Release);
+
+        if flush_due(self.policy, &mut self.unflushed) {
+            self.flush()?;
+        }
+
+        Ok(size)
+    }
+
+    /// Commits the current read index.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let header = self.mapping.header();
+
+        header
+            .committed_tail
+            .store(header.tail.load(Ordering::Relaxed), Ordering::Release);
+        self.mapping.sync_header()?;
+        self.unflushed = 0;
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::{
+        io::{Seek, SeekFrom, Write},
+        path::PathBuf,
+    };
+
+    use super::*;
+
+    // A ring file in the temp directory, removed once the test is done.
+    struct RingFile(PathBuf);
+
+    impl RingFile {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("spsc-durable-{}-{name}", std::process::id()));
+
+            Self(path)
+        }
+
+        fn reopen(&self) -> (DurableWriter<u64>, DurableReader<u64>) {
+            let ring = unsafe { DurableRing::<u64>::open(&self.0) }.unwrap();
+            ring.flush_policy(FlushPolicy::Manual).split()
+        }
+    }
+
+    impl Drop for RingFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn create(file: &RingFile, capacity: usize) -> (DurableWriter<u64>, DurableReader<u64>) {
+        DurableRing::<u64>::create(&file.0, capacity)
+            .unwrap()
+            .flush_policy(FlushPolicy::Manual)
+            .split()
+    }
+
+    fn read_all(reader: &mut DurableReader<u64>) -> Vec<u64> {
+        let mut values = Vec::new();
+        let mut value = [0];
+        while reader.read(&mut value).unwrap() == 1 {
+            values.push(value[0]);
+        }
+
+        values
+    }
+
+    #[test]
+    fn reads_not_flushed_before_a_drop_are_delivered_again() {
+        let file = RingFile::new("at-least-once");
+        let (mut writer, mut reader) = create(&file, 8);
+        assert_eq!(writer.write(&[1, 2, 3]).unwrap(), 3);
+        writer.flush().unwrap();
+        assert_eq!(read_all(&mut reader), [1, 2, 3]);
+        drop((writer, reader));
+
+        // The reads were never committed, so all of them come back.
+        let (_writer, mut reader) = file.reopen();
+        assert_eq!(read_all(&mut reader), [1, 2, 3]);
+    }
+
+    #[test]
+    fn flushed_reads_are_not_delivered_again() {
+        let file = RingFile::new("exactly-once");
+        let (mut writer, mut reader) = create(&file, 8);
+        assert_eq!(writer.write(&[1, 2, 3]).unwrap(), 3);
+        assert_eq!(writer.write(&[4]).unwrap(), 1);
+        writer.flush().unwrap();
+        let mut values = [0; 3];
+        assert_eq!(reader.read(&mut values).unwrap(), 3);
+        reader.flush().unwrap();
+        drop((writer, reader));
+
+        let (mut writer, mut reader) = file.reopen();
+        assert_eq!(read_all(&mut reader), [4]);
+        reader.flush().unwrap();
+        writer.flush().unwrap();
+        drop((writer, reader));
+
+        let (_writer, mut reader) = file.reopen();
+        assert_eq!(read_all(&mut reader), []);
+    }
+
+    #[test]
+    fn a_torn_tail_behind_the_commit_is_discarded() {
+        let file = RingFile::new("torn-tail");
+        let (mut writer, reader) = create(&file, 8);
+        assert_eq!(writer.write(&[1, 2]).unwrap(), 2);
+        writer.flush().unwrap();
+        // Published to the live reader, but the writer dies before the
+        // commit, halfway through the next slot on disk.
+        assert_eq!(writer.write(&[3, 4]).unwrap(), 2);
+        drop((writer, reader));
+
+        let mut raw = OpenOptions::new().write(true).open(&file.0).unwrap();
+        let slot = Mapping::<u64>::slots_offset() + 2 * size_of::<u64>();
+        raw.seek(SeekFrom::Start(slot as u64 + 4)).unwrap();
+        raw.write_all(&[0xff; 4]).unwrap();
+        drop(raw);
+
+        let (mut writer, mut reader) = file.reopen();
+        assert_eq!(read_all(&mut reader), [1, 2]);
+        // The write index is back at the commit, so the torn slots are
+        // simply written over.
+        assert_eq!(writer.write(&[5]).unwrap(), 1);
+        assert_eq!(read_all(&mut reader), [5]);
+    }
+
+    #[test]
+    fn open_rejects_a_file_it_did_not_create() {
+        let file = RingFile::new("foreign");
+        std::fs::write(&file.0, [0u8; 256]).unwrap();
+
+        let result = unsafe { DurableRing::<u64>::open(&file.0) };
+        assert!(matches!(result, Err(DurableError::InvalidFile)));
+    }
+}
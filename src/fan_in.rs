@@ -0,0 +1,149 @@
+use crate::ring_buffer::{BufferReader, Disconnected, Reader, RingBuffer, RingBufferError, Writer};
+
+pub fn fan_in<T: Copy>(
+    capacity_per_producer: usize,
+    producers: usize,
+) -> Result<(Vec<Writer<T>>, FanInReader<T>), RingBufferError> {
+    let mut writers = Vec::with_capacity(producers);
+    let mut readers = Vec::with_capacity(producers);
+
+    for _ in 0..producers {
+        let (writer, reader) = RingBuffer::new(capacity_per_producer)?;
+        writers.push(writer);
+        readers.push(reader);
+    }
+
+    Ok((writers, FanInReader::new(readers)))
+}
+
+pub struct FanInReader<T: Copy> {
+    readers: Vec<Reader<T>>,
+    next: usize,
+}
+
+impl<T: Copy> FanInReader<T> {
+    fn new(readers: Vec<Reader<T>>) -> Self {
+        Self { readers, next: 0 }
+    }
+
+    #[inline(always)]
+    pub fn producers(&self) -> usize {
+        self.readers.len()
+    }
+
+    /// Reads one burst from the next producer ring that has data queued,
+    /// visiting the rings round-robin so that a busy producer cannot starve
+    /// the others.
+    ///
+    /// A ring whose writer has been dropped or has closed it is left out
+    /// from then on, once everything queued in it has been read. Returns
+    /// `Ok(0)` when no producer has data ready yet, and `Err(Disconnected)`
+    /// once every producer is gone and nothing is left to read.
+    pub fn read(&mut self, buffer: &mut [T]) -> Result<usize, Disconnected> {
+        let mut offset = 0;
+
+        while offset < self.readers.len() {
+            let index = (self.next + offset) % self.readers.len();
+            let reader = &mut self.readers[index];
+            let n = reader.read(buffer);
+
+            if n > 0 {
+                self.next = (index + 1) % self.readers.len();
+                return Ok(n);
+            }
+
+            // The end of the stream is checked before the queue, so nothing
+            // can be published after the ring is seen empty.
+            if reader.is_finished() && reader.is_empty() {
+                // The rings after `index` move down by one, so the same
+                // offset now names the ring that followed the removed one.
+                self.readers.remove(index);
+                if index < self.next {
+                    self.next -= 1;
+                }
+                self.next %= self.readers.len().max(1);
+                continue;
+            }
+
+            offset += 1;
+        }
+
+        if self.readers.is_empty() {
+            return Err(Disconnected);
+        }
+
+        Ok(0)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use crate::ring_buffer::BufferWriter;
+
+    #[test]
+    fn four_producers_deliver_everything_then_terminate() {
+        const PER_PRODUCER: u64 = 10_000;
+
+        let (writers, mut reader) = fan_in::<u64>(16, 4).unwrap();
+        let handles: Vec<_> = writers
+            .into_iter()
+            .enumerate()
+            .map(|(producer, mut writer)| {
+                std::thread::spawn(move || {
+                    let base = producer as u64 * PER_PRODUCER;
+                    for value in base..base + PER_PRODUCER {
+                        while writer.write(&[value]) == 0 {
+                            std::thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let mut received = Vec::new();
+        let mut buffer = [0; 8];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => std::thread::yield_now(),
+                Ok(n) => received.extend_from_slice(&buffer[..n]),
+                Err(Disconnected) => break,
+            }
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Each producer's elements arrive in order, whatever the interleaving.
+        for producer in 0..4 {
+            let base = producer * PER_PRODUCER;
+            let own: Vec<u64> = received
+                .iter()
+                .copied()
+                .filter(|value| (base..base + PER_PRODUCER).contains(value))
+                .collect();
+            assert_eq!(own, (base..base + PER_PRODUCER).collect::<Vec<_>>());
+        }
+        assert_eq!(received.len() as u64, 4 * PER_PRODUCER);
+        assert_eq!(reader.producers(), 0);
+    }
+
+    #[test]
+    fn dropped_producers_leave_after_their_data_is_read() {
+        let (mut writers, mut reader) = fan_in::<u32>(4, 3).unwrap();
+        writers[1].write(&[1, 2]);
+        let last = writers.pop().unwrap();
+        drop(writers);
+
+        let mut buffer = [0; 2];
+        assert_eq!(reader.read(&mut buffer), Ok(2));
+        assert_eq!(buffer, [1, 2]);
+        // Only the producer that is still alive remains.
+        assert_eq!(reader.read(&mut buffer), Ok(0));
+        assert_eq!(reader.producers(), 1);
+
+        drop(last);
+        assert_eq!(reader.read(&mut buffer), Err(Disconnected));
+        assert_eq!(reader.producers(), 0);
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::ring_buffer::{BufferReader, BufferWriter, Reader, RingBuffer, Writer};
+
+// A C API for byte rings. The handles are opaque to C, which only ever
+// holds pointers to them. Every entry point checks its pointers for null
+// and catches panics, which must not unwind into C; failures show up as a
+// null handle, `false`, or zero bytes transferred.
+
+/// A ring that has not been split into its halves yet.
+pub struct SpscRing {
+    writer: Writer<u8>,
+    reader: Reader<u8>,
+}
+
+pub struct SpscWriter {
+    writer: Writer<u8>,
+}
+
+pub struct SpscReader {
+    reader: Reader<u8>,
+}
+
+// C only learns of a panic through the fallback value, so nothing broken
+// by it is observed on the way out.
+fn guard<R>(fallback: R, f: impl FnOnce() -> R) -> R {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// Creates a byte ring holding up to `capacity` bytes, or returns null if
+/// it cannot be created.
+#[no_mangle]
+pub extern "C" fn spsc_ring_new(capacity: usize) -> *mut SpscRing {
+    guard(ptr::null_mut(), || match RingBuffer::new(capacity) {
+        Ok((writer, reader)) => Box::into_raw(Box::new(SpscRing { writer, reader })),
+        Err(_) => ptr::null_mut(),
+    })
+}
+
+/// Splits `ring` into its writer and reader, stored through `writer` and
+/// `reader`, and frees `ring`. Returns `false`, leaving everything as it
+/// was, if any of the pointers is null.
+///
+/// # Safety
+///
+/// `ring` must come from [`spsc_ring_new`] and not have been split or
+/// freed yet. `writer` and `reader` must be valid for a pointer write.
+#[no_mangle]
+pub unsafe extern "C" fn spsc_ring_split(
+    ring: *mut SpscRing,
+    writer: *mut *mut SpscWriter,
+    reader: *mut *mut SpscReader,
+) -> bool {
+    if ring.is_null() || writer.is_null() || reader.is_null() {
+        return false;
+    }
+
+    guard(false, || {
+        let ring = Box::from_raw(ring);
+        writer.write(Box::into_raw(Box::new(SpscWriter {
+            writer: ring.writer,
+        })));
+        reader.write(Box::into_raw(Box::new(SpscReader {
+            reader: ring.reader,
+        })));
+
+        true
+    })
+}
+
+/// Frees a ring that was never split. Null is ignored.
+///
+/// # Safety
+///
+/// `ring` must be null or come from [`spsc_ring_new`] and not have been
+/// split or freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn spsc_ring_free(ring: *mut SpscRing) {
+    if !ring.is_null() {
+        guard((), || drop(Box::from_raw(ring)));
+    }
+}
+
+/// Writes as many of the `len` bytes at `data` as fit and returns how many
+/// were written.
+///
+/// # Safety
+///
+/// `writer` must be null or a live writer from [`spsc_ring_split`], used by
+/// one thread at a time. `data` must be valid for reading `len` bytes
+/// unless `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn spsc_writer_write(
+    writer: *mut SpscWriter,
+    data: *const u8,
+    len: usize,
+) -> usize {
+    if writer.is_null() || data.is_null() || len == 0 {
+        return 0;
+    }
+
+    let writer = &mut *writer;
+    let data = slice::from_raw_parts(data, len);
+    guard(0, || writer.writer.write(data))
+}
+
+/// Frees a writer. The reader sees the ring as disconnected once it has
+/// read what is still queued. Null is ignored.
+///
+/// # Safety
+///
+/// `writer` must be null or a live writer from [`spsc_ring_split`].
+#[no_mangle]
+pub unsafe extern "C" fn spsc_writer_free(writer: *mut SpscWriter) {
+    if !writer.is_null() {
+        guard((), || drop(Box::from_raw(writer)));
+    }
+}
+
+/// Reads up to `len` bytes into `data` and returns how many were read.
+///
+/// # Safety
+///
+/// `reader` must be null or a live reader from [`spsc_ring_split`], used by
+/// one thread at a time. `data` must be valid for writing `len` bytes
+/// unless `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn spsc_reader_read(
+    reader: *mut SpscReader,
+    data: *mut u8,
+    len: usize,
+) -> usize {
+    if reader.is_null() || data.is_null() || len == 0 {
+        return 0;
+    }
+
+    let reader = &mut *reader;
+    let data = slice::from_raw_parts_mut(data, len);
+    guard(0, || reader.reader.read(data))
+}
+
+/// Returns `true` once the writer has been freed. Bytes it wrote before
+/// that may still be queued.
+///
+/// # Safety
+///
+/// As for [`spsc_reader_read`].
+#[no_mangle]
+pub unsafe extern "C" fn spsc_reader_is_disconnected(reader: *const SpscReader) -> bool {
+    !reader.is_null() && (*reader).reader.is_disconnected()
+}
+
+/// Frees a reader. Null is ignored.
+///
+/// # Safety
+///
+/// `reader` must be null or a live reader from [`spsc_ring_split`].
+#[no_mangle]
+pub unsafe extern "C" fn spsc_reader_free(reader: *mut SpscReader) {
+    if !reader.is_null() {
+        guard((), || drop(Box::from_raw(reader)));
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::ptr;
+
+    // Declared again the way a C header would, with the handles opaque, so
+    // the calls go through the exported symbols rather than the Rust paths.
+    #[repr(C)]
+    struct SpscRing {
+        _opaque: [u8; 0],
+    }
+
+    #[repr(C)]
+    struct SpscWriter {
+        _opaque: [u8; 0],
+    }
+
+    #[repr(C)]
+    struct SpscReader {
+        _opaque: [u8; 0],
+    }
+
+    extern "C" {
+        fn spsc_ring_new(capacity: usize) -> *mut SpscRing;
+        fn spsc_ring_split(
+            ring: *mut SpscRing,
+            writer: *mut *mut SpscWriter,
+            reader: *mut *mut SpscReader,
+        ) -> bool;
+        fn spsc_ring_free(ring: *mut SpscRing);
+        fn spsc_writer_write(writer: *mut SpscWriter, data: *const u8, len: usize) -> usize;
+        fn spsc_writer_free(writer: *mut SpscWriter);
+        fn spsc_reader_read(reader: *mut SpscReader, data: *mut u8, len: usize) -> usize;
+        fn spsc_reader_is_disconnected(reader: *const SpscReader) -> bool;
+        fn spsc_reader_free(reader: *mut SpscReader);
+    }
+
+    #[test]
+    fn bytes_go_through_the_c_api_in_order() {
+        unsafe {
+            let ring = spsc_ring_new(4);
+            assert!(!ring.is_null());
+
+            let mut writer = ptr::null_mut();
+            let mut reader = ptr::null_mut();
+            assert!(spsc_ring_split(ring, &mut writer, &mut reader));
+            assert!(!writer.is_null() && !reader.is_null());
+
+            let data = *b"abcdef";
+            assert_eq!(spsc_writer_write(writer, data.as_ptr(), data.len()), 4);
+            let mut out = [0; 3];
+            assert_eq!(spsc_reader_read(reader, out.as_mut_ptr(), out.len()), 3);
+            assert_eq!(out, *b"abc");
+
+            // The rest wraps around the end of the ring.
+            assert_eq!(spsc_writer_write(writer, data[4..].as_ptr(), 2), 2);
+            assert!(!spsc_reader_is_disconnected(reader));
+            spsc_writer_free(writer);
+            assert!(spsc_reader_is_disconnected(reader));
+
+            // What was queued before the writer went is still there.
+            assert_eq!(spsc_reader_read(reader, out.as_mut_ptr(), out.len()), 3);
+            assert_eq!(out, *b"def");
+            assert_eq!(spsc_reader_read(reader, out.as_mut_ptr(), out.len()), 0);
+            spsc_reader_free(reader);
+        }
+    }
+
+    #[test]
+    fn null_pointers_are_turned_away() {
+        unsafe {
+            assert!(spsc_ring_new(0).is_null());
+
+            let mut writer = ptr::null_mut();
+            let mut reader = ptr::null_mut();
+            assert!(!spsc_ring_split(ptr::null_mut(), &mut writer, &mut reader));
+            assert!(writer.is_null() && reader.is_null());
+
+            let mut byte = 0;
+            assert_eq!(spsc_writer_write(ptr::null_mut(), &byte, 1), 0);
+            assert_eq!(spsc_reader_read(ptr::null_mut(), &mut byte, 1), 0);
+            assert!(!spsc_reader_is_disconnected(ptr::null()));
+
+            spsc_ring_free(ptr::null_mut());
+            spsc_writer_free(ptr::null_mut());
+            spsc_reader_free(ptr::null_mut());
+
+            // Null data is refused by live handles as well.
+            let ring = spsc_ring_new(2);
+            assert!(spsc_ring_split(ring, &mut writer, &mut reader));
+            assert_eq!(spsc_writer_write(writer, ptr::null(), 1), 0);
+            assert_eq!(spsc_writer_write(writer, &byte, 0), 0);
+            assert_eq!(spsc_reader_read(reader, ptr::null_mut(), 1), 0);
+            spsc_writer_free(writer);
+            spsc_reader_free(reader);
+        }
+    }
+
+    #[test]
+    fn a_ring_is_split_only_once() {
+        unsafe {
+            let ring = spsc_ring_new(2);
+            let mut writer = ptr::null_mut();
+            let mut reader = ptr::null_mut();
+
+            // A refused split leaves the ring whole, out-pointers untouched.
+            assert!(!spsc_ring_split(ring, ptr::null_mut(), &mut reader));
+            assert!(!spsc_ring_split(ring, &mut writer, ptr::null_mut()));
+            assert!(writer.is_null() && reader.is_null());
+
+            // The first split that goes through consumes the ring; the
+            // handles are all that is left, and freeing them is all the
+            // cleanup there is. Splitting `ring` again would be a use after
+            // free, which the safety contract rules out.
+            assert!(spsc_ring_split(ring, &mut writer, &mut reader));
+            let byte = 7;
+            assert_eq!(spsc_writer_write(writer, &byte, 1), 1);
+            let mut out = 0;
+            assert_eq!(spsc_reader_read(reader, &mut out, 1), 1);
+            assert_eq!(out, 7);
+            spsc_reader_free(reader);
+            spsc_writer_free(writer);
+
+            // A ring freed unsplit takes both halves with it.
+            let ring = spsc_ring_new(2);
+            assert!(!spsc_ring_split(ring, ptr::null_mut(), ptr::null_mut()));
+            spsc_ring_free(ring);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    future::{self, Future},
+    task::{Context, Poll},
+};
+
+use crate::ring_buffer::{BufferReader, BufferWriter, Reader, RingBuffer, RingBufferError, Writer};
+
+/// Creates a ring whose halves can be awaited from async tasks.
+///
+/// A side that finds the ring full (or empty) registers its task's waker in
+/// the shared header and is woken when the other side moves its index or is
+/// dropped. To make that hand-over airtight, every index store on this ring
+/// is followed by a `SeqCst` fence, which the rings from
+/// [`RingBuffer::new`] skip; keep those for purely synchronous use.
+pub fn ring_buffer<T: Copy>(
+    capacity: usize,
+) -> Result<(AsyncWriter<T>, AsyncReader<T>), RingBufferError> {
+    let (writer, reader) =
+        RingBuffer::with_options(capacity, false, true, None, Default::default(), ())?;
+
+    Ok((AsyncWriter { writer }, AsyncReader { reader }))
+}
+
+pub struct AsyncWriter<T: Copy> {
+    writer: Writer<T>,
+}
+
+impl<T: Copy> AsyncWriter<T> {
+    /// Writes as much of `buffer` as fits, or registers the task to be woken
+    /// once there is room. Ready with zero only if `buffer` is empty, the
+    /// reader is gone, or the ring is closed.
+    pub fn poll_write(&mut self, cx: &mut Context<'_>, buffer: &[T]) -> Poll<usize> {
+        if buffer.is_empty() {
+            return Poll::Ready(0);
+        }
+
+        if let Some(n) = self.try_write(buffer) {
+            self.writer.unregister_waker();
+            return Poll::Ready(n);
+        }
+
+        // The reader may have freed slots between the attempt above and the
+        // registration, in which case nobody is left to wake the task.
+        self.writer.register_waker(cx.waker());
+        match self.try_write(buffer) {
+            Some(n) => {
+                self.writer.unregister_waker();
+                Poll::Ready(n)
+            }
+            None => Poll::Pending,
+        }
+    }
+
+    fn try_write(&mut self, buffer: &[T]) -> Option<usize> {
+        let n = self.writer.write(buffer);
+
+        (n > 0 || self.writer.is_disconnected() || self.writer.is_closed()).then_some(n)
+    }
+
+    /// Waits until part of `buffer` fits and writes it, returning the number
+    /// of elements written as [`AsyncWriter::poll_write`] does.
+    pub fn write<'a>(&'a mut self, buffer: &'a [T]) -> impl Future<Output = usize> + 'a {
+        future::poll_fn(move |cx| self.poll_write(cx, buffer))
+    }
+
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.writer.is_disconnected()
+    }
+
+    /// Ends the stream like [`Writer::close`], waking a pending reader.
+    pub fn close(&self) {
+        self.writer.close();
+    }
+
+    #[inline(always)]
+    pub fn is_closed(&self) -> bool {
+        self.writer.is_closed()
+    }
+}
+
+pub struct AsyncReader<T: Copy> {
+    reader: Reader<T>,
+}
+
+impl<T: Copy> AsyncReader<T> {
+    /// Reads as many queued elements as fit in `buffer`, or registers the
+    /// task to be woken once data arrives. Ready with zero only if `buffer`
+    /// is empty, or the writer is gone or has closed the ring and everything
+    /// it published has been read.
+    pub fn poll_read(&mut self, cx: &mut Context<'_>, buffer: &mut [T]) -> Poll<usize> {
+        if buffer.is_empty() {
+            return Poll::Ready(0);
+        }
+
+        if let Some(n) = self.try_read(buffer) {
+            self.reader.unregister_waker();
+            return Poll::Ready(n);
+        }
+
+        // As in `AsyncWriter::poll_write`, data published before the
+        // registration would otherwise never wake the task.
+        self.reader.register_waker(cx.waker());
+        match self.try_read(buffer) {
+            Some(n) => {
+                self.reader.unregister_waker();
+                Poll::Ready(n)
+            }
+            None => Poll::Pending,
+        }
+    }
+
+    fn try_read(&mut self, buffer: &mut [T]) -> Option<usize> {
+        // Checked before reading, so data published right before the writer
+        // went away or closed the ring is still drained.
+        let finished = self.reader.is_finished();
+        let n = self.reader.read(buffer);
+
+        (n > 0 || finished).then_some(n)
+    }
+
+    /// Waits for data and reads it, returning the number of elements read
+    /// as [`AsyncReader::poll_read`] does.
+    pub fn read<'a>(&'a mut self, buffer: &'a mut [T]) -> impl Future<Output = usize> + 'a {
+        future::poll_fn(move |cx| self.poll_read(cx, buffer))
+    }
+
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.reader.is_disconnected()
+    }
+
+    /// Returns `true` once the stream has ended, see [`Reader::is_closed`].
+    #[inline(always)]
+    pub fn is_closed(&self) -> bool {
+        self.reader.is_closed()
+    }
+}
@@ -0,0 +1,59 @@
+//! Index arithmetic shared by the rings that keep a free-running head and
+//! tail index in a fixed storage.
+
+/// Picks the value at which the free-running indices wrap around, with
+/// zero standing for the full `usize` range, or `None` if `capacity` cannot
+/// be used.
+///
+/// An index has to map to the same slot on both sides of the wrap, so the
+/// wrap must be a multiple of the capacity. For powers of two the natural
+/// overflow already is one. Otherwise it is the largest multiple that still
+/// leaves room for one more capacity below the overflow, because callers add
+/// offsets of up to a capacity to an index before turning it into a slot.
+/// Telling a full ring from an empty one takes a wrap of at least twice the
+/// capacity, which rules out odd sizes above a third of the index range.
+pub(crate) const fn index_wrap(capacity: usize) -> Option<usize> {
+    if capacity == 0 {
+        return None;
+    }
+    if capacity.is_power_of_two() {
+        return Some(0);
+    }
+
+    // `capacity` does not divide 2^BITS, so this is also 2^BITS / capacity.
+    let wrap = (usize::MAX / capacity - 1) * capacity;
+    if wrap / capacity >= 2 {
+        Some(wrap)
+    } else {
+        None
+    }
+}
+
+/// Moves `index` forward by `offset`, wrapping at `wrap`.
+#[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+#[inline(always)]
+pub(crate) fn offset_index(wrap: usize, index: usize, offset: usize) -> usize {
+    let index = index.wrapping_add(offset);
+
+    // With a wrap of zero, meaning the full index range, this subtracts
+    // nothing and the addition above has already wrapped.
+    if index >= wrap {
+        index - wrap
+    } else {
+        index
+    }
+}
+
+/// Number of steps from `from` forward to `to` for indices wrapping at
+/// `wrap`.
+#[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+#[inline(always)]
+pub(crate) fn distance(wrap: usize, from: usize, to: usize) -> usize {
+    let distance = to.wrapping_sub(from);
+
+    if to < from {
+        distance.wrapping_add(wrap)
+    } else {
+        distance
+    }
+}
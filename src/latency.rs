@@ -0,0 +1,245 @@
+use std::time::Instant;
+
+use crate::{
+    clock::{Clock, MonotonicClock},
+    ring_buffer::{
+        BufferReader, BufferWriter, ReadBuf, Reader, RingBuffer, RingBufferError, Writer,
+    },
+};
+
+/// Number of buckets in a [`LatencySnapshot`]. Bucket `i` counts delays of
+/// `[2^(i - 1), 2^i)` ticks, bucket 0 counts zero-tick delays, and the last
+/// bucket also takes everything longer.
+pub const LATENCY_BUCKETS: usize = 40;
+
+/// A cheap monotonic counter used to stamp elements. The unit is up to the
+/// implementation; [`ClockTicks`] counts nanoseconds.
+pub trait Ticks {
+    fn ticks(&self) -> u64;
+}
+
+/// Nanoseconds elapsed on a [`Clock`] since this source was created, so
+/// that a [`MockClock`](crate::clock::MockClock) can drive the histogram in
+/// tests.
+#[derive(Clone, Debug)]
+pub struct ClockTicks<C: Clock = MonotonicClock> {
+    clock: C,
+    origin: Instant,
+}
+
+impl Default for ClockTicks {
+    fn default() -> Self {
+        Self::new(MonotonicClock)
+    }
+}
+
+impl<C: Clock> ClockTicks<C> {
+    pub fn new(clock: C) -> Self {
+        let origin = clock.now();
+
+        Self { clock, origin }
+    }
+}
+
+impl<C: Clock> Ticks for ClockTicks<C> {
+    #[inline(always)]
+    fn ticks(&self) -> u64 {
+        self.clock
+            .now()
+            .saturating_duration_since(self.origin)
+            .as_nanos() as u64
+    }
+}
+
+pub type InstrumentedHalves<T, K = ClockTicks> =
+    (InstrumentedWriter<T, K>, InstrumentedReader<T, K>);
+
+/// Creates a ring that records how long each element waited between
+/// [`InstrumentedWriter::write`] and [`InstrumentedReader::read`], in
+/// nanoseconds.
+///
+/// The stamps travel in a second ring of the same capacity, so `T` is
+/// unchanged and the plain [`RingBuffer`] halves pay nothing for this.
+pub fn instrumented<T: Copy>(capacity: usize) -> Result<InstrumentedHalves<T>, RingBufferError> {
+    instrumented_with_ticks(capacity, ClockTicks::default())
+}
+
+/// Like [`instrumented`], but stamps elements with `ticks`. The delays in
+/// the histogram are in its unit.
+pub fn instrumented_with_ticks<T: Copy, K: Ticks + Clone>(
+    capacity: usize,
+    ticks: K,
+) -> Result<InstrumentedHalves<T, K>, RingBufferError> {
+    let (writer, reader) = RingBuffer::new(capacity)?;
+    let (stamp_writer, stamp_reader) = RingBuffer::new(capacity)?;
+
+    Ok((
+        InstrumentedWriter {
+            writer,
+            stamps: stamp_writer,
+            ticks: ticks.clone(),
+        },
+        InstrumentedReader {
+            reader,
+            stamps: stamp_reader,
+            ticks,
+            histogram: [0; LATENCY_BUCKETS],
+            count: 0,
+            total: 0,
+            max: 0,
+        },
+    ))
+}
+
+pub struct InstrumentedWriter<T: Copy, K: Ticks = ClockTicks> {
+    writer: Writer<T>,
+    stamps: Writer<u64>,
+    ticks: K,
+}
+
+impl<T: Copy, K: Ticks> InstrumentedWriter<T, K> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.writer.capacity()
+    }
+
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.writer.is_disconnected()
+    }
+}
+
+impl<T: Copy, K: Ticks> BufferWriter<T> for InstrumentedWriter<T, K> {
+    /// Writes as much of `buffer` as fits, stamping every element with the
+    /// same tick. The stamps are published first, so the reader always
+    /// finds one for each element it takes.
+    fn write(&mut self, buffer: &[T]) -> usize {
+        let n = buffer
+            .len()
+            .min(self.writer.free_len())
+            .min(self.stamps.free_len());
+        if n == 0 {
+            return 0;
+        }
+
+        let stamp = self.ticks.ticks();
+        self.stamps.write_iter(std::iter::repeat(stamp).take(n));
+        self.writer.write(&buffer[..n])
+    }
+}
+
+pub struct InstrumentedReader<T: Copy, K: Ticks = ClockTicks> {
+    reader: Reader<T>,
+    stamps: Reader<u64>,
+    ticks: K,
+    histogram: [u64; LATENCY_BUCKETS],
+    count: u64,
+    total: u64,
+    max: u64,
+}
+
+impl<T: Copy, K: Ticks> InstrumentedReader<T, K> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.reader.capacity()
+    }
+
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.reader.is_disconnected()
+    }
+
+    /// The delays recorded so far.
+    pub fn snapshot(&self) -> LatencySnapshot {
+        LatencySnapshot {
+            buckets: self.histogram,
+            count: self.count,
+            total: self.total,
+            max: self.max,
+        }
+    }
+
+    /// Returns the delays recorded so far and starts over.
+    pub fn take_snapshot(&mut self) -> LatencySnapshot {
+        let snapshot = self.snapshot();
+        self.histogram = [0; LATENCY_BUCKETS];
+        self.count = 0;
+        self.total = 0;
+        self.max = 0;
+
+        snapshot
+    }
+
+    #[inline(always)]
+    fn record(&mut self, delay: u64) {
+        let bucket = (u64::BITS - delay.leading_zeros()) as usize;
+
+        self.histogram[bucket.min(LATENCY_BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.total = self.total.saturating_add(delay);
+        self.max = self.max.max(delay);
+    }
+}
+
+impl<T: Copy, K: Ticks> BufferReader<T> for InstrumentedReader<T, K> {
+    /// Reads as many elements as fit in `buffer` and records the delay of
+    /// each one.
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        let before = buffer.filled();
+        self.reader.read_buf(buffer);
+        let n = buffer.filled() - before;
+        if n == 0 {
+            return;
+        }
+
+        let now = self.ticks.ticks();
+        for _ in 0..n {
+            // Published before the element it belongs to.
+            let stamp = self.stamps.try_pop().unwrap_or(now);
+            self.record(now.saturating_sub(stamp));
+        }
+    }
+}
+
+/// A histogram of queueing delays, as returned by
+/// [`InstrumentedReader::snapshot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LatencySnapshot {
+    /// Element counts per power-of-two bucket, see [`LATENCY_BUCKETS`].
+    pub buckets: [u64; LATENCY_BUCKETS],
+    pub count: u64,
+    /// Sum of all recorded delays, saturating.
+    pub total: u64,
+    pub max: u64,
+}
+
+impl LatencySnapshot {
+    pub fn mean(&self) -> Option<u64> {
+        (self.count > 0).then(|| self.total / self.count)
+    }
+
+    /// Upper bound of the bucket holding the `quantile` (from 0 to 1) of
+    /// the recorded delays, or `None` if nothing was recorded.
+    pub fn quantile(&self, quantile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((self.count as f64 * quantile.clamp(0.0, 1.0)).ceil() as u64).max(1);
+        let mut seen = 0;
+        let bucket = self
+            .buckets
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(LATENCY_BUCKETS - 1);
+
+        Some(match bucket {
+            0 => 0,
+            bucket if bucket == LATENCY_BUCKETS - 1 => self.max,
+            bucket => (1 << bucket) - 1,
+        })
+    }
+}
@@ -1,2 +1,104 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod arena;
+#[cfg(feature = "std")]
+pub mod backend;
+#[cfg(feature = "std")]
+pub mod bip;
+#[cfg(feature = "std")]
+pub mod broadcast;
+#[cfg(feature = "std")]
+pub mod cancel;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod coalesce;
+#[cfg(feature = "lz4")]
+pub mod compress;
+#[cfg(feature = "std")]
+pub mod duplex;
+#[cfg(all(target_os = "linux", feature = "std"))]
+pub mod durable;
+#[cfg(feature = "std")]
+pub mod fan_in;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "async")]
+pub mod futures;
+#[cfg(feature = "alloc")]
+mod index;
+#[cfg(feature = "std")]
+pub mod latency;
+#[cfg(feature = "std")]
+pub mod local;
+#[cfg(feature = "std")]
+pub mod mailbox;
+#[cfg(feature = "std")]
+pub mod mpsc;
+#[cfg(all(unix, feature = "notify"))]
+mod notifier;
+#[cfg(feature = "std")]
+pub mod overwrite;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod recycle;
+#[cfg(feature = "std")]
+pub mod region;
+#[cfg(feature = "alloc")]
 pub mod ring_buffer;
+#[cfg(feature = "rkyv")]
+pub mod rkyv_channel;
+#[cfg(feature = "std")]
+pub mod seqring;
+#[cfg(feature = "serde")]
+pub mod serde_channel;
+#[cfg(all(target_os = "linux", feature = "shm"))]
+pub mod shm;
+#[cfg(feature = "std")]
+pub mod spill;
+#[cfg(feature = "alloc")]
+pub mod static_ring;
+#[cfg(feature = "alloc")]
+mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod throttle;
+#[cfg(feature = "std")]
+pub mod timed;
+#[cfg(feature = "tokio")]
+pub mod tokio_io;
+#[cfg(feature = "bytemuck")]
+pub mod typed_frame;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub mod uring;
+#[cfg(feature = "std")]
 pub mod vecdeque;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+pub use backend::{AnyRingBuffer, Backend};
+#[cfg(feature = "std")]
+pub use broadcast::broadcast;
+#[cfg(feature = "std")]
+pub use duplex::duplex;
+#[cfg(feature = "std")]
+pub use fan_in::fan_in;
+#[cfg(feature = "std")]
+pub use mailbox::mailbox;
+#[cfg(feature = "std")]
+pub use overwrite::ring_buffer_overwriting;
+#[cfg(feature = "std")]
+pub use recycle::recycle;
+#[cfg(feature = "alloc")]
+pub use ring_buffer::{ring_buffer_with_context, ring_buffer_with_watermarks};
+#[cfg(feature = "rkyv")]
+pub use rkyv_channel::rkyv_channel;
+#[cfg(feature = "serde")]
+pub use serde_channel::serde_channel;
@@ -0,0 +1,226 @@
+use std::{
+    cell::{Cell, UnsafeCell},
+    mem::MaybeUninit,
+    ptr,
+    rc::Rc,
+};
+
+use crate::{
+    index,
+    ring_buffer::{
+        BufferReader, BufferWriter, RawBufferReader, RawBufferWriter, ReadBuf, RingBufferError,
+    },
+};
+
+/// Creates a ring for passing data between tasks on the same thread. The
+/// indices are plain cells and the handles are `!Send`, so there is no
+/// atomic or fencing cost, and generic code over [`BufferWriter`] and
+/// [`BufferReader`] works unchanged.
+pub fn ring_buffer<T: Copy>(
+    capacity: usize,
+) -> Result<(LocalWriter<T>, LocalReader<T>), RingBufferError> {
+    if capacity == 0 {
+        return Err(RingBufferError::ZeroCapacity);
+    }
+    let wrap = index::index_wrap(capacity).ok_or(RingBufferError::CapacityOverflow)?;
+
+    let shared = Rc::new(Shared {
+        buffer: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        capacity,
+        wrap,
+        head: Cell::new(0),
+        tail: Cell::new(0),
+    });
+
+    Ok((
+        LocalWriter {
+            shared: shared.clone(),
+        },
+        LocalReader { shared },
+    ))
+}
+
+struct Shared<T: Copy> {
+    // Slots stay uninitialized until the writer fills them, and a slot is
+    // only read back once the head has moved past it.
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    capacity: usize,
+    // The indices wrap like those of the lock-free ring, see
+    // `index::index_wrap`.
+    wrap: usize,
+    head: Cell<usize>,
+    tail: Cell<usize>,
+}
+
+impl<T: Copy> Shared<T> {
+    // Points into the whole storage rather than at a single cell, so a run
+    // of slots up to the end of the storage can be copied through it.
+    #[inline(always)]
+    fn slot(&self, index: usize) -> *mut T {
+        let slots = self.buffer.as_ptr();
+        UnsafeCell::raw_get(slots.wrapping_add(index % self.capacity)).cast()
+    }
+}
+
+pub struct LocalWriter<T: Copy> {
+    shared: Rc<Shared<T>>,
+}
+
+impl<T: Copy> LocalWriter<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl<T: Copy> LocalWriter<T> {
+    // Number of free slots and the index of the first.
+    #[inline(always)]
+    fn free(&self) -> (usize, usize) {
+        let head_index = self.shared.head.get();
+        let tail_index = self.shared.tail.get();
+
+        let free = self.shared.capacity - index::distance(self.shared.wrap, tail_index, head_index);
+        (free, head_index)
+    }
+}
+
+impl<T: Copy> RawBufferWriter<T> for LocalWriter<T> {
+    #[inline(always)]
+    fn available(&self, size: usize) -> (usize, usize) {
+        let (available, head_index) = self.free();
+        if available >= size {
+            (size, head_index)
+        } else {
+            (0, head_index)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get_mut(&mut self, index: usize) -> &mut T {
+        unsafe { &mut *self.shared.slot(index) }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        let head = &self.shared.head;
+        head.set(index::offset_index(self.shared.wrap, head.get(), offset));
+    }
+}
+
+impl<T: Copy> BufferWriter<T> for LocalWriter<T> {
+    /// Writes as much of `buffer` as currently fits and returns the number
+    /// of elements written, like the lock-free ring.
+    #[inline(always)]
+    fn write(&mut self, buffer: &[T]) -> usize {
+        let (free, index) = self.free();
+        let len = buffer.len().min(free);
+        let start = index % self.shared.capacity;
+        let first = len.min(self.shared.capacity - start);
+
+        // The `len` free slots run from `start` to at most the end of the
+        // storage and carry on from its front, and they are published only
+        // once both runs are written.
+        unsafe {
+            ptr::copy_nonoverlapping(buffer.as_ptr(), self.shared.slot(start), first);
+            ptr::copy_nonoverlapping(buffer[first..].as_ptr(), self.shared.slot(0), len - first);
+            self.advance_index(len);
+        }
+
+        len
+    }
+}
+
+pub struct LocalReader<T: Copy> {
+    shared: Rc<Shared<T>>,
+}
+
+impl<T: Copy> LocalReader<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl<T: Copy> LocalReader<T> {
+    // Number of queued elements and the index of the oldest.
+    #[inline(always)]
+    fn queued(&self) -> (usize, usize) {
+        let head_index = self.shared.head.get();
+        let tail_index = self.shared.tail.get();
+
+        (
+            index::distance(self.shared.wrap, tail_index, head_index),
+            tail_index,
+        )
+    }
+}
+
+impl<T: Copy> RawBufferReader<T> for LocalReader<T> {
+    #[inline(always)]
+    fn filled(&self, size: usize) -> (usize, usize) {
+        let (filled, tail_index) = self.queued();
+        if filled >= size {
+            (size, tail_index)
+        } else {
+            (0, tail_index)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get(&self, index: usize) -> &T {
+        unsafe { &*self.shared.slot(index) }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        let tail = &self.shared.tail;
+        tail.set(index::offset_index(self.shared.wrap, tail.get(), offset));
+    }
+}
+
+impl<T: Copy> BufferReader<T> for LocalReader<T> {
+    /// Reads as many queued elements as fit in `buffer`.
+    #[inline(always)]
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        let (queued, index) = self.queued();
+        let len = buffer.remaining().min(queued);
+        let start = index % self.shared.capacity;
+        let first = len.min(self.shared.capacity - start);
+
+        // The `len` queued elements run from `start` to at most the end of
+        // the storage and carry on from its front, and their slots are
+        // released only once both runs are copied out.
+        unsafe {
+            let out = buffer.unfilled_mut().as_mut_ptr().cast::<T>();
+            ptr::copy_nonoverlapping(self.shared.slot(start), out, first);
+            ptr::copy_nonoverlapping(self.shared.slot(0), out.add(first), len - first);
+            buffer.advance(len);
+            self.advance_index(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfers_take_what_fits_across_the_wrap() {
+        let (mut writer, mut reader) = ring_buffer::<u64>(5).unwrap();
+        let mut buffer = [0; 8];
+
+        assert_eq!(writer.write(&[1, 2, 3]), 3);
+        assert_eq!(reader.read(&mut buffer[..2]), 2);
+        // Two slots are left before the end of the storage and two after
+        // its front, so only four of the six fit.
+        assert_eq!(writer.write(&[4, 5, 6, 7, 8, 9]), 4);
+        assert_eq!(writer.write(&[9]), 0);
+
+        assert_eq!(reader.read(&mut buffer), 5);
+        assert_eq!(buffer[..5], [3, 4, 5, 6, 7]);
+        assert_eq!(reader.read(&mut buffer), 0);
+    }
+}
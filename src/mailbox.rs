@@ -0,0 +1,283 @@
+use std::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use crate::ring_buffer::{
+    Backoff, BufferReader, BufferWriter, RawBufferReader, RawBufferWriter, ReadBuf,
+};
+
+const EMPTY: u8 = 0;
+const FULL: u8 = 1;
+
+/// Creates a single-slot channel for handing one value over at a time.
+///
+/// This is what a ring with capacity 1 is used for, without the index
+/// arithmetic: the slot is guarded by one state word that flips between
+/// empty and full, so a hand-over costs one store on each side. Both
+/// halves also implement [`BufferWriter`]/[`BufferReader`] with a capacity
+/// of one element, so generic code over the ring works unchanged.
+pub fn mailbox<T: Copy>() -> (MailboxWriter<T>, MailboxReader<T>) {
+    let shared = Arc::new(Shared {
+        slot: UnsafeCell::new(MaybeUninit::uninit()),
+        state: EMPTY.into(),
+        disconnected: false.into(),
+    });
+
+    (
+        MailboxWriter {
+            shared: shared.clone(),
+        },
+        MailboxReader { shared },
+    )
+}
+
+struct Shared<T> {
+    // Holds a value only while `state` is full.
+    slot: UnsafeCell<MaybeUninit<T>>,
+    state: AtomicU8,
+    disconnected: AtomicBool,
+}
+
+// `state` hands the slot back and forth: only the side it names may touch
+// the `UnsafeCell`, and the hand-over is a release/acquire pair.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    #[inline(always)]
+    fn is(&self, state: u8) -> bool {
+        self.state.load(Ordering::Acquire) == state
+    }
+
+    #[inline(always)]
+    fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::Acquire)
+    }
+}
+
+pub struct MailboxWriter<T: Copy> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Copy> MailboxWriter<T> {
+    /// Returns `true` once the reader has been dropped.
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.is_disconnected()
+    }
+
+    /// Places `value` in the slot, or hands it back if the previous value
+    /// has not been taken yet.
+    #[inline(always)]
+    pub fn put(&mut self, value: T) -> Result<(), T> {
+        if !self.shared.is(EMPTY) {
+            return Err(value);
+        }
+
+        unsafe { (*self.shared.slot.get()).write(value) };
+        self.shared.state.store(FULL, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Waits until the slot is empty and places `value` in it. Hands the
+    /// value back if the reader disconnects first.
+    pub fn put_blocking(&mut self, mut value: T) -> Result<(), T> {
+        let mut backoff = Backoff::new();
+
+        loop {
+            if self.is_disconnected() {
+                return Err(value);
+            }
+            match self.put(value) {
+                Ok(()) => return Ok(()),
+                Err(rejected) => value = rejected,
+            }
+            backoff.wait();
+        }
+    }
+}
+
+impl<T: Copy> Drop for MailboxWriter<T> {
+    fn drop(&mut self) {
+        self.shared.disconnected.store(true, Ordering::Release);
+    }
+}
+
+impl<T: Copy> RawBufferWriter<T> for MailboxWriter<T> {
+    #[inline(always)]
+    fn available(&self, size: usize) -> (usize, usize) {
+        if size <= 1 && self.shared.is(EMPTY) {
+            (size, 0)
+        } else {
+            (0, 0)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get_mut(&mut self, _index: usize) -> &mut T {
+        // Only meaningful after `available` reported the slot empty; the
+        // reader does not touch it until the state flips back to full.
+        unsafe { &mut *self.shared.slot.get().cast() }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        if offset > 0 {
+            self.shared.state.store(FULL, Ordering::Release);
+        }
+    }
+}
+
+// Like a ring of capacity one, a longer buffer transfers its first element.
+impl<T: Copy> BufferWriter<T> for MailboxWriter<T> {
+    #[inline(always)]
+    fn write(&mut self, buffer: &[T]) -> usize {
+        match buffer {
+            [value, ..] => self.put(*value).map_or(0, |()| 1),
+            [] => 0,
+        }
+    }
+}
+
+pub struct MailboxReader<T: Copy> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Copy> MailboxReader<T> {
+    /// Returns `true` once the writer has been dropped. A value it put
+    /// before that may still be waiting in the slot.
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.is_disconnected()
+    }
+
+    /// Takes the value out of the slot, if there is one.
+    #[inline(always)]
+    pub fn take(&mut self) -> Option<T> {
+        if !self.shared.is(FULL) {
+            return None;
+        }
+
+        // The writer filled the slot before flipping the state to full.
+        let value = unsafe { (*self.shared.slot.get()).assume_init() };
+        self.shared.state.store(EMPTY, Ordering::Release);
+
+        Some(value)
+    }
+
+    /// Waits for a value and takes it. Returns `None` once the writer has
+    /// disconnected and the slot is empty.
+    pub fn take_blocking(&mut self) -> Option<T> {
+        let mut backoff = Backoff::new();
+
+        loop {
+            let disconnected = self.is_disconnected();
+            if let Some(value) = self.take() {
+                return Some(value);
+            }
+            if disconnected {
+                return None;
+            }
+            backoff.wait();
+        }
+    }
+}
+
+impl<T: Copy> Drop for MailboxReader<T> {
+    fn drop(&mut self) {
+        self.shared.disconnected.store(true, Ordering::Release);
+    }
+}
+
+impl<T: Copy> RawBufferReader<T> for MailboxReader<T> {
+    #[inline(always)]
+    fn filled(&self, size: usize) -> (usize, usize) {
+        if size <= 1 && self.shared.is(FULL) {
+            (size, 0)
+        } else {
+            (0, 0)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get(&self, _index: usize) -> &T {
+        // Only meaningful after `filled` reported the slot full.
+        unsafe { &*self.shared.slot.get().cast() }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        if offset > 0 {
+            self.shared.state.store(EMPTY, Ordering::Release);
+        }
+    }
+}
+
+impl<T: Copy> BufferReader<T> for MailboxReader<T> {
+    #[inline(always)]
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        if buffer.remaining() > 0 {
+            if let Some(value) = self.take() {
+                let _ = buffer.push(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hands_over_values_with_no_all_zero_form() {
+        // References may not be null, so an all-zero slot would not be a
+        // valid `&str` even before anything is put in it.
+        let (mut writer, mut reader) = mailbox::<&'static str>();
+        assert_eq!(reader.take(), None);
+
+        assert_eq!(writer.put("first"), Ok(()));
+        assert_eq!(writer.put("second"), Err("second"));
+        assert_eq!(reader.take(), Some("first"));
+        assert_eq!(reader.take(), None);
+
+        let mut buffer = [""];
+        assert_eq!(writer.write(&["third"]), 1);
+        assert_eq!(reader.read(&mut buffer), 1);
+        assert_eq!(buffer, ["third"]);
+    }
+
+    #[test]
+    fn blocking_take_ends_after_the_last_value() {
+        let (mut writer, mut reader) = mailbox::<u64>();
+
+        let producer = std::thread::spawn(move || {
+            for value in 0..1000 {
+                writer.put_blocking(value).unwrap();
+            }
+        });
+
+        let received: Vec<u64> = std::iter::from_fn(|| reader.take_blocking()).collect();
+        producer.join().unwrap();
+        assert_eq!(received, (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn longer_buffers_move_one_value_at_a_time() {
+        let (mut writer, mut reader) = mailbox::<u64>();
+        let mut buffer = [0; 3];
+
+        assert_eq!(writer.write(&[]), 0);
+        assert_eq!(writer.write(&[1, 2, 3]), 1);
+        assert_eq!(writer.write(&[2, 3]), 0);
+        assert_eq!(reader.read(&mut buffer), 1);
+        assert_eq!(buffer[0], 1);
+        assert_eq!(reader.read(&mut buffer), 0);
+        assert_eq!(reader.read(&mut []), 0);
+    }
+}
@@ -0,0 +1,147 @@
+use alloc::sync::Arc;
+
+use crate::{
+    ring_buffer::{CachePadded, Reader, RingBuffer, RingBufferError, Writer},
+    sync::AtomicUsize,
+};
+
+/// Creates a ring with any number of producers and a single consumer.
+/// [`Sender`] can be cloned and shared between threads, and the consumer
+/// is the usual [`Reader`], which sees the writer as disconnected once
+/// every sender is gone.
+///
+/// Producers reserve slots by moving a shared claim index forward with a
+/// compare-and-swap, fill them, and then publish in claim order, so each
+/// producer's elements arrive in the order it sent them. A producer that
+/// stalls between claiming and publishing holds back the ones that
+/// claimed after it. The single-producer [`Writer`] pays for none of this.
+pub fn channel<T: Copy>(capacity: usize) -> Result<(Sender<T>, Reader<T>), RingBufferError> {
+    let (writer, reader) = RingBuffer::new(capacity)?;
+    let sender = Sender {
+        shared: Arc::new(Shared {
+            writer,
+            claim: CachePadded(AtomicUsize::new(0)),
+        }),
+    };
+
+    Ok((sender, reader))
+}
+
+struct Shared<T> {
+    // Never written through directly, only with `Writer::write_shared`. It
+    // counts as the one writer of the ring until the last sender is dropped.
+    writer: Writer<T>,
+    claim: CachePadded<AtomicUsize>,
+}
+
+// The writer's own state is not touched by `write_shared`, which is all
+// the senders use, so the writer can be shared between them.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: Copy> Sender<T> {
+    /// Writes as much of `buffer` as currently fits and returns the number
+    /// of elements written, like [`crate::ring_buffer::BufferWriter::write`].
+    /// The elements written by one call stay together in the ring.
+    #[inline(always)]
+    pub fn write(&self, buffer: &[T]) -> usize {
+        self.shared.writer.write_shared(&self.shared.claim, buffer)
+    }
+
+    /// Writes one element, or hands it back if the ring is full.
+    #[inline(always)]
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        match self.write(&[value]) {
+            0 => Err(value),
+            _ => Ok(()),
+        }
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.writer.capacity()
+    }
+
+    /// Returns `true` once the reader has been dropped.
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.writer.is_disconnected()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::ring_buffer::BufferReader;
+
+    const PRODUCERS: u64 = 4;
+    const PER_PRODUCER: u64 = 1 << 20;
+
+    // Tags each value with its producer in the upper half, so the consumer
+    // can check every producer's sequence on its own.
+    fn tagged(producer: u64, sequence: u64) -> u64 {
+        producer << 32 | sequence
+    }
+
+    #[test]
+    fn every_producer_arrives_whole_and_in_order() {
+        let (sender, mut reader) = channel::<u64>(256).unwrap();
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|producer| {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    let mut sequence = 0;
+                    while sequence < PER_PRODUCER {
+                        // Bursts of varying length, so claims of different
+                        // sizes interleave.
+                        let len = (sequence % 7 + 1).min(PER_PRODUCER - sequence);
+                        let burst: Vec<_> = (sequence..sequence + len)
+                            .map(|sequence| tagged(producer, sequence))
+                            .collect();
+                        match sender.write(&burst) {
+                            0 => thread::yield_now(),
+                            n => sequence += n as u64,
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let mut next = [0; PRODUCERS as usize];
+        let mut buffer = [0; 64];
+        loop {
+            let finished = reader.is_disconnected();
+            match reader.read(&mut buffer) {
+                0 if finished => break,
+                0 => thread::yield_now(),
+                n => {
+                    for &value in &buffer[..n] {
+                        let producer = (value >> 32) as usize;
+                        assert_eq!(value & 0xffff_ffff, next[producer], "producer {producer}");
+                        next[producer] += 1;
+                    }
+                }
+            }
+        }
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(next, [PER_PRODUCER; PRODUCERS as usize]);
+    }
+}
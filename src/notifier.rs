@@ -0,0 +1,87 @@
+//! A file descriptor that becomes readable when the ring has news for the
+//! reader, so it can be waited on with `epoll`, `poll` or a reactor.
+
+#[cfg(target_os = "linux")]
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::{
+    io,
+    os::fd::{AsRawFd, RawFd},
+};
+#[cfg(not(target_os = "linux"))]
+use std::{
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+};
+
+/// An `eventfd` on Linux and a non-blocking socket pair elsewhere. Both
+/// stay readable from the first signal until drained, however many
+/// signals came in between.
+pub(crate) struct Notifier {
+    #[cfg(target_os = "linux")]
+    fd: OwnedFd,
+    #[cfg(not(target_os = "linux"))]
+    pipe: (UnixStream, UnixStream),
+}
+
+impl Notifier {
+    #[cfg(target_os = "linux")]
+    pub(crate) fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn new() -> io::Result<Self> {
+        let (receiver, sender) = UnixStream::pair()?;
+        receiver.set_nonblocking(true)?;
+        sender.set_nonblocking(true)?;
+
+        Ok(Self {
+            pipe: (receiver, sender),
+        })
+    }
+
+    /// Makes the descriptor readable. A full counter or pipe means it
+    /// already is, so failures are ignored.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn signal(&self) {
+        let one = 1u64;
+        unsafe { libc::write(self.fd.as_raw_fd(), (&one as *const u64).cast(), 8) };
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn signal(&self) {
+        let _ = (&self.pipe.1).write(&[1]);
+    }
+
+    /// Consumes every pending signal, leaving the descriptor unreadable.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn drain(&self) {
+        let mut count = 0u64;
+        unsafe { libc::read(self.fd.as_raw_fd(), (&mut count as *mut u64).cast(), 8) };
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn drain(&self) {
+        let mut buffer = [0; 64];
+        while matches!((&self.pipe.0).read(&mut buffer), Ok(n) if n > 0) {}
+    }
+}
+
+impl AsRawFd for Notifier {
+    #[cfg(target_os = "linux")]
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn as_raw_fd(&self) -> RawFd {
+        self.pipe.0.as_raw_fd()
+    }
+}
@@ -0,0 +1,303 @@
+use std::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr,
+    sync::{
+        atomic::{fence, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::ring_buffer::RingBufferError;
+
+/// Creates a ring whose writer never waits: once the ring is full, each new
+/// element replaces the oldest unread one, like a flight recorder.
+///
+/// The guarantees are weaker than those of [`crate::ring_buffer`]:
+///
+/// - The reader sees a subsequence of what was written, in order. Elements the
+///   writer laps before they are read are skipped, and
+///   [`OverwritingReader::overwritten`] counts them.
+/// - A reader that falls behind resumes from the most recent `capacity`
+///   elements, so after the writer stops it reads exactly the last `capacity`
+///   elements written.
+/// - Values are never torn. Each slot is guarded like a seqlock: the reader
+///   copies it out, then checks that the writer did not touch the slot
+///   meanwhile, and treats it as overwritten if it did.
+/// - Against a writer that laps it continuously, the reader keeps skipping
+///   ahead and may return nothing for as long as that lasts.
+///
+/// Positions and stamps are 64-bit, so they never wrap in practice.
+pub fn ring_buffer_overwriting<T: Copy>(
+    capacity: usize,
+) -> Result<(OverwritingWriter<T>, OverwritingReader<T>), RingBufferError> {
+    if capacity == 0 {
+        return Err(RingBufferError::ZeroCapacity);
+    }
+
+    let slots = (0..capacity)
+        .map(|_| Slot {
+            stamp: AtomicU64::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        })
+        .collect();
+    let shared = Arc::new(Shared { slots, capacity });
+
+    Ok((
+        OverwritingWriter {
+            shared: shared.clone(),
+            head: 0,
+        },
+        OverwritingReader {
+            shared,
+            tail: 0,
+            overwritten: 0,
+        },
+    ))
+}
+
+// The writer stamps the slot for position `p` with `2p + 1` while it copies
+// the value in and with `2p + 2` once it is done, so the reader expecting
+// position `p` only accepts a slot stamped `2p + 2`. The initial stamp of 0
+// is below every published one.
+struct Slot<T> {
+    stamp: AtomicU64,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+struct Shared<T> {
+    slots: Box<[Slot<T>]>,
+    capacity: usize,
+}
+
+// The writer may overwrite a slot the reader is copying, but the stamps
+// above tell the reader when that happened, and a torn copy is thrown away
+// rather than returned. Values only cross threads by copy, hence `T: Send`.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    #[inline(always)]
+    fn slot(&self, position: u64) -> &Slot<T> {
+        let index = (position % self.capacity as u64) as usize;
+
+        // Safety: the remainder is below the capacity, the slice length.
+        unsafe { self.slots.get_unchecked(index) }
+    }
+}
+
+#[inline(always)]
+fn published(position: u64) -> u64 {
+    2 * position + 2
+}
+
+// The position a stamp was written for, whether the write finished or not.
+#[inline(always)]
+fn stamped_position(stamp: u64) -> u64 {
+    (stamp - 1) / 2
+}
+
+pub struct OverwritingWriter<T: Copy> {
+    shared: Arc<Shared<T>>,
+    head: u64,
+}
+
+impl<T: Copy> OverwritingWriter<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Appends `value`, replacing the oldest element if the ring is full.
+    #[inline(always)]
+    pub fn push(&mut self, value: T) {
+        let position = self.head;
+        let slot = self.shared.slot(position);
+
+        slot.stamp.store(published(position) - 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        // Safety: only this writer stores into slots. A reader may copy the
+        // slot concurrently, but it reads it as `MaybeUninit<T>` and throws
+        // the copy away once it sees the stamp changed.
+        unsafe { ptr::write_volatile(slot.value.get(), MaybeUninit::new(value)) };
+        slot.stamp.store(published(position), Ordering::Release);
+
+        self.head = position + 1;
+    }
+
+    /// Appends every element of `buffer` in order. Only the last `capacity`
+    /// of them can still be read afterwards if the reader is not keeping up.
+    pub fn write(&mut self, buffer: &[T]) {
+        for value in buffer {
+            self.push(*value);
+        }
+    }
+}
+
+pub struct OverwritingReader<T: Copy> {
+    shared: Arc<Shared<T>>,
+    tail: u64,
+    overwritten: u64,
+}
+
+impl<T: Copy> OverwritingReader<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Total number of elements the writer replaced before they were read.
+    pub fn overwritten(&self) -> u64 {
+        self.overwritten
+    }
+
+    /// Takes the oldest element still in the ring, skipping any the writer
+    /// has overwritten.
+    pub fn try_pop(&mut self) -> Option<T> {
+        loop {
+            let position = self.tail;
+            let slot = self.shared.slot(position);
+
+            let before = slot.stamp.load(Ordering::Acquire);
+            if before < published(position) {
+                return None;
+            }
+
+            if before == published(position) {
+                // Safety: the copy is only trusted if the stamp is unchanged
+                // afterwards, meaning the writer did not touch the slot while
+                // it was taken, so it holds the value published for
+                // `position`.
+                let value = unsafe { ptr::read_volatile(slot.value.get()) };
+                fence(Ordering::Acquire);
+                let after = slot.stamp.load(Ordering::Relaxed);
+
+                if after == before {
+                    self.tail = position + 1;
+
+                    return Some(unsafe { value.assume_init() });
+                }
+
+                self.skip_to(after);
+            } else {
+                self.skip_to(before);
+            }
+        }
+    }
+
+    /// Reads as many elements as fit in `buffer`, skipping any the writer
+    /// has overwritten, and returns the number of elements read.
+    pub fn read(&mut self, buffer: &mut [T]) -> usize {
+        let mut read = 0;
+
+        while read < buffer.len() {
+            match self.try_pop() {
+                Some(value) => {
+                    buffer[read] = value;
+                    read += 1;
+                }
+                None => break,
+            }
+        }
+
+        read
+    }
+
+    // The slot at `tail` carries `stamp`, left by the writer at a later
+    // position that maps to the same slot. Only the last `capacity` positions
+    // up to that one can still be in the ring, so reading resumes at the
+    // oldest of them.
+    #[cold]
+    fn skip_to(&mut self, stamp: u64) {
+        let position = stamped_position(stamp);
+        let tail = position + 1 - self.capacity() as u64;
+
+        self.overwritten += tail - self.tail;
+        self.tail = tail;
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_sees_the_last_capacity_elements_after_ten_laps() {
+        for capacity in [1, 3, 8] {
+            let (mut writer, mut reader) = ring_buffer_overwriting::<u64>(capacity).unwrap();
+            let total = 10 * capacity as u64;
+            for value in 0..total {
+                writer.push(value);
+            }
+
+            let mut buffer = vec![0; 2 * capacity];
+            assert_eq!(reader.read(&mut buffer), capacity);
+            let expected: Vec<u64> = (total - capacity as u64..total).collect();
+            assert_eq!(buffer[..capacity], expected[..]);
+            assert_eq!(reader.overwritten(), total - capacity as u64);
+            assert_eq!(reader.try_pop(), None);
+        }
+    }
+
+    #[test]
+    fn a_reader_that_keeps_up_loses_nothing() {
+        let (mut writer, mut reader) = ring_buffer_overwriting::<u64>(4).unwrap();
+
+        for round in 0..10 {
+            writer.write(&[3 * round, 3 * round + 1, 3 * round + 2]);
+            let mut buffer = [0; 3];
+            assert_eq!(reader.read(&mut buffer), 3);
+            assert_eq!(buffer, [3 * round, 3 * round + 1, 3 * round + 2]);
+        }
+        assert_eq!(reader.overwritten(), 0);
+    }
+
+    #[test]
+    fn a_partial_lap_skips_only_what_was_overwritten() {
+        let (mut writer, mut reader) = ring_buffer_overwriting::<u64>(4).unwrap();
+        writer.write(&[0, 1]);
+        assert_eq!(reader.try_pop(), Some(0));
+
+        // Position 1 is still unread when 5 lands on its slot.
+        writer.write(&[2, 3, 4, 5]);
+        let mut buffer = [0; 8];
+        assert_eq!(reader.read(&mut buffer), 4);
+        assert_eq!(buffer[..4], [2, 3, 4, 5]);
+        assert_eq!(reader.overwritten(), 1);
+    }
+
+    #[test]
+    fn concurrent_reads_are_in_order_and_never_torn() {
+        const TOTAL: u64 = 200_000;
+        let (mut writer, mut reader) = ring_buffer_overwriting::<[u64; 4]>(8).unwrap();
+
+        let producer = std::thread::spawn(move || {
+            for value in 0..TOTAL {
+                writer.push([value; 4]);
+            }
+        });
+
+        let mut last = None;
+        let mut received = 0;
+        while !producer.is_finished() {
+            if let Some(value) = reader.try_pop() {
+                assert!(
+                    value.iter().all(|&word| word == value[0]),
+                    "torn: {value:?}"
+                );
+                assert!(last < Some(value[0]));
+                last = Some(value[0]);
+                received += 1;
+            }
+        }
+        producer.join().unwrap();
+        while let Some(value) = reader.try_pop() {
+            assert!(last < Some(value[0]));
+            last = Some(value[0]);
+            received += 1;
+        }
+
+        assert_eq!(last, Some(TOTAL - 1));
+        assert_eq!(received + reader.overwritten(), TOTAL);
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    slice,
+    time::{Duration, Instant},
+};
+
+use pyo3::{
+    buffer::PyBuffer,
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+
+use crate::ring_buffer::{BufferReader, BufferWriter, Reader, RingBuffer, Writer};
+
+// Blocking calls release the GIL in slices of this length so that pending
+// signals (e.g. KeyboardInterrupt) are still delivered while waiting.
+const WAIT_SLICE: Duration = Duration::from_millis(10);
+
+#[pyclass]
+pub struct ByteWriter {
+    writer: Writer<u8>,
+}
+
+impl From<Writer<u8>> for ByteWriter {
+    fn from(writer: Writer<u8>) -> Self {
+        Self { writer }
+    }
+}
+
+#[pymethods]
+impl ByteWriter {
+    #[getter]
+    fn capacity(&self) -> usize {
+        self.writer.capacity()
+    }
+
+    fn write(&mut self, data: &[u8]) -> usize {
+        self.writer.write(data)
+    }
+
+    fn write_blocking(&mut self, py: Python<'_>, data: &[u8]) -> PyResult<usize> {
+        if data.len() > self.writer.capacity() {
+            return Err(PyValueError::new_err(
+                "data is larger than the ring capacity",
+            ));
+        }
+
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let n = py.allow_threads(|| wait(|| self.writer.write(data)));
+            if n > 0 {
+                return Ok(n);
+            }
+            py.check_signals()?;
+        }
+    }
+}
+
+#[pyclass]
+pub struct ByteReader {
+    reader: Reader<u8>,
+}
+
+impl From<Reader<u8>> for ByteReader {
+    fn from(reader: Reader<u8>) -> Self {
+        Self { reader }
+    }
+}
+
+#[pymethods]
+impl ByteReader {
+    #[getter]
+    fn capacity(&self) -> usize {
+        self.reader.capacity()
+    }
+
+    fn read<'py>(&mut self, py: Python<'py>, n: usize) -> Bound<'py, PyBytes> {
+        let mut buffer = vec![0; n];
+        let filled = self.reader.read(&mut buffer);
+
+        PyBytes::new_bound(py, &buffer[..filled])
+    }
+
+    fn read_into(&mut self, buffer: PyBuffer<u8>) -> PyResult<usize> {
+        if buffer.readonly() || !buffer.is_c_contiguous() {
+            return Err(PyValueError::new_err(
+                "expected a writable, C-contiguous buffer",
+            ));
+        }
+
+        // Safety: the buffer is writable and contiguous, and `PyBuffer` keeps
+        // the exporting object alive for the duration of the call.
+        let buffer = unsafe {
+            slice::from_raw_parts_mut(buffer.buf_ptr().cast::<u8>(), buffer.item_count())
+        };
+
+        Ok(self.reader.read(buffer))
+    }
+
+    fn read_blocking<'py>(&mut self, py: Python<'py>, n: usize) -> PyResult<Bound<'py, PyBytes>> {
+        if n > self.reader.capacity() {
+            return Err(PyValueError::new_err("n is larger than the ring capacity"));
+        }
+
+        let mut buffer = vec![0; n];
+        if n == 0 {
+            return Ok(PyBytes::new_bound(py, &buffer));
+        }
+
+        loop {
+            let filled = py.allow_threads(|| wait(|| self.reader.read(&mut buffer)));
+            if filled > 0 {
+                return Ok(PyBytes::new_bound(py, &buffer[..filled]));
+            }
+            py.check_signals()?;
+        }
+    }
+}
+
+fn wait(mut transfer: impl FnMut() -> usize) -> usize {
+    let deadline = Instant::now() + WAIT_SLICE;
+
+    loop {
+        let n = transfer();
+        if n > 0 || Instant::now() >= deadline {
+            return n;
+        }
+        std::thread::yield_now();
+    }
+}
+
+#[pyfunction]
+fn byte_ring(capacity: usize) -> PyResult<(ByteWriter, ByteReader)> {
+    let (writer, reader) = RingBuffer::<u8>::new(capacity)
+        .map_err(|error| PyRuntimeError::new_err(format!("{error:?}")))?;
+
+    Ok((writer.into(), reader.into()))
+}
+
+#[pymodule]
+fn spsc(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<ByteWriter>()?;
+    m.add_class::<ByteReader>()?;
+    m.add_function(wrap_pyfunction!(byte_ring, m)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use std::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::ring_buffer::Backoff;
+
+/// Creates a pair of rings running in opposite directions for passing
+/// buffers around without allocating: filled buffers go from the producer
+/// to the consumer, and emptied ones come back for reuse.
+///
+/// `make` is called `n_buffers` times up front and the buffers start on the
+/// return path, so the producer's first [`Producer::obtain`] calls succeed
+/// immediately. Each direction has room for exactly `n_buffers`, so as long
+/// as only these buffers circulate, [`Producer::submit`] and
+/// [`Consumer::recycle`] never find their ring full.
+///
+/// When shutting down, each side first collects the buffers waiting for it.
+/// The side that leaves last also collects whatever the other side left
+/// queued, so [`Producer::shutdown`] and [`Consumer::shutdown`] between
+/// them return every buffer that is not held by user code.
+pub fn recycle<B: Send>(n_buffers: usize, make: impl Fn() -> B) -> (Producer<B>, Consumer<B>) {
+    assert!(n_buffers > 0, "n_buffers must not be zero");
+
+    let shared = Arc::new(Shared {
+        filled: Lane::new(n_buffers),
+        emptied: Lane::new(n_buffers),
+        live: 2.into(),
+    });
+    for _ in 0..n_buffers {
+        // Cannot fail: the lane has room for exactly `n_buffers`.
+        let _ = unsafe { shared.emptied.push(make()) };
+    }
+
+    (
+        Producer {
+            shared: shared.clone(),
+            left: false,
+        },
+        Consumer {
+            shared,
+            left: false,
+        },
+    )
+}
+
+// A bounded queue that moves values instead of copying them. Each lane has
+// one pushing and one popping side, except during shutdown, when the last
+// side to leave owns both ends.
+struct Lane<B> {
+    slots: Box<[UnsafeCell<MaybeUninit<B>>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+impl<B> Lane<B> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            head: 0.into(),
+            tail: 0.into(),
+        }
+    }
+
+    #[inline(always)]
+    fn slot(&self, index: usize) -> *mut MaybeUninit<B> {
+        self.slots[index % self.slots.len()].get()
+    }
+
+    /// Safety: only one thread may push at a time.
+    unsafe fn push(&self, value: B) -> Result<(), B> {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        if head.wrapping_sub(tail) == self.slots.len() {
+            return Err(value);
+        }
+
+        (*self.slot(head)).write(value);
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Safety: only one thread may pop at a time.
+    unsafe fn pop(&self) -> Option<B> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        let value = (*self.slot(tail)).assume_init_read();
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+        Some(value)
+    }
+
+    /// Safety: as for [`Lane::pop`].
+    unsafe fn drain_into(&self, buffers: &mut Vec<B>) {
+        while let Some(buffer) = self.pop() {
+            buffers.push(buffer);
+        }
+    }
+}
+
+struct Shared<B> {
+    filled: Lane<B>,
+    emptied: Lane<B>,
+    live: AtomicU8,
+}
+
+// Each lane has one pushing and one popping side, which only touch the slots
+// their index hands them, so buffers simply move between the two threads.
+unsafe impl<B: Send> Send for Shared<B> {}
+unsafe impl<B: Send> Sync for Shared<B> {}
+
+impl<B> Shared<B> {
+    #[inline(always)]
+    fn is_alone(&self) -> bool {
+        self.live.load(Ordering::Acquire) == 1
+    }
+
+    /// Marks one side as gone after it has drained `own`, the lane it pops
+    /// from. The last side out also drains `other`, since nobody else will.
+    unsafe fn leave(&self, own: &Lane<B>, other: &Lane<B>) -> Vec<B> {
+        let mut buffers = Vec::new();
+        own.drain_into(&mut buffers);
+
+        if self.live.fetch_sub(1, Ordering::AcqRel) == 1 {
+            own.drain_into(&mut buffers);
+            other.drain_into(&mut buffers);
+        }
+
+        buffers
+    }
+}
+
+pub struct Producer<B: Send> {
+    shared: Arc<Shared<B>>,
+    left: bool,
+}
+
+impl<B: Send> Producer<B> {
+    /// Takes an emptied buffer off the return path, if one is waiting.
+    #[inline(always)]
+    pub fn try_obtain(&mut self) -> Option<B> {
+        unsafe { self.shared.emptied.pop() }
+    }
+
+    /// Waits for an emptied buffer. Returns `None` once the consumer has
+    /// gone and no buffer is left on the return path.
+    pub fn obtain(&mut self) -> Option<B> {
+        let mut backoff = Backoff::new();
+
+        loop {
+            let alone = self.shared.is_alone();
+            if let Some(buffer) = self.try_obtain() {
+                return Some(buffer);
+            }
+            if alone {
+                return None;
+            }
+            backoff.wait();
+        }
+    }
+
+    /// Passes a filled buffer to the consumer. Hands it back only if the
+    /// ring is full, which takes more buffers than [`recycle`] created.
+    #[inline(always)]
+    pub fn submit(&mut self, buffer: B) -> Result<(), B> {
+        unsafe { self.shared.filled.push(buffer) }
+    }
+
+    /// Leaves the pair and returns the buffers waiting on the return path,
+    /// plus everything still queued in either direction if the consumer has
+    /// already left.
+    pub fn shutdown(mut self) -> Vec<B> {
+        self.left = true;
+
+        unsafe { self.shared.leave(&self.shared.emptied, &self.shared.filled) }
+    }
+}
+
+impl<B: Send> Drop for Producer<B> {
+    fn drop(&mut self) {
+        if !self.left {
+            unsafe { self.shared.leave(&self.shared.emptied, &self.shared.filled) };
+        }
+    }
+}
+
+pub struct Consumer<B: Send> {
+    shared: Arc<Shared<B>>,
+    left: bool,
+}
+
+impl<B: Send> Consumer<B> {
+    /// Takes a filled buffer, if one is waiting.
+    #[inline(always)]
+    pub fn try_receive(&mut self) -> Option<B> {
+        unsafe { self.shared.filled.pop() }
+    }
+
+    /// Waits for a filled buffer. Returns `None` once the producer has gone
+    /// and everything it submitted has been received.
+    pub fn receive(&mut self) -> Option<B> {
+        let mut backoff = Backoff::new();
+
+        loop {
+            let alone = self.shared.is_alone();
+            if let Some(buffer) = self.try_receive() {
+                return Some(buffer);
+            }
+            if alone {
+                return None;
+            }
+            backoff.wait();
+        }
+    }
+
+    /// Returns an emptied buffer to the producer for reuse. Hands it back
+    /// only if the ring is full, which takes more buffers than [`recycle`]
+    /// created.
+    #[inline(always)]
+    pub fn recycle(&mut self, buffer: B) -> Result<(), B> {
+        unsafe { self.shared.emptied.push(buffer) }
+    }
+
+    /// Leaves the pair and returns the filled buffers not yet received,
+    /// plus everything still queued in either direction if the producer has
+    /// already left.
+    pub fn shutdown(mut self) -> Vec<B> {
+        self.left = true;
+
+        unsafe { self.shared.leave(&self.shared.filled, &self.shared.emptied) }
+    }
+}
+
+impl<B: Send> Drop for Consumer<B> {
+    fn drop(&mut self) {
+        if !self.left {
+            unsafe { self.shared.leave(&self.shared.filled, &self.shared.emptied) };
+        }
+    }
+}
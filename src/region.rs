@@ -0,0 +1,280 @@
+use std::{
+    mem::{align_of, size_of},
+    ptr::NonNull,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::ring_buffer::RingBufferError;
+
+const MAGIC: u32 = u32::from_le_bytes(*b"SPSC");
+const VERSION: u32 = 1;
+
+/// Layout of the shared header at the start of a region. The slots follow
+/// at the first offset suitably aligned for the element type.
+#[repr(C)]
+struct Header {
+    magic: AtomicU32,
+    version: AtomicU32,
+    capacity: AtomicU32,
+    element_size: AtomicU32,
+    head: AtomicU32,
+    tail: AtomicU32,
+}
+
+/// Splits a caller-provided region into its header and slots and returns
+/// the number of slots that fit.
+unsafe fn layout<T>(
+    ptr: *mut u8,
+    len: usize,
+) -> Result<(NonNull<Header>, NonNull<T>, u32), RingBufferError> {
+    let header = NonNull::new(ptr.cast::<Header>()).ok_or(RingBufferError::InvalidRegion)?;
+    if ptr as usize % align_of::<Header>() != 0 || size_of::<T>() == 0 {
+        return Err(RingBufferError::InvalidRegion);
+    }
+
+    let slots_offset =
+        (ptr as usize + size_of::<Header>()).next_multiple_of(align_of::<T>()) - ptr as usize;
+    let capacity = len
+        .checked_sub(slots_offset)
+        .map(|slots_len| slots_len / size_of::<T>())
+        .and_then(|capacity| u32::try_from(capacity).ok())
+        .filter(|&capacity| capacity > 0)
+        .ok_or(RingBufferError::InvalidRegion)?;
+    let slots = NonNull::new_unchecked(ptr.add(slots_offset).cast::<T>());
+
+    Ok((header, slots, capacity))
+}
+
+/// Size of a region holding a header and `capacity` slots, for a region
+/// that starts page aligned, or `None` if that does not fit in a `usize`.
+#[cfg(all(target_os = "linux", feature = "shm"))]
+pub(crate) fn region_len<T>(capacity: usize) -> Option<usize> {
+    size_of::<Header>()
+        .next_multiple_of(align_of::<T>())
+        .checked_add(capacity.checked_mul(size_of::<T>())?)
+}
+
+/// Writes a fresh header into the region, making it an empty ring. Call this
+/// exactly once, before either side attaches.
+///
+/// # Safety
+///
+/// `ptr..ptr + len` must be valid for reads and writes for as long as any
+/// handle attached to it is alive, and nothing else may be using it.
+pub unsafe fn init<T: Copy>(ptr: *mut u8, len: usize) -> Result<(), RingBufferError> {
+    let (header, _, capacity) = layout::<T>(ptr, len)?;
+    let header = header.as_ref();
+
+    header.version.store(VERSION, Ordering::Relaxed);
+    header.capacity.store(capacity, Ordering::Relaxed);
+    header
+        .element_size
+        .store(size_of::<T>() as u32, Ordering::Relaxed);
+    header.head.store(0, Ordering::Relaxed);
+    header.tail.store(0, Ordering::Relaxed);
+    header.magic.store(MAGIC, Ordering::Release);
+
+    Ok(())
+}
+
+struct Region<T: Copy> {
+    header: NonNull<Header>,
+    slots: NonNull<T>,
+    capacity: u32,
+}
+
+impl<T: Copy> Region<T> {
+    unsafe fn attach(ptr: *mut u8, len: usize) -> Result<Self, RingBufferError> {
+        let (header, slots, capacity) = layout::<T>(ptr, len)?;
+        let view = header.as_ref();
+
+        if view.magic.load(Ordering::Acquire) != MAGIC
+            || view.version.load(Ordering::Relaxed) != VERSION
+            || view.capacity.load(Ordering::Relaxed) != capacity
+            || view.element_size.load(Ordering::Relaxed) != size_of::<T>() as u32
+        {
+            return Err(RingBufferError::InvalidRegion);
+        }
+
+        Ok(Self {
+            header,
+            slots,
+            capacity,
+        })
+    }
+
+    #[inline(always)]
+    fn header(&self) -> &Header {
+        unsafe { self.header.as_ref() }
+    }
+
+    #[inline(always)]
+    fn slot(&self, index: u32) -> *mut T {
+        unsafe { self.slots.as_ptr().add((index % self.capacity) as usize) }
+    }
+}
+
+/// Writer half of a ring living in caller-provided memory, e.g. a
+/// `SharedArrayBuffer`-backed wasm linear memory or a shared mapping.
+pub struct SharedWriter<T: Copy> {
+    region: Region<T>,
+}
+
+// The region is caller-provided memory that `init` requires to outlive every
+// handle, and the writer only stores into free slots, as in the heap ring.
+unsafe impl<T: Copy + Send> Send for SharedWriter<T> {}
+
+impl<T: Copy> SharedWriter<T> {
+    /// # Safety
+    ///
+    /// The region must have been set up with [`init`] for the same `T` and
+    /// stay valid while the handle is alive, and at most one writer may be
+    /// attached at a time.
+    pub unsafe fn attach(ptr: *mut u8, len: usize) -> Result<Self, RingBufferError> {
+        Ok(Self {
+            region: Region::attach(ptr, len)?,
+        })
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> u32 {
+        self.region.capacity
+    }
+
+    pub fn write(&mut self, buffer: &[T]) -> u32 {
+        let header = self.region.header();
+        let head_index = header.head.load(Ordering::Relaxed);
+        let tail_index = header.tail.load(Ordering::Acquire);
+
+        let available = self.region.capacity - head_index.wrapping_sub(tail_index);
+        let size = match u32::try_from(buffer.len()) {
+            Ok(size) if size > 0 && size <= available => size,
+            _ => return 0,
+        };
+
+        for (offset, value) in buffer.iter().enumerate() {
+            unsafe {
+                self.region
+                    .slot(head_index.wrapping_add(offset as u32))
+                    .write(*value)
+            };
+        }
+        header
+            .head
+            .store(head_index.wrapping_add(size), Ordering::Release);
+        notify(&header.head);
+
+        size
+    }
+
+    /// Waits until all of `buffer` fits and writes it. On wasm this parks
+    /// with `memory.atomic.wait32`, which browsers only allow on worker
+    /// threads; the main thread must use [`SharedWriter::write`].
+    pub fn write_blocking(&mut self, buffer: &[T]) -> u32 {
+        if buffer.is_empty() || buffer.len() > self.region.capacity as usize {
+            return 0;
+        }
+
+        loop {
+            let tail_index = self.region.header().tail.load(Ordering::Acquire);
+            match self.write(buffer) {
+                0 => wait(&self.region.header().tail, tail_index),
+                n => return n,
+            }
+        }
+    }
+}
+
+pub struct SharedReader<T: Copy> {
+    region: Region<T>,
+}
+
+// As for `SharedWriter`, with the reader only loading published slots.
+unsafe impl<T: Copy + Send> Send for SharedReader<T> {}
+
+impl<T: Copy> SharedReader<T> {
+    /// # Safety
+    ///
+    /// The region must have been set up with [`init`] for the same `T` and
+    /// stay valid while the handle is alive, and at most one reader may be
+    /// attached at a time.
+    pub unsafe fn attach(ptr: *mut u8, len: usize) -> Result<Self, RingBufferError> {
+        Ok(Self {
+            region: Region::attach(ptr, len)?,
+        })
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> u32 {
+        self.region.capacity
+    }
+
+    pub fn read(&mut self, buffer: &mut [T]) -> u32 {
+        let header = self.region.header();
+        let head_index = header.head.load(Ordering::Acquire);
+        let tail_index = header.tail.load(Ordering::Relaxed);
+
+        let filled = head_index.wrapping_sub(tail_index);
+        let size = match u32::try_from(buffer.len()) {
+            Ok(size) if size > 0 && size <= filled => size,
+            _ => return 0,
+        };
+
+        for (offset, value) in buffer.iter_mut().enumerate() {
+            *value = unsafe {
+                self.region
+                    .slot(tail_index.wrapping_add(offset as u32))
+                    .read()
+            };
+        }
+        header
+            .tail
+            .store(tail_index.wrapping_add(size), Ordering::Release);
+        notify(&header.tail);
+
+        size
+    }
+
+    /// Waits until `buffer` can be filled and reads into it. The same
+    /// main-thread restriction as [`SharedWriter::write_blocking`] applies.
+    pub fn read_blocking(&mut self, buffer: &mut [T]) -> u32 {
+        if buffer.is_empty() || buffer.len() > self.region.capacity as usize {
+            return 0;
+        }
+
+        loop {
+            let head_index = self.region.header().head.load(Ordering::Acquire);
+            match self.read(buffer) {
+                0 => wait(&self.region.header().head, head_index),
+                n => return n,
+            }
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "atomics"))]
+#[inline(always)]
+fn wait(atomic: &AtomicU32, current: u32) {
+    // Wake up at least once a millisecond in case a notification was missed.
+    unsafe {
+        core::arch::wasm32::memory_atomic_wait32(atomic.as_ptr().cast(), current as i32, 1_000_000);
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "atomics"))]
+#[inline(always)]
+fn notify(atomic: &AtomicU32) {
+    unsafe {
+        core::arch::wasm32::memory_atomic_notify(atomic.as_ptr().cast(), u32::MAX);
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "atomics")))]
+#[inline(always)]
+fn wait(_atomic: &AtomicU32, _current: u32) {
+    std::thread::yield_now();
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "atomics")))]
+#[inline(always)]
+fn notify(_atomic: &AtomicU32) {}
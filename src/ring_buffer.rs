@@ -1,246 +1,3747 @@
+use alloc::vec::Vec;
+#[cfg(feature = "async")]
+use core::task::Waker;
+use core::{
+    alloc::Layout,
+    cell::Cell,
+    marker::PhantomData,
+    mem::{ManuallyDrop, MaybeUninit},
+    num::NonZeroUsize,
+    ops::Deref,
+    ptr::{self, NonNull},
+    slice,
+};
+#[cfg(feature = "std")]
 use std::{
-    mem::MaybeUninit,
-    ptr::NonNull,
-    sync::{
-        atomic::{AtomicU32, Ordering},
-        Arc,
-    },
+    io,
+    sync::Mutex,
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+#[cfg(all(unix, feature = "notify"))]
+use std::{
+    os::fd::{AsRawFd, RawFd},
+    sync::OnceLock,
 };
 
-pub trait BufferWriter<T: Copy> {
-    fn available(&self, size: u32) -> (u32, u32);
+#[cfg(feature = "std")]
+use crate::cancel::CancelToken;
+#[cfg(all(unix, feature = "notify"))]
+use crate::notifier::Notifier;
+#[cfg(feature = "stats")]
+use crate::sync::AtomicU64;
+use crate::{
+    index,
+    sync::{fence, Arc, AtomicBool, AtomicUsize, Ordering},
+};
+
+#[cfg(feature = "stats")]
+pub const OCCUPANCY_BUCKETS: usize = 8;
+
+/// Totals kept by a ring built with the `stats` feature, as returned by
+/// [`Writer::stats`] and [`Reader::stats`]. The counters are read one by one
+/// while both sides keep going, so they need not add up at any one instant.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Elements published by the writer.
+    pub written: u64,
+    /// Elements consumed by the reader.
+    pub read: u64,
+    /// Calls to `write` or `try_push` that found the ring full.
+    pub write_failures: u64,
+    /// Calls to `read` or `try_pop` that found the ring empty.
+    pub read_failures: u64,
+    /// Highest occupancy seen right after a publish.
+    pub high_water: usize,
+}
+
+/// The sending side of any ring in this crate, lock-free or not, so that
+/// code generic over it can swap one implementation for another.
+pub trait BufferWriter<T: Copy> {
+    fn write(&mut self, buffer: &[T]) -> usize;
+}
+
+/// The receiving side of any ring in this crate, the counterpart of
+/// [`BufferWriter`].
+pub trait BufferReader<T: Copy> {
+    /// Reads as many queued elements as fit into the unfilled part of
+    /// `buffer`, which need not be initialized. This is what lets
+    /// [`Writer::write_from`] read straight into another ring's free slots.
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>);
+
+    fn read(&mut self, buffer: &mut [T]) -> usize {
+        let mut buffer = ReadBuf::from_init(buffer);
+        self.read_buf(&mut buffer);
+
+        buffer.filled()
+    }
+}
+
+/// Slot-level access for rings whose storage can be addressed directly,
+/// used to stage a write in place before publishing it. Rings behind a
+/// lock cannot hand out slots, so they only implement [`BufferWriter`].
+///
+/// Getting the indices wrong hands out slots the reader is using or
+/// publishes slots that were never written, so the methods that take one
+/// are `unsafe`. Safe code stages writes through [`Writer::claim`] instead.
+pub trait RawBufferWriter<T: Copy>: BufferWriter<T> {
+    /// Returns `(size, index)` if at least `size` slots are free from
+    /// `index` on, and `(0, index)` otherwise.
+    fn available(&self, size: usize) -> (usize, usize);
+
+    /// # Safety
+    ///
+    /// `index` must lie within a run returned by the last call to
+    /// [`RawBufferWriter::available`], not yet published.
+    unsafe fn get_mut(&mut self, index: usize) -> &mut T;
+
+    /// Publishes the next `offset` slots to the reader.
+    ///
+    /// # Safety
+    ///
+    /// `offset` must not exceed the run returned by the last call to
+    /// [`RawBufferWriter::available`], and every slot in it must have been
+    /// written.
+    unsafe fn advance_index(&mut self, offset: usize);
+}
+
+/// Slot-level access on the reading side, the counterpart of
+/// [`RawBufferWriter`]. Safe code reads in place through
+/// [`Reader::read_chunk`] instead.
+pub trait RawBufferReader<T: Copy>: BufferReader<T> {
+    /// Returns `(size, index)` if at least `size` elements are queued from
+    /// `index` on, and `(0, index)` otherwise.
+    fn filled(&self, size: usize) -> (usize, usize);
+
+    /// # Safety
+    ///
+    /// `index` must lie within a run returned by the last call to
+    /// [`RawBufferReader::filled`], not yet consumed.
+    unsafe fn get(&self, index: usize) -> &T;
+
+    /// Hands the next `offset` slots back to the writer.
+    ///
+    /// # Safety
+    ///
+    /// `offset` must not exceed the run returned by the last call to
+    /// [`RawBufferReader::filled`], and no reference from
+    /// [`RawBufferReader::get`] into it may be used afterwards.
+    unsafe fn advance_index(&mut self, offset: usize);
+}
+
+// The buffer traits must stay object safe so that handles can be stored as
+// `Box<dyn BufferWriter<T>>` / `Box<dyn BufferReader<T>>`.
+const _: Option<&dyn BufferWriter<u8>> = None;
+const _: Option<&dyn BufferReader<u8>> = None;
+const _: Option<&dyn RawBufferWriter<u8>> = None;
+const _: Option<&dyn RawBufferReader<u8>> = None;
+
+/// Storage for [`BufferReader::read_buf`] that need not be initialized,
+/// like [`WriteChunk`] on the writing side. It counts the leading slots
+/// written so far, and only ever through initialized values, so a reader
+/// cannot report a slot it never filled.
+pub struct ReadBuf<'a, T> {
+    slots: &'a mut [MaybeUninit<T>],
+    // Leading slots holding read values.
+    filled: usize,
+}
+
+impl<'a, T: Copy> ReadBuf<'a, T> {
+    pub fn new(slots: &'a mut [MaybeUninit<T>]) -> Self {
+        Self { slots, filled: 0 }
+    }
+
+    // Only initialized values are ever written through the buffer, so the
+    // slots stay valid as `T`s.
+    fn from_init(slots: &'a mut [T]) -> Self {
+        Self::new(unsafe { &mut *(slots as *mut [T] as *mut [MaybeUninit<T>]) })
+    }
+
+    /// The number of leading slots that hold read values.
+    #[inline(always)]
+    pub fn filled(&self) -> usize {
+        self.filled
+    }
+
+    /// The number of slots left to fill.
+    #[inline(always)]
+    pub fn remaining(&self) -> usize {
+        self.slots.len() - self.filled
+    }
+
+    /// Writes `values` after the slots filled so far and returns how many
+    /// fit.
+    pub fn fill(&mut self, values: &[T]) -> usize {
+        let n = values.len().min(self.remaining());
+
+        for (slot, value) in self.slots[self.filled..].iter_mut().zip(&values[..n]) {
+            slot.write(*value);
+        }
+        self.filled += n;
+
+        n
+    }
+
+    /// Writes one value after the slots filled so far, or hands it back if
+    /// every slot is filled.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        match self.slots.get_mut(self.filled) {
+            Some(slot) => {
+                slot.write(value);
+                self.filled += 1;
+                Ok(())
+            }
+            None => Err(value),
+        }
+    }
+
+    /// Returns the slots not filled yet, to copy into in bulk.
+    ///
+    /// # Safety
+    ///
+    /// Nothing but initialized values may be written into them, since the
+    /// buffer may be backed by a slice of `T`s.
+    #[inline(always)]
+    pub unsafe fn unfilled_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        &mut self.slots[self.filled..]
+    }
+
+    /// Counts the next `n` slots as filled.
+    ///
+    /// # Safety
+    ///
+    /// The first `n` slots of [`ReadBuf::unfilled_mut`] must have been
+    /// written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is more than [`ReadBuf::remaining`].
+    #[inline(always)]
+    pub unsafe fn advance(&mut self, n: usize) {
+        assert!(n <= self.remaining(), "filled more slots than there are");
+
+        self.filled += n;
+    }
+}
+
+pub struct RingBuffer<T, C = ()> {
+    inner: Arc<RingBufferInner<T, C>>,
+}
+
+struct RingBufferInner<T, C = ()> {
+    // The first of `capacity` slots. Both sides reach the slots through
+    // pointers derived from this, one slot or run at a time, and never
+    // through a reference to the whole storage, which would also cover the
+    // slots the other side is using.
+    buffer: NonNull<MaybeUninit<T>>,
+    storage: Storage,
+    layout: Layout,
+    context: C,
+    capacity: NonZeroUsize,
+    wrap: usize,
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    writers: AtomicUsize,
+    readers: AtomicUsize,
+    // Set by `Writer::close` after its last publish, so a reader that sees
+    // it also sees everything written before.
+    closed: AtomicBool,
+    // Parked in `Reader::read_exact` and woken by the writer, and the
+    // other way around.
+    reader_waiter: Waiter,
+    writer_waiter: Waiter,
+    wipe_on_read: bool,
+    watermarks: Option<Watermarks>,
+    // Raised by the writer and lowered by the reader as they cross the
+    // watermarks.
+    congested: AtomicBool,
+    #[cfg(feature = "stats")]
+    histogram: [AtomicU64; OCCUPANCY_BUCKETS],
+    #[cfg(feature = "stats")]
+    histogram_shift: u32,
+    // Each side only updates its own counters, on a line of their own.
+    #[cfg(feature = "stats")]
+    writer_counters: CachePadded<Counters>,
+    #[cfg(feature = "stats")]
+    reader_counters: CachePadded<Counters>,
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    locked: AtomicBool,
+}
+
+// Scales occupancies down so that a full ring lands in the last bucket.
+#[cfg(feature = "stats")]
+fn histogram_shift(capacity: usize) -> u32 {
+    (usize::BITS - capacity.saturating_sub(1).leading_zeros())
+        .saturating_sub(OCCUPANCY_BUCKETS.trailing_zeros())
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Watermarks {
+    high: usize,
+    low: usize,
+}
+
+#[cfg(feature = "stats")]
+#[derive(Default)]
+struct Counters {
+    transferred: AtomicU64,
+    failures: AtomicU64,
+    // Only kept by the writer.
+    high_water: AtomicUsize,
+}
+
+#[cfg(feature = "stats")]
+impl Counters {
+    // Like the indices, each counter has a single mutator, so a relaxed load
+    // and store does instead of a read-modify-write.
+    #[inline(always)]
+    fn add(counter: &AtomicU64, n: usize) {
+        counter.store(
+            counter.load(Ordering::Relaxed).wrapping_add(n as u64),
+            Ordering::Relaxed,
+        );
+    }
+
+    #[inline(always)]
+    fn record(&self, n: usize) {
+        Self::add(&self.transferred, n);
+    }
+
+    #[inline(always)]
+    fn record_failure(&self) {
+        Self::add(&self.failures, 1);
+    }
+}
+
+// Gives the value a cache line of its own. The writer stores to `head` and
+// the reader to `tail` on every transfer; sharing a line, each store would
+// also evict the line the other side is polling, along with the read-mostly
+// fields around them.
+#[repr(align(64))]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for CachePadded<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "std"))]
+impl<T, C> RingBufferInner<T, C> {
+    // Locked slots sit on pages of their own, see `Storage::layout`, so
+    // this never touches memory shared with other allocations. The header
+    // is left alone for the same reason.
+    fn locked_region(&self) -> (*const libc::c_void, usize) {
+        (self.buffer.as_ptr().cast_const().cast(), self.layout.size())
+    }
+
+    fn unlock_memory(&self) {
+        let (ptr, len) = self.locked_region();
+        unsafe { libc::munlock(ptr, len) };
+    }
+}
+
+impl<T, C> RingBufferInner<T, C> {
+    /// Overwrites `len` slots starting at `index` with zeros.
+    fn wipe_slots(&self, index: usize, len: usize) {
+        let start = index % self.capacity;
+        let first = len.min(self.capacity.get() - start);
+        let slots = self.buffer.as_ptr();
+
+        unsafe {
+            wipe(slots.add(start), first);
+            wipe(slots, len - first);
+        }
+    }
+}
+
+impl<T, C> Drop for RingBufferInner<T, C> {
+    fn drop(&mut self) {
+        // Elements still queued are owned by the ring; everything outside
+        // `[tail, head)` was either never written or already moved out.
+        if core::mem::needs_drop::<T>() {
+            let tail_index = self.tail.load(Ordering::Relaxed);
+            let head_index = self.head.load(Ordering::Relaxed);
+            let mut queued = head_index.wrapping_sub(tail_index);
+            if head_index < tail_index {
+                queued = queued.wrapping_add(self.wrap);
+            }
+
+            let slots = self.buffer.as_ptr();
+            for offset in 0..queued {
+                let slot = tail_index.wrapping_add(offset) % self.capacity;
+                unsafe { (*slots.add(slot)).assume_init_drop() };
+            }
+        }
+
+        if self.wipe_on_read {
+            self.wipe_slots(0, self.capacity.get());
+        }
+
+        #[cfg(all(target_os = "linux", feature = "std"))]
+        if self.locked.load(Ordering::Relaxed) {
+            self.unlock_memory();
+        }
+
+        // Both halves share this header through one `Arc`, so this runs
+        // exactly once, after the last handle is gone.
+        unsafe { self.storage.free(self.buffer, self.layout) };
+    }
+}
+
+/// Size of the huge pages asked for with [`RingBufferBuilder::huge_pages`],
+/// the default on x86-64 and AArch64 Linux.
+#[cfg(all(target_os = "linux", feature = "hugepages"))]
+pub const HUGE_PAGE_SIZE: usize = 2 << 20;
+
+/// Where the slots come from, set through [`RingBufferBuilder::align`],
+/// [`RingBufferBuilder::huge_pages`] and [`RingBufferBuilder::locked`], and
+/// kept so that the slots are freed the same way.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Storage {
+    // Minimum alignment of the first slot, or zero for that of `T`.
+    align: usize,
+    #[cfg(all(target_os = "linux", feature = "hugepages"))]
+    huge_pages: bool,
+    // `mlock` works on whole pages, and unlocking a page unlocks it for
+    // every allocation on it. Locked slots therefore get whole pages.
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    locked: bool,
+}
+
+impl Storage {
+    // Checked up front so that a size the allocator could never be asked
+    // for is told apart from the allocator turning down a valid one.
+    fn layout<T>(self, capacity: usize) -> Result<Layout, RingBufferError> {
+        let mut layout =
+            Layout::array::<T>(capacity).map_err(|_| RingBufferError::CapacityOverflow)?;
+        if self.align != 0 {
+            if !self.align.is_power_of_two() {
+                return Err(RingBufferError::InvalidAlignment);
+            }
+            // A mapping is only aligned to the page size.
+            #[cfg(all(target_os = "linux", feature = "hugepages"))]
+            if self.huge_pages && self.align > HUGE_PAGE_SIZE {
+                return Err(RingBufferError::InvalidAlignment);
+            }
+
+            layout = layout
+                .align_to(self.align)
+                .map_err(|_| RingBufferError::InvalidAlignment)?;
+        }
+
+        #[cfg(all(target_os = "linux", feature = "std"))]
+        if self.locked && layout.size() != 0 {
+            layout = layout
+                .align_to(page_size())
+                .map_err(|_| RingBufferError::CapacityOverflow)?
+                .pad_to_align();
+        }
+
+        Ok(layout)
+    }
+
+    // Slots stay uninitialized until the writer stores into them, so any
+    // `T` is fine, including types for which all-zero bytes are not a valid
+    // value.
+    fn allocate<T>(self, layout: Layout) -> Result<NonNull<MaybeUninit<T>>, RingBufferError> {
+        #[cfg(all(target_os = "linux", feature = "hugepages"))]
+        if self.huge_pages {
+            let ptr = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    huge_page_len(layout),
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_HUGETLB,
+                    -1,
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                let errno = std::io::Error::last_os_error()
+                    .raw_os_error()
+                    .unwrap_or_default();
+
+                return Err(RingBufferError::HugePagesUnavailable { errno });
+            }
+
+            return NonNull::new(ptr.cast()).ok_or(RingBufferError::Initialize);
+        }
+
+        // Nothing to allocate, but the pointer must still be aligned.
+        if layout.size() == 0 {
+            let dangling = ptr::null_mut::<u8>().wrapping_add(layout.align());
+
+            return NonNull::new(dangling.cast()).ok_or(RingBufferError::Initialize);
+        }
+        let ptr = unsafe { alloc::alloc::alloc(layout) };
+
+        NonNull::new(ptr.cast()).ok_or(RingBufferError::AllocationFailed)
+    }
+
+    /// Safety: `slots` must come from [`Storage::allocate`] with the same
+    /// `layout`, and must not be used afterwards.
+    unsafe fn free<T>(self, slots: NonNull<MaybeUninit<T>>, layout: Layout) {
+        #[cfg(all(target_os = "linux", feature = "hugepages"))]
+        if self.huge_pages {
+            libc::munmap(slots.as_ptr().cast(), huge_page_len(layout));
+            return;
+        }
+
+        if layout.size() != 0 {
+            alloc::alloc::dealloc(slots.as_ptr().cast(), layout);
+        }
+    }
+}
+
+// Mappings come in whole huge pages, and an empty one cannot be made.
+#[cfg(all(target_os = "linux", feature = "hugepages"))]
+fn huge_page_len(layout: Layout) -> usize {
+    layout.size().max(1).next_multiple_of(HUGE_PAGE_SIZE)
+}
+
+#[cfg(feature = "zeroize")]
+unsafe fn wipe<T>(slots: *mut T, len: usize) {
+    for offset in 0..len {
+        zeroize::zeroize_flat_type(slots.add(offset));
+    }
+}
+
+// Nothing reads the zeroed slots back, so plain stores could be dropped as
+// dead; a fence only orders them and does not stop that. Volatile stores
+// always happen, one byte at a time since `T` may have any alignment.
+#[cfg(not(feature = "zeroize"))]
+unsafe fn wipe<T>(slots: *mut T, len: usize) {
+    let bytes = slots.cast::<u8>();
+    for offset in 0..core::mem::size_of::<T>() * len {
+        core::ptr::write_volatile(bytes.add(offset), 0);
+    }
+}
+
+// The header is only reached through a raw pointer, which opts out of the
+// auto traits. A handle may move to another thread because each side only
+// touches the slots its index hands it, and elements cross threads by value,
+// hence `T: Send`. Both sides borrow the context at once and either may drop
+// it, hence `C: Send + Sync`. `Sync` is left out on purpose: the halves keep
+// their cached indices in `Cell`s and must not be used from two threads.
+unsafe impl<T: Send, C: Send + Sync> Send for RingBuffer<T, C> {}
+
+impl<T, C> core::fmt::Debug for RingBuffer<T, C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "capacity: {:?}, head: {:?}, tail: {:?}",
+            self.inner.capacity, self.inner.head, self.inner.tail,
+        )
+    }
+}
+
+impl<T, C> RingBuffer<T, C> {
+    // Shared by the `Debug` impls of both halves. Relaxed loads only, so
+    // formatting a handle never orders anything against the other side.
+    fn fmt_half(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+        name: &str,
+        peers: &AtomicUsize,
+    ) -> core::fmt::Result {
+        let tail = self.inner.tail.load(Ordering::Relaxed);
+        let head = self.inner.head.load(Ordering::Relaxed);
+
+        f.debug_struct(name)
+            .field("capacity", &self.capacity())
+            .field("head", &head)
+            .field("tail", &tail)
+            .field("len", &self.distance(tail, head).min(self.capacity()))
+            .field("disconnected", &(peers.load(Ordering::Relaxed) == 0))
+            .finish()
+    }
+}
+
+impl<T, C> Clone for RingBuffer<T, C> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> RingBuffer<T> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(capacity: usize) -> Result<(Writer<T>, Reader<T>), RingBufferError> {
+        Self::builder().capacity(capacity).build()
+    }
+
+    /// Starts a [`RingBufferBuilder`], for combining the options of the
+    /// other constructors.
+    pub fn builder() -> RingBufferBuilder<T> {
+        RingBufferBuilder::new()
+    }
+
+    /// Like [`RingBuffer::new`], but zeroes every slot as soon as the reader
+    /// has consumed it, before the slot is handed back to the writer, and
+    /// zeroes the whole ring when the last handle is dropped. Use this for
+    /// secrets that must not linger in the queue.
+    ///
+    /// Every read pays for an extra store per element, roughly doubling the
+    /// memory traffic on the consumer side. Only the ring's own storage is
+    /// wiped; clearing the buffers data is copied into is up to the caller.
+    /// With the `zeroize` feature the wipe goes through the `zeroize` crate.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new_wiping(capacity: usize) -> Result<(Writer<T>, Reader<T>), RingBufferError> {
+        Self::builder().capacity(capacity).wiping(true).build()
+    }
+
+    /// Like [`RingBuffer::new`], but pins the slot storage in RAM with
+    /// `mlock(2)` so it can never be swapped out. The slots are rounded up
+    /// to whole pages of their own, since locks apply to pages; the small
+    /// shared header stays on the heap and is not locked. The memory is
+    /// unlocked again when the last handle is dropped.
+    ///
+    /// Fails with [`RingBufferError::MemLockFailed`] if the pages cannot be
+    /// locked, typically because `RLIMIT_MEMLOCK` is too small.
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new_locked(capacity: usize) -> Result<(Writer<T>, Reader<T>), RingBufferError> {
+        Self::builder().capacity(capacity).locked(true).build()
+    }
+
+    /// Like [`RingBuffer::new`], but the reader also gets a file descriptor,
+    /// [`Reader::as_raw_fd`], that becomes readable when data arrives, for
+    /// consumers driven by `epoll`, `poll` or a reactor instead of a
+    /// blocking call. It is an `eventfd` on Linux and a socket pair on
+    /// other Unix systems.
+    ///
+    /// The writer signals it at most once per [`Reader::drain_notification`]:
+    /// on the first publish after the reader has drained it, and when the
+    /// last writer goes away. Like rings polled from futures, the ring pays
+    /// a fence on every index store so that no signal is missed.
+    #[cfg(all(unix, feature = "notify"))]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new_notifying(capacity: usize) -> Result<(Writer<T>, Reader<T>), RingBufferError> {
+        Self::builder().capacity(capacity).notifying(true).build()
+    }
+}
+
+/// Options for a lock-free ring, set one by one and checked together by
+/// [`RingBufferBuilder::build`]. Each option defaults to off, and the
+/// capacity to zero, which `build` rejects.
+pub struct RingBufferBuilder<T> {
+    capacity: usize,
+    wiping: bool,
+    watermarks: Option<(usize, usize)>,
+    storage: Storage,
+    #[cfg(all(unix, feature = "notify"))]
+    notifying: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for RingBufferBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> RingBufferBuilder<T> {
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            wiping: false,
+            watermarks: None,
+            storage: Storage::default(),
+            #[cfg(all(unix, feature = "notify"))]
+            notifying: false,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Zeroes consumed slots, as for [`RingBuffer::new_wiping`].
+    pub fn wiping(mut self, wiping: bool) -> Self {
+        self.wiping = wiping;
+        self
+    }
+
+    /// Keeps a congestion flag between the two watermarks, as for
+    /// [`ring_buffer_with_watermarks`].
+    pub fn watermarks(mut self, high: usize, low: usize) -> Self {
+        self.watermarks = Some((high, low));
+        self
+    }
+
+    /// Aligns the first slot to at least `align` bytes, which must be a
+    /// power of two, e.g. to a cache line or a page.
+    pub fn align(mut self, align: usize) -> Self {
+        self.storage.align = align;
+        self
+    }
+
+    /// Maps the slots on anonymous huge pages of [`HUGE_PAGE_SIZE`] instead
+    /// of taking them from the global allocator, rounding the storage up to
+    /// whole pages. The pages must have been reserved, e.g. through
+    /// `/proc/sys/vm/nr_hugepages`.
+    #[cfg(all(target_os = "linux", feature = "hugepages"))]
+    pub fn huge_pages(mut self, huge_pages: bool) -> Self {
+        self.storage.huge_pages = huge_pages;
+        self
+    }
+
+    /// Pins the ring in RAM, as for [`RingBuffer::new_locked`].
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    pub fn locked(mut self, locked: bool) -> Self {
+        self.storage.locked = locked;
+        self
+    }
+
+    /// Gives the reader a readiness descriptor, as for
+    /// [`RingBuffer::new_notifying`].
+    #[cfg(all(unix, feature = "notify"))]
+    pub fn notifying(mut self, notifying: bool) -> Self {
+        self.notifying = notifying;
+        self
+    }
+
+    /// Creates the ring, or fails with the error naming the first option
+    /// that does not work: [`RingBufferError::ZeroCapacity`] or
+    /// [`RingBufferError::CapacityOverflow`] for the capacity, and
+    /// [`RingBufferError::InvalidWatermarks`] for watermarks that are out of
+    /// order or above the capacity, [`RingBufferError::InvalidAlignment`] for
+    /// an alignment that is not a power of two or that the backing cannot
+    /// provide, and [`RingBufferError::HugePagesUnavailable`] if no huge
+    /// pages could be mapped.
+    pub fn build(self) -> Result<(Writer<T>, Reader<T>), RingBufferError> {
+        if self.capacity == 0 {
+            return Err(RingBufferError::ZeroCapacity);
+        }
+        let watermarks = match self.watermarks {
+            Some((high, low)) if low >= high || high > self.capacity => {
+                return Err(RingBufferError::InvalidWatermarks);
+            }
+            Some((high, low)) => Some(Watermarks { high, low }),
+            None => None,
+        };
+
+        #[cfg(all(unix, feature = "notify"))]
+        let notifier = if self.notifying {
+            Some(Notifier::new().map_err(|_| RingBufferError::Initialize)?)
+        } else {
+            None
+        };
+        #[cfg(all(unix, feature = "notify"))]
+        let fenced_wakeups = self.notifying;
+        #[cfg(not(all(unix, feature = "notify")))]
+        let fenced_wakeups = false;
+
+        let (writer, reader) = RingBuffer::with_options(
+            self.capacity,
+            self.wiping,
+            fenced_wakeups,
+            watermarks,
+            self.storage,
+            (),
+        )?;
+
+        #[cfg(all(unix, feature = "notify"))]
+        if let Some(notifier) = notifier {
+            let waiter = &reader.ring_buffer.inner.reader_waiter;
+            let _ = waiter.notifier.set(notifier);
+            // Armed from the start, so the first publish is signalled even if
+            // the reader never drained.
+            waiter.parked.store(true, Ordering::Release);
+        }
+
+        #[cfg(all(target_os = "linux", feature = "std"))]
+        if self.storage.locked {
+            writer.ring_buffer.lock_memory()?;
+        }
+
+        Ok((writer, reader))
+    }
+}
+
+/// The two halves of a ring carrying a context of type `C`.
+pub type Halves<T, C> = (Writer<T, C>, Reader<T, C>);
+
+/// Creates a ring whose shared header also holds `context`, reachable from
+/// both halves through [`Writer::context`] and [`Reader::context`]. The
+/// context is dropped together with the ring, once the last handle is gone.
+pub fn ring_buffer_with_context<T: Copy, C: Send + Sync>(
+    capacity: usize,
+    context: C,
+) -> Result<Halves<T, C>, RingBufferError> {
+    RingBuffer::with_options(capacity, false, false, None, Storage::default(), context)
+}
+
+/// Creates a ring that raises a shared congestion flag, read through
+/// [`Writer::is_congested`] and [`Reader::is_congested`], once a publish
+/// leaves at least `high` elements queued, and lowers it once a read
+/// leaves at most `low`. In between the flag keeps its last state, so a
+/// producer throttling on it does not flap around a single threshold.
+///
+/// Both sides judge the occupancy by the other side's index as last seen
+/// and only re-read it near a threshold, so a ring far from both pays no
+/// extra atomic operations. The flag is advisory: it follows the
+/// occupancy as of the latest transfer that crossed a threshold.
+pub fn ring_buffer_with_watermarks<T: Copy>(
+    capacity: usize,
+    high: usize,
+    low: usize,
+) -> Result<(Writer<T>, Reader<T>), RingBufferError> {
+    RingBuffer::builder()
+        .capacity(capacity)
+        .watermarks(high, low)
+        .build()
+}
+
+/// Moves the queued elements of a ring into fresh storage of `capacity`
+/// slots, in order, and carries on with that. Both halves are borrowed, so
+/// this runs at a point where the caller has stopped both sides; the
+/// context, wakers and other settings stay as they were.
+///
+/// Fails without touching the ring if the halves are not the only two
+/// handles of one ring, if fewer than [`Reader::len`] slots are asked for,
+/// or if the high watermark would end up above the capacity. A ring locked
+/// into memory is locked again afterwards; should that fail, the ring has
+/// still been resized and is left unlocked.
+pub fn grow<T, C>(
+    writer: &mut Writer<T, C>,
+    reader: &mut Reader<T, C>,
+    capacity: usize,
+) -> Result<(), RingBufferError> {
+    if !Arc::ptr_eq(&writer.ring_buffer.inner, &reader.ring_buffer.inner)
+        || Arc::strong_count(&writer.ring_buffer.inner) != 2
+    {
+        return Err(RingBufferError::NotExclusive);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    let locked = writer.ring_buffer.inner.locked.load(Ordering::Relaxed);
+
+    let len = {
+        // Holding both halves mutably, the reader's handle goes unused for
+        // the swap, so its count is lent to the writer's. That one is then
+        // the only handle left, and `Arc::get_mut` hands out the header.
+        let _lent = unsafe { LentCount::new(&reader.ring_buffer.inner) };
+        writer.ring_buffer.reallocate(capacity)?
+    };
+    writer.cached_tail.set(0);
+    reader.cached_head.set(len);
+
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    if locked {
+        writer.ring_buffer.lock_memory()?;
+    }
+
+    Ok(())
+}
+
+// Takes one handle's count off the ring for as long as it is alive, and
+// puts it back when dropped, however the caller's work ends.
+struct LentCount<'a, T, C> {
+    inner: &'a Arc<RingBufferInner<T, C>>,
+}
+
+impl<'a, T, C> LentCount<'a, T, C> {
+    /// Safety: `inner` must not be used, dropped or cloned until the
+    /// returned guard is dropped, and another handle must keep the ring
+    /// alive in the meantime.
+    unsafe fn new(inner: &'a Arc<RingBufferInner<T, C>>) -> Self {
+        Arc::decrement_strong_count(Arc::as_ptr(inner));
+
+        Self { inner }
+    }
+}
+
+impl<T, C> Drop for LentCount<'_, T, C> {
+    fn drop(&mut self) {
+        unsafe { Arc::increment_strong_count(Arc::as_ptr(self.inner)) };
+    }
+}
+
+impl<T, C> RingBuffer<T, C> {
+    pub(crate) fn with_options(
+        capacity: usize,
+        wipe_on_read: bool,
+        fenced_wakeups: bool,
+        watermarks: Option<Watermarks>,
+        storage: Storage,
+        context: C,
+    ) -> Result<Halves<T, C>, RingBufferError> {
+        let capacity_nonzero = NonZeroUsize::new(capacity).ok_or(RingBufferError::ZeroCapacity)?;
+        let wrap = index::index_wrap(capacity).ok_or(RingBufferError::CapacityOverflow)?;
+        let layout = storage.layout::<T>(capacity)?;
+        let buffer = storage.allocate(layout)?;
+
+        let ring_buffer = Self {
+            inner: RingBufferInner {
+                buffer,
+                storage,
+                layout,
+                context,
+                capacity: capacity_nonzero,
+                wrap,
+                head: CachePadded(AtomicUsize::new(0)),
+                tail: CachePadded(AtomicUsize::new(0)),
+                writers: AtomicUsize::new(1),
+                closed: AtomicBool::new(false),
+                readers: AtomicUsize::new(1),
+                reader_waiter: Waiter::new(fenced_wakeups),
+                writer_waiter: Waiter::new(fenced_wakeups),
+                wipe_on_read,
+                watermarks,
+                congested: AtomicBool::new(false),
+                #[cfg(feature = "stats")]
+                histogram: Default::default(),
+                #[cfg(feature = "stats")]
+                histogram_shift: histogram_shift(capacity),
+                #[cfg(feature = "stats")]
+                writer_counters: CachePadded(Counters::default()),
+                #[cfg(feature = "stats")]
+                reader_counters: CachePadded(Counters::default()),
+                #[cfg(all(target_os = "linux", feature = "std"))]
+                locked: AtomicBool::new(false),
+            }
+            .into(),
+        };
+        let writer = Writer::new(ring_buffer.clone());
+        let reader = Reader::new(ring_buffer);
+
+        Ok((writer, reader))
+    }
+
+    /// Swaps the storage for `capacity` fresh slots holding the queued
+    /// elements from index zero on, and returns how many there are. Fails
+    /// with [`RingBufferError::NotExclusive`] unless this is the only
+    /// handle of the ring.
+    fn reallocate(&mut self, capacity: usize) -> Result<usize, RingBufferError> {
+        let capacity_nonzero = NonZeroUsize::new(capacity).ok_or(RingBufferError::ZeroCapacity)?;
+        let wrap = index::index_wrap(capacity).ok_or(RingBufferError::CapacityOverflow)?;
+        let layout = self.inner.storage.layout::<T>(capacity)?;
+
+        let tail_index = self.inner.tail.load(Ordering::Relaxed);
+        let len = self.distance(tail_index, self.inner.head.load(Ordering::Relaxed));
+        if capacity < len {
+            return Err(RingBufferError::CapacityBelowLen);
+        }
+        if matches!(self.inner.watermarks, Some(watermarks) if watermarks.high > capacity) {
+            return Err(RingBufferError::InvalidWatermarks);
+        }
+        if Arc::strong_count(&self.inner) != 1 {
+            return Err(RingBufferError::NotExclusive);
+        }
+
+        let buffer = self.inner.storage.allocate::<T>(layout)?;
+        // The queued run is moved out before the header is borrowed mutably.
+        unsafe { self.load_raw(tail_index, buffer.as_ptr().cast(), len) };
+
+        let inner = Arc::get_mut(&mut self.inner).ok_or(RingBufferError::NotExclusive)?;
+        #[cfg(all(target_os = "linux", feature = "std"))]
+        if inner.locked.swap(false, Ordering::Relaxed) {
+            inner.unlock_memory();
+        }
+        // The elements were moved out, so the old slots only need wiping.
+        if inner.wipe_on_read {
+            inner.wipe_slots(0, inner.capacity.get());
+        }
+        unsafe { inner.storage.free(inner.buffer, inner.layout) };
+
+        inner.buffer = buffer;
+        inner.layout = layout;
+        inner.capacity = capacity_nonzero;
+        inner.wrap = wrap;
+        inner.tail.store(0, Ordering::Relaxed);
+        inner.head.store(len, Ordering::Release);
+        #[cfg(feature = "stats")]
+        {
+            inner.histogram_shift = histogram_shift(capacity);
+        }
+
+        Ok(len)
+    }
+
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    fn lock_memory(&self) -> Result<(), RingBufferError> {
+        let (ptr, len) = self.inner.locked_region();
+        if unsafe { libc::mlock(ptr, len) } != 0 {
+            let errno = std::io::Error::last_os_error()
+                .raw_os_error()
+                .unwrap_or_default();
+
+            return Err(RingBufferError::MemLockFailed { errno });
+        }
+        self.inner.locked.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn storage(&self) -> *mut MaybeUninit<T> {
+        self.inner.buffer.as_ptr()
+    }
+
+    /// The `len` slots from slot `start` on, which must not run past the
+    /// end of the storage.
+    ///
+    /// Safety: the run must belong to the caller's side for as long as the
+    /// slice is alive.
+    #[allow(clippy::mut_from_ref)]
+    #[inline(always)]
+    unsafe fn run_mut(&self, start: usize, len: usize) -> &mut [MaybeUninit<T>] {
+        slice::from_raw_parts_mut(self.storage().add(start), len)
+    }
+
+    /// Like [`RingBuffer::run_mut`], for reading.
+    ///
+    /// Safety: as for `run_mut`.
+    #[inline(always)]
+    unsafe fn run(&self, start: usize, len: usize) -> &[MaybeUninit<T>] {
+        slice::from_raw_parts(self.storage().add(start), len)
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity.get()
+    }
+
+    // The read index is loaded first, so the write index can only be newer
+    // and the distance only overstates; the clamp covers the reader having
+    // moved past the loaded write index in between.
+    #[inline(always)]
+    fn occupied(&self) -> usize {
+        let tail_index = self.tail_index();
+        let head_index = self.head_index();
+
+        self.distance(tail_index, head_index).min(self.capacity())
+    }
+
+    #[inline(always)]
+    fn slot_index(&self, index: usize) -> usize {
+        index % self.inner.capacity
+    }
+
+    #[inline(always)]
+    fn slot(&self, index: usize) -> *mut MaybeUninit<T> {
+        // Safety: the slot index is below the capacity, the storage length.
+        unsafe { self.storage().add(self.slot_index(index)) }
+    }
+
+    /// Safety: the slot must be free, i.e. outside `[tail, head)`.
+    #[inline(always)]
+    unsafe fn store(&self, index: usize, value: T) {
+        (*self.slot(index)).write(value);
+    }
+
+    /// Safety: the slot must hold a published value, i.e. lie within
+    /// `[tail, head)`.
+    #[inline(always)]
+    unsafe fn load(&self, index: usize) -> T {
+        (*self.slot(index)).assume_init_read()
+    }
+
+    // Splits a run of `len` slots from `index` at the end of the storage,
+    // returning its start and the length of the part before the end.
+    #[inline(always)]
+    fn split_run(&self, index: usize, len: usize) -> (usize, usize) {
+        let start = self.slot_index(index);
+
+        (start, len.min(self.capacity() - start))
+    }
+
+    /// Copies `values` into the slots from `index` on, as at most two
+    /// `memcpy`s around the end of the storage.
+    ///
+    /// Safety: the `values.len()` slots from `index` must be free.
+    #[inline(always)]
+    unsafe fn store_slice(&self, index: usize, values: &[T]) {
+        let storage = self.storage().cast::<T>();
+        let (start, first) = self.split_run(index, values.len());
+
+        ptr::copy_nonoverlapping(values.as_ptr(), storage.add(start), first);
+        ptr::copy_nonoverlapping(values.as_ptr().add(first), storage, values.len() - first);
+    }
+
+    /// Copies the slots from `index` on into `values`, mirroring
+    /// [`RingBuffer::store_slice`].
+    ///
+    /// Safety: the `values.len()` slots from `index` must hold published
+    /// values.
+    #[inline(always)]
+    unsafe fn load_slice(&self, index: usize, values: &mut [T]) {
+        self.load_raw(index, values.as_mut_ptr(), values.len());
+    }
+
+    /// Like [`RingBuffer::load_slice`], but into `len` elements at `dst`,
+    /// which need not be initialized.
+    ///
+    /// Safety: as for `load_slice`, and `dst` must be valid for `len`
+    /// writes.
+    #[inline(always)]
+    unsafe fn load_raw(&self, index: usize, dst: *mut T, len: usize) {
+        let storage = self.storage().cast_const().cast::<T>();
+        let (start, first) = self.split_run(index, len);
+
+        ptr::copy_nonoverlapping(storage.add(start), dst, first);
+        ptr::copy_nonoverlapping(storage, dst.add(first), len - first);
+    }
+
+    #[inline(always)]
+    pub fn head_index(&self) -> usize {
+        self.inner.head.load(Ordering::Acquire)
+    }
+
+    #[inline(always)]
+    pub fn tail_index(&self) -> usize {
+        self.inner.tail.load(Ordering::Acquire)
+    }
+
+    // Each index is only moved by its own side, so advancing it is a plain
+    // load and a release store rather than a read-modify-write.
+    #[inline(always)]
+    fn advance_head_index(&self, offset: usize) -> usize {
+        let head_index = self.inner.head.load(Ordering::Relaxed);
+        self.inner
+            .head
+            .store(self.offset_index(head_index, offset), Ordering::Release);
+        self.inner.reader_waiter.notify();
+
+        head_index
+    }
+
+    #[inline(always)]
+    fn advance_tail_index(&self, offset: usize) -> usize {
+        let tail_index = self.inner.tail.load(Ordering::Relaxed);
+        self.inner
+            .tail
+            .store(self.offset_index(tail_index, offset), Ordering::Release);
+        self.inner.writer_waiter.notify();
+
+        #[cfg(feature = "stats")]
+        self.inner.reader_counters.record(offset);
+
+        tail_index
+    }
+
+    /// Moves `index` forward by `offset`, wrapping at the index limit.
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    fn offset_index(&self, index: usize, offset: usize) -> usize {
+        index::offset_index(self.inner.wrap, index, offset)
+    }
+
+    /// Number of steps from `from` forward to `to`.
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    fn distance(&self, from: usize, to: usize) -> usize {
+        index::distance(self.inner.wrap, from, to)
+    }
+
+    // The read index the writer has cached can only lag, so the occupancy
+    // it gives is an upper bound, and is confirmed before raising the flag.
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    fn note_publish(&self, cached_tail: usize, head_index: usize) {
+        if let Some(watermarks) = &self.inner.watermarks {
+            if self.distance(cached_tail, head_index) >= watermarks.high {
+                self.raise_congestion(watermarks.high, head_index);
+            }
+        }
+    }
+
+    #[cold]
+    fn raise_congestion(&self, high: usize, head_index: usize) {
+        if !self.inner.congested.load(Ordering::Relaxed)
+            && self.distance(self.tail_index(), head_index) >= high
+        {
+            self.inner.congested.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Mirrors `note_publish`: the cached write index understates the
+    // occupancy, which is confirmed before lowering the flag.
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    fn note_consume(&self, tail_index: usize, cached_head: usize) {
+        if let Some(watermarks) = &self.inner.watermarks {
+            if self.distance(tail_index, cached_head) <= watermarks.low
+                && self.inner.congested.load(Ordering::Relaxed)
+            {
+                self.lower_congestion(watermarks.low, tail_index);
+            }
+        }
+    }
+
+    #[cold]
+    fn lower_congestion(&self, low: usize, tail_index: usize) {
+        if self.distance(tail_index, self.head_index()) <= low {
+            self.inner.congested.store(false, Ordering::Relaxed);
+        }
+    }
+
+    // Buckets are picked with a shift rather than a division, so they are
+    // equal-width fractions of the capacity rounded up to a power of two.
+    #[cfg(feature = "stats")]
+    #[inline(always)]
+    fn record_publish(&self, head_index: usize, count: usize) {
+        let filled = self.distance(self.tail_index(), head_index);
+        let bucket = (filled >> self.inner.histogram_shift).min(OCCUPANCY_BUCKETS - 1);
+
+        self.inner.histogram[bucket].fetch_add(1, Ordering::Relaxed);
+
+        let counters = &self.inner.writer_counters;
+        counters.record(count);
+        if filled > counters.high_water.load(Ordering::Relaxed) {
+            counters.high_water.store(filled, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> Stats {
+        let writer = &self.inner.writer_counters;
+        let reader = &self.inner.reader_counters;
+
+        Stats {
+            written: writer.transferred.load(Ordering::Relaxed),
+            read: reader.transferred.load(Ordering::Relaxed),
+            write_failures: writer.failures.load(Ordering::Relaxed),
+            read_failures: reader.failures.load(Ordering::Relaxed),
+            high_water: writer.high_water.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub struct Writer<T, C = ()> {
+    ring_buffer: RingBuffer<T, C>,
+    // The read index as last seen. It can only lag behind the real one and
+    // so understate the free space, and is re-read only when it does.
+    cached_tail: Cell<usize>,
+}
+
+impl<T, C> Writer<T, C> {
+    /// Returns a second writer for the same ring. It counts as a live
+    /// writer for disconnection like the original.
+    ///
+    /// # Safety
+    ///
+    /// The ring supports a single producer. The caller must make sure that
+    /// no two writer handles of one ring are ever used at the same time,
+    /// for example by handing the duplicate to another thread only after
+    /// this one is done writing; concurrent writers overwrite each other's
+    /// slots and publish uninitialized ones.
+    pub unsafe fn duplicate(&self) -> Self {
+        self.ring_buffer
+            .inner
+            .writers
+            .fetch_add(1, Ordering::Relaxed);
+
+        Self {
+            ring_buffer: self.ring_buffer.clone(),
+            cached_tail: Cell::new(self.ring_buffer.tail_index()),
+        }
+    }
+}
+
+/// A [`Writer`] taken apart by [`Writer::into_raw`], for handing it across
+/// a boundary that cannot carry the Rust type. While in this form it still
+/// counts as a live writer and keeps the ring allocated.
+#[derive(Debug)]
+pub struct RawWriterParts {
+    /// The shared header, an opaque pointer to pass back to
+    /// [`Writer::from_raw`].
+    pub header: *const (),
+    /// The first slot of the storage.
+    pub buffer: *mut (),
+    pub capacity: usize,
+    /// The write index, moved by the writer.
+    pub head: *const AtomicUsize,
+    /// The read index, moved by the reader.
+    pub tail: *const AtomicUsize,
+}
+
+impl<T, C> Writer<T, C> {
+    /// Takes the writer apart into raw pointers without giving up its hold
+    /// on the ring. Turn it back into a writer with [`Writer::from_raw`],
+    /// or the ring is never freed.
+    pub fn into_raw(self) -> RawWriterParts {
+        let this = ManuallyDrop::new(self);
+        let inner = &this.ring_buffer.inner;
+        let parts = RawWriterParts {
+            header: Arc::as_ptr(inner).cast(),
+            buffer: this.ring_buffer.storage().cast(),
+            capacity: inner.capacity.get(),
+            head: &*inner.head,
+            tail: &*inner.tail,
+        };
+        // The reference moves into `parts.header`, and the writer count
+        // stays as it is.
+        let _ = Arc::into_raw(unsafe { ptr::read(inner) });
+
+        parts
+    }
+
+    /// Puts a writer back together from [`Writer::into_raw`].
+    ///
+    /// # Safety
+    ///
+    /// `parts` must come from `Writer::<T, C>::into_raw` with the same `T`
+    /// and `C`, possibly in another library, and each one may be turned
+    /// back into a writer only once. The ring stays allocated until the
+    /// rebuilt writer and every other handle are dropped, so the pointers
+    /// in `parts` must not be used after that.
+    pub unsafe fn from_raw(parts: RawWriterParts) -> Self {
+        let inner = Arc::from_raw(parts.header.cast::<RingBufferInner<T, C>>());
+
+        Self::new(RingBuffer { inner })
+    }
+}
+
+impl<T, C> core::fmt::Debug for Writer<T, C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.ring_buffer
+            .fmt_half(f, "Writer", &self.ring_buffer.inner.readers)
+    }
+}
+
+impl<T, C> Drop for Writer<T, C> {
+    fn drop(&mut self) {
+        self.ring_buffer
+            .inner
+            .writers
+            .fetch_sub(1, Ordering::Release);
+        self.ring_buffer.inner.reader_waiter.notify();
+    }
+}
+
+impl<T: Copy, C> RawBufferWriter<T> for Writer<T, C> {
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    fn available(&self, size: usize) -> (usize, usize) {
+        let (available, head_index) = self.free_for(size);
+        if available >= size {
+            (size, head_index)
+        } else {
+            (0, head_index)
+        }
+    }
+
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    unsafe fn get_mut(&mut self, index: usize) -> &mut T {
+        // The slot may not hold a value yet; callers only store through the
+        // reference, which for a `Copy` type never reads the old contents.
+        unsafe { &mut *self.ring_buffer.slot(index).cast::<T>() }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        self.publish(offset);
+    }
+}
+
+impl<T: Copy, C> BufferWriter<T> for Writer<T, C> {
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    /// Writes as much of `buffer` as currently fits, like
+    /// [`std::io::Write::write`], and returns the number of elements
+    /// written. Zero means the ring is full (or `buffer` is empty).
+    fn write(&mut self, buffer: &[T]) -> usize {
+        if let [value] = buffer {
+            return self.try_push(*value).map_or(0, |()| 1);
+        }
+
+        let (free, index) = self.free_for(buffer.len());
+        let n = free.min(buffer.len());
+
+        if n > 0 {
+            unsafe { self.ring_buffer.store_slice(index, &buffer[..n]) };
+            self.publish(n);
+        } else {
+            #[cfg(feature = "stats")]
+            if !buffer.is_empty() {
+                self.ring_buffer.inner.writer_counters.record_failure();
+            }
+        }
+
+        n
+    }
+}
+
+impl<T, C> Writer<T, C> {
+    fn new(ring_buffer: RingBuffer<T, C>) -> Self {
+        Self {
+            cached_tail: Cell::new(ring_buffer.tail_index()),
+            ring_buffer,
+        }
+    }
+
+    /// Writes one element, or hands it back if the ring is full.
+    ///
+    /// This is the burst-1 path of `write`: one occupancy check, one slot
+    /// store and one release store of the head, without a slice to build.
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        let (free, head_index) = self.free_for(1);
+        if free == 0 {
+            #[cfg(feature = "stats")]
+            self.ring_buffer.inner.writer_counters.record_failure();
+
+            return Err(value);
+        }
+
+        unsafe { self.ring_buffer.store(head_index, value) };
+        // Only this handle moves the head, so a plain store publishes.
+        let head_index = self.ring_buffer.offset_index(head_index, 1);
+        self.ring_buffer
+            .inner
+            .head
+            .store(head_index, Ordering::Release);
+        self.ring_buffer.inner.reader_waiter.notify();
+        self.ring_buffer
+            .note_publish(self.cached_tail.get(), head_index);
+
+        #[cfg(feature = "stats")]
+        self.ring_buffer.record_publish(head_index, 1);
+
+        Ok(())
+    }
+
+    /// Writes elements pulled from `iter` until it runs out or the ring is
+    /// full, and returns the number written. Nothing is pulled that does not
+    /// fit, so the rest of the iterator is left for a later call.
+    ///
+    /// The elements are stored as they are pulled and published together
+    /// with a single head update, so the reader never sees part of the burst
+    /// early. The upper bound of the iterator's size hint decides how much
+    /// free space to look for, so a short exact-size iterator can go by the
+    /// cached read index without reloading it.
+    pub fn write_iter<I: Iterator<Item = T>>(&mut self, mut iter: I) -> usize {
+        let capacity = self.capacity();
+        let wanted = iter
+            .size_hint()
+            .1
+            .map_or(capacity, |upper| upper.min(capacity));
+        let (free, head_index) = self.free_for(wanted);
+
+        let mut written = 0;
+        while written < free {
+            let Some(value) = iter.next() else {
+                break;
+            };
+
+            unsafe {
+                self.ring_buffer
+                    .store(head_index.wrapping_add(written), value)
+            };
+            written += 1;
+        }
+
+        if written > 0 {
+            let head_index = self.ring_buffer.advance_head_index(written);
+            self.ring_buffer.note_publish(
+                self.cached_tail.get(),
+                self.ring_buffer.offset_index(head_index, written),
+            );
+
+            #[cfg(feature = "stats")]
+            self.ring_buffer
+                .record_publish(head_index.wrapping_add(written), written);
+        }
+
+        written
+    }
+
+    #[inline(always)]
+    pub fn context(&self) -> &C {
+        &self.ring_buffer.inner.context
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.ring_buffer.capacity()
+    }
+
+    /// Number of queued elements. Like the other occupancy calls, this is a
+    /// snapshot that the other side may already have changed by the time it
+    /// returns.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ring_buffer.occupied()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline(always)]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Number of free slots, as a snapshot like [`Self::len`].
+    #[inline(always)]
+    pub fn free_len(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    /// Returns `true` while the ring is congested, on rings built with
+    /// [`ring_buffer_with_watermarks`]. Always `false` on other rings.
+    #[inline(always)]
+    pub fn is_congested(&self) -> bool {
+        self.ring_buffer.inner.congested.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` once every reader handle has been dropped.
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.ring_buffer.inner.readers.load(Ordering::Acquire) == 0
+    }
+
+    /// Returns `true` once [`Writer::close`] has been called.
+    #[inline(always)]
+    pub fn is_closed(&self) -> bool {
+        self.ring_buffer.inner.closed.load(Ordering::Relaxed)
+    }
+
+    /// Ends the stream while keeping this handle alive. The reader still
+    /// gets everything written so far, after which [`Reader::is_closed`]
+    /// turns `true` and its blocking and async calls stop waiting. Writes
+    /// from here on are turned away. Closing again does nothing.
+    pub fn close(&self) {
+        if !self.ring_buffer.inner.closed.swap(true, Ordering::AcqRel) {
+            self.ring_buffer.inner.reader_waiter.notify();
+        }
+    }
+
+    // Like `free`, but trusts the cached read index as long as it shows at
+    // least `wanted` free slots, so the common case touches no cache line
+    // the reader writes to.
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    fn free_for(&self, wanted: usize) -> (usize, usize) {
+        let head_index = self.ring_buffer.inner.head.load(Ordering::Relaxed);
+        let capacity = self.ring_buffer.capacity();
+
+        // A closed ring has no room for anything, which is how every write
+        // path turns writes away.
+        if self.is_closed() {
+            return (0, head_index);
+        }
+
+        let filled = self
+            .ring_buffer
+            .distance(self.cached_tail.get(), head_index);
+        let free = capacity.saturating_sub(filled);
+        if free >= wanted {
+            return (free, head_index);
+        }
+
+        let tail_index = self.ring_buffer.tail_index();
+        self.cached_tail.set(tail_index);
+
+        (
+            capacity.saturating_sub(self.ring_buffer.distance(tail_index, head_index)),
+            head_index,
+        )
+    }
+
+    // Publishes the next `offset` slots, which the caller has filled.
+    #[inline(always)]
+    fn publish(&mut self, offset: usize) {
+        let head_index = self.ring_buffer.advance_head_index(offset);
+        self.ring_buffer.note_publish(
+            self.cached_tail.get(),
+            self.ring_buffer.offset_index(head_index, offset),
+        );
+
+        #[cfg(feature = "stats")]
+        self.ring_buffer
+            .record_publish(head_index.wrapping_add(offset), offset);
+    }
+}
+
+impl<T: Copy, C> Writer<T, C> {
+    /// Number of publishes that left the ring in each occupancy bucket, from
+    /// emptiest to fullest.
+    #[cfg(feature = "stats")]
+    pub fn occupancy_histogram(&self) -> [u64; OCCUPANCY_BUCKETS] {
+        core::array::from_fn(|bucket| {
+            self.ring_buffer.inner.histogram[bucket].load(Ordering::Relaxed)
+        })
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn reset_occupancy_histogram(&self) {
+        for bucket in &self.ring_buffer.inner.histogram {
+            bucket.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// A snapshot of the ring's counters, the same from either half.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.ring_buffer.stats()
+    }
+
+    /// Like [`BufferWriter::write`], but fails once the reader is gone or
+    /// the ring is closed, so a producer can tell a full ring from a dead
+    /// one and stop.
+    #[inline(always)]
+    pub fn try_write(&mut self, buffer: &[T]) -> Result<usize, TryWriteError> {
+        if self.is_closed() {
+            return Err(TryWriteError::Closed);
+        }
+        if self.is_disconnected() {
+            return Err(TryWriteError::Disconnected);
+        }
+
+        Ok(self.write(buffer))
+    }
+
+    /// Blocks until the reader has consumed everything published so far.
+    ///
+    /// Returns [`DrainError::Disconnected`] instead of waiting forever when
+    /// the reader goes away with elements still queued.
+    #[cfg(feature = "std")]
+    pub fn wait_drained(&self) -> Result<(), DrainError> {
+        self.wait_drained_until(None)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn wait_drained_timeout(&self, timeout: Duration) -> Result<(), DrainError> {
+        self.wait_drained_until(Instant::now().checked_add(timeout))
+    }
+
+    #[cfg(feature = "std")]
+    fn wait_drained_until(&self, deadline: Option<Instant>) -> Result<(), DrainError> {
+        let drained = || self.ring_buffer.head_index() == self.ring_buffer.tail_index();
+        let mut backoff = Backoff::new();
+
+        loop {
+            if drained() {
+                return Ok(());
+            }
+            if self.is_disconnected() {
+                return Err(DrainError::Disconnected);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(DrainError::Timeout);
+            }
+
+            // Every read notifies the writer side, as does the reader going
+            // away, so a parked writer wakes for both outcomes.
+            if backoff.is_completed() {
+                self.ring_buffer
+                    .inner
+                    .writer_waiter
+                    .wait_until(deadline, || drained() || self.is_disconnected());
+            } else {
+                backoff.wait();
+            }
+        }
+    }
+
+    /// Gives up this writer handle and waits for the reader to consume
+    /// everything that was published through it.
+    #[cfg(feature = "std")]
+    pub fn close_and_drain(self) -> Result<(), DrainError> {
+        let this = ManuallyDrop::new(self);
+        this.ring_buffer
+            .inner
+            .writers
+            .fetch_sub(1, Ordering::Release);
+        this.ring_buffer.inner.reader_waiter.notify();
+        let result = this.wait_drained();
+
+        // The writer count is already released, so skip `Drop for Writer` and
+        // only let go of the shared handle.
+        drop(unsafe { core::ptr::read(&this.ring_buffer) });
+
+        result
+    }
+
+    /// Writes all of `buffer`, waiting for space as needed. After a short
+    /// spin the thread parks until the reader frees slots, so a full ring
+    /// does not keep a core busy. Fails if the reader goes away first, or
+    /// right away if the ring is closed.
+    #[cfg(feature = "std")]
+    pub fn write_all(&mut self, buffer: &[T]) -> Result<(), TransferError> {
+        let mut backoff = Backoff::new();
+        let mut written = 0;
+
+        while written < buffer.len() {
+            if self.is_closed() {
+                return Err(TransferError::Closed {
+                    transferred: written,
+                });
+            }
+            if self.is_disconnected() {
+                return Err(TransferError::Disconnected {
+                    transferred: written,
+                });
+            }
+
+            match self.write(&buffer[written..]) {
+                0 if backoff.is_completed() => {
+                    self.wait_for_space(None);
+                }
+                0 => backoff.wait(),
+                n => {
+                    written += n;
+                    backoff.reset();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes as much of `buffer` as it can without ever parking and
+    /// returns the number of elements written. Stops once all of `buffer`
+    /// is written, once `max_spins` attempts in a row found the ring full,
+    /// or as soon as the reader is gone or the ring is closed.
+    ///
+    /// The `n`-th retry in a row first spins `2^n` times with
+    /// [`core::hint::spin_loop`], up to 64, and retries after that yield
+    /// the thread instead. Any progress starts the sequence over.
+    #[cfg(feature = "std")]
+    pub fn write_busy(&mut self, buffer: &[T], max_spins: usize) -> usize {
+        let mut backoff = Backoff::new();
+        let mut spins = 0;
+        let mut written = 0;
+
+        while written < buffer.len() && !self.is_disconnected() && !self.is_closed() {
+            match self.write(&buffer[written..]) {
+                0 if spins == max_spins => break,
+                0 => {
+                    backoff.snooze();
+                    spins += 1;
+                }
+                n => {
+                    written += n;
+                    backoff.reset();
+                    spins = 0;
+                }
+            }
+        }
+
+        written
+    }
+
+    /// Writes as much of `buffer` as fits before `timeout` runs out and
+    /// returns the number of elements written, waiting like
+    /// [`Writer::write_all`] in between. A zero timeout makes this a single
+    /// non-blocking [`BufferWriter::write`].
+    ///
+    /// Fails only if nothing was written: with [`TimeoutError::Timeout`] if
+    /// the ring stayed full, [`TimeoutError::Disconnected`] if the reader
+    /// went away, or [`TimeoutError::Closed`] if the ring is closed.
+    #[cfg(feature = "std")]
+    pub fn write_timeout(
+        &mut self,
+        buffer: &[T],
+        timeout: Duration,
+    ) -> Result<usize, TimeoutError> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut backoff = Backoff::new();
+        let mut written = 0;
+
+        let error = loop {
+            if self.is_closed() {
+                break TimeoutError::Closed;
+            }
+            if self.is_disconnected() {
+                break TimeoutError::Disconnected;
+            }
+
+            match self.write(&buffer[written..]) {
+                0 => {}
+                n => {
+                    written += n;
+                    backoff.reset();
+                }
+            }
+            if written == buffer.len() {
+                return Ok(written);
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break TimeoutError::Timeout;
+            } else if backoff.is_completed() {
+                self.wait_for_space(deadline);
+            } else {
+                backoff.wait();
+            }
+        };
+
+        if written > 0 {
+            Ok(written)
+        } else {
+            Err(error)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn wait_for_space(&self, deadline: Option<Instant>) {
+        self.wait_for_space_or(deadline, || false);
+    }
+
+    /// Registers `waker` to be woken once the reader frees slots or goes
+    /// away. Only used on rings built with fenced wake-ups.
+    #[cfg(feature = "async")]
+    pub(crate) fn register_waker(&self, waker: &Waker) {
+        self.ring_buffer.inner.writer_waiter.register(waker);
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn unregister_waker(&self) {
+        self.ring_buffer.inner.writer_waiter.unregister();
+    }
+
+    /// Blocks until all of `buffer` has been written, `token` is cancelled,
+    /// or the reader disconnects. Errors report how many elements made it
+    /// into the ring before the call gave up.
+    #[cfg(feature = "std")]
+    pub fn write_all_cancellable(
+        &mut self,
+        buffer: &[T],
+        token: &CancelToken,
+    ) -> Result<(), TransferError> {
+        let _waiter = token.register();
+        let mut backoff = Backoff::new();
+        let mut written = 0;
+
+        while written < buffer.len() {
+            if token.is_cancelled() {
+                return Err(TransferError::Cancelled {
+                    transferred: written,
+                });
+            }
+            if self.is_closed() {
+                return Err(TransferError::Closed {
+                    transferred: written,
+                });
+            }
+            if self.is_disconnected() {
+                return Err(TransferError::Disconnected {
+                    transferred: written,
+                });
+            }
+
+            match self.write(&buffer[written..]) {
+                0 if backoff.is_completed() => {
+                    self.wait_for_space_or(None, || token.is_cancelled());
+                }
+                0 => backoff.wait(),
+                n => {
+                    written += n;
+                    backoff.reset();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Parks until there is space, the reader goes away, or `interrupted`
+    // holds. A caller whose interruption unparks the thread itself, like a
+    // cancelled token, ends the wait as promptly as a read would.
+    #[cfg(feature = "std")]
+    fn wait_for_space_or(&self, deadline: Option<Instant>, interrupted: impl Fn() -> bool) {
+        self.ring_buffer
+            .inner
+            .writer_waiter
+            .wait_until(deadline, || {
+                interrupted() || self.is_disconnected() || self.free_for(1).0 > 0
+            });
+    }
+
+    /// Writes as much of `buffer` as currently fits and returns the part that
+    /// did not, so a retry loop only has to keep the returned slice around.
+    pub fn write_remainder<'a>(&mut self, buffer: &'a [T]) -> &'a [T] {
+        let written = self.write(buffer);
+
+        &buffer[written..]
+    }
+
+    /// Touches every page of the slot storage so that the first pass over the
+    /// ring does not page-fault on the producer's critical path. On Linux the
+    /// kernel is also asked to read the range ahead with `MADV_WILLNEED`.
+    ///
+    /// Meant to be called during setup: it does nothing unless the ring is
+    /// empty. Slot contents are rewritten in place and left unchanged.
+    pub fn prefault(&mut self) {
+        if self.free().0 != self.capacity() {
+            return;
+        }
+
+        let ptr = self.ring_buffer.storage().cast::<MaybeUninit<u8>>();
+        let len = core::mem::size_of::<T>() * self.capacity();
+        if len == 0 {
+            return;
+        }
+
+        let page_size = page_size();
+        #[cfg(target_os = "linux")]
+        unsafe {
+            let start = ptr as usize & !(page_size - 1);
+            libc::madvise(
+                start as *mut libc::c_void,
+                ptr as usize + len - start,
+                libc::MADV_WILLNEED,
+            );
+        }
+
+        let touch = |offset: usize| unsafe {
+            let byte = ptr.add(offset);
+            byte.write_volatile(byte.read_volatile());
+        };
+        (0..len).step_by(page_size).for_each(touch);
+        touch(len - 1);
+    }
+
+    #[inline(always)]
+    pub(crate) fn free(&self) -> (usize, usize) {
+        self.free_for(usize::MAX)
+    }
+
+    /// Claims room for up to `buffer.len()` elements by moving `claim`
+    /// forward, stores them, and publishes them once every earlier claim
+    /// has been published. Returns the number written. Any number of
+    /// threads may call this at once through a shared writer, as long as
+    /// the ring is written in no other way; `claim` starts out at the write
+    /// index.
+    #[cfg(feature = "std")]
+    pub(crate) fn write_shared(&self, claim: &AtomicUsize, buffer: &[T]) -> usize {
+        let ring_buffer = &self.ring_buffer;
+        let capacity = ring_buffer.capacity();
+
+        let (start, n) = loop {
+            // The read index is loaded first, so the claim can only be ahead
+            // of it and the distance is never negative.
+            let tail_index = ring_buffer.tail_index();
+            let start = claim.load(Ordering::Relaxed);
+            let free = capacity.saturating_sub(ring_buffer.distance(tail_index, start));
+            let n = free.min(buffer.len());
+            if n == 0 {
+                return 0;
+            }
+
+            let end = ring_buffer.offset_index(start, n);
+            if claim
+                .compare_exchange_weak(start, end, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+            {
+                break (start, n);
+            }
+        };
+
+        unsafe { ring_buffer.store_slice(start, &buffer[..n]) };
+
+        // Claims are published in the order they were made, so the write
+        // index never passes a claim whose slots are still being filled.
+        #[cfg(not(loom))]
+        let mut backoff = Backoff::new();
+        while ring_buffer.inner.head.load(Ordering::Acquire) != start {
+            // A model has to run the producer being waited on, not spin.
+            #[cfg(loom)]
+            crate::sync::yield_now();
+            #[cfg(not(loom))]
+            backoff.snooze();
+        }
+        ring_buffer
+            .inner
+            .head
+            .store(ring_buffer.offset_index(start, n), Ordering::Release);
+        ring_buffer.inner.reader_waiter.notify();
+
+        n
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[inline(always)]
+    pub(crate) fn contiguous_mut(&mut self, index: usize, len: usize) -> &mut [T] {
+        let start = index % self.ring_buffer.capacity();
+
+        let slots = unsafe { self.ring_buffer.run_mut(start, len) };
+
+        // The slots are free and the caller only writes into them, so the
+        // cast does not need them to be initialized.
+        unsafe { &mut *(slots as *mut [MaybeUninit<T>] as *mut [T]) }
+    }
+
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    #[inline(always)]
+    pub(crate) fn storage(&self) -> *mut T {
+        self.ring_buffer.storage().cast()
+    }
+
+    /// Writes the slices of `bufs` back to back as one burst and returns the
+    /// total written. The burst is all-or-nothing: if the slices do not fit
+    /// together, nothing is written and this returns zero, so the reader
+    /// never sees the leading slices without the rest. A set larger than
+    /// the capacity never fits.
+    pub fn write_vectored(&mut self, bufs: &[&[T]]) -> usize {
+        let total = bufs
+            .iter()
+            .try_fold(0usize, |total, buf| total.checked_add(buf.len()));
+        let Some(total) = total.filter(|&total| total > 0) else {
+            return 0;
+        };
+
+        let (free, index) = self.free_for(total);
+        if free < total {
+            return 0;
+        }
+
+        let mut offset = 0;
+        for buf in bufs {
+            unsafe {
+                self.ring_buffer
+                    .store_slice(index.wrapping_add(offset), buf)
+            };
+            offset += buf.len();
+        }
+        self.publish(total);
+
+        total
+    }
+
+    /// Recombines this writer with `reader` and tears the ring down,
+    /// returning the elements the reader had not consumed yet, oldest
+    /// first. The storage is freed before this returns.
+    ///
+    /// Fails and hands both halves back if they belong to different rings,
+    /// or if duplicated handles of the ring are still alive.
+    pub fn unsplit(self, mut reader: Reader<T, C>) -> Result<Vec<T>, Halves<T, C>> {
+        if !Arc::ptr_eq(&self.ring_buffer.inner, &reader.ring_buffer.inner)
+            || Arc::strong_count(&self.ring_buffer.inner) != 2
+        {
+            return Err((self, reader));
+        }
+
+        let remaining = reader.drain().collect();
+        drop(reader);
+        drop(self);
+
+        Ok(remaining)
+    }
+
+    /// Splits `len` free slots starting at `index` into the part before the
+    /// end of the storage and the part that wrapped around to the front,
+    /// mirroring [`Reader::segments`]. Free slots may never have been
+    /// written, so they are handed out uninitialized.
+    fn segments_mut(
+        &mut self,
+        index: usize,
+        len: usize,
+    ) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        let capacity = self.ring_buffer.capacity();
+        let start = index % capacity;
+        let first = len.min(capacity - start);
+
+        // The slots are free and the reader does not look at them until they
+        // are published, as for `RawBufferWriter::get_mut`. The two runs do
+        // not overlap since `len` is at most the capacity.
+        unsafe {
+            (
+                self.ring_buffer.run_mut(start, first),
+                self.ring_buffer.run_mut(0, len - first),
+            )
+        }
+    }
+
+    /// Moves up to `max` elements from `src` straight into this ring and
+    /// returns how many were moved. `src` reads into the free slots in
+    /// place through [`BufferReader::read_buf`], one call per contiguous
+    /// run of them, so nothing goes through a scratch buffer. Both sides
+    /// only advance by what was moved.
+    pub fn write_from<R: BufferReader<T> + ?Sized>(&mut self, src: &mut R, max: usize) -> usize {
+        let (free, index) = self.free_for(max);
+        let len = free.min(max);
+
+        let (first, second) = self.segments_mut(index, len);
+        let first_len = first.len();
+        let mut buffer = ReadBuf::new(first);
+        src.read_buf(&mut buffer);
+        let mut n = buffer.filled();
+        if n == first_len && !second.is_empty() {
+            let mut buffer = ReadBuf::new(second);
+            src.read_buf(&mut buffer);
+            n += buffer.filled();
+        }
+        if n > 0 {
+            self.publish(n);
+        }
+
+        n
+    }
+
+    /// Reserves the next `n` free slots so they can be filled in place,
+    /// or returns `None` if fewer than `n` are free. Nothing becomes
+    /// visible to the reader until [`WriteChunk::commit`]; dropping the
+    /// chunk without committing publishes nothing. Use
+    /// [`WriteChunk::commit_filled`] to publish without `unsafe`.
+    pub fn claim(&mut self, n: usize) -> Option<WriteChunk<'_, T, C>> {
+        let (free, index) = self.free_for(n);
+
+        (free >= n).then_some(WriteChunk {
+            writer: self,
+            index,
+            len: n,
+            filled: 0,
+        })
+    }
+
+    pub fn write_array<const N: usize>(&mut self, values: [T; N]) -> Result<(), [T; N]> {
+        if self.available(N).0 < N {
+            return Err(values);
+        }
+
+        self.write(&values);
+
+        Ok(())
+    }
+}
+
+pub struct Reader<T, C = ()> {
+    ring_buffer: RingBuffer<T, C>,
+    // The write index as last seen, mirroring `Writer::cached_tail`.
+    cached_head: Cell<usize>,
+}
+
+impl<T, C> Reader<T, C> {
+    /// Returns a second reader for the same ring, like
+    /// [`Writer::duplicate`].
+    ///
+    /// # Safety
+    ///
+    /// The ring supports a single consumer. The caller must make sure that
+    /// no two reader handles of one ring are ever used at the same time;
+    /// concurrent readers hand out the same elements twice.
+    pub unsafe fn duplicate(&self) -> Self {
+        self.ring_buffer
+            .inner
+            .readers
+            .fetch_add(1, Ordering::Relaxed);
+
+        Self {
+            ring_buffer: self.ring_buffer.clone(),
+            cached_head: Cell::new(self.ring_buffer.head_index()),
+        }
+    }
+}
+
+/// A [`Reader`] taken apart by [`Reader::into_raw`], the counterpart of
+/// [`RawWriterParts`].
+#[derive(Debug)]
+pub struct RawReaderParts {
+    /// The shared header, an opaque pointer to pass back to
+    /// [`Reader::from_raw`].
+    pub header: *const (),
+    /// The first slot of the storage.
+    pub buffer: *const (),
+    pub capacity: usize,
+    /// The write index, moved by the writer.
+    pub head: *const AtomicUsize,
+    /// The read index, moved by the reader.
+    pub tail: *const AtomicUsize,
+}
+
+impl<T, C> Reader<T, C> {
+    /// Takes the reader apart into raw pointers, like [`Writer::into_raw`].
+    pub fn into_raw(self) -> RawReaderParts {
+        let this = ManuallyDrop::new(self);
+        let inner = &this.ring_buffer.inner;
+        let parts = RawReaderParts {
+            header: Arc::as_ptr(inner).cast(),
+            buffer: this.ring_buffer.storage().cast_const().cast(),
+            capacity: inner.capacity.get(),
+            head: &*inner.head,
+            tail: &*inner.tail,
+        };
+        let _ = Arc::into_raw(unsafe { ptr::read(inner) });
+
+        parts
+    }
+
+    /// Puts a reader back together from [`Reader::into_raw`].
+    ///
+    /// # Safety
+    ///
+    /// As for [`Writer::from_raw`], with parts from `Reader::<T, C>::into_raw`.
+    pub unsafe fn from_raw(parts: RawReaderParts) -> Self {
+        let inner = Arc::from_raw(parts.header.cast::<RingBufferInner<T, C>>());
+
+        Self::new(RingBuffer { inner })
+    }
+}
+
+impl<T, C> core::fmt::Debug for Reader<T, C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.ring_buffer
+            .fmt_half(f, "Reader", &self.ring_buffer.inner.writers)
+    }
+}
+
+impl<T, C> Drop for Reader<T, C> {
+    fn drop(&mut self) {
+        self.ring_buffer
+            .inner
+            .readers
+            .fetch_sub(1, Ordering::Release);
+        self.ring_buffer.inner.writer_waiter.notify();
+    }
+}
+
+impl<T: Copy, C> RawBufferReader<T> for Reader<T, C> {
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    fn filled(&self, size: usize) -> (usize, usize) {
+        let (filled, tail_index) = self.pending_for(size);
+        if filled >= size {
+            (size, tail_index)
+        } else {
+            (0, tail_index)
+        }
+    }
+
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    unsafe fn get(&self, index: usize) -> &T {
+        self.slot_ref(index)
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        self.release(offset);
+    }
+}
+
+impl<T: Copy, C> BufferReader<T> for Reader<T, C> {
+    #[inline(always)]
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        let (pending, index) = self.pending_for(buffer.remaining());
+        let n = pending.min(buffer.remaining());
+
+        if n > 0 {
+            let (first, second) = self.segments(index, n);
+            buffer.fill(first);
+            buffer.fill(second);
+            self.release(n);
+        } else {
+            #[cfg(feature = "stats")]
+            if buffer.remaining() > 0 {
+                self.ring_buffer.inner.reader_counters.record_failure();
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    /// Reads as many elements as are queued, up to `buffer.len()`, and
+    /// returns the number read. Zero means the ring is empty (or `buffer`
+    /// is).
+    fn read(&mut self, buffer: &mut [T]) -> usize {
+        if let [slot] = buffer {
+            return self.try_pop().map_or(0, |value| {
+                *slot = value;
+                1
+            });
+        }
+
+        let (pending, index) = self.pending_for(buffer.len());
+        let n = pending.min(buffer.len());
+
+        if n > 0 {
+            unsafe { self.ring_buffer.load_slice(index, &mut buffer[..n]) };
+            self.release(n);
+        } else {
+            #[cfg(feature = "stats")]
+            if !buffer.is_empty() {
+                self.ring_buffer.inner.reader_counters.record_failure();
+            }
+        }
+
+        n
+    }
+}
+
+impl<T, C> Reader<T, C> {
+    fn new(ring_buffer: RingBuffer<T, C>) -> Self {
+        Self {
+            cached_head: Cell::new(ring_buffer.head_index()),
+            ring_buffer,
+        }
+    }
+
+    /// Takes one element, or returns `None` if the ring is empty. The
+    /// burst-1 path of `read`, mirroring [`Writer::try_push`].
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    pub fn try_pop(&mut self) -> Option<T> {
+        let (pending, tail_index) = self.pending_for(1);
+        if pending == 0 {
+            #[cfg(feature = "stats")]
+            self.ring_buffer.inner.reader_counters.record_failure();
+
+            return None;
+        }
+
+        let value = unsafe { self.ring_buffer.load(tail_index) };
+        if self.ring_buffer.inner.wipe_on_read {
+            self.ring_buffer.inner.wipe_slots(tail_index, 1);
+        }
+        let tail_index = self.ring_buffer.offset_index(tail_index, 1);
+        self.ring_buffer
+            .inner
+            .tail
+            .store(tail_index, Ordering::Release);
+        self.ring_buffer.inner.writer_waiter.notify();
+        self.ring_buffer
+            .note_consume(tail_index, self.cached_head.get());
+
+        #[cfg(feature = "stats")]
+        self.ring_buffer.inner.reader_counters.record(1);
+
+        Some(value)
+    }
+
+    /// Discards up to `n` queued elements without copying them out and
+    /// returns how many were discarded. Never moves past what the writer
+    /// has published.
+    pub fn skip(&mut self, n: usize) -> usize {
+        let (pending, tail_index) = self.pending_for(n);
+        let n = pending.min(n);
+
+        if core::mem::needs_drop::<T>() {
+            // Each element is moved out before its slot is handed back, so a
+            // destructor that panics leaves nothing dropped in the queue.
+            for _ in 0..n {
+                drop(self.try_pop());
+            }
+
+            return n;
+        }
+
+        if n > 0 {
+            if self.ring_buffer.inner.wipe_on_read {
+                self.ring_buffer.inner.wipe_slots(tail_index, n);
+            }
+            self.ring_buffer.advance_tail_index(n);
+            self.ring_buffer.note_consume(
+                self.ring_buffer.offset_index(tail_index, n),
+                self.cached_head.get(),
+            );
+        }
+
+        n
+    }
+
+    #[inline(always)]
+    pub fn context(&self) -> &C {
+        &self.ring_buffer.inner.context
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.ring_buffer.capacity()
+    }
+
+    /// Number of queued elements. Like the other occupancy calls, this is a
+    /// snapshot that the other side may already have changed by the time it
+    /// returns.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ring_buffer.occupied()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline(always)]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Number of free slots, as a snapshot like [`Self::len`].
+    #[inline(always)]
+    pub fn free_len(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    /// Returns `true` while the ring is congested, like
+    /// [`Writer::is_congested`].
+    #[inline(always)]
+    pub fn is_congested(&self) -> bool {
+        self.ring_buffer.inner.congested.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` once every writer handle has been dropped. Elements
+    /// published before that may still be queued. A closed ring is not
+    /// disconnected, see [`Reader::is_closed`].
+    #[inline(always)]
+    pub fn is_disconnected(&self) -> bool {
+        self.ring_buffer.inner.writers.load(Ordering::Acquire) == 0
+    }
+
+    // Whether the writer will publish nothing more, because every writer
+    // handle is gone or the ring is closed. Both flags are set after the
+    // last publish, so whatever is queued once this returns `true` is all
+    // that is left; the blocking and async reads drain it and then end.
+    #[inline(always)]
+    pub(crate) fn is_finished(&self) -> bool {
+        self.is_disconnected() || self.ring_buffer.inner.closed.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` once the writer has called [`Writer::close`] and
+    /// everything it published before has been read, which marks the end
+    /// of the stream. Unlike [`Reader::is_disconnected`] this is never
+    /// `true` while elements are still queued.
+    pub fn is_closed(&self) -> bool {
+        // The flag is set after the last publish, so the index read after
+        // it is final.
+        self.ring_buffer.inner.closed.load(Ordering::Acquire)
+            && self.ring_buffer.head_index() == self.ring_buffer.inner.tail.load(Ordering::Relaxed)
+    }
+
+    // Like `pending`, but trusts the cached write index as long as it shows
+    // at least `wanted` queued elements.
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
+    #[inline(always)]
+    fn pending_for(&self, wanted: usize) -> (usize, usize) {
+        let tail_index = self.ring_buffer.inner.tail.load(Ordering::Relaxed);
+
+        // A copy taken by a duplicate may be older than the read index, which
+        // shows up as more than a full ring and forces a re-read.
+        let pending = self
+            .ring_buffer
+            .distance(tail_index, self.cached_head.get());
+        if pending >= wanted && pending <= self.ring_buffer.capacity() {
+            return (pending, tail_index);
+        }
+
+        let head_index = self.ring_buffer.head_index();
+        self.cached_head.set(head_index);
+
+        (
+            self.ring_buffer.distance(tail_index, head_index),
+            tail_index,
+        )
+    }
+
+    // Only slots within `[tail, head)` hold values, and callers only pass
+    // indices `pending_for` has shown to be there.
+    #[inline(always)]
+    fn slot_ref(&self, index: usize) -> &T {
+        unsafe { (*self.ring_buffer.slot(index)).assume_init_ref() }
+    }
+
+    // Hands the next `offset` slots, which the caller has read, back to the
+    // writer.
+    #[inline(always)]
+    fn release(&mut self, offset: usize) {
+        if self.ring_buffer.inner.wipe_on_read {
+            let tail_index = self.ring_buffer.inner.tail.load(Ordering::Relaxed);
+            self.ring_buffer.inner.wipe_slots(tail_index, offset);
+        }
+        let tail_index = self.ring_buffer.advance_tail_index(offset);
+        self.ring_buffer.note_consume(
+            self.ring_buffer.offset_index(tail_index, offset),
+            self.cached_head.get(),
+        );
+    }
+}
+
+impl<T: Copy, C> Reader<T, C> {
+    /// A snapshot of the ring's counters, like [`Writer::stats`].
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.ring_buffer.stats()
+    }
+
+    /// Like [`BufferReader::read`], but fails once the writer is gone or has
+    /// closed the ring, and everything it published has been read, telling
+    /// the two apart like [`Writer::try_write`]. `Ok(0)` only ever means the
+    /// ring is empty for now.
+    #[inline(always)]
+    pub fn try_read(&mut self, buffer: &mut [T]) -> Result<usize, TryReadError> {
+        // Checked before reading, so data published right before the
+        // writer went away is still drained.
+        let finished = self.is_finished();
+
+        match self.read(buffer) {
+            0 if finished && !buffer.is_empty() && self.is_closed() => Err(TryReadError::Closed),
+            0 if finished && !buffer.is_empty() => Err(TryReadError::Disconnected),
+            n => Ok(n),
+        }
+    }
+
+    /// Attaches a new writer after every writer handle has been dropped, for
+    /// example because the producer thread panicked. Queued elements are
+    /// kept and the new writer continues right after the last one published.
+    ///
+    /// The writer count is claimed with a single compare-and-swap from zero,
+    /// so concurrent calls from duplicated readers hand out at most one
+    /// writer.
+    ///
+    /// A ring closed with [`Writer::close`] has ended for good, so this
+    /// fails with [`RecoverError::Closed`] rather than reopening it.
+    pub fn recover_writer(&self) -> Result<Writer<T, C>, RecoverError> {
+        let inner = &self.ring_buffer.inner;
+        inner
+            .writers
+            .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+            .map_err(|_| RecoverError::WriterAlive)?;
+
+        // With no writer left, nothing can close the ring any more, so the
+        // flag is final here.
+        if inner.closed.load(Ordering::Acquire) {
+            inner.writers.store(0, Ordering::Release);
+            return Err(RecoverError::Closed);
+        }
+
+        Ok(Writer::new(self.ring_buffer.clone()))
+    }
+
+    /// Fills all of `buffer`, waiting for data as needed, and parks like
+    /// [`Writer::write_all`] while the ring is empty. Fails once the writer
+    /// has gone or closed the ring and everything it published has been
+    /// read.
+    #[cfg(feature = "std")]
+    pub fn read_exact(&mut self, buffer: &mut [T]) -> Result<(), TransferError> {
+        let mut backoff = Backoff::new();
+        let mut read = 0;
+
+        while read < buffer.len() {
+            let finished = self.is_finished();
+            match self.read(&mut buffer[read..]) {
+                0 if finished => return Err(self.ended(read)),
+                0 if backoff.is_completed() => {
+                    self.wait_for_data(None);
+                }
+                0 => backoff.wait(),
+                n => {
+                    read += n;
+                    backoff.reset();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns an iterator that takes elements one at a time, waiting like
+    /// [`Reader::read_exact`] while the ring is empty. It ends once the
+    /// writer has gone or closed the ring and everything it published has
+    /// been read.
+    #[cfg(feature = "std")]
+    pub fn iter(&mut self) -> Iter<'_, T, C> {
+        Iter { reader: self }
+    }
+
+    #[cfg(feature = "std")]
+    fn pop_wait(&mut self) -> Option<T> {
+        let mut backoff = Backoff::new();
+
+        loop {
+            // Checked before the pop, so that nothing published ahead of the
+            // disconnect is missed.
+            let finished = self.is_finished();
+            if let Some(value) = self.try_pop() {
+                return Some(value);
+            }
+            if finished {
+                return None;
+            }
+
+            if backoff.is_completed() {
+                self.wait_for_data(None);
+            } else {
+                backoff.wait();
+            }
+        }
+    }
+
+    /// Reads into `buffer` without ever parking and returns the number of
+    /// elements read, backing off between attempts like
+    /// [`Writer::write_busy`]. Stops once `buffer` is full, once
+    /// `max_spins` attempts in a row found the ring empty, or as soon as
+    /// the writer is gone or has closed the ring and nothing is left to
+    /// read.
+    #[cfg(feature = "std")]
+    pub fn read_busy(&mut self, buffer: &mut [T], max_spins: usize) -> usize {
+        let mut backoff = Backoff::new();
+        let mut spins = 0;
+        let mut read = 0;
+
+        while read < buffer.len() {
+            let finished = self.is_finished();
+            match self.read(&mut buffer[read..]) {
+                0 if finished || spins == max_spins => break,
+                0 => {
+                    backoff.snooze();
+                    spins += 1;
+                }
+                n => {
+                    read += n;
+                    backoff.reset();
+                    spins = 0;
+                }
+            }
+        }
+
+        read
+    }
+
+    /// Reads into `buffer` until it is full or `timeout` runs out and
+    /// returns the number of elements read, waiting like
+    /// [`Reader::read_exact`] in between. A zero timeout makes this a
+    /// single non-blocking [`BufferReader::read`].
+    ///
+    /// Fails only if nothing was read: with [`TimeoutError::Timeout`] if the
+    /// ring stayed empty, [`TimeoutError::Disconnected`] if the writer went
+    /// away without publishing anything more, or [`TimeoutError::Closed`]
+    /// if the writer closed the ring and all of it has been read.
+    #[cfg(feature = "std")]
+    pub fn read_timeout(
+        &mut self,
+        buffer: &mut [T],
+        timeout: Duration,
+    ) -> Result<usize, TimeoutError> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut backoff = Backoff::new();
+        let mut read = 0;
+
+        let error = loop {
+            let finished = self.is_finished();
+            match self.read(&mut buffer[read..]) {
+                0 if finished && self.is_closed() => break TimeoutError::Closed,
+                0 if finished => break TimeoutError::Disconnected,
+                0 => {}
+                n => {
+                    read += n;
+                    backoff.reset();
+                }
+            }
+            if read == buffer.len() {
+                return Ok(read);
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break TimeoutError::Timeout;
+            } else if backoff.is_completed() {
+                self.wait_for_data(deadline);
+            } else {
+                backoff.wait();
+            }
+        };
+
+        if read > 0 {
+            Ok(read)
+        } else {
+            Err(error)
+        }
+    }
+
+    // The error for a read that found the stream over, which tells a close
+    // apart from the writer going away.
+    #[cfg(feature = "std")]
+    fn ended(&self, transferred: usize) -> TransferError {
+        if self.is_closed() {
+            TransferError::Closed { transferred }
+        } else {
+            TransferError::Disconnected { transferred }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn wait_for_data(&self, deadline: Option<Instant>) {
+        self.wait_for_data_or(deadline, || false);
+    }
+
+    /// Registers `waker` to be woken once the writer publishes or goes
+    /// away, mirroring [`Writer::register_waker`].
+    #[cfg(feature = "async")]
+    pub(crate) fn register_waker(&self, waker: &Waker) {
+        self.ring_buffer.inner.reader_waiter.register(waker);
+    }
+
+    /// The descriptor to wait on for readability, or `None` unless the ring
+    /// was built with [`RingBuffer::new_notifying`].
+    #[cfg(all(unix, feature = "notify"))]
+    pub fn as_raw_fd(&self) -> Option<RawFd> {
+        let notifier = self.ring_buffer.inner.reader_waiter.notifier.get()?;
+
+        Some(notifier.as_raw_fd())
+    }
+
+    /// Clears the descriptor from [`Reader::as_raw_fd`] and re-arms it for
+    /// the next publish. Call this once the descriptor is readable, then
+    /// read until the ring is empty before waiting again: anything
+    /// published before this call is left for those reads, and anything
+    /// after it makes the descriptor readable again.
+    #[cfg(all(unix, feature = "notify"))]
+    pub fn drain_notification(&self) {
+        let waiter = &self.ring_buffer.inner.reader_waiter;
+        if let Some(notifier) = waiter.notifier.get() {
+            notifier.drain();
+            waiter.parked.store(true, Ordering::Release);
+            fence(Ordering::SeqCst);
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn unregister_waker(&self) {
+        self.ring_buffer.inner.reader_waiter.unregister();
+    }
+
+    /// Blocks until `buffer` has been filled, `token` is cancelled, or the
+    /// writer disconnects or closes the ring and everything it published
+    /// has been read. Errors
+    /// report how many elements were copied into `buffer` before the call
+    /// gave up.
+    #[cfg(feature = "std")]
+    pub fn read_exact_cancellable(
+        &mut self,
+        buffer: &mut [T],
+        token: &CancelToken,
+    ) -> Result<(), TransferError> {
+        let _waiter = token.register();
+        let mut backoff = Backoff::new();
+        let mut read = 0;
+
+        while read < buffer.len() {
+            if token.is_cancelled() {
+                return Err(TransferError::Cancelled { transferred: read });
+            }
+
+            // Check for disconnection before reading so that anything
+            // published right before the writer went away is still drained.
+            let finished = self.is_finished();
+            match self.read(&mut buffer[read..]) {
+                0 if finished => return Err(self.ended(read)),
+                0 if backoff.is_completed() => {
+                    self.wait_for_data_or(None, || token.is_cancelled());
+                }
+                0 => backoff.wait(),
+                n => {
+                    read += n;
+                    backoff.reset();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // The reader's side of `Writer::wait_for_space_or`.
+    #[cfg(feature = "std")]
+    fn wait_for_data_or(&self, deadline: Option<Instant>, interrupted: impl Fn() -> bool) {
+        self.ring_buffer
+            .inner
+            .reader_waiter
+            .wait_until(deadline, || {
+                interrupted() || self.is_finished() || self.pending_for(1).0 > 0
+            });
+    }
+
+    #[inline(always)]
+    pub(crate) fn pending(&self) -> (usize, usize) {
+        self.pending_for(usize::MAX)
+    }
+
+    /// Splits `len` elements starting at `index` into the part before the
+    /// end of the storage and the part that wrapped around to the front.
+    #[inline(always)]
+    pub(crate) fn segments(&self, index: usize, len: usize) -> (&[T], &[T]) {
+        let capacity = self.ring_buffer.capacity();
+        let start = index % capacity;
+        let first = len.min(capacity - start);
+        // Safety: callers pass a run within `[tail, head)`.
+        unsafe {
+            (
+                assume_init(self.ring_buffer.run(start, first)),
+                assume_init(self.ring_buffer.run(0, len - first)),
+            )
+        }
+    }
+
+    /// Reads up to `out.len()` elements, converting each with `f` on the way
+    /// out, and returns how many were converted.
+    pub fn read_map_into<U>(&mut self, out: &mut [U], mut f: impl FnMut(&T) -> U) -> usize {
+        let (filled, index) = self.pending();
+        let n = filled.min(out.len());
+
+        if n > 0 {
+            let (first, second) = self.segments(index, n);
+            for (slot, value) in out.iter_mut().zip(first.iter().chain(second)) {
+                *slot = f(value);
+            }
+            self.release(n);
+        }
+
+        n
+    }
+
+    /// Consumes queued elements, copying the first of every `stride` into
+    /// `out` and discarding the others, and returns `(kept, discarded)`.
+    /// Sampling restarts at the first queued element on each call, and stops
+    /// early once `out` is full so nothing past the last kept element is
+    /// dropped.
+    pub fn read_sampled(&mut self, out: &mut [T], stride: usize) -> (usize, usize) {
+        let stride = stride.max(1);
+        let (filled, index) = self.pending();
+        // With `out` filling up, the run ends right at the last kept element.
+        let n = match out.len() {
+            0 => 0,
+            len => filled.min((len - 1).saturating_mul(stride).saturating_add(1)),
+        };
+
+        if n == 0 {
+            return (0, 0);
+        }
+
+        let (first, second) = self.segments(index, n);
+        let mut kept = 0;
+        for (segment_start, segment) in [(0, first), (first.len(), second)] {
+            // Offset of the first sampled element within this segment.
+            let skip = (stride - segment_start % stride) % stride;
+            for value in segment.iter().skip(skip).step_by(stride) {
+                out[kept] = *value;
+                kept += 1;
+            }
+        }
+        self.release(n);
+
+        (kept, n - kept)
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[inline(always)]
+    pub(crate) fn contiguous(&self, index: usize, len: usize) -> &[T] {
+        let start = index % self.ring_buffer.capacity();
+
+        unsafe { assume_init(self.ring_buffer.run(start, len)) }
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Option<[T; N]> {
+        let (filled, index) = self.filled(N);
+
+        if filled == N {
+            let values = core::array::from_fn(|offset| unsafe {
+                self.ring_buffer.load(index.wrapping_add(offset))
+            });
+            self.release(filled);
+
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the oldest queued element without consuming it, or `None` if
+    /// the ring is empty. Only published slots are ever handed out.
+    #[inline(always)]
+    pub fn peek(&self) -> Option<&T> {
+        let (pending, tail_index) = self.pending_for(1);
+
+        (pending > 0).then(|| self.slot_ref(tail_index))
+    }
+
+    /// Copies up to `out.len()` of the oldest queued elements into `out`
+    /// without consuming them and returns how many were copied. Follow up
+    /// with [`Reader::advance`] to consume the ones that were handled.
+    pub fn peek_slice(&mut self, out: &mut [T]) -> usize {
+        let (pending, index) = self.pending_for(out.len());
+        let n = pending.min(out.len());
+
+        let (first, second) = self.segments(index, n);
+        out[..first.len()].copy_from_slice(first);
+        out[first.len()..n].copy_from_slice(second);
+
+        n
+    }
+
+    /// Consumes `n` queued elements, typically after inspecting them with
+    /// [`Reader::peek`] or [`Reader::peek_slice`]. Unlike
+    /// [`RawBufferReader::advance_index`], this refuses to move past what the
+    /// writer has published and consumes nothing in that case.
+    pub fn advance(&mut self, n: usize) -> Result<(), AdvanceError> {
+        let (pending, _) = self.pending_for(n);
+        if pending < n {
+            return Err(AdvanceError::NotQueued { pending });
+        }
+
+        self.release(n);
+
+        Ok(())
+    }
+
+    /// Returns a copy of the element `offset` positions after the read index
+    /// without consuming anything.
+    ///
+    /// This is a snapshot with respect to the writer: an element at a valid
+    /// offset stays put until it is consumed, but offsets that are out of
+    /// range now may become available as the writer publishes more data.
+    pub fn peek_at(&self, offset: usize) -> Option<T> {
+        let size = offset.checked_add(1)?;
+        let (filled, index) = self.filled(size);
+
+        if filled > 0 {
+            Some(unsafe { self.ring_buffer.load(index.wrapping_add(offset)) })
+        } else {
+            None
+        }
+    }
+
+    /// Walks the elements queued when the call is made, oldest first,
+    /// without consuming them. Elements the writer publishes afterwards are
+    /// not included. The iterator borrows the reader, so nothing can be
+    /// consumed until it is dropped; use [`Reader::advance`] or
+    /// [`Reader::skip`] afterwards to consume however many were inspected.
+    pub fn peek_iter(&self) -> impl ExactSizeIterator<Item = &T> + '_ {
+        let (pending, index) = self.pending();
+
+        (0..pending).map(move |offset| self.slot_ref(index.wrapping_add(offset)))
+    }
+
+    /// Fills the slices of `bufs` in order with queued elements, as one
+    /// read of their combined length, and returns the total read. A slice
+    /// is only started once the ones before it are full.
+    pub fn read_vectored(&mut self, bufs: &mut [&mut [T]]) -> usize {
+        let wanted = bufs
+            .iter()
+            .fold(0usize, |total, buf| total.saturating_add(buf.len()));
+        let (pending, index) = self.pending_for(wanted);
+        let n = pending.min(wanted);
+
+        let mut offset = 0;
+        for buf in bufs.iter_mut() {
+            let len = buf.len().min(n - offset);
+            unsafe {
+                self.ring_buffer
+                    .load_slice(index.wrapping_add(offset), &mut buf[..len])
+            };
+            offset += len;
+        }
+        if n > 0 {
+            self.release(n);
+        }
+
+        n
+    }
+
+    /// Reads like [`BufferReader::read`], but into storage that need not be
+    /// initialized. Returns `n` once the first `n` elements of `out` hold
+    /// read values; the rest of `out` is left untouched.
+    pub fn read_uninit(&mut self, out: &mut [MaybeUninit<T>]) -> usize {
+        let (pending, index) = self.pending_for(out.len());
+        let n = pending.min(out.len());
+
+        if n > 0 {
+            unsafe {
+                self.ring_buffer
+                    .load_raw(index, out.as_mut_ptr().cast::<T>(), n)
+            };
+            self.release(n);
+        }
+
+        n
+    }
+
+    /// Appends up to `max` queued elements to `vec` and returns how many
+    /// were appended. Reserves room for what is queued and reads into the
+    /// spare capacity, so nothing is zero-filled first.
+    pub fn read_into_vec(&mut self, vec: &mut Vec<T>, max: usize) -> usize {
+        let (pending, _) = self.pending_for(max);
+        vec.reserve(pending.min(max));
+
+        let spare = vec.spare_capacity_mut();
+        let len = spare.len().min(max);
+        let n = self.read_uninit(&mut spare[..len]);
+        // `read_uninit` has initialized exactly the first `n` spare slots.
+        unsafe { vec.set_len(vec.len() + n) };
+
+        n
+    }
+
+    /// Moves up to `max` queued elements into `dst` and returns how many
+    /// were moved, the counterpart of [`Writer::write_from`]. `dst` copies
+    /// straight out of the queued slots, and only what it took is consumed.
+    pub fn read_into_writer<W: BufferWriter<T> + ?Sized>(
+        &mut self,
+        dst: &mut W,
+        max: usize,
+    ) -> usize {
+        let (pending, index) = self.pending_for(max);
+        let len = pending.min(max);
+
+        let (first, second) = self.segments(index, len);
+        // A writer claiming more than it was given must not release
+        // elements it never took.
+        let mut n = dst.write(first).min(first.len());
+        if n == first.len() && !second.is_empty() {
+            n += dst.write(second).min(second.len());
+        }
+        if n > 0 {
+            self.release(n);
+        }
+
+        n
+    }
 
-    fn get_mut(&mut self, index: u32) -> &mut T;
+    /// Exposes up to `max` of the oldest queued elements in place, or
+    /// returns `None` if the ring is empty (or `max` is zero). The slots
+    /// stay taken until [`ReadChunk::consume`] hands them back to the
+    /// writer.
+    pub fn read_chunk(&mut self, max: usize) -> Option<ReadChunk<'_, T, C>> {
+        let (pending, index) = self.pending_for(max);
+        let len = pending.min(max);
 
-    fn advance_index(&mut self, offset: u32);
+        (len > 0).then_some(ReadChunk {
+            reader: self,
+            index,
+            len,
+        })
+    }
+
+    /// Takes the elements queued when the call is made, oldest first. Like
+    /// [`Reader::peek_iter`], elements the writer publishes afterwards are
+    /// left for the next call, so one drain does a bounded amount of work.
+    ///
+    /// Each element is consumed as the iterator yields it. Dropping the
+    /// iterator early leaves the ones not yet yielded queued.
+    pub fn drain(&mut self) -> Drain<'_, T, C> {
+        let (remaining, _) = self.pending();
+
+        Drain {
+            reader: self,
+            remaining,
+        }
+    }
+
+    pub fn pop_ref(&mut self) -> Option<PopGuard<'_, T, C>> {
+        let (filled, index) = self.filled(1);
 
-    fn write(&mut self, buffer: &[T]) -> u32;
+        if filled > 0 {
+            Some(PopGuard {
+                reader: self,
+                index,
+            })
+        } else {
+            None
+        }
+    }
 }
 
-pub trait BufferReader<T: Copy> {
-    fn filled(&self, size: u32) -> (u32, u32);
+// Fixed-width integers over byte rings. Values are all-or-nothing like the
+// other record calls: a write that does not fit hands the value back, and a
+// read with too few bytes buffered consumes nothing.
+macro_rules! integer_transfers {
+    ($($ty:ty => $write_le:ident, $write_be:ident, $read_le:ident, $read_be:ident;)*) => {
+        impl<C> Writer<u8, C> {
+            $(
+                pub fn $write_le(&mut self, value: $ty) -> Result<(), $ty> {
+                    self.write_array(value.to_le_bytes()).map(|_| ()).map_err(|_| value)
+                }
 
-    fn get(&self, index: u32) -> &T;
+                pub fn $write_be(&mut self, value: $ty) -> Result<(), $ty> {
+                    self.write_array(value.to_be_bytes()).map(|_| ()).map_err(|_| value)
+                }
+            )*
+        }
 
-    fn advance_index(&mut self, offset: u32);
+        impl<C> Reader<u8, C> {
+            $(
+                pub fn $read_le(&mut self) -> Option<$ty> {
+                    self.read_array().map(<$ty>::from_le_bytes)
+                }
 
-    fn read(&mut self, buffer: &mut [T]) -> u32;
+                pub fn $read_be(&mut self) -> Option<$ty> {
+                    self.read_array().map(<$ty>::from_be_bytes)
+                }
+            )*
+        }
+    };
 }
 
-pub struct RingBuffer<T: Copy> {
-    inner: Arc<RingBufferInner<T>>,
+integer_transfers! {
+    u16 => write_u16_le, write_u16_be, read_u16_le, read_u16_be;
+    u32 => write_u32_le, write_u32_be, read_u32_le, read_u32_be;
+    u64 => write_u64_le, write_u64_be, read_u64_le, read_u64_be;
 }
 
-struct RingBufferInner<T: Copy> {
-    buffer: NonNull<Vec<T>>,
-    capacity: u32,
-    head: AtomicU32,
-    tail: AtomicU32,
+#[cfg(feature = "std")]
+impl<C> Writer<u8, C> {
+    /// Reads from `source` straight into the free slots after the write
+    /// index and publishes whatever it returned. Only the contiguous free
+    /// region is offered, so a call may fill less than the free space.
+    ///
+    /// `Ok(0)` means `source` reached end of file. A full ring is reported
+    /// as [`io::ErrorKind::WouldBlock`] without calling `source` at all.
+    pub fn fill_from(&mut self, source: &mut impl io::Read) -> io::Result<usize> {
+        let (free, index) = self.free();
+        if free == 0 {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+
+        let capacity = self.capacity();
+        let start = index % capacity;
+        let len = free.min(capacity - start);
+        let region = unsafe { self.ring_buffer.run_mut(start, len) };
+        // `io::Read` implementations may look at the buffer they are given,
+        // so the free slots are zeroed before they are handed out.
+        region.fill(MaybeUninit::new(0));
+        let region = unsafe { assume_init_mut(region) };
+        let n = source.read(region)?.min(len);
+        self.publish(n);
+
+        Ok(n)
+    }
 }
 
-unsafe impl<T: Copy> Send for RingBuffer<T> {}
+#[cfg(feature = "std")]
+impl<C> Reader<u8, C> {
+    /// Writes every queued byte to `sink` and consumes it, returning the
+    /// number of bytes drained (zero when the ring is empty). If `sink`
+    /// fails, the bytes of the segment it failed on stay queued.
+    pub fn drain_to(&mut self, sink: &mut impl io::Write) -> io::Result<usize> {
+        let (pending, index) = self.pending();
+        let (first, second) = self.segments(index, pending);
+        let first_len = first.len();
 
-impl<T: Copy> std::fmt::Debug for RingBuffer<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "capacity: {:?}, head: {:?}, tail: {:?}",
-            self.inner.capacity, self.inner.head, self.inner.tail,
-        )
+        sink.write_all(first)?;
+        let result = sink.write_all(second);
+        self.release(if result.is_ok() { pending } else { first_len });
+
+        result.map(|()| pending)
     }
 }
 
-impl<T: Copy> Clone for RingBuffer<T> {
-    fn clone(&self) -> Self {
-        Self {
-            inner: self.inner.clone(),
+/// Writes like the non-blocking end of a pipe: a call writes as many bytes
+/// as fit and fails with [`io::ErrorKind::WouldBlock`] when none do, so a
+/// producer decides for itself how to wait before retrying. Once the reader is
+/// gone or the ring is closed it fails with [`io::ErrorKind::BrokenPipe`].
+/// Since `WouldBlock` is an error, [`io::Write::write_all`] gives up on a full
+/// ring; use the inherent [`Writer::write_all`] to block until everything is
+/// written.
+#[cfg(feature = "std")]
+impl<C> io::Write for Writer<u8, C> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        if self.is_disconnected() || self.is_closed() {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+
+        match BufferWriter::write(self, buffer) {
+            0 if !buffer.is_empty() => Err(io::ErrorKind::WouldBlock.into()),
+            n => Ok(n),
         }
     }
+
+    /// Everything written is already visible to the reader.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
-impl<T: Copy> RingBuffer<T> {
-    pub fn new(capacity: usize) -> Result<(Writer<T>, Reader<T>), RingBufferError> {
-        let mut buffer = Vec::<T>::with_capacity(capacity);
-        let t = unsafe { MaybeUninit::<T>::zeroed().assume_init() };
-        (0..capacity).for_each(|_| buffer.push(t));
-        let buffer_ptr = Box::into_raw(Box::new(buffer));
+/// Blocks like the reading end of a pipe: a call waits until at least one
+/// byte is queued and reads as many as fit. `Ok(0)` means end of stream,
+/// i.e. the writer is gone or has closed the ring and everything it wrote
+/// has been read, so an empty ring is never mistaken for it. Use
+/// [`Reader::try_read`] where waiting is not wanted.
+#[cfg(feature = "std")]
+impl<C> io::Read for Reader<u8, C> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut backoff = Backoff::new();
 
-        let ring_buffer = Self {
-            inner: RingBufferInner {
-                buffer: NonNull::new(buffer_ptr).ok_or(RingBufferError::Initialize)?,
-                capacity: capacity.try_into().unwrap(),
-                head: 0.into(),
-                tail: 0.into(),
+        while !buffer.is_empty() {
+            let finished = self.is_finished();
+            match BufferReader::read(self, buffer) {
+                0 if finished => break,
+                0 if backoff.is_completed() => self.wait_for_data(None),
+                0 => backoff.wait(),
+                n => return Ok(n),
             }
-            .into(),
-        };
-        let writer = Writer::new(ring_buffer.clone());
-        let reader = Reader::new(ring_buffer);
+        }
 
-        Ok((writer, reader))
+        Ok(0)
     }
+}
+
+pub struct PopGuard<'a, T: Copy, C = ()> {
+    reader: &'a mut Reader<T, C>,
+    index: usize,
+}
+
+impl<T: Copy, C> Deref for PopGuard<'_, T, C> {
+    type Target = T;
 
     #[inline(always)]
-    pub fn as_mut(&self) -> &mut Vec<T> {
-        unsafe { self.inner.buffer.as_ptr().as_mut().unwrap() }
+    fn deref(&self) -> &T {
+        self.reader.slot_ref(self.index)
     }
+}
 
-    #[inline(always)]
-    pub fn as_ref(&self) -> &Vec<T> {
-        unsafe { self.inner.buffer.as_ref() }
+impl<T: Copy, C> PopGuard<'_, T, C> {
+    pub fn consume(self) {
+        self.reader.release(1);
     }
+}
+
+pub struct ReadChunk<'a, T: Copy, C = ()> {
+    reader: &'a mut Reader<T, C>,
+    index: usize,
+    len: usize,
+}
 
+impl<T: Copy, C> ReadChunk<'_, T, C> {
     #[inline(always)]
-    pub fn capacity(&self) -> u32 {
-        self.inner.capacity
+    pub fn len(&self) -> usize {
+        self.len
     }
 
     #[inline(always)]
-    pub fn head_index(&self) -> u32 {
-        self.inner.head.load(Ordering::SeqCst)
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
+    /// Returns the exposed elements in ring order: the run up to the end of
+    /// the storage, then the part that wrapped around to the front.
     #[inline(always)]
-    pub fn tail_index(&self) -> u32 {
-        self.inner.tail.load(Ordering::SeqCst)
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        self.reader.segments(self.index, self.len)
     }
 
+    /// Consumes the first `count` exposed elements, freeing their slots for
+    /// the writer. The rest stay queued.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is more than were exposed.
+    pub fn consume(self, count: usize) {
+        assert!(
+            count <= self.len,
+            "consumed more elements than were exposed"
+        );
+
+        self.reader.release(count);
+    }
+}
+
+pub struct Drain<'a, T: Copy, C = ()> {
+    reader: &'a mut Reader<T, C>,
+    remaining: usize,
+}
+
+impl<T: Copy, C> Iterator for Drain<'_, T, C> {
+    type Item = T;
+
     #[inline(always)]
-    fn advance_head_index(&self, offset: u32) -> u32 {
-        self.inner.head.fetch_add(offset, Ordering::SeqCst)
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        // Counted from a published write index, and only this reader
+        // consumes, so the element is still there.
+        self.reader.try_pop()
     }
 
     #[inline(always)]
-    fn advance_tail_index(&self, offset: u32) -> u32 {
-        self.inner.tail.fetch_add(offset, Ordering::SeqCst)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
-pub struct Writer<T: Copy> {
-    ring_buffer: RingBuffer<T>,
+impl<T: Copy, C> ExactSizeIterator for Drain<'_, T, C> {}
+
+impl<T: Copy, C> core::iter::FusedIterator for Drain<'_, T, C> {}
+
+/// Blocking iterator returned by [`Reader::iter`].
+#[cfg(feature = "std")]
+pub struct Iter<'a, T: Copy, C = ()> {
+    reader: &'a mut Reader<T, C>,
 }
 
-impl<T: Copy> Clone for Writer<T> {
-    fn clone(&self) -> Self {
-        Self {
-            ring_buffer: self.ring_buffer.clone(),
-        }
+#[cfg(feature = "std")]
+impl<T: Copy, C> Iterator for Iter<'_, T, C> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.reader.pop_wait()
     }
 }
 
-impl<T: Copy> BufferWriter<T> for Writer<T> {
-    #[inline(always)]
-    fn available(&self, size: u32) -> (u32, u32) {
-        let head_index = self.ring_buffer.head_index();
-        let tail_index = self.ring_buffer.tail_index();
-        let capacity = self.ring_buffer.capacity();
+/// Blocking iterator that owns the reader, like [`Reader::iter`].
+#[cfg(feature = "std")]
+pub struct IntoIter<T: Copy, C = ()> {
+    reader: Reader<T, C>,
+}
 
-        let available = capacity - head_index.wrapping_sub(tail_index);
-        if available >= size {
-            (size, head_index)
-        } else {
-            (0, head_index)
-        }
+#[cfg(feature = "std")]
+impl<T: Copy, C> Iterator for IntoIter<T, C> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.reader.pop_wait()
     }
+}
 
-    #[inline(always)]
-    fn get_mut(&mut self, index: u32) -> &mut T {
-        let index = index % self.ring_buffer.capacity();
+#[cfg(feature = "std")]
+impl<T: Copy, C> IntoIterator for Reader<T, C> {
+    type Item = T;
+    type IntoIter = IntoIter<T, C>;
+
+    fn into_iter(self) -> IntoIter<T, C> {
+        IntoIter { reader: self }
+    }
+}
 
-        self.ring_buffer.as_mut().get_mut(index as usize).unwrap()
+#[cfg(feature = "std")]
+impl<'a, T: Copy, C> IntoIterator for &'a mut Reader<T, C> {
+    type Item = T;
+    type IntoIter = Iter<'a, T, C>;
+
+    fn into_iter(self) -> Iter<'a, T, C> {
+        self.iter()
     }
+}
+
+pub struct WriteChunk<'a, T: Copy, C = ()> {
+    writer: &'a mut Writer<T, C>,
+    index: usize,
+    len: usize,
+    // Leading slots written through `fill`, which may be published safely.
+    filled: usize,
+}
 
+impl<T: Copy, C> WriteChunk<'_, T, C> {
     #[inline(always)]
-    fn advance_index(&mut self, offset: u32) {
-        self.ring_buffer.advance_head_index(offset);
+    pub fn len(&self) -> usize {
+        self.len
     }
 
     #[inline(always)]
-    fn write(&mut self, buffer: &[T]) -> u32 {
-        let (available, index) = self.available(buffer.len() as u32);
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 
-        if available > 0 {
-            for offset in 0..available {
-                let data = self.get_mut(index + offset);
-                *data = buffer[offset as usize];
-            }
-            self.advance_index(available);
+    /// Returns the claimed slots in ring order: the run up to the end of the
+    /// storage, then the part that wrapped around to the front. The second
+    /// slice is empty unless the claim crosses the end. The slots may never
+    /// have been written, like [`Vec::spare_capacity_mut`].
+    pub fn as_mut_slices(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        self.writer.segments_mut(self.index, self.len)
+    }
 
-            available
-        } else {
-            0
+    /// Writes `values` into the claimed slots after those filled so far and
+    /// returns how many fit.
+    pub fn fill(&mut self, values: &[T]) -> usize {
+        let filled = self.filled;
+        let n = values.len().min(self.len - filled);
+        let (first, second) = self.as_mut_slices();
+
+        for (slot, value) in first
+            .iter_mut()
+            .chain(second.iter_mut())
+            .skip(filled)
+            .zip(&values[..n])
+        {
+            slot.write(*value);
         }
+        self.filled += n;
+
+        n
+    }
+
+    /// Publishes every slot written through [`WriteChunk::fill`] and gives
+    /// up the rest.
+    pub fn commit_filled(self) {
+        let filled = self.filled;
+
+        // `fill` wrote each of these slots.
+        unsafe { self.commit(filled) };
+    }
+
+    /// Publishes the first `count` claimed slots and gives up the rest.
+    ///
+    /// # Safety
+    ///
+    /// The first `count` slots of [`WriteChunk::as_mut_slices`] must have
+    /// been written, since the reader takes them as initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is more than were claimed.
+    pub unsafe fn commit(self, count: usize) {
+        assert!(count <= self.len, "committed more slots than were claimed");
+
+        self.writer.publish(count);
     }
 }
 
-impl<T: Copy> Writer<T> {
-    fn new(ring_buffer: RingBuffer<T>) -> Self {
-        Self { ring_buffer }
+#[cfg(feature = "std")]
+const SPIN_LIMIT: u32 = 64;
+#[cfg(feature = "std")]
+const YIELD_LIMIT: u32 = 128;
+#[cfg(feature = "std")]
+const BUSY_SPIN_SHIFT: u32 = 6;
+#[cfg(feature = "std")]
+const PARK_TIMEOUT: Duration = Duration::from_micros(100);
+#[cfg(feature = "std")]
+const WAKE_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// Wait policy for the blocking calls: busy-spin briefly, then yield, then
+/// park in short slices. Parked threads are also woken early by
+/// [`CancelToken::cancel`].
+#[cfg(feature = "std")]
+pub(crate) struct Backoff {
+    step: u32,
+}
+
+#[cfg(feature = "std")]
+impl Backoff {
+    pub(crate) fn new() -> Self {
+        Self { step: 0 }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.step = 0;
+    }
+
+    /// Returns `true` once spinning and yielding have run their course and
+    /// the caller should rather sleep until it is woken.
+    pub(crate) fn is_completed(&self) -> bool {
+        self.step >= YIELD_LIMIT
+    }
+
+    pub(crate) fn wait(&mut self) {
+        if self.step < SPIN_LIMIT {
+            core::hint::spin_loop();
+        } else if self.step < YIELD_LIMIT {
+            std::thread::yield_now();
+        } else {
+            std::thread::park_timeout(PARK_TIMEOUT);
+        }
+        self.step = self.step.saturating_add(1);
+    }
+
+    /// Waits without parking, for the busy calls: spins twice as long each
+    /// step, up to `2^BUSY_SPIN_SHIFT` times, then yields.
+    pub(crate) fn snooze(&mut self) {
+        if self.step <= BUSY_SPIN_SHIFT {
+            for _ in 0..1u32 << self.step {
+                core::hint::spin_loop();
+            }
+        } else {
+            std::thread::yield_now();
+        }
+        self.step = self.step.saturating_add(1);
     }
 }
 
-pub struct Reader<T: Copy> {
-    ring_buffer: RingBuffer<T>,
+/// Where one side of a ring sleeps until the other side moves its index or
+/// goes away.
+///
+/// The sleeper announces itself in `parked` and then re-checks its
+/// condition; the other side publishes and then looks at `parked`. Closing
+/// the gap between the two for good takes a `SeqCst` fence on every index
+/// store, which costs the non-blocking calls several times their burst-1
+/// throughput. Unless the ring is `fenced`, only the sleeper fences, and a
+/// thread sleeps for at most [`WAKE_TIMEOUT`] at a time, which bounds the
+/// rare wake-up that slips through. Tasks have no such fallback, so rings
+/// polled from futures are always fenced.
+struct Waiter {
+    parked: AtomicBool,
+    fenced: bool,
+    #[cfg(feature = "std")]
+    sleeper: Mutex<Option<Sleeper>>,
+    // Signalled instead of, or as well as, the sleeper. Here `parked`
+    // stands for "armed": the first notify after the reader arms it
+    // signals and disarms, so a burst costs one system call.
+    #[cfg(all(unix, feature = "notify"))]
+    notifier: OnceLock<Notifier>,
 }
 
-impl<T: Copy> Clone for Reader<T> {
-    fn clone(&self) -> Self {
+#[cfg(feature = "std")]
+#[derive(Clone)]
+enum Sleeper {
+    Thread(Thread),
+    #[cfg(feature = "async")]
+    Task(Waker),
+}
+
+impl Waiter {
+    fn new(fenced: bool) -> Self {
         Self {
-            ring_buffer: self.ring_buffer.clone(),
+            parked: AtomicBool::new(false),
+            fenced,
+            #[cfg(feature = "std")]
+            sleeper: Mutex::new(None),
+            #[cfg(all(unix, feature = "notify"))]
+            notifier: OnceLock::new(),
         }
     }
-}
 
-impl<T: Copy> BufferReader<T> for Reader<T> {
-    #[inline(always)]
-    fn filled(&self, size: u32) -> (u32, u32) {
-        let head_index = self.ring_buffer.head_index();
-        let tail_index = self.ring_buffer.tail_index();
+    /// Parks the current thread until `ready` returns `true` or `deadline`
+    /// passes. Spurious wake-ups just re-check both.
+    #[cfg(feature = "std")]
+    fn wait_until(&self, deadline: Option<Instant>, ready: impl Fn() -> bool) {
+        *self.sleeper.lock().unwrap() = Some(Sleeper::Thread(thread::current()));
 
-        let filled = head_index.wrapping_sub(tail_index);
-        if filled >= size {
-            (size, tail_index)
-        } else {
-            (0, tail_index)
+        loop {
+            self.parked.store(true, Ordering::Release);
+            fence(Ordering::SeqCst);
+            if ready() {
+                break;
+            }
+
+            let timeout = match deadline {
+                None => WAKE_TIMEOUT,
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(left) if !left.is_zero() => left.min(WAKE_TIMEOUT),
+                    _ => break,
+                },
+            };
+            thread::park_timeout(timeout);
         }
+        self.parked.store(false, Ordering::Relaxed);
     }
 
-    #[inline(always)]
-    fn get(&self, index: u32) -> &T {
-        let index = index % self.ring_buffer.capacity();
+    /// Makes `waker` the one woken by the next [`Waiter::notify`]. The
+    /// caller re-checks its condition afterwards, as a sleeping thread
+    /// would.
+    #[cfg(feature = "async")]
+    fn register(&self, waker: &Waker) {
+        {
+            let mut sleeper = self.sleeper.lock().unwrap();
+            if !matches!(&*sleeper, Some(Sleeper::Task(current)) if current.will_wake(waker)) {
+                *sleeper = Some(Sleeper::Task(waker.clone()));
+            }
+        }
+        self.parked.store(true, Ordering::Release);
+        fence(Ordering::SeqCst);
+    }
 
-        self.ring_buffer.as_ref().get(index as usize).unwrap()
+    /// Stops [`Waiter::notify`] from waking the registered task, once it
+    /// no longer waits.
+    #[cfg(feature = "async")]
+    fn unregister(&self) {
+        self.parked.store(false, Ordering::Relaxed);
     }
 
+    // Called on every index store, so the common case is a single load.
+    #[cfg_attr(feature = "no-panic", no_panic::no_panic)]
     #[inline(always)]
-    fn advance_index(&mut self, offset: u32) {
-        self.ring_buffer.advance_tail_index(offset);
+    fn notify(&self) {
+        if self.fenced {
+            fence(Ordering::SeqCst);
+        }
+        if self.parked.load(Ordering::Acquire) {
+            wake(self);
+        }
     }
+}
 
-    #[inline(always)]
-    fn read(&mut self, buffer: &mut [T]) -> u32 {
-        let (filled, index) = self.filled(buffer.len() as u32);
+// Out of line and `extern "C"`, so a panic in here aborts instead of
+// unwinding into the transfer calls, which keeps those free of panic paths.
+// Neither locking nor unparking panics in practice, and the lock is only
+// ever held briefly, by a sleeper registering itself. A waker is called
+// after the lock is released, in case it polls its task right away.
+#[cold]
+#[inline(never)]
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+extern "C" fn wake(waiter: &Waiter) {
+    #[cfg(all(unix, feature = "notify"))]
+    if let Some(notifier) = waiter.notifier.get() {
+        if waiter.parked.swap(false, Ordering::AcqRel) {
+            notifier.signal();
+        }
+    }
 
-        if filled > 0 {
-            for offset in 0..filled {
-                buffer[offset as usize] = *self.get(index + offset);
-            }
-            self.advance_index(filled);
+    #[cfg(feature = "std")]
+    {
+        let sleeper = match waiter.sleeper.lock() {
+            Ok(sleeper) => sleeper.clone(),
+            Err(_) => return,
+        };
 
-            filled
-        } else {
-            0
+        match sleeper {
+            Some(Sleeper::Thread(thread)) => thread.unpark(),
+            #[cfg(feature = "async")]
+            Some(Sleeper::Task(waker)) => waker.wake(),
+            None => {}
         }
     }
 }
 
-impl<T: Copy> Reader<T> {
-    fn new(ring_buffer: RingBuffer<T>) -> Self {
-        Self { ring_buffer }
+/// Safety: every element of `slots` must be initialized.
+#[inline(always)]
+unsafe fn assume_init<T>(slots: &[MaybeUninit<T>]) -> &[T] {
+    &*(slots as *const [MaybeUninit<T>] as *const [T])
+}
+
+/// Safety: every element of `slots` must be initialized.
+#[cfg(feature = "std")]
+#[inline(always)]
+unsafe fn assume_init_mut<T>(slots: &mut [MaybeUninit<T>]) -> &mut [T] {
+    &mut *(slots as *mut [MaybeUninit<T>] as *mut [T])
+}
+
+fn page_size() -> usize {
+    #[cfg(target_os = "linux")]
+    {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if page_size > 0 {
+            return page_size as usize;
+        }
     }
+
+    4096
 }
 
-#[derive(Debug)]
+/// The other half of the ring has been dropped, or, for a reader, the
+/// writer has closed it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Disconnected;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TransferError {
+    Cancelled {
+        transferred: usize,
+    },
+    Disconnected {
+        transferred: usize,
+    },
+    /// The writer closed the ring, see [`Writer::close`].
+    Closed {
+        transferred: usize,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DrainError {
+    Disconnected,
+    Timeout,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryReadError {
+    /// The writer has been dropped.
+    Disconnected,
+    /// The writer closed this ring, see [`Writer::close`].
+    Closed,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryWriteError {
+    /// The reader has been dropped.
+    Disconnected,
+    /// This ring was closed, see [`Writer::close`].
+    Closed,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TimeoutError {
+    Timeout,
+    Disconnected,
+    /// The writer closed the ring, see [`Writer::close`].
+    Closed,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AdvanceError {
+    NotQueued { pending: usize },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecoverError {
+    WriterAlive,
+    /// The ring was closed, see [`Writer::close`].
+    Closed,
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum RingBufferError {
     Initialize,
+    /// A capacity of zero was asked for.
+    ZeroCapacity,
+    /// The capacity is too large to index or to allocate storage for.
+    CapacityOverflow,
+    /// The allocator could not provide storage for the ring.
+    AllocationFailed,
+    InvalidRegion,
+    MemLockFailed {
+        errno: i32,
+    },
+    /// A [`crate::static_ring::StaticRingBuffer`] was split a second time.
+    AlreadySplit,
+    /// The low watermark is not below the high one, or the high one is
+    /// above the capacity.
+    InvalidWatermarks,
+    /// A new capacity cannot hold the elements still queued.
+    CapacityBelowLen,
+    /// The halves belong to different rings, or other handles to the ring
+    /// are still alive.
+    NotExclusive,
+    /// The alignment asked for is not a power of two, or is larger than
+    /// the backing memory can be aligned to.
+    InvalidAlignment,
+    /// Mapping huge pages for the ring failed, typically because none are
+    /// reserved.
+    HugePagesUnavailable {
+        errno: i32,
+    },
+}
+
+impl core::fmt::Display for RingBufferError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Initialize => f.write_str("failed to initialize the ring buffer"),
+            Self::ZeroCapacity => f.write_str("ring buffer capacity must be non-zero"),
+            Self::CapacityOverflow => f.write_str("ring buffer capacity is too large"),
+            Self::AllocationFailed => f.write_str("failed to allocate ring buffer storage"),
+            Self::InvalidRegion => f.write_str("memory region cannot hold a ring buffer"),
+            Self::MemLockFailed { errno } => {
+                write!(f, "failed to lock ring buffer memory (errno {errno})")
+            }
+            Self::AlreadySplit => f.write_str("ring buffer has already been split"),
+            Self::InvalidWatermarks => f.write_str("ring buffer watermarks are out of order"),
+            Self::CapacityBelowLen => {
+                f.write_str("ring buffer capacity is below the number of queued elements")
+            }
+            Self::NotExclusive => f.write_str("ring buffer halves are not exclusively held"),
+            Self::InvalidAlignment => f.write_str("ring buffer alignment cannot be provided"),
+            Self::HugePagesUnavailable { errno } => {
+                write!(
+                    f,
+                    "failed to map huge pages for the ring buffer (errno {errno})"
+                )
+            }
+        }
+    }
 }
+
+#[cfg(feature = "std")]
+impl std::error::Error for RingBufferError {}
+
+#[cfg(all(test, not(loom)))]
+mod tests;
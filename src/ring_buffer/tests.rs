@@ -0,0 +1,2438 @@
+use super::*;
+
+#[test]
+fn write_array_is_all_or_nothing_when_capacity_is_not_a_multiple() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(10).unwrap();
+
+    assert_eq!(writer.write_array([1, 2, 3]), Ok(()));
+    assert_eq!(writer.write_array([4, 5, 6]), Ok(()));
+    assert_eq!(writer.write_array([7, 8, 9]), Ok(()));
+    // One slot is left, so the next record is handed back whole.
+    assert_eq!(writer.write_array([10, 11, 12]), Err([10, 11, 12]));
+
+    assert_eq!(reader.read_array::<3>(), Some([1, 2, 3]));
+    // The retry now fits, straddling the end of the storage.
+    assert_eq!(writer.write_array([10, 11, 12]), Ok(()));
+    assert_eq!(reader.read_array::<3>(), Some([4, 5, 6]));
+    assert_eq!(reader.read_array::<3>(), Some([7, 8, 9]));
+    assert_eq!(reader.read_array::<3>(), Some([10, 11, 12]));
+    assert_eq!(reader.read_array::<3>(), None);
+}
+
+#[test]
+fn write_array_interleaves_with_read_array() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(7).unwrap();
+    let mut next = 0;
+    let mut expected = 0;
+
+    for round in 0..100 {
+        while writer.write_array([next, next + 1]).is_ok() {
+            next += 2;
+        }
+        for _ in 0..=round % 3 {
+            match reader.read_array::<2>() {
+                Some(record) => {
+                    assert_eq!(record, [expected, expected + 1]);
+                    expected += 2;
+                }
+                None => break,
+            }
+        }
+    }
+    while let Some(record) = reader.read_array::<2>() {
+        assert_eq!(record, [expected, expected + 1]);
+        expected += 2;
+    }
+
+    assert_eq!(expected, next);
+    assert_eq!(reader.read_array::<1>(), None);
+}
+
+#[test]
+fn pop_ref_dropped_without_consuming_keeps_the_element() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(4).unwrap();
+    writer.write(&[1, 2]);
+
+    {
+        let guard = reader.pop_ref().unwrap();
+        assert_eq!(*guard, 1);
+    }
+
+    let mut buffer = [0; 2];
+    assert_eq!(reader.read(&mut buffer), 2);
+    assert_eq!(buffer, [1, 2]);
+}
+
+#[test]
+fn pop_ref_consume_moves_to_the_next_element() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(4).unwrap();
+    writer.write(&[1, 2]);
+
+    reader.pop_ref().unwrap().consume();
+    let guard = reader.pop_ref().unwrap();
+    assert_eq!(*guard, 2);
+    guard.consume();
+
+    assert!(reader.pop_ref().is_none());
+}
+
+// A byte ring of 16 whose indices have been moved to two slots before the
+// end of the storage.
+fn peek_ring() -> (Writer<u8>, Reader<u8>) {
+    let (mut writer, mut reader) = RingBuffer::<u8>::new(16).unwrap();
+    let mut filler = [0; 14];
+    assert_eq!(writer.write(&filler), 14);
+    assert_eq!(reader.read(&mut filler), 14);
+
+    (writer, reader)
+}
+
+#[test]
+fn peek_at_reads_both_sides_of_the_wrap() {
+    let (mut writer, mut reader) = peek_ring();
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5]), 5);
+
+    // Offsets 0 and 1 sit before the end of the storage, 2 to 4 after it.
+    let peeked: Vec<_> = (0..5).map(|offset| reader.peek_at(offset)).collect();
+    assert_eq!(peeked, [Some(1), Some(2), Some(3), Some(4), Some(5)]);
+
+    // Nothing was consumed.
+    let mut buffer = [0; 5];
+    assert_eq!(reader.read(&mut buffer), 5);
+    assert_eq!(buffer, [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn peek_at_past_what_is_queued_returns_none() {
+    let (mut writer, mut reader) = peek_ring();
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+
+    assert_eq!(reader.peek_at(3), None);
+    assert_eq!(reader.peek_at(16), None);
+    assert_eq!(reader.peek_at(usize::MAX), None);
+
+    assert_eq!(reader.read(&mut [0]), 1);
+    assert_eq!(reader.peek_at(1), Some(3));
+    assert_eq!(reader.peek_at(2), None);
+}
+
+// Moves everything from `writer` to `reader` through trait objects only, a
+// chunk at a time so the transfer wraps the ring several times.
+fn transfer_dyn(writer: &mut dyn BufferWriter<u64>, reader: &mut dyn BufferReader<u64>) {
+    let source: Vec<u64> = (0..100).collect();
+    let mut received = Vec::new();
+    let mut chunk = [0; 5];
+
+    let mut written = 0;
+    while received.len() < source.len() {
+        written += writer.write(&source[written..(written + 5).min(source.len())]);
+        let n = reader.read(&mut chunk);
+        received.extend_from_slice(&chunk[..n]);
+    }
+
+    assert_eq!(received, source);
+}
+
+#[test]
+fn transfer_through_dyn_lock_free() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(7).unwrap();
+    transfer_dyn(&mut writer, &mut reader);
+
+    let (writer, reader) = RingBuffer::<u64>::new(7).unwrap();
+    let mut writer: Box<dyn BufferWriter<u64>> = Box::new(writer);
+    let mut reader: Box<dyn BufferReader<u64>> = Box::new(reader);
+    transfer_dyn(&mut *writer, &mut *reader);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn transfer_through_dyn_mutex() {
+    let (mut writer, mut reader) = crate::vecdeque::ring_buffer::<u64>(7);
+    transfer_dyn(&mut writer, &mut reader);
+
+    let (writer, reader) = crate::vecdeque::ring_buffer::<u64>(7);
+    let mut writer: Box<dyn BufferWriter<u64>> = Box::new(writer);
+    let mut reader: Box<dyn BufferReader<u64>> = Box::new(reader);
+    transfer_dyn(&mut *writer, &mut *reader);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn occupancy_histogram_counts_each_fill_level() {
+    // With eight slots every fill level below full has a bucket of its own.
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(8).unwrap();
+    for value in 0..8 {
+        assert_eq!(writer.write(&[value]), 1);
+    }
+    assert_eq!(writer.occupancy_histogram(), [0, 1, 1, 1, 1, 1, 1, 2]);
+
+    writer.reset_occupancy_histogram();
+    assert_eq!(writer.occupancy_histogram(), [0; OCCUPANCY_BUCKETS]);
+
+    // Publishes that cross the wrap land by fill level as well.
+    let mut buffer = [0; 6];
+    reader.read(&mut buffer);
+    writer.write(&[0; 3]);
+    writer.write(&[0; 2]);
+    assert_eq!(writer.occupancy_histogram(), [0, 0, 0, 0, 0, 1, 0, 1]);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn occupancy_histogram_buckets_are_fractions_of_the_capacity() {
+    // 64 slots make buckets of eight fill levels each.
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(64).unwrap();
+    let mut buffer = [0; 64];
+    for fill in [1, 7, 8, 20, 33, 63, 64] {
+        writer.write(&buffer[..fill]);
+        reader.read(&mut buffer[..fill]);
+    }
+    assert_eq!(writer.occupancy_histogram(), [2, 1, 1, 0, 1, 0, 0, 2]);
+}
+
+// The raw bytes of every slot, whatever the indices say about them.
+fn storage_bytes<T: Copy>(ring_buffer: &RingBuffer<T>) -> Vec<u8> {
+    let len = core::mem::size_of::<T>() * ring_buffer.capacity();
+    let bytes = ring_buffer.storage().cast::<u8>();
+
+    (0..len)
+        .map(|offset| unsafe { core::ptr::read_volatile(bytes.add(offset)) })
+        .collect()
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn stats_count_a_known_workload() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(512).unwrap();
+    let mut buffer = [0; 112];
+
+    // Reads on the empty ring fail; an empty buffer is no failure.
+    assert_eq!(reader.read(&mut buffer), 0);
+    assert_eq!(reader.try_pop(), None);
+    assert_eq!(reader.read(&mut []), 0);
+
+    // 1000 written and 600 read, the occupancy climbing by 20 a round up
+    // to 430 right after the last publish.
+    for _ in 0..20 {
+        assert_eq!(writer.write(&buffer[..50]), 50);
+        assert_eq!(reader.read(&mut buffer[..30]), 30);
+    }
+    assert_eq!(
+        writer.stats(),
+        Stats {
+            written: 1000,
+            read: 600,
+            write_failures: 0,
+            read_failures: 2,
+            high_water: 430,
+        }
+    );
+
+    // Filling the ring takes the high water to the capacity, and writes on
+    // the full ring fail; again an empty buffer is no failure.
+    assert_eq!(writer.write(&buffer), 112);
+    assert_eq!(writer.write(&buffer), 0);
+    assert_eq!(writer.try_push(0), Err(0));
+    assert_eq!(writer.write(&[]), 0);
+
+    let stats = Stats {
+        written: 1112,
+        read: 600,
+        write_failures: 2,
+        read_failures: 2,
+        high_water: 512,
+    };
+    assert_eq!(writer.stats(), stats);
+    assert_eq!(reader.stats(), stats);
+}
+
+#[test]
+fn prefault_leaves_slot_contents_intact() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(1000).unwrap();
+    let values: Vec<u32> = (0..1000).map(|value| value * 7).collect();
+    assert_eq!(writer.write(&values), 1000);
+    let mut buffer = vec![0; 1000];
+    assert_eq!(reader.read(&mut buffer), 1000);
+
+    let before = storage_bytes(&writer.ring_buffer);
+    writer.prefault();
+    assert_eq!(storage_bytes(&writer.ring_buffer), before);
+
+    // The ring still works from where the indices were.
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+    assert_eq!(reader.read(&mut buffer[..3]), 3);
+    assert_eq!(buffer[..3], [1, 2, 3]);
+}
+
+#[test]
+fn prefault_does_nothing_once_elements_are_queued() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(4).unwrap();
+    assert_eq!(writer.write(&[1, 2]), 2);
+
+    writer.prefault();
+    let mut buffer = [0; 2];
+    assert_eq!(reader.read(&mut buffer), 2);
+    assert_eq!(buffer, [1, 2]);
+}
+
+// Resident memory of this process in bytes, from `/proc/self/statm`.
+#[cfg(all(target_os = "linux", feature = "std"))]
+fn resident_bytes() -> usize {
+    let statm = std::fs::read_to_string("/proc/self/statm").unwrap();
+    let pages: usize = statm.split_whitespace().nth(1).unwrap().parse().unwrap();
+
+    pages * page_size()
+}
+
+#[cfg(all(target_os = "linux", feature = "std"))]
+#[test]
+fn prefault_makes_the_storage_resident() {
+    const LEN: usize = 64 << 20;
+
+    // Large enough for the allocator to map fresh pages, which only become
+    // resident once touched.
+    let (mut writer, _reader) = RingBuffer::<u8>::new(LEN).unwrap();
+    let before = resident_bytes();
+    writer.prefault();
+    let grown = resident_bytes().saturating_sub(before);
+
+    assert!(grown >= LEN / 2, "grew by {grown} bytes");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn locked_ring_gets_pages_of_its_own() {
+    let (mut writer, mut reader) = RingBuffer::<u8>::new_locked(100).unwrap();
+    let inner = &writer.ring_buffer.inner;
+    assert!(inner.locked.load(Ordering::Relaxed));
+    assert_eq!(inner.buffer.as_ptr() as usize % page_size(), 0);
+    assert_eq!(inner.layout.size(), page_size());
+
+    writer.write(&[1, 2, 3]);
+    let mut buffer = [0; 3];
+    assert_eq!(reader.read(&mut buffer), 3);
+    assert_eq!(buffer, [1, 2, 3]);
+}
+
+// Set in the child process that `locking_fails_under_a_tiny_rlimit` starts.
+#[cfg(target_os = "linux")]
+const MEMLOCK_CHILD: &str = "SPSC_TEST_MEMLOCK_CHILD";
+
+// The limit is per process, so it is lowered in a copy of the test binary
+// running only this test rather than under the other tests.
+#[cfg(target_os = "linux")]
+#[test]
+fn locking_fails_under_a_tiny_rlimit() {
+    if std::env::var_os(MEMLOCK_CHILD).is_some() {
+        memlock_child();
+        return;
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "ring_buffer::tests::locking_fails_under_a_tiny_rlimit",
+            "--test-threads=1",
+            "--nocapture",
+        ])
+        .env(MEMLOCK_CHILD, "1")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+}
+
+#[cfg(target_os = "linux")]
+fn memlock_child() {
+    let page = page_size();
+    let limit = libc::rlimit {
+        rlim_cur: page as libc::rlim_t,
+        rlim_max: page as libc::rlim_t,
+    };
+    unsafe {
+        assert_eq!(libc::setrlimit(libc::RLIMIT_MEMLOCK, &limit), 0);
+        // Root may lock past the limit, so give that up first.
+        if libc::geteuid() == 0 {
+            assert_eq!(libc::setgid(65534), 0);
+            assert_eq!(libc::setuid(65534), 0);
+        }
+    }
+
+    // A single page fits, since nothing else of the ring is locked.
+    let halves = RingBuffer::<u8>::new_locked(page).unwrap();
+
+    // A second page while the first is still held does not.
+    let result = RingBuffer::<u8>::new_locked(page).map(|_| ());
+    assert!(matches!(
+        result,
+        Err(RingBufferError::MemLockFailed { errno }) if errno == libc::ENOMEM || errno == libc::EPERM
+    ));
+
+    // Dropping the ring gives the page back.
+    drop(halves);
+    RingBuffer::<u8>::new_locked(page).unwrap();
+}
+
+#[test]
+fn wait_drained_returns_once_the_reader_caught_up() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(8).unwrap();
+    writer.write(&[1, 2, 3]);
+
+    let consumer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        let mut buffer = [0; 3];
+        assert_eq!(reader.read(&mut buffer), 3);
+        reader
+    });
+
+    assert_eq!(writer.wait_drained(), Ok(()));
+    assert_eq!(writer.free(), (8, 3));
+    drop(consumer.join().unwrap());
+}
+
+#[test]
+fn wait_drained_times_out_while_elements_stay_queued() {
+    let (mut writer, _reader) = RingBuffer::<u32>::new(8).unwrap();
+    writer.write(&[1]);
+
+    let started = Instant::now();
+    assert_eq!(
+        writer.wait_drained_timeout(Duration::from_millis(20)),
+        Err(DrainError::Timeout)
+    );
+    assert!(started.elapsed() >= Duration::from_millis(20));
+
+    // Nothing queued means nothing to wait for, even with no time left.
+    let (writer, _reader) = RingBuffer::<u32>::new(8).unwrap();
+    assert_eq!(writer.wait_drained_timeout(Duration::ZERO), Ok(()));
+}
+
+#[test]
+fn wait_drained_fails_when_the_reader_drops_while_waiting() {
+    let (mut writer, reader) = RingBuffer::<u32>::new(8).unwrap();
+    writer.write(&[1, 2]);
+
+    let consumer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        drop(reader);
+    });
+
+    assert_eq!(writer.wait_drained(), Err(DrainError::Disconnected));
+    consumer.join().unwrap();
+}
+
+#[test]
+fn close_and_drain_lets_the_reader_finish_first() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(8).unwrap();
+    writer.write(&[1, 2, 3]);
+
+    let consumer = std::thread::spawn(move || {
+        let mut buffer = [0; 3];
+        while reader.read(&mut buffer) == 0 {
+            std::thread::yield_now();
+        }
+        buffer
+    });
+
+    assert_eq!(writer.close_and_drain(), Ok(()));
+    assert_eq!(consumer.join().unwrap(), [1, 2, 3]);
+}
+
+// Cancels `token` once the other thread has had time to block, and returns
+// when that happened.
+#[cfg(feature = "std")]
+fn cancel_later(token: &CancelToken) -> std::thread::JoinHandle<Instant> {
+    let token = token.clone();
+
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        token.cancel();
+        Instant::now()
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn cancel_wakes_a_reader_blocked_on_an_empty_ring() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(8).unwrap();
+    writer.write(&[1, 2]);
+
+    let token = CancelToken::new();
+    let canceller = cancel_later(&token);
+    let mut buffer = [0; 5];
+    let result = reader.read_exact_cancellable(&mut buffer, &token);
+    let returned = Instant::now();
+
+    assert_eq!(result, Err(TransferError::Cancelled { transferred: 2 }));
+    assert_eq!(buffer[..2], [1, 2]);
+    let cancelled = canceller.join().unwrap();
+    assert!(returned.saturating_duration_since(cancelled) < Duration::from_millis(200));
+
+    // The token stays cancelled.
+    assert_eq!(
+        reader.read_exact_cancellable(&mut buffer, &token),
+        Err(TransferError::Cancelled { transferred: 0 })
+    );
+    drop(writer);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn cancel_wakes_a_writer_blocked_on_a_full_ring() {
+    let (mut writer, reader) = RingBuffer::<u32>::new(4).unwrap();
+
+    let token = CancelToken::new();
+    let canceller = cancel_later(&token);
+    let result = writer.write_all_cancellable(&[1, 2, 3, 4, 5, 6], &token);
+    let returned = Instant::now();
+
+    assert_eq!(result, Err(TransferError::Cancelled { transferred: 4 }));
+    let cancelled = canceller.join().unwrap();
+    assert!(returned.saturating_duration_since(cancelled) < Duration::from_millis(200));
+    assert_eq!(reader.pending().0, 4);
+}
+
+// Waits for the thread blocked on `waiter` to park on it, so that whatever
+// the test does next has to wake it rather than be seen by a poll.
+fn until_parked(waiter: &Waiter) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !waiter.parked.load(Ordering::Acquire) {
+        assert!(Instant::now() < deadline, "the other side never parked");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn a_write_wakes_a_reader_parked_with_a_token() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(8).unwrap();
+    let inner = writer.ring_buffer.clone();
+
+    let consumer = std::thread::spawn(move || {
+        let token = CancelToken::new();
+        let mut buffer = [0; 3];
+        let result = reader.read_exact_cancellable(&mut buffer, &token);
+        (result, buffer)
+    });
+
+    until_parked(&inner.inner.reader_waiter);
+    writer.write(&[1, 2, 3]);
+
+    assert_eq!(consumer.join().unwrap(), (Ok(()), [1, 2, 3]));
+}
+
+#[test]
+fn a_read_wakes_a_writer_parked_with_a_token() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(2).unwrap();
+    let inner = writer.ring_buffer.clone();
+
+    let producer = std::thread::spawn(move || {
+        let token = CancelToken::new();
+        writer.write_all_cancellable(&[1, 2, 3, 4], &token)
+    });
+
+    until_parked(&inner.inner.writer_waiter);
+    let mut buffer = [0; 4];
+    reader.read_exact(&mut buffer).unwrap();
+
+    assert_eq!(producer.join().unwrap(), Ok(()));
+    assert_eq!(buffer, [1, 2, 3, 4]);
+}
+
+#[test]
+fn read_map_into_converts_across_the_wrap() {
+    let (mut writer, mut reader) = RingBuffer::<i16>::new(5).unwrap();
+    writer.write(&[0, 0, 0, 100]);
+    let mut skipped = [0; 3];
+    reader.read(&mut skipped);
+    // The queued run now starts at slot 3 and wraps after slot 4.
+    assert_eq!(writer.write(&[i16::MIN, -1, 16384, i16::MAX]), 4);
+
+    let mut out = [f32::NAN; 6];
+    let n = reader.read_map_into(&mut out, |&sample| sample as f32 / 32768.0);
+
+    assert_eq!(n, 5);
+    assert_eq!(
+        out[..5],
+        [
+            100.0 / 32768.0,
+            -1.0,
+            -1.0 / 32768.0,
+            0.5,
+            32767.0 / 32768.0
+        ]
+    );
+    assert!(out[5].is_nan());
+    assert_eq!(reader.pending().0, 0);
+}
+
+#[test]
+fn read_map_into_stops_at_the_output_length() {
+    let (mut writer, mut reader) = RingBuffer::<i16>::new(4).unwrap();
+    writer.write(&[1, 2, 3]);
+
+    let mut out = [0.0f32; 2];
+    assert_eq!(
+        reader.read_map_into(&mut out, |&sample| f32::from(sample)),
+        2
+    );
+    assert_eq!(out, [1.0, 2.0]);
+    assert_eq!(reader.pending().0, 1);
+}
+
+#[test]
+fn wiping_ring_clears_consumed_slots() {
+    const PATTERN: u64 = 0xa5a5_a5a5_a5a5_a5a5;
+    let slot = |bytes: &[u8], index: usize| bytes[index * 8..(index + 1) * 8].to_vec();
+
+    let (mut writer, mut reader) = RingBuffer::<u64>::new_wiping(4).unwrap();
+    writer.write(&[PATTERN; 4]);
+
+    let mut buffer = [0; 2];
+    assert_eq!(reader.read(&mut buffer), 2);
+    assert_eq!(buffer, [PATTERN; 2]);
+    let bytes = storage_bytes(&reader.ring_buffer);
+    assert_eq!(slot(&bytes, 0), [0; 8]);
+    assert_eq!(slot(&bytes, 1), [0; 8]);
+    assert_eq!(slot(&bytes, 2), PATTERN.to_ne_bytes());
+
+    assert_eq!(reader.read_array::<1>(), Some([PATTERN]));
+    assert_eq!(reader.read_array::<1>(), Some([PATTERN]));
+    assert_eq!(storage_bytes(&reader.ring_buffer), [0; 32]);
+
+    // A read that wraps clears both runs.
+    writer.write(&[PATTERN; 3]);
+    reader.read(&mut buffer[..1]);
+    writer.write(&[PATTERN; 2]);
+    let mut buffer = [0; 4];
+    assert_eq!(reader.read(&mut buffer), 4);
+    assert_eq!(storage_bytes(&reader.ring_buffer), [0; 32]);
+}
+
+#[test]
+fn plain_ring_leaves_consumed_slots_alone() {
+    let (mut writer, mut reader) = RingBuffer::<u8>::new(4).unwrap();
+    writer.write(&[7; 4]);
+    let mut buffer = [0; 4];
+    reader.read(&mut buffer);
+
+    assert_eq!(storage_bytes(&reader.ring_buffer), [7; 4]);
+}
+
+// A byte ring whose next write lands `before_wrap` bytes before the end of
+// the storage.
+fn byte_ring_at(before_wrap: usize) -> Halves<u8, &'static str> {
+    let (mut writer, mut reader) = ring_buffer_with_context::<u8, _>(16, "bytes").unwrap();
+    let mut filler = [0; 16];
+    writer.write(&filler[..16 - before_wrap]);
+    reader.read(&mut filler[..16 - before_wrap]);
+
+    (writer, reader)
+}
+
+#[test]
+fn integers_straddling_the_wrap_round_trip() {
+    for before_wrap in 1..8 {
+        let (mut writer, mut reader) = byte_ring_at(before_wrap);
+        assert_eq!(*writer.context(), "bytes");
+
+        if before_wrap < 2 {
+            writer.write_u16_le(0x0102).unwrap();
+            writer.write_u16_be(0x0304).unwrap();
+            assert_eq!(reader.read_u16_le(), Some(0x0102));
+            assert_eq!(reader.read_u16_be(), Some(0x0304));
+        }
+
+        let (mut writer, mut reader) = byte_ring_at(before_wrap);
+        if before_wrap < 4 {
+            writer.write_u32_le(0x0102_0304).unwrap();
+            assert_eq!(reader.read_u32_le(), Some(0x0102_0304));
+            writer.write_u32_be(0x0506_0708).unwrap();
+            assert_eq!(reader.read_u32_be(), Some(0x0506_0708));
+        }
+
+        let (mut writer, mut reader) = byte_ring_at(before_wrap);
+        writer.write_u64_le(0x0102_0304_0506_0708).unwrap();
+        assert_eq!(reader.read_u64_le(), Some(0x0102_0304_0506_0708));
+        let (mut writer, mut reader) = byte_ring_at(before_wrap);
+        writer.write_u64_be(0x0102_0304_0506_0708).unwrap();
+        assert_eq!(reader.read_u64_be(), Some(0x0102_0304_0506_0708));
+    }
+}
+
+#[test]
+fn integers_straddling_the_wrap_keep_their_byte_order() {
+    let (mut writer, mut reader) = byte_ring_at(2);
+    writer.write_u32_be(0x0102_0304).unwrap();
+    let mut bytes = [0; 4];
+    assert_eq!(reader.read(&mut bytes), 4);
+    assert_eq!(bytes, [1, 2, 3, 4]);
+
+    let (mut writer, mut reader) = byte_ring_at(2);
+    writer.write_u32_le(0x0102_0304).unwrap();
+    assert_eq!(reader.read(&mut bytes), 4);
+    assert_eq!(bytes, [4, 3, 2, 1]);
+}
+
+#[test]
+fn integers_need_every_byte_present() {
+    let (mut writer, mut reader) = byte_ring_at(1);
+    writer.write(&[1, 2, 3]);
+
+    assert_eq!(reader.read_u32_le(), None);
+    assert_eq!(reader.pending().0, 3);
+    writer.write(&[4]);
+    assert_eq!(reader.read_u32_le(), Some(0x0403_0201));
+
+    // A value that does not fit is handed back whole.
+    writer.write(&[0; 13]);
+    assert_eq!(writer.write_u32_be(7), Err(7));
+    assert_eq!(writer.free().0, 3);
+}
+
+#[test]
+fn write_remainder_returns_what_did_not_fit() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(4).unwrap();
+
+    assert_eq!(writer.write_remainder(&[1, 2, 3, 4, 5, 6]), [5, 6]);
+    // A full ring hands everything back.
+    assert_eq!(writer.write_remainder(&[5, 6]), [5, 6]);
+    reader.skip(1);
+    assert_eq!(writer.write_remainder(&[5, 6]), [6]);
+    reader.skip(1);
+    assert!(writer.write_remainder(&[6]).is_empty());
+}
+
+#[test]
+fn write_remainder_loop_feeds_a_slow_consumer_exactly_once() {
+    const TOTAL: u32 = 20_000;
+
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(7).unwrap();
+    let consumer = std::thread::spawn(move || {
+        let mut received = Vec::new();
+        let mut buffer = [0; 3];
+        while received.len() < TOTAL as usize {
+            match reader.read(&mut buffer) {
+                0 => std::thread::yield_now(),
+                n => received.extend_from_slice(&buffer[..n]),
+            }
+            if received.len() % 1000 == 0 {
+                std::thread::sleep(Duration::from_micros(100));
+            }
+        }
+        received
+    });
+
+    let source: Vec<u32> = (0..TOTAL).collect();
+    for chunk in source.chunks(50) {
+        let mut buffer = chunk;
+        while !buffer.is_empty() {
+            buffer = writer.write_remainder(buffer);
+            std::thread::yield_now();
+        }
+    }
+
+    assert_eq!(consumer.join().unwrap(), source);
+}
+
+#[test]
+fn read_sampled_keeps_every_stride_across_the_wrap() {
+    for stride in 1..=5 {
+        let (mut writer, mut reader) = RingBuffer::<u32>::new(8).unwrap();
+        writer.write(&[0; 5]);
+        reader.skip(5);
+        // Queued from slot 5 on, wrapping after three elements.
+        writer.write(&[10, 11, 12, 13, 14, 15, 16, 17]);
+
+        let mut out = [0; 8];
+        let (kept, discarded) = reader.read_sampled(&mut out, stride);
+
+        let expected: Vec<u32> = (10..18).step_by(stride).collect();
+        assert_eq!(out[..kept], expected, "stride {stride}");
+        assert_eq!(kept + discarded, 8);
+        assert_eq!(reader.pending().0, 0);
+    }
+}
+
+#[test]
+fn read_sampled_stops_at_the_last_kept_element() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(16).unwrap();
+    writer.write(&(0..16).collect::<Vec<_>>());
+
+    let mut out = [0; 2];
+    assert_eq!(reader.read_sampled(&mut out, 3), (2, 2));
+    assert_eq!(out, [0, 3]);
+    // Sampling picks up right after the last kept element.
+    assert_eq!(reader.peek(), Some(&4));
+
+    assert_eq!(reader.read_sampled(&mut [], 3), (0, 0));
+    assert_eq!(reader.pending().0, 12);
+
+    let mut out = [0; 8];
+    assert_eq!(reader.read_sampled(&mut out, 5), (3, 9));
+    assert_eq!(out[..3], [4, 9, 14]);
+    assert_eq!(reader.pending().0, 0);
+}
+
+#[test]
+fn both_halves_share_one_context() {
+    let (writer, reader) = ring_buffer_with_context::<u8, _>(4, String::from("shared")).unwrap();
+
+    assert!(std::ptr::eq(writer.context(), reader.context()));
+    assert_eq!(writer.context(), "shared");
+}
+
+#[test]
+fn the_context_is_dropped_once_after_the_last_handle() {
+    struct Context(std::sync::Arc<AtomicUsize>);
+
+    impl Drop for Context {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let drops = std::sync::Arc::new(AtomicUsize::new(0));
+    let new = || ring_buffer_with_context::<u8, _>(4, Context(drops.clone())).unwrap();
+
+    // The writer goes last.
+    let (writer, reader) = new();
+    drop(reader);
+    assert_eq!(drops.load(Ordering::Relaxed), 0);
+    drop(writer);
+    assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+    // The reader goes last.
+    let (writer, reader) = new();
+    drop(writer);
+    assert_eq!(drops.load(Ordering::Relaxed), 1);
+    drop(reader);
+    assert_eq!(drops.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn recovered_writer_continues_the_stream() {
+    let (writer, mut reader) = RingBuffer::<u64>::new(7).unwrap();
+
+    // The first producer dies part way through, with data still queued and
+    // the indices past the end of the storage.
+    let producer = std::thread::spawn(move || {
+        let mut writer = writer;
+        writer.write_all(&(0..402).collect::<Vec<_>>()).unwrap();
+        writer.write_all(&[402, 403, 404]).unwrap();
+        panic!("producer crashed");
+    });
+
+    let mut received = vec![0; 400];
+    reader.read_exact(&mut received).unwrap();
+    assert!(producer.join().is_err());
+    assert!(reader.is_disconnected());
+
+    let mut writer = reader.recover_writer().unwrap();
+    assert!(!reader.is_disconnected());
+    let consumer = std::thread::spawn(move || {
+        let mut rest = vec![0; 603];
+        reader.read_exact(&mut rest).unwrap();
+        rest
+    });
+    writer.write_all(&(405..1003).collect::<Vec<_>>()).unwrap();
+
+    received.extend(consumer.join().unwrap());
+    assert_eq!(received, (0..1003).collect::<Vec<_>>());
+}
+
+#[test]
+fn recover_writer_fails_while_a_writer_is_alive() {
+    let (writer, reader) = RingBuffer::<u64>::new(4).unwrap();
+    assert_eq!(
+        reader.recover_writer().err(),
+        Some(RecoverError::WriterAlive)
+    );
+
+    drop(writer);
+    let recovered = reader.recover_writer().unwrap();
+    // The recovered writer counts as alive too, so there is never a second.
+    assert_eq!(
+        reader.recover_writer().err(),
+        Some(RecoverError::WriterAlive)
+    );
+
+    drop(recovered);
+    assert!(reader.recover_writer().is_ok());
+}
+
+#[test]
+fn peek_iter_walks_the_queue_across_the_wrap_without_consuming() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(7).unwrap();
+    assert_eq!(writer.write(&[0, 0, 0, 0, 0]), 5);
+    assert_eq!(reader.skip(5), 5);
+
+    // Six elements from slot 5 on, so the last four sit at the front.
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5, 6]), 6);
+    assert!(reader.peek_iter().copied().eq(1..=6));
+    assert_eq!(reader.pending().0, 6);
+
+    assert_eq!(reader.skip(3), 3);
+    assert!(reader.peek_iter().copied().eq(4..=6));
+
+    let mut buffer = [0; 3];
+    assert_eq!(reader.read(&mut buffer), 3);
+    assert_eq!(buffer, [4, 5, 6]);
+    assert_eq!(reader.peek_iter().next(), None);
+}
+
+#[test]
+fn peek_iter_length_is_the_queue_length_at_creation() {
+    let (mut writer, reader) = RingBuffer::<u64>::new(8).unwrap();
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+
+    let iter = reader.peek_iter();
+    assert_eq!(iter.len(), reader.pending().0);
+    // Elements published afterwards are not part of it.
+    assert_eq!(writer.write(&[4, 5]), 2);
+    assert_eq!(iter.len(), 3);
+    assert!(iter.copied().eq(1..=3));
+}
+
+#[test]
+fn write_takes_what_fits_when_bursts_do_not_divide_the_capacity() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(10).unwrap();
+    let mut next = 0;
+    let mut expected = 0;
+
+    // Bursts of 4 into 10 slots, drained one element at a time, so every
+    // write is cut short at some point and most land across the wrap.
+    for _ in 0..50 {
+        let burst: Vec<u64> = (next..next + 4).collect();
+        let free = writer.free().0;
+        let written = writer.write(&burst);
+        assert_eq!(written, free.min(4));
+        next += written as u64;
+
+        assert_eq!(reader.read_array::<1>(), Some([expected]));
+        expected += 1;
+    }
+
+    let mut rest = vec![0; 10];
+    let read = reader.read(&mut rest);
+    assert!(rest[..read].iter().copied().eq(expected..next));
+}
+
+#[test]
+fn full_write_stops_at_one_short_of_the_burst() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(4096).unwrap();
+    assert_eq!(writer.write(&[0]), 1);
+
+    // 4095 free slots and a burst of 4096: everything but the last fits.
+    let burst: Vec<u64> = (1..=4096).collect();
+    assert_eq!(writer.write(&burst), 4095);
+    assert_eq!(reader.read_array::<1>(), Some([0]));
+    assert_eq!(writer.write(&burst[4095..]), 1);
+    assert!(reader.peek_iter().copied().eq(1..=4096));
+}
+
+#[test]
+fn read_returns_what_is_queued_across_the_wrap() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(7).unwrap();
+    assert_eq!(writer.write(&[0; 5]), 5);
+    assert_eq!(reader.skip(5), 5);
+
+    // Three elements from slot 5 on, asked for with room for a hundred.
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+    let mut buffer = [0; 100];
+    assert_eq!(reader.read(&mut buffer), 3);
+    assert_eq!(buffer[..3], [1, 2, 3]);
+
+    // Once the writer is gone, a partial read still hands over the rest.
+    assert_eq!(writer.write(&[4, 5, 6, 7, 8, 9]), 6);
+    drop(writer);
+    assert_eq!(reader.read(&mut buffer[..4]), 4);
+    assert_eq!(buffer[..4], [4, 5, 6, 7]);
+    assert_eq!(reader.read(&mut buffer), 2);
+    assert_eq!(buffer[..2], [8, 9]);
+    assert_eq!(reader.read(&mut buffer), 0);
+}
+
+// A ring whose indices both start `before_wrap` steps short of the point
+// where they wrap around.
+fn ring_near_the_wrap(capacity: usize, before_wrap: usize) -> Halves<u64, ()> {
+    let (writer, reader) = RingBuffer::<u64>::new(capacity).unwrap();
+    let start = writer.ring_buffer.inner.wrap.wrapping_sub(before_wrap);
+
+    writer
+        .ring_buffer
+        .inner
+        .head
+        .store(start, Ordering::Relaxed);
+    writer
+        .ring_buffer
+        .inner
+        .tail
+        .store(start, Ordering::Relaxed);
+    writer.cached_tail.set(start);
+    reader.cached_head.set(start);
+
+    (writer, reader)
+}
+
+#[test]
+fn fifo_order_holds_across_the_index_wrap() {
+    for capacity in [4000, 7, 4096] {
+        let (mut writer, mut reader) = ring_near_the_wrap(capacity, capacity / 2 + 1);
+        let start = writer.ring_buffer.head_index();
+
+        // Bursts one short of the capacity, so the slot a burst starts at
+        // moves on every round.
+        let mut buffer = vec![0; capacity - 1];
+        let (mut next, mut expected) = (0u64, 0u64);
+        for _ in 0..5 {
+            let burst: Vec<u64> = (next..next + buffer.len() as u64).collect();
+            next += writer.write(&burst) as u64;
+
+            let read = reader.read(&mut buffer[..capacity / 3 + 1]);
+            for value in &buffer[..read] {
+                assert_eq!(*value, expected, "capacity {capacity}");
+                expected += 1;
+            }
+        }
+        while expected < next {
+            let read = reader.read(&mut buffer);
+            assert!(read > 0);
+            for value in &buffer[..read] {
+                assert_eq!(*value, expected, "capacity {capacity}");
+                expected += 1;
+            }
+        }
+
+        // The indices went past the wrap and came out below where they
+        // started.
+        assert!(writer.ring_buffer.head_index() < start);
+        assert_eq!(reader.pending().0, 0);
+    }
+}
+
+#[test]
+fn peek_slice_copies_without_consuming() {
+    let (mut writer, mut reader) = byte_ring_at(2);
+    assert_eq!(writer.write(&[1, 2, 3, 4]), 4);
+
+    let mut out = [0; 6];
+    assert_eq!(reader.peek_slice(&mut out), 4);
+    assert_eq!(out, [1, 2, 3, 4, 0, 0]);
+    assert_eq!(reader.peek_slice(&mut out[..3]), 3);
+    assert_eq!(out[..3], [1, 2, 3]);
+    assert_eq!(reader.pending().0, 4);
+    assert_eq!(writer.free().0, 12);
+}
+
+#[test]
+fn advance_across_the_wrap_consumes_exactly_n() {
+    let (mut writer, mut reader) = byte_ring_at(2);
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5]), 5);
+
+    assert_eq!(reader.advance(3), Ok(()));
+    assert_eq!(reader.pending().0, 2);
+    assert_eq!(writer.free().0, 14);
+    assert_eq!(reader.try_pop(), Some(4));
+
+    // More than is queued is refused whole.
+    assert_eq!(
+        reader.advance(2),
+        Err(AdvanceError::NotQueued { pending: 1 })
+    );
+    assert_eq!(reader.try_pop(), Some(5));
+    assert_eq!(reader.advance(0), Ok(()));
+}
+
+#[test]
+fn claim_commits_fewer_slots_than_claimed() {
+    let (mut writer, reader) = RingBuffer::<u64>::new(8).unwrap();
+
+    let mut chunk = writer.claim(5).unwrap();
+    assert_eq!(chunk.len(), 5);
+    assert_eq!(chunk.fill(&[1, 2, 3]), 3);
+    // Nothing is visible before the commit.
+    assert_eq!(reader.pending().0, 0);
+    chunk.commit_filled();
+
+    assert_eq!(reader.pending().0, 3);
+    assert!(reader.peek_iter().copied().eq(1..=3));
+
+    // Slots given up by a short commit are claimed again next time.
+    let mut chunk = writer.claim(5).unwrap();
+    let (first, second) = chunk.as_mut_slices();
+    assert_eq!((first.len(), second.len()), (5, 0));
+    first[0].write(4);
+    first[1].write(5);
+    unsafe { chunk.commit(2) };
+    assert!(reader.peek_iter().copied().eq(1..=5));
+
+    // Dropping a chunk publishes nothing.
+    {
+        let mut chunk = writer.claim(3).unwrap();
+        chunk.fill(&[9, 9, 9]);
+    }
+    assert_eq!(reader.pending().0, 5);
+    assert!(writer.claim(4).is_none());
+}
+
+#[test]
+fn claim_spans_the_wrap_in_two_slices() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(8).unwrap();
+    assert_eq!(writer.write(&[0; 6]), 6);
+    assert_eq!(reader.advance(6), Ok(()));
+
+    let mut chunk = writer.claim(5).unwrap();
+    let (first, second) = chunk.as_mut_slices();
+    assert_eq!((first.len(), second.len()), (2, 3));
+
+    // `fill` carries on across the end of the storage.
+    assert_eq!(chunk.fill(&[1, 2]), 2);
+    assert_eq!(chunk.fill(&[3, 4, 5, 6]), 3);
+    assert_eq!(chunk.fill(&[7]), 0);
+    chunk.commit_filled();
+
+    let mut buffer = [0; 8];
+    assert_eq!(reader.read(&mut buffer), 5);
+    assert_eq!(buffer[..5], [1, 2, 3, 4, 5]);
+}
+
+#[test]
+#[should_panic(expected = "committed more slots than were claimed")]
+fn claim_refuses_to_commit_more_than_claimed() {
+    let (mut writer, _reader) = RingBuffer::<u64>::new(4).unwrap();
+    let chunk = writer.claim(2).unwrap();
+
+    unsafe { chunk.commit(3) };
+}
+
+#[test]
+fn a_dropped_read_chunk_consumes_nothing() {
+    let (mut writer, mut reader) = byte_ring_at(8);
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+
+    {
+        // Goes out of scope without `consume`.
+        let chunk = reader.read_chunk(2).unwrap();
+        assert_eq!(chunk.as_slices(), (&[1, 2][..], &[][..]));
+    }
+
+    assert_eq!(reader.pending().0, 3);
+    assert_eq!(writer.free().0, 13);
+    let chunk = reader.read_chunk(8).unwrap();
+    assert_eq!(chunk.as_slices(), (&[1, 2, 3][..], &[][..]));
+}
+
+#[test]
+fn a_read_chunk_consuming_zero_leaves_the_ring_as_it_was() {
+    let (mut writer, mut reader) = byte_ring_at(8);
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+
+    reader.read_chunk(3).unwrap().consume(0);
+    assert_eq!(reader.pending().0, 3);
+    assert!(reader.read_chunk(0).is_none());
+
+    reader.read_chunk(3).unwrap().consume(1);
+    assert_eq!(reader.try_pop(), Some(2));
+}
+
+#[test]
+fn a_read_chunk_spans_the_wrap_in_two_slices() {
+    let (mut writer, mut reader) = byte_ring_at(2);
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5]), 5);
+
+    let chunk = reader.read_chunk(4).unwrap();
+    assert_eq!(chunk.len(), 4);
+    assert_eq!(chunk.as_slices(), (&[1, 2][..], &[3, 4][..]));
+    chunk.consume(3);
+
+    // The slots consumed on both sides of the wrap are free again.
+    assert_eq!(writer.free().0, 14);
+    assert_eq!(reader.try_pop(), Some(4));
+}
+
+#[test]
+#[should_panic(expected = "consumed more elements than were exposed")]
+fn a_read_chunk_refuses_to_consume_past_its_end() {
+    let (mut writer, mut reader) = byte_ring_at(2);
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+
+    reader.read_chunk(2).unwrap().consume(3);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn read_exact_parks_while_a_slow_writer_trickles_in() {
+    const ITEMS: u32 = 20;
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(4).unwrap();
+    let inner = writer.ring_buffer.clone();
+
+    let consumer = std::thread::spawn(move || {
+        let mut buffer = [0; ITEMS as usize];
+        reader.read_exact(&mut buffer).map(|()| buffer)
+    });
+
+    // Each element only goes in once the reader has parked for it, which it
+    // never would while spinning.
+    for value in 0..ITEMS {
+        until_parked(&inner.inner.reader_waiter);
+        writer.write_all(&[value]).unwrap();
+    }
+
+    let expected: Vec<_> = (0..ITEMS).collect();
+    assert_eq!(consumer.join().unwrap().unwrap()[..], expected);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_all_parks_while_a_slow_reader_drains() {
+    const ITEMS: u32 = 40;
+    const CAPACITY: u32 = 4;
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(CAPACITY as usize).unwrap();
+    let inner = writer.ring_buffer.clone();
+
+    let producer = std::thread::spawn(move || {
+        let values: Vec<_> = (0..ITEMS).collect();
+        writer.write_all(&values)
+    });
+
+    // The writer is blocked for as long as more is left than the ring
+    // holds, and each slot is only freed once it has parked.
+    for expected in 0..ITEMS {
+        if expected + CAPACITY < ITEMS {
+            until_parked(&inner.inner.writer_waiter);
+        }
+        let mut buffer = [0];
+        reader.read_exact(&mut buffer).unwrap();
+        assert_eq!(buffer, [expected]);
+    }
+
+    assert_eq!(producer.join().unwrap(), Ok(()));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn read_timeout_expires_on_an_empty_ring() {
+    let (_writer, mut reader) = RingBuffer::<u32>::new(4).unwrap();
+    let timeout = Duration::from_millis(20);
+
+    let started = Instant::now();
+    assert_eq!(
+        reader.read_timeout(&mut [0; 2], timeout),
+        Err(TimeoutError::Timeout)
+    );
+    let waited = started.elapsed();
+    assert!(
+        waited >= timeout && waited < Duration::from_secs(5),
+        "{waited:?}"
+    );
+
+    // A zero timeout is a single attempt.
+    assert_eq!(
+        reader.read_timeout(&mut [0; 2], Duration::ZERO),
+        Err(TimeoutError::Timeout)
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_timeout_expires_on_a_full_ring_with_what_fit() {
+    let (mut writer, _reader) = RingBuffer::<u32>::new(4).unwrap();
+    let timeout = Duration::from_millis(20);
+
+    // What fits is written, and the rest is given up on at the deadline.
+    let started = Instant::now();
+    assert_eq!(writer.write_timeout(&[1, 2, 3, 4, 5, 6], timeout), Ok(4));
+    assert!(started.elapsed() >= timeout);
+    assert_eq!(
+        writer.write_timeout(&[7], timeout),
+        Err(TimeoutError::Timeout)
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn a_write_wakes_a_reader_before_its_deadline() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(8).unwrap();
+    let inner = writer.ring_buffer.clone();
+
+    let consumer = std::thread::spawn(move || {
+        let started = Instant::now();
+        let mut buffer = [0; 3];
+        let result = reader.read_timeout(&mut buffer, Duration::from_secs(60));
+        (result, buffer, started.elapsed())
+    });
+
+    until_parked(&inner.inner.reader_waiter);
+    writer.write(&[1, 2, 3]);
+
+    let (result, buffer, waited) = consumer.join().unwrap();
+    assert_eq!((result, buffer), (Ok(3), [1, 2, 3]));
+    assert!(waited < Duration::from_secs(5), "{waited:?}");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn a_read_wakes_a_writer_before_its_deadline() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(2).unwrap();
+    let inner = writer.ring_buffer.clone();
+
+    let producer = std::thread::spawn(move || {
+        let started = Instant::now();
+        let result = writer.write_timeout(&[1, 2, 3, 4], Duration::from_secs(60));
+        (result, started.elapsed())
+    });
+
+    until_parked(&inner.inner.writer_waiter);
+    let mut buffer = [0; 4];
+    reader.read_exact(&mut buffer).unwrap();
+
+    let (result, waited) = producer.join().unwrap();
+    assert_eq!(result, Ok(4));
+    assert!(waited < Duration::from_secs(5), "{waited:?}");
+    assert_eq!(buffer, [1, 2, 3, 4]);
+}
+
+#[test]
+fn try_read_drains_what_was_written_before_the_writer_dropped() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(16).unwrap();
+
+    let producer = std::thread::spawn(move || {
+        writer.write_all(&(0..1000).collect::<Vec<_>>()).unwrap();
+        // Dropped mid-stream, with whatever the reader has not taken yet
+        // still queued.
+    });
+
+    let mut received = Vec::new();
+    let mut buffer = [0; 7];
+    while let Ok(n) = reader.try_read(&mut buffer) {
+        received.extend_from_slice(&buffer[..n]);
+    }
+    producer.join().unwrap();
+
+    assert_eq!(received, (0..1000).collect::<Vec<_>>());
+    assert!(reader.is_disconnected());
+    assert_eq!(
+        reader.try_read(&mut buffer),
+        Err(TryReadError::Disconnected)
+    );
+    // An empty buffer asks for nothing, so it is never told off.
+    assert_eq!(reader.try_read(&mut []), Ok(0));
+}
+
+#[test]
+fn try_write_fails_once_the_reader_is_gone() {
+    let (mut writer, reader) = RingBuffer::<u64>::new(4).unwrap();
+    assert_eq!(writer.try_write(&[1, 2, 3, 4, 5]), Ok(4));
+    assert_eq!(writer.try_write(&[5]), Ok(0));
+    assert!(!writer.is_disconnected());
+
+    drop(reader);
+    assert!(writer.is_disconnected());
+    assert_eq!(writer.try_write(&[5]), Err(TryWriteError::Disconnected));
+}
+
+#[test]
+fn occupancy_adds_up_to_the_capacity_at_quiescent_points() {
+    for capacity in [1, 7, 8] {
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(capacity).unwrap();
+        let deque = crate::vecdeque::RingBuffer::<u64>::new(capacity);
+        let mut queued = 0;
+        let mut buffer = [0; 2];
+
+        for round in 0..5 * capacity {
+            let burst = vec![0; round % 4 + 1];
+            queued += writer.write(&burst);
+            deque.write(&burst);
+            let read = reader.read(&mut buffer[..round % 3]);
+            deque.read(&mut buffer[..round % 3]);
+            queued -= read;
+
+            for (len, free_len) in [
+                (writer.len(), writer.free_len()),
+                (reader.len(), reader.free_len()),
+                (deque.len(), deque.free_len()),
+            ] {
+                assert_eq!(len, queued);
+                assert_eq!(len + free_len, capacity);
+            }
+            assert_eq!(writer.is_empty(), queued == 0);
+            assert_eq!(reader.is_empty(), queued == 0);
+            assert_eq!(writer.is_full(), queued == capacity);
+            assert_eq!(reader.is_full(), queued == capacity);
+            assert_eq!(deque.is_full(), queued == capacity);
+        }
+    }
+}
+
+#[test]
+fn io_write_reports_a_full_ring_and_a_gone_reader() {
+    use std::io::{ErrorKind, Write};
+
+    let (mut writer, reader) = RingBuffer::<u8>::new(4).unwrap();
+
+    assert_eq!(Write::write(&mut writer, b"abcdef").unwrap(), 4);
+    assert_eq!(Write::write(&mut writer, b"").unwrap(), 0);
+    let full = Write::write(&mut writer, b"g").unwrap_err();
+    assert_eq!(full.kind(), ErrorKind::WouldBlock);
+
+    drop(reader);
+    let gone = Write::write(&mut writer, b"g").unwrap_err();
+    assert_eq!(gone.kind(), ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn io_copy_moves_megabytes_through_a_small_ring() {
+    use std::io::{ErrorKind, Write};
+
+    const LEN: usize = 4 << 20;
+    let data: Vec<u8> = (0..LEN).map(|i| (i * 31 + i / 4099) as u8).collect();
+    let (mut writer, mut reader) = RingBuffer::<u8>::new(4096).unwrap();
+
+    let source = data.clone();
+    let producer = std::thread::spawn(move || {
+        let mut rest = &source[..];
+        while !rest.is_empty() {
+            // Odd chunk sizes so the writes straddle the end of the storage.
+            let chunk = rest.len().min(1000);
+            match Write::write(&mut writer, &rest[..chunk]) {
+                Ok(n) => rest = &rest[n..],
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                    std::thread::yield_now();
+                }
+                Err(error) => panic!("unexpected error: {error}"),
+            }
+        }
+    });
+
+    // The reader blocks until data arrives and sees end of stream once the
+    // producer drops its writer.
+    let mut sink = Vec::with_capacity(LEN);
+    let copied = std::io::copy(&mut reader, &mut sink).unwrap();
+    producer.join().unwrap();
+
+    assert_eq!(copied, LEN as u64);
+    assert!(sink == data);
+}
+// Counts its drops in a shared counter.
+struct DropCounter(std::sync::Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn every_element_is_dropped_exactly_once() {
+    let drops = std::sync::Arc::new(AtomicUsize::new(0));
+    let (mut writer, mut reader) = RingBuffer::<DropCounter>::new(7).unwrap();
+
+    // Move the indices past the end of the storage first, so the queued
+    // elements sit on both sides of it.
+    for _ in 0..5 {
+        assert!(writer.try_push(DropCounter(drops.clone())).is_ok());
+        drop(reader.try_pop());
+    }
+    for _ in 0..6 {
+        assert!(writer.try_push(DropCounter(drops.clone())).is_ok());
+    }
+    drop(reader.try_pop());
+    drop(reader.try_pop());
+    assert_eq!(drops.load(Ordering::Relaxed), 7);
+
+    // The four still queued go with the last handle, and nothing twice.
+    drop(writer);
+    assert_eq!(drops.load(Ordering::Relaxed), 7);
+    drop(reader);
+    assert_eq!(drops.load(Ordering::Relaxed), 11);
+}
+
+#[test]
+fn queued_arcs_are_released_with_the_ring() {
+    let payload = std::sync::Arc::new(String::from("payload"));
+    let (mut writer, mut reader) = RingBuffer::<std::sync::Arc<String>>::new(3).unwrap();
+
+    for _ in 0..3 {
+        assert!(writer.try_push(payload.clone()).is_ok());
+    }
+    assert_eq!(std::sync::Arc::strong_count(&payload), 4);
+
+    // A rejected element is handed back, not kept or dropped by the ring.
+    let rejected = writer.try_push(payload.clone()).unwrap_err();
+    assert_eq!(std::sync::Arc::strong_count(&payload), 5);
+    drop(rejected);
+
+    // A popped element belongs to the caller; the ring never drops it again.
+    let popped = reader.try_pop().unwrap();
+    assert_eq!(*popped, "payload");
+    assert!(writer.try_push(payload.clone()).is_ok());
+    drop(popped);
+    assert_eq!(std::sync::Arc::strong_count(&payload), 4);
+
+    drop(reader);
+    assert_eq!(std::sync::Arc::strong_count(&payload), 4);
+    drop(writer);
+    assert_eq!(std::sync::Arc::strong_count(&payload), 1);
+}
+
+#[test]
+fn owned_strings_move_through_the_ring_in_order() {
+    let (mut writer, mut reader) = RingBuffer::<String>::new(3).unwrap();
+
+    let consumer = std::thread::spawn(move || {
+        let mut received = Vec::new();
+        loop {
+            // Checked before popping, so nothing published before the writer
+            // went away is missed.
+            let disconnected = reader.is_disconnected();
+            match reader.try_pop() {
+                Some(value) => received.push(value),
+                None if disconnected => break,
+                None => std::thread::yield_now(),
+            }
+        }
+        received
+    });
+
+    for i in 0..1000 {
+        let mut value = i.to_string();
+        while let Err(rejected) = writer.try_push(value) {
+            value = rejected;
+            std::thread::yield_now();
+        }
+    }
+    drop(writer);
+
+    let expected: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+    assert_eq!(consumer.join().unwrap(), expected);
+}
+
+#[test]
+fn handles_dropped_at_once_on_two_threads_free_the_ring_once() {
+    let drops = std::sync::Arc::new(AtomicUsize::new(0));
+
+    for round in 0..200 {
+        let (mut writer, reader) = RingBuffer::<DropCounter>::new(4).unwrap();
+        for _ in 0..3 {
+            assert!(writer.try_push(DropCounter(drops.clone())).is_ok());
+        }
+
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+        let other = barrier.clone();
+        let thread = std::thread::spawn(move || {
+            other.wait();
+            drop(reader);
+        });
+        barrier.wait();
+        drop(writer);
+        thread.join().unwrap();
+
+        assert_eq!(drops.load(Ordering::Relaxed), 3 * (round + 1));
+    }
+}
+
+#[test]
+fn write_iter_takes_only_what_fits_and_leaves_the_rest() {
+    let (mut writer, mut reader) = byte_ring_at(2);
+    assert_eq!(writer.write(&[0; 11]), 11);
+
+    // Five slots are free, across the wrap; the sixth element stays in the
+    // iterator rather than being pulled and lost.
+    let mut values = 1..=20;
+    assert_eq!(writer.write_iter(values.by_ref()), 5);
+    assert_eq!(values.next(), Some(6));
+
+    let mut buffer = [0; 16];
+    assert_eq!(reader.read(&mut buffer), 16);
+    assert_eq!(buffer[11..], [1, 2, 3, 4, 5]);
+
+    // Without an upper bound in its size hint, the iterator is still only
+    // pulled for as much room as there is.
+    let mut odd = (7..).filter(|value| value % 2 == 1);
+    assert_eq!(writer.write_iter(odd.by_ref()), 16);
+    assert_eq!(odd.next(), Some(39));
+}
+
+#[test]
+fn write_iter_with_an_empty_iterator_publishes_nothing() {
+    let (mut writer, reader) = byte_ring_at(2);
+
+    assert_eq!(writer.write_iter(core::iter::empty()), 0);
+    assert_eq!(writer.write_iter([1, 2].into_iter().filter(|_| false)), 0);
+    assert!(reader.is_empty());
+    assert_eq!(
+        writer.ring_buffer.head_index(),
+        reader.ring_buffer.tail_index()
+    );
+}
+
+#[test]
+fn write_vectored_lays_three_slices_across_the_wrap() {
+    let (mut writer, mut reader) = byte_ring_at(4);
+
+    assert_eq!(writer.write_vectored(&[&[1, 2], &[3, 4, 5], &[6]]), 6);
+    let mut buffer = [0; 6];
+    assert_eq!(reader.read(&mut buffer), 6);
+    assert_eq!(buffer, [1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn write_vectored_writes_nothing_unless_every_slice_fits() {
+    let (mut writer, mut reader) = byte_ring_at(4);
+    assert_eq!(writer.write(&[0; 10]), 10);
+
+    // Six slots are free: the first two slices would fit, all three do not.
+    assert_eq!(writer.write_vectored(&[&[1, 2], &[3, 4, 5], &[6, 7]]), 0);
+    assert_eq!(writer.len(), 10);
+    assert_eq!(writer.write_vectored(&[&[], &[]]), 0);
+    assert_eq!(writer.write_vectored(&[&[1, 2], &[3, 4, 5], &[6]]), 6);
+
+    let mut buffer = [0; 16];
+    assert_eq!(reader.read(&mut buffer), 16);
+    assert_eq!(buffer[10..], [1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn read_vectored_fills_three_slices_in_order_across_the_wrap() {
+    let (mut writer, mut reader) = byte_ring_at(3);
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5, 6, 7, 8]), 8);
+
+    let (mut first, mut second, mut third) = ([0; 2], [0; 3], [0; 3]);
+    assert_eq!(
+        reader.read_vectored(&mut [&mut first, &mut second, &mut third]),
+        8
+    );
+    assert_eq!((first, second, third), ([1, 2], [3, 4, 5], [6, 7, 8]));
+}
+
+#[test]
+fn read_vectored_stops_partway_when_less_is_queued() {
+    let (mut writer, mut reader) = byte_ring_at(3);
+    assert_eq!(writer.write(&[1, 2, 3, 4]), 4);
+
+    // The second slice is only partly filled, and the third not started.
+    let (mut first, mut second, mut third) = ([0; 2], [0; 3], [0; 3]);
+    assert_eq!(
+        reader.read_vectored(&mut [&mut first, &mut second, &mut third]),
+        4
+    );
+    assert_eq!((first, second, third), ([1, 2], [3, 4, 0], [0; 3]));
+    assert!(reader.is_empty());
+}
+
+// Waits for the notification descriptor like an event loop would, and
+// fails instead of hanging if a publish never signals it.
+#[cfg(all(target_os = "linux", feature = "notify"))]
+fn poll_readable(fd: RawFd) {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ready = unsafe { libc::poll(&mut pollfd, 1, 5_000) };
+    assert_eq!(ready, 1, "no wakeup within 5 s");
+    assert_ne!(pollfd.revents & libc::POLLIN, 0);
+}
+
+#[cfg(all(target_os = "linux", feature = "notify"))]
+#[test]
+fn a_poll_loop_misses_no_wakeup_from_a_bursty_writer() {
+    const ITEMS: u64 = 200_000;
+
+    let (mut writer, mut reader) = RingBuffer::<u64>::new_notifying(64).unwrap();
+    let fd = reader.as_raw_fd().unwrap();
+
+    let producer = std::thread::spawn(move || {
+        let mut next = 0;
+        let mut burst = 0;
+        while next < ITEMS {
+            // Bursts of up to a ring and a half, some back to back and some
+            // after a pause long enough for the reader to drain and poll.
+            let len = (burst * 37 % 96 + 1).min(ITEMS - next);
+            let values: Vec<_> = (next..next + len).collect();
+            let mut written = 0;
+            while written < values.len() {
+                match writer.write(&values[written..]) {
+                    0 => std::thread::yield_now(),
+                    n => written += n,
+                }
+            }
+            next += len;
+            burst += 1;
+            if burst % 5 == 0 {
+                std::thread::sleep(Duration::from_micros(50));
+            }
+        }
+    });
+
+    let mut expected = 0;
+    let mut buffer = [0; 32];
+    let mut wakeups = 0;
+    loop {
+        poll_readable(fd);
+        reader.drain_notification();
+        wakeups += 1;
+
+        // Everything published before the drain is read here, anything
+        // after it signals the descriptor again.
+        loop {
+            match reader.read(&mut buffer) {
+                0 => break,
+                n => {
+                    for &value in &buffer[..n] {
+                        assert_eq!(value, expected);
+                        expected += 1;
+                    }
+                }
+            }
+        }
+        // The writer going away signals the descriptor one last time.
+        if reader.is_disconnected() && reader.is_empty() {
+            break;
+        }
+    }
+
+    producer.join().unwrap();
+    assert_eq!(expected, ITEMS);
+    // The writer signals transitions, not elements.
+    assert!(wakeups < ITEMS, "{wakeups} wakeups");
+}
+
+#[test]
+fn constructors_report_the_specific_capacity_error() {
+    assert_eq!(
+        RingBuffer::<u64>::new(0).err(),
+        Some(RingBufferError::ZeroCapacity)
+    );
+    // Past the index range, and past what a `Layout` can describe.
+    assert_eq!(
+        RingBuffer::<u8>::new(usize::MAX).err(),
+        Some(RingBufferError::CapacityOverflow)
+    );
+    assert_eq!(
+        RingBuffer::<u64>::new(1 << 62).err(),
+        Some(RingBufferError::CapacityOverflow)
+    );
+    // Describable, but far more memory than any allocator hands out.
+    assert_eq!(
+        RingBuffer::<u8>::new(1 << 62).err(),
+        Some(RingBufferError::AllocationFailed)
+    );
+    assert_eq!(
+        ring_buffer_with_watermarks::<u64>(8, 2, 4).err(),
+        Some(RingBufferError::InvalidWatermarks)
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn ring_buffer_error_works_with_boxed_errors() {
+    fn assert_error<E: std::error::Error + Send + Sync + 'static>() {}
+    assert_error::<RingBufferError>();
+
+    fn build(capacity: usize) -> Result<Halves<u64, ()>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(RingBuffer::<u64>::new(capacity)?)
+    }
+    let Err(error) = build(0) else {
+        panic!("a zero capacity must be rejected")
+    };
+    assert_eq!(error.to_string(), "ring buffer capacity must be non-zero");
+    assert_eq!(
+        error.downcast_ref::<RingBufferError>(),
+        Some(&RingBufferError::ZeroCapacity)
+    );
+    assert!(build(4).is_ok());
+}
+
+#[test]
+fn debug_shows_each_half_without_the_elements() {
+    // Not `Debug` itself.
+    #[derive(Clone, Copy)]
+    struct Opaque;
+
+    let (mut writer, mut reader) = RingBuffer::<Opaque>::new(4).unwrap();
+    assert_eq!(writer.write(&[Opaque; 3]), 3);
+    assert_eq!(reader.read(&mut [Opaque]), 1);
+    assert_eq!(
+        format!("{writer:?}"),
+        "Writer { capacity: 4, head: 3, tail: 1, len: 2, disconnected: false }"
+    );
+    assert_eq!(
+        format!("{reader:?}"),
+        "Reader { capacity: 4, head: 3, tail: 1, len: 2, disconnected: false }"
+    );
+
+    // Each half reports whether its peer is gone.
+    drop(writer);
+    assert_eq!(
+        format!("{reader:?}"),
+        "Reader { capacity: 4, head: 3, tail: 1, len: 2, disconnected: true }"
+    );
+}
+
+#[test]
+fn drain_of_an_empty_ring_yields_nothing() {
+    let (_writer, mut reader) = RingBuffer::<u64>::new(4).unwrap();
+
+    let mut drain = reader.drain();
+    assert_eq!(drain.len(), 0);
+    assert_eq!(drain.next(), None);
+}
+
+#[test]
+fn drain_dropped_halfway_leaves_the_rest_queued() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(8).unwrap();
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5, 6]), 6);
+
+    {
+        let mut drain = reader.drain();
+        assert_eq!(drain.next(), Some(1));
+        assert_eq!(drain.next(), Some(2));
+        assert_eq!(drain.next(), Some(3));
+    }
+    assert_eq!(reader.len(), 3);
+    assert_eq!(reader.drain().collect::<Vec<_>>(), [4, 5, 6]);
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn drain_crosses_the_wrap_and_stops_at_its_snapshot() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(5).unwrap();
+    assert_eq!(writer.write(&[0, 0, 0]), 3);
+    assert_eq!(reader.skip(3), 3);
+    assert_eq!(writer.write(&[1, 2, 3, 4]), 4);
+
+    let mut drain = reader.drain();
+    assert_eq!(drain.len(), 4);
+    assert_eq!(drain.next(), Some(1));
+    // A slot freed by the drain is filled again, but the element written
+    // there is left for the next call.
+    assert_eq!(writer.write(&[5]), 1);
+    assert_eq!(drain.collect::<Vec<_>>(), [2, 3, 4]);
+    assert_eq!(reader.drain().collect::<Vec<_>>(), [5]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn iter_collects_everything_written_before_the_writer_dropped() {
+    const ITEMS: u64 = 10_000;
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(3).unwrap();
+
+    let producer = std::thread::spawn(move || {
+        for value in 0..ITEMS {
+            let mut value = value;
+            while let Err(rejected) = writer.try_push(value) {
+                value = rejected;
+                std::thread::yield_now();
+            }
+        }
+    });
+
+    // The ring is far smaller than the stream, so the iterator parks on an
+    // empty ring many times before it sees the end.
+    let received: Vec<u64> = reader.iter().collect();
+    producer.join().unwrap();
+    assert!(received.iter().copied().eq(0..ITEMS));
+    assert_eq!(reader.iter().next(), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn owned_iter_drains_what_is_left_after_the_writer_dropped() {
+    let (mut writer, reader) = RingBuffer::<u64>::new(4).unwrap();
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+    drop(writer);
+
+    let mut received = Vec::new();
+    for value in reader {
+        received.push(value);
+    }
+    assert_eq!(received, [1, 2, 3]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn busy_transfers_return_at_once_when_the_peer_is_gone() {
+    // With no spin limit, only the disconnect can end these calls.
+    let (mut writer, reader) = RingBuffer::<u32>::new(2).unwrap();
+    assert_eq!(writer.write(&[1, 2]), 2);
+    drop(reader);
+    assert_eq!(writer.write_busy(&[3], usize::MAX), 0);
+
+    // The reader still gets what was queued before the writer went.
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(4).unwrap();
+    assert_eq!(writer.write(&[1, 2]), 2);
+    drop(writer);
+    let mut buffer = [0; 4];
+    assert_eq!(reader.read_busy(&mut buffer, usize::MAX), 2);
+    assert_eq!(buffer[..2], [1, 2]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn busy_transfers_return_at_once_on_a_closed_ring() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(2).unwrap();
+    assert_eq!(writer.write(&[1]), 1);
+    writer.close();
+
+    assert_eq!(writer.write_busy(&[2], usize::MAX), 0);
+    let mut buffer = [0; 2];
+    assert_eq!(reader.read_busy(&mut buffer, usize::MAX), 1);
+    assert_eq!(buffer[0], 1);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn busy_transfers_give_up_after_the_spin_limit() {
+    let (mut writer, mut reader) = RingBuffer::<u32>::new(2).unwrap();
+
+    // One attempt, then one retry per spin, each of them failing.
+    let mut buffer = [0; 2];
+    assert_eq!(reader.read_busy(&mut buffer, 0), 0);
+    assert_eq!(reader.stats().read_failures, 1);
+    assert_eq!(reader.read_busy(&mut buffer, 5), 0);
+    assert_eq!(reader.stats().read_failures, 7);
+
+    // The two that fit go in, then the third fails once and once more for
+    // each spin.
+    assert_eq!(writer.write_busy(&[1, 2, 3], 3), 2);
+    assert_eq!(writer.stats().write_failures, 4);
+    assert_eq!(writer.write_busy(&[3], 0), 0);
+    assert_eq!(writer.stats().write_failures, 5);
+}
+
+#[test]
+fn congestion_keeps_its_state_between_the_watermarks() {
+    let (mut writer, mut reader) = ring_buffer_with_watermarks::<u8>(16, 12, 4).unwrap();
+    let congested = |writer: &Writer<u8>, reader: &Reader<u8>| {
+        assert_eq!(writer.is_congested(), reader.is_congested());
+        writer.is_congested()
+    };
+
+    // Start part way round, so the occupancy is measured across the wrap.
+    assert_eq!(writer.write(&[0; 10]), 10);
+    assert_eq!(reader.skip(10), 10);
+
+    // Rising: clear below the high watermark, raised on reaching it.
+    for len in 1..12 {
+        assert!(writer.try_push(0).is_ok());
+        assert!(!congested(&writer, &reader), "rising through {len}");
+    }
+    assert!(writer.try_push(0).is_ok());
+    assert!(congested(&writer, &reader));
+
+    // Falling: still raised above the low watermark, cleared on reaching
+    // it.
+    for len in (5..12).rev() {
+        assert_eq!(reader.skip(1), 1);
+        assert!(congested(&writer, &reader), "falling through {len}");
+    }
+    assert_eq!(reader.skip(1), 1);
+    assert!(!congested(&writer, &reader));
+
+    // Rising again stays clear until the high watermark, not the low one.
+    for len in 5..12 {
+        assert!(writer.try_push(0).is_ok());
+        assert!(!congested(&writer, &reader), "rising again through {len}");
+    }
+    assert!(writer.try_push(0).is_ok());
+    assert!(congested(&writer, &reader));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn three_chained_rings_keep_the_stream_in_order() {
+    const ITEMS: u64 = 20_000;
+    let (mut first_writer, mut first_reader) = RingBuffer::<u64>::new(5).unwrap();
+    let (mut second_writer, mut second_reader) = RingBuffer::<u64>::new(7).unwrap();
+    let (mut third_writer, mut third_reader) = RingBuffer::<u64>::new(3).unwrap();
+
+    let producer = std::thread::spawn(move || {
+        for value in 0..ITEMS {
+            let mut value = value;
+            while let Err(rejected) = first_writer.try_push(value) {
+                value = rejected;
+                std::thread::yield_now();
+            }
+        }
+    });
+    // Each hop moves bursts that divide neither capacity, so the runs on
+    // both sides wrap at varying offsets.
+    let splice = std::thread::spawn(move || loop {
+        let disconnected = first_reader.is_disconnected();
+        if second_writer.write_from(&mut first_reader, 4) == 0 {
+            if disconnected && first_reader.is_empty() {
+                break;
+            }
+            std::thread::yield_now();
+        }
+    });
+    let forward = std::thread::spawn(move || loop {
+        let disconnected = second_reader.is_disconnected();
+        if second_reader.read_into_writer(&mut third_writer, 6) == 0 {
+            if disconnected && second_reader.is_empty() {
+                break;
+            }
+            std::thread::yield_now();
+        }
+    });
+
+    let received: Vec<u64> = third_reader.iter().collect();
+    producer.join().unwrap();
+    splice.join().unwrap();
+    forward.join().unwrap();
+    assert!(received.iter().copied().eq(0..ITEMS));
+}
+
+// Fills every slot it is handed, and as a writer claims to have taken more
+// than it was given.
+struct Overclaiming;
+
+impl BufferReader<u64> for Overclaiming {
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, u64>) {
+        while buffer.push(9).is_ok() {}
+    }
+}
+
+impl BufferWriter<u64> for Overclaiming {
+    fn write(&mut self, buffer: &[u64]) -> usize {
+        buffer.len() + 5
+    }
+}
+
+#[test]
+fn splices_only_count_what_fit_in_each_run() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(4).unwrap();
+    assert_eq!(writer.write(&[0, 0, 0]), 3);
+    assert_eq!(reader.skip(3), 3);
+
+    // Both free runs are offered, one slot before the wrap and two after.
+    assert_eq!(writer.write_from(&mut Overclaiming, 3), 3);
+    assert_eq!(writer.len(), 3);
+    assert_eq!(reader.read_into_writer(&mut Overclaiming, 2), 2);
+    assert_eq!(reader.len(), 1);
+    assert_eq!(reader.try_pop(), Some(9));
+}
+
+#[test]
+fn write_from_fills_slots_that_were_never_written() {
+    let (mut source, mut from) = RingBuffer::<u64>::new(8).unwrap();
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(5).unwrap();
+    assert_eq!(source.write(&[1, 2, 3, 4, 5, 6, 7]), 7);
+
+    // The destination is fresh, so every slot it hands out is uninitialized.
+    assert_eq!(writer.write_from(&mut from, 8), 5);
+    assert_eq!(from.len(), 2);
+
+    let mut out = [0; 5];
+    assert_eq!(reader.read(&mut out), 5);
+    assert_eq!(out, [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn read_buf_counts_only_what_was_filled() {
+    let mut slots = [MaybeUninit::<u64>::uninit(); 4];
+    let mut buffer = ReadBuf::new(&mut slots);
+
+    assert_eq!(buffer.fill(&[1, 2, 3]), 3);
+    assert_eq!(buffer.push(4), Ok(()));
+    assert_eq!(buffer.push(5), Err(5));
+    assert_eq!(buffer.fill(&[6]), 0);
+    assert_eq!((buffer.filled(), buffer.remaining()), (4, 0));
+}
+
+#[test]
+#[should_panic(expected = "filled more slots than there are")]
+fn read_buf_refuses_to_count_past_its_end() {
+    let mut slots = [MaybeUninit::<u64>::uninit(); 2];
+    let mut buffer = ReadBuf::new(&mut slots);
+
+    unsafe { buffer.advance(3) };
+}
+
+#[test]
+fn read_into_vec_appends_across_the_wrap() {
+    let (mut writer, mut reader) = byte_ring_at(3);
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5, 6, 7]), 7);
+
+    // Spare capacity both used as it is and grown, with elements already
+    // in the vector left alone.
+    let mut vec = Vec::with_capacity(4);
+    vec.push(0);
+    assert_eq!(reader.read_into_vec(&mut vec, 5), 5);
+    assert_eq!(vec, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(reader.read_into_vec(&mut vec, 10), 2);
+    assert_eq!(vec, [0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(reader.read_into_vec(&mut vec, 10), 0);
+    assert_eq!(vec.len(), 8);
+}
+
+#[test]
+fn read_into_vec_with_a_max_of_zero_reads_nothing() {
+    let (mut writer, mut reader) = byte_ring_at(3);
+    assert_eq!(writer.write(&[1, 2, 3, 4]), 4);
+
+    let mut vec = vec![9];
+    assert_eq!(reader.read_into_vec(&mut vec, 0), 0);
+    assert_eq!(vec, [9]);
+    assert_eq!(reader.len(), 4);
+}
+
+#[test]
+fn read_uninit_leaves_the_unfilled_tail_alone() {
+    let (mut writer, mut reader) = byte_ring_at(2);
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+
+    let mut out = [MaybeUninit::new(0xee); 5];
+    assert_eq!(reader.read_uninit(&mut out), 3);
+    let out = out.map(|slot| unsafe { slot.assume_init() });
+    assert_eq!(out, [1, 2, 3, 0xee, 0xee]);
+}
+
+#[test]
+fn unsplit_returns_the_unread_elements_in_order() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(5).unwrap();
+    assert_eq!(writer.write(&[0, 0, 0, 0]), 4);
+    assert_eq!(reader.skip(4), 4);
+    // Queued on both sides of the end of the storage.
+    assert_eq!(writer.write(&[1, 2, 3]), 3);
+
+    assert_eq!(writer.unsplit(reader).unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn unsplit_of_an_empty_ring_returns_nothing() {
+    let (writer, reader) = RingBuffer::<u64>::new(5).unwrap();
+
+    assert!(writer.unsplit(reader).unwrap().is_empty());
+}
+
+#[test]
+fn unsplit_hands_mismatched_halves_back() {
+    let (mut writer, _reader) = RingBuffer::<u64>::new(4).unwrap();
+    let (_other_writer, other_reader) = RingBuffer::<u64>::new(4).unwrap();
+    assert_eq!(writer.write(&[7]), 1);
+
+    let (writer, other_reader) = writer.unsplit(other_reader).unwrap_err();
+    assert_eq!(writer.len(), 1);
+    assert!(other_reader.is_empty());
+}
+
+#[test]
+fn builder_without_a_capacity_is_rejected() {
+    assert_eq!(
+        RingBuffer::<u64>::builder()
+            .wiping(true)
+            .build()
+            .unwrap_err(),
+        RingBufferError::ZeroCapacity
+    );
+}
+
+#[test]
+fn builder_with_a_capacity_past_the_index_range_is_rejected() {
+    assert_eq!(
+        RingBuffer::<u64>::builder()
+            .capacity(1 << 62)
+            .build()
+            .unwrap_err(),
+        RingBufferError::CapacityOverflow
+    );
+}
+
+#[test]
+fn builder_with_watermarks_out_of_order_is_rejected() {
+    for (high, low) in [(2, 4), (4, 4)] {
+        assert_eq!(
+            RingBuffer::<u64>::builder()
+                .capacity(8)
+                .watermarks(high, low)
+                .build()
+                .unwrap_err(),
+            RingBufferError::InvalidWatermarks,
+            "high {high}, low {low}"
+        );
+    }
+}
+
+#[test]
+fn builder_with_a_high_watermark_above_the_capacity_is_rejected() {
+    assert_eq!(
+        RingBuffer::<u64>::builder()
+            .capacity(8)
+            .watermarks(9, 2)
+            .build()
+            .unwrap_err(),
+        RingBufferError::InvalidWatermarks
+    );
+}
+
+#[test]
+fn builder_reports_the_capacity_before_the_watermarks() {
+    assert_eq!(
+        RingBuffer::<u64>::builder()
+            .watermarks(2, 4)
+            .build()
+            .unwrap_err(),
+        RingBufferError::ZeroCapacity
+    );
+}
+
+#[test]
+fn builder_combines_wiping_and_watermarks() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::builder()
+        .capacity(8)
+        .wiping(true)
+        .watermarks(6, 2)
+        .build()
+        .unwrap();
+    assert_eq!(writer.capacity(), 8);
+
+    assert_eq!(writer.write(&[u64::MAX; 8]), 8);
+    assert!(reader.is_congested());
+
+    // Reading down to the low watermark clears the flag, and the slots
+    // read are wiped.
+    let mut buffer = [0; 6];
+    assert_eq!(reader.read(&mut buffer), 6);
+    assert!(!writer.is_congested());
+    let bytes = storage_bytes(&reader.ring_buffer);
+    assert_eq!(bytes[..48], [0; 48]);
+    assert_eq!(bytes[48..], [0xff; 16]);
+}
+
+// Raw parts are plain pointers, which Rust does not let cross threads on
+// their own; a caller handing them to another runtime vouches for that.
+struct Smuggled<P>(P);
+
+unsafe impl<P> Send for Smuggled<P> {}
+
+#[test]
+fn raw_parts_rebuild_halves_that_transfer_and_free_the_ring() {
+    let drops = std::sync::Arc::new(AtomicUsize::new(0));
+    let (writer, reader) = RingBuffer::<DropCounter>::new(4).unwrap();
+    let writer = Smuggled(writer.into_raw());
+    let reader = Smuggled(reader.into_raw());
+
+    // Taken apart, both halves still count as connected.
+    let producer = std::thread::spawn({
+        let drops = drops.clone();
+        move || {
+            let mut writer = unsafe { Writer::<DropCounter>::from_raw({ writer }.0) };
+            assert!(!writer.is_disconnected());
+            for _ in 0..10 {
+                let mut value = DropCounter(drops.clone());
+                while let Err(rejected) = writer.try_push(value) {
+                    value = rejected;
+                    std::thread::yield_now();
+                }
+            }
+        }
+    });
+
+    let mut reader = unsafe { Reader::<DropCounter>::from_raw({ reader }.0) };
+    let mut received = 0;
+    while received < 8 {
+        match reader.try_pop() {
+            Some(value) => {
+                drop(value);
+                received += 1;
+            }
+            None => std::thread::yield_now(),
+        }
+    }
+    producer.join().unwrap();
+    assert!(reader.is_disconnected());
+    assert_eq!(drops.load(Ordering::Relaxed), 8);
+
+    // The two still queued go with the last handle, once.
+    drop(reader);
+    assert_eq!(drops.load(Ordering::Relaxed), 10);
+}
+
+#[test]
+fn grow_keeps_a_wrapped_queue_in_order() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(5).unwrap();
+    assert_eq!(writer.write(&[0, 0, 0]), 3);
+    assert_eq!(reader.skip(3), 3);
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5]), 5);
+
+    grow(&mut writer, &mut reader, 12).unwrap();
+    assert_eq!(writer.capacity(), 12);
+    assert_eq!(reader.len(), 5);
+
+    // The larger ring keeps working across its own wrap afterwards.
+    assert_eq!(writer.write(&(6..=12).collect::<Vec<_>>()), 7);
+    assert_eq!(writer.write(&[13]), 0);
+    let mut buffer = [0; 12];
+    assert_eq!(reader.read(&mut buffer[..10]), 10);
+    assert_eq!(writer.write(&[13, 14, 15]), 3);
+    assert_eq!(reader.read(&mut buffer), 5);
+    assert_eq!(buffer[..5], [11, 12, 13, 14, 15]);
+}
+
+#[test]
+fn grow_rejects_a_capacity_below_the_queued_count() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(8).unwrap();
+    assert_eq!(writer.write(&[1, 2, 3, 4, 5]), 5);
+
+    assert_eq!(
+        grow(&mut writer, &mut reader, 4),
+        Err(RingBufferError::CapacityBelowLen)
+    );
+    // Nothing moved, and shrinking to exactly the queued count is fine.
+    assert_eq!(writer.capacity(), 8);
+    grow(&mut writer, &mut reader, 5).unwrap();
+    assert_eq!(writer.write(&[6]), 0);
+    assert_eq!(reader.drain().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn grow_rejects_halves_of_different_rings() {
+    let (mut writer, _reader) = RingBuffer::<u64>::new(4).unwrap();
+    let (_other_writer, mut other_reader) = RingBuffer::<u64>::new(4).unwrap();
+
+    assert_eq!(
+        grow(&mut writer, &mut other_reader, 16),
+        Err(RingBufferError::NotExclusive)
+    );
+}
+
+#[test]
+fn aligned_slots_start_on_the_requested_boundary() {
+    for align in [64, 4096, HUGE_PAGE_SIZE] {
+        let (mut writer, mut reader) = RingBuffer::<u8>::builder()
+            .capacity(3)
+            .align(align)
+            .build()
+            .unwrap();
+        assert_eq!(writer.ring_buffer.storage() as usize % align, 0, "{align}");
+
+        assert_eq!(writer.write(&[1, 2, 3]), 3);
+        let mut buffer = [0; 3];
+        assert_eq!(reader.read(&mut buffer), 3);
+        assert_eq!(buffer, [1, 2, 3]);
+    }
+}
+
+#[test]
+fn alignments_that_cannot_be_provided_are_rejected() {
+    // Not a power of two, and past what a `Layout` can describe.
+    for align in [3, 48, 1 << (usize::BITS - 1)] {
+        let error = RingBuffer::<u64>::builder()
+            .capacity(4)
+            .align(align)
+            .build()
+            .unwrap_err();
+        assert_eq!(error, RingBufferError::InvalidAlignment, "{align}");
+    }
+    assert_eq!(
+        RingBufferError::InvalidAlignment.to_string(),
+        "ring buffer alignment cannot be provided"
+    );
+}
+
+#[cfg(all(target_os = "linux", feature = "hugepages"))]
+#[test]
+fn huge_pages_cannot_be_aligned_past_a_page() {
+    assert_eq!(
+        RingBuffer::<u8>::builder()
+            .capacity(4)
+            .huge_pages(true)
+            .align(2 * HUGE_PAGE_SIZE)
+            .build()
+            .unwrap_err(),
+        RingBufferError::InvalidAlignment
+    );
+}
+
+#[cfg(all(target_os = "linux", feature = "hugepages"))]
+#[test]
+fn huge_page_slots_start_on_a_huge_page() {
+    let built = RingBuffer::<u64>::builder()
+        .capacity(1024)
+        .huge_pages(true)
+        .build();
+    // Nothing to check on a machine without reserved huge pages, but the
+    // error has to say so.
+    let (mut writer, mut reader) = match built {
+        Err(RingBufferError::HugePagesUnavailable { errno }) => {
+            assert_ne!(errno, 0);
+            return;
+        }
+        built => built.unwrap(),
+    };
+    assert_eq!(writer.ring_buffer.storage() as usize % HUGE_PAGE_SIZE, 0);
+
+    assert_eq!(writer.write(&[7; 1024]), 1024);
+    let mut buffer = [0; 1024];
+    assert_eq!(reader.read(&mut buffer), 1024);
+    assert_eq!(buffer, [7; 1024]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reader_gets_everything_written_before_the_close_then_the_end() {
+    const ITEMS: u64 = 10_000;
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(7).unwrap();
+
+    // The consumer starts first and keeps parking on the small ring, so the
+    // close has to wake it once the last element is read.
+    let consumer = std::thread::spawn(move || {
+        let received: Vec<u64> = reader.iter().collect();
+        (received, reader)
+    });
+
+    let values: Vec<u64> = (0..ITEMS).collect();
+    assert_eq!(writer.write_all(&values), Ok(()));
+    writer.close();
+
+    let (received, mut reader) = consumer.join().unwrap();
+    assert!(received.iter().copied().eq(0..ITEMS));
+
+    // The writer is still alive: the stream ended because it was closed.
+    assert!(reader.is_closed());
+    assert!(!reader.is_disconnected());
+    assert_eq!(
+        reader.read_exact(&mut [0; 2]),
+        Err(TransferError::Closed { transferred: 0 })
+    );
+    assert_eq!(reader.try_read(&mut [0]), Err(TryReadError::Closed));
+    assert_eq!(reader.try_pop(), None);
+}
+
+#[test]
+fn try_write_tells_a_closed_ring_from_a_gone_reader() {
+    let (mut writer, reader) = RingBuffer::<u64>::new(4).unwrap();
+    assert_eq!(writer.try_write(&[1]), Ok(1));
+
+    writer.close();
+    writer.close();
+    assert_eq!(writer.try_write(&[2]), Err(TryWriteError::Closed));
+    assert_eq!(writer.write(&[2]), 0);
+
+    drop(reader);
+    assert_eq!(writer.try_write(&[2]), Err(TryWriteError::Closed));
+}
+
+#[test]
+fn recover_writer_refuses_a_closed_ring() {
+    let (mut writer, mut reader) = RingBuffer::<u64>::new(4).unwrap();
+    assert_eq!(writer.write(&[1, 2]), 2);
+    writer.close();
+    assert!(!reader.is_disconnected());
+    drop(writer);
+
+    assert!(reader.is_disconnected());
+    assert_eq!(reader.recover_writer().err(), Some(RecoverError::Closed));
+    // The refused claim is handed back, and the queue is untouched.
+    assert!(reader.is_disconnected());
+    assert_eq!(reader.recover_writer().err(), Some(RecoverError::Closed));
+    assert_eq!(reader.try_pop(), Some(1));
+    assert_eq!(reader.try_pop(), Some(2));
+    assert!(reader.is_closed());
+}
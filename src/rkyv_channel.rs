@@ -0,0 +1,314 @@
+use std::{marker::PhantomData, ops::Deref, slice};
+
+use rkyv::{
+    ser::{
+        serializers::{
+            AllocScratch, AllocSerializer, BufferSerializer, CompositeSerializer,
+            CompositeSerializerError, FallbackScratch, HeapScratch, SharedSerializeMap,
+        },
+        Serializer,
+    },
+    Archive, Serialize,
+};
+
+use crate::ring_buffer::{
+    RawBufferReader, RawBufferWriter, Reader, RingBuffer, RingBufferError, Writer,
+};
+
+const BLOCK_SIZE: usize = 16;
+const FRAME_DATA: u32 = 0;
+const FRAME_PADDING: u32 = 1;
+
+/// Ring slot type. Frames always start on a block boundary, so archived
+/// values get 16-byte alignment without any per-frame padding arithmetic.
+#[repr(C, align(16))]
+#[derive(Clone, Copy)]
+struct Block([u8; BLOCK_SIZE]);
+
+impl Block {
+    fn header(kind: u32, len: u32) -> Self {
+        let mut block = [0; BLOCK_SIZE];
+        block[0..4].copy_from_slice(&kind.to_le_bytes());
+        block[4..8].copy_from_slice(&len.to_le_bytes());
+
+        Self(block)
+    }
+
+    fn kind(&self) -> u32 {
+        u32::from_le_bytes([self.0[0], self.0[1], self.0[2], self.0[3]])
+    }
+
+    fn len(&self) -> u32 {
+        u32::from_le_bytes([self.0[4], self.0[5], self.0[6], self.0[7]])
+    }
+}
+
+pub type RingSerializer<'a> = CompositeSerializer<
+    BufferSerializer<&'a mut [u8]>,
+    FallbackScratch<HeapScratch<256>, AllocScratch>,
+    SharedSerializeMap,
+>;
+
+pub fn rkyv_channel<T: Archive>(
+    byte_capacity: usize,
+) -> Result<(ArchiveSender<T>, ArchiveReceiver<T>), RingBufferError> {
+    let (writer, reader) = RingBuffer::<Block>::new(byte_capacity.div_ceil(BLOCK_SIZE))?;
+
+    Ok((
+        ArchiveSender {
+            writer,
+            _marker: PhantomData,
+        },
+        ArchiveReceiver {
+            reader,
+            _marker: PhantomData,
+        },
+    ))
+}
+
+#[derive(Debug)]
+pub enum SendError {
+    /// The archived frame is larger than the ring and can never be sent.
+    FrameTooLarge,
+    /// The ring does not have a contiguous region large enough right now.
+    Full,
+    Serialize,
+}
+
+pub struct ArchiveSender<T> {
+    writer: Writer<Block>,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T> ArchiveSender<T>
+where
+    T: Archive + for<'a> Serialize<RingSerializer<'a>> + Serialize<AllocSerializer<256>>,
+{
+    /// Archives `message` directly into the ring.
+    ///
+    /// Frames are never split across the end of the storage: when the region
+    /// up to the end is too small, the remainder is skipped with a padding
+    /// frame and the message is placed at the start instead.
+    pub fn send(&mut self, message: &T) -> Result<(), SendError> {
+        let capacity = self.writer.capacity();
+        let (free, index) = self.writer.free();
+        let to_end = capacity - index % capacity;
+
+        if free > 0 {
+            let blocks = free.min(to_end) - 1;
+            if let Some(len) = self.archive_at(index.wrapping_add(1), blocks, message)? {
+                // The header slot and the `blocks` archived into are free.
+                unsafe {
+                    *self.writer.get_mut(index) = Block::header(FRAME_DATA, len);
+                    self.writer
+                        .advance_index(1 + (len as usize).div_ceil(BLOCK_SIZE));
+                }
+
+                return Ok(());
+            }
+        }
+
+        if free > to_end {
+            let start = index.wrapping_add(to_end);
+            let blocks = free - to_end - 1;
+            if let Some(len) = self.archive_at(start.wrapping_add(1), blocks, message)? {
+                // The padding, the header and the archived blocks all lie
+                // within the `free` slots from `index`.
+                unsafe {
+                    *self.writer.get_mut(index) = Block::header(FRAME_PADDING, 0);
+                    *self.writer.get_mut(start) = Block::header(FRAME_DATA, len);
+                    self.writer
+                        .advance_index(to_end + 1 + (len as usize).div_ceil(BLOCK_SIZE));
+                }
+
+                return Ok(());
+            }
+        }
+
+        let len = rkyv::to_bytes::<_, 256>(message)
+            .map_err(|_| SendError::Serialize)?
+            .len();
+        if 1 + len.div_ceil(BLOCK_SIZE) > capacity {
+            return Err(SendError::FrameTooLarge);
+        }
+
+        // The frame fits an empty ring but not the space left before the end
+        // of the storage. Skip to the start now so that it fits once the
+        // reader catches up.
+        if free >= to_end && to_end < capacity {
+            // At least `to_end` slots from `index` are free.
+            unsafe {
+                *self.writer.get_mut(index) = Block::header(FRAME_PADDING, 0);
+                self.writer.advance_index(to_end);
+            }
+        }
+
+        Err(SendError::Full)
+    }
+
+    fn archive_at(
+        &mut self,
+        index: usize,
+        blocks: usize,
+        message: &T,
+    ) -> Result<Option<u32>, SendError> {
+        let slots = self.writer.contiguous_mut(index, blocks);
+        let bytes = unsafe {
+            slice::from_raw_parts_mut(slots.as_mut_ptr().cast::<u8>(), slots.len() * BLOCK_SIZE)
+        };
+        let mut serializer = CompositeSerializer::new(
+            BufferSerializer::new(bytes),
+            FallbackScratch::default(),
+            SharedSerializeMap::default(),
+        );
+
+        match serializer.serialize_value(message) {
+            Ok(_) => Ok(Some(serializer.into_serializer().pos() as u32)),
+            Err(CompositeSerializerError::SerializerError(_)) => Ok(None),
+            Err(_) => Err(SendError::Serialize),
+        }
+    }
+}
+
+pub struct ArchiveReceiver<T> {
+    reader: Reader<Block>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Archive> ArchiveReceiver<T> {
+    /// Returns a guard over the next archived message. The frame stays in
+    /// the ring, and cannot be overwritten by the writer, until the guard is
+    /// dropped.
+    pub fn recv(&mut self) -> Option<ArchivedGuard<'_, T>> {
+        let capacity = self.reader.capacity();
+
+        loop {
+            let (filled, index) = self.reader.filled(1);
+            if filled == 0 {
+                return None;
+            }
+
+            // `filled` showed the header at `index`, and a padding frame
+            // covers every slot up to the end of the storage.
+            let header = unsafe { *self.reader.get(index) };
+            if header.kind() == FRAME_PADDING {
+                unsafe { self.reader.advance_index(capacity - index % capacity) };
+                continue;
+            }
+
+            return Some(ArchivedGuard {
+                reader: &mut self.reader,
+                index,
+                len: header.len() as usize,
+                _marker: PhantomData,
+            });
+        }
+    }
+}
+
+pub struct ArchivedGuard<'a, T: Archive> {
+    reader: &'a mut Reader<Block>,
+    index: usize,
+    len: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Archive> Deref for ArchivedGuard<'_, T> {
+    type Target = T::Archived;
+
+    fn deref(&self) -> &T::Archived {
+        let blocks = self.len.div_ceil(BLOCK_SIZE);
+        let slots = self.reader.contiguous(self.index.wrapping_add(1), blocks);
+        let bytes = unsafe { slice::from_raw_parts(slots.as_ptr().cast::<u8>(), self.len) };
+
+        // Safety: the frame was archived from a `T` by `ArchiveSender::send`
+        // into 16-byte aligned, contiguous slots that the writer cannot reuse
+        // while this guard holds the read index back.
+        unsafe { rkyv::archived_root::<T>(bytes) }
+    }
+}
+
+impl<T: Archive> Drop for ArchivedGuard<'_, T> {
+    fn drop(&mut self) {
+        // The header and its blocks were published together by the sender.
+        unsafe { self.reader.advance_index(1 + self.len.div_ceil(BLOCK_SIZE)) };
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::mem::align_of;
+
+    use rkyv::{Archive, Serialize};
+
+    use super::*;
+
+    #[derive(Archive, Serialize)]
+    struct Reading {
+        sensor: u16,
+        values: Vec<f64>,
+    }
+
+    #[derive(Archive, Serialize)]
+    struct Batch {
+        id: u64,
+        station: String,
+        readings: Vec<Reading>,
+    }
+
+    fn batch(id: u64) -> Batch {
+        Batch {
+            id,
+            station: format!("station-{id}"),
+            readings: (0..id % 4)
+                .map(|sensor| Reading {
+                    sensor: sensor as u16,
+                    values: (0..sensor).map(|value| value as f64 * 0.5).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn nested_structs_are_read_in_place_across_the_wrap() {
+        let (mut sender, mut receiver) = rkyv_channel::<Batch>(1024).unwrap();
+
+        // Far more bytes than the ring holds, so frames keep landing at new
+        // offsets and padding frames skip the end of the storage.
+        for id in 0..200 {
+            sender.send(&batch(id)).unwrap();
+            let archived = receiver.recv().unwrap();
+
+            let root: *const ArchivedBatch = &*archived;
+            assert_eq!(root as usize % align_of::<ArchivedBatch>(), 0);
+            assert_eq!(archived.id, id);
+            assert_eq!(archived.station.as_str(), format!("station-{id}"));
+            assert_eq!(archived.readings.len() as u64, id % 4);
+            for (sensor, reading) in archived.readings.iter().enumerate() {
+                assert_eq!(reading.sensor as usize, sensor);
+                let values: Vec<f64> = reading.values.iter().copied().collect();
+                let expected: Vec<f64> = (0..sensor).map(|value| value as f64 * 0.5).collect();
+                assert_eq!(values, expected);
+            }
+        }
+        assert!(receiver.recv().is_none());
+    }
+
+    #[test]
+    fn the_writer_cannot_reuse_a_frame_while_its_guard_is_alive() {
+        let (mut sender, mut receiver) = rkyv_channel::<Batch>(512).unwrap();
+        let message = batch(3);
+        while sender.send(&message).is_ok() {}
+
+        let guard = receiver.recv().unwrap();
+        assert_eq!(guard.id, 3);
+        // Only the guarded frame could make room, and it is still held.
+        assert!(matches!(sender.send(&message), Err(SendError::Full)));
+        assert_eq!(guard.station.as_str(), "station-3");
+        assert_eq!(guard.readings[2].values.len(), 2);
+
+        drop(guard);
+        // The frame is consumed now, so its slots take one of the same size.
+        assert!(sender.send(&message).is_ok());
+    }
+}
@@ -0,0 +1,304 @@
+use std::{cell::UnsafeCell, mem::MaybeUninit};
+
+use crate::{
+    ring_buffer::{
+        BufferReader, BufferWriter, RawBufferReader, RawBufferWriter, ReadBuf, RingBufferError,
+    },
+    sync::{Arc, AtomicU64, Ordering},
+};
+
+/// Creates a ring where every slot carries its own sequence stamp instead
+/// of both sides sharing a head and a tail index.
+///
+/// Each side keeps its position to itself and only looks at the stamp of the
+/// slot it is about to touch: a slot at position `i` is free for the writer
+/// while its stamp is `i`, and holds data for the reader once the writer
+/// has set it to `i + 1`. The reader hands it back by setting it to
+/// `i + capacity`, the position the writer will reach it at next. Under
+/// heavy traffic the two sides then mostly touch different cache lines,
+/// rather than bouncing the shared index pair between cores on every call.
+///
+/// Positions and stamps are 64-bit, so they never wrap in practice. The
+/// capacity must be at least 2: with a single slot, "published at `i`" and
+/// "free at `i + 1`" would be the same stamp.
+pub fn ring_buffer<T: Copy>(
+    capacity: usize,
+) -> Result<(SeqWriter<T>, SeqReader<T>), RingBufferError> {
+    if capacity < 2 {
+        return Err(RingBufferError::Initialize);
+    }
+
+    let slots = (0..capacity)
+        .map(|index| Slot {
+            stamp: AtomicU64::new(index as u64),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        })
+        .collect();
+    let shared = Arc::new(Shared { slots, capacity });
+
+    Ok((
+        SeqWriter {
+            shared: shared.clone(),
+            head: 0,
+        },
+        SeqReader { shared, tail: 0 },
+    ))
+}
+
+struct Slot<T> {
+    stamp: AtomicU64,
+    // Holds a value only between the writer's publish and the reader's
+    // release of the slot.
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+struct Shared<T> {
+    slots: Box<[Slot<T>]>,
+    capacity: usize,
+}
+
+// A slot is written by the writer only after its stamp says the reader is
+// done with it, and read only after the stamp says it was published, so the
+// `UnsafeCell`s are never accessed from both sides at once.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    #[inline(always)]
+    fn slot(&self, position: u64) -> &Slot<T> {
+        let index = (position % self.capacity as u64) as usize;
+
+        // Safety: the remainder is below the capacity, the slice length.
+        unsafe { self.slots.get_unchecked(index) }
+    }
+}
+
+// The trait calls pass `usize` indices handed out by `available`/`filled`,
+// which are the low bits of `base` on 32-bit targets; this recovers the
+// full position.
+#[inline(always)]
+fn position(base: u64, index: usize) -> u64 {
+    base.wrapping_add(index.wrapping_sub(base as usize) as u64)
+}
+
+pub struct SeqWriter<T: Copy> {
+    shared: Arc<Shared<T>>,
+    head: u64,
+}
+
+impl<T: Copy> SeqWriter<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    // Number of consecutive free slots from the write position, up to
+    // `max`. The reader frees slots in order, so the run ends at the first
+    // slot it still holds; when the last slot asked for is free, so are all
+    // before it.
+    #[inline(always)]
+    fn free_run(&self, max: usize) -> usize {
+        let max = max.min(self.capacity());
+        if self.available(max).0 == max {
+            return max;
+        }
+
+        (0..max)
+            .take_while(|&offset| {
+                let position = self.head + offset as u64;
+                self.shared.slot(position).stamp.load(Ordering::Acquire) == position
+            })
+            .count()
+    }
+}
+
+impl<T: Copy> RawBufferWriter<T> for SeqWriter<T> {
+    // The reader frees slots in order, so the run of `size` slots is free
+    // exactly when its last slot is.
+    #[inline(always)]
+    fn available(&self, size: usize) -> (usize, usize) {
+        let head = self.head as usize;
+        if size == 0 || size > self.capacity() {
+            return (0, head);
+        }
+
+        let last = self.head + (size - 1) as u64;
+        if self.shared.slot(last).stamp.load(Ordering::Acquire) == last {
+            (size, head)
+        } else {
+            (0, head)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get_mut(&mut self, index: usize) -> &mut T {
+        let position = position(self.head, index);
+
+        unsafe { &mut *self.shared.slot(position).value.get().cast() }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        for _ in 0..offset {
+            let position = self.head;
+            self.shared
+                .slot(position)
+                .stamp
+                .store(position + 1, Ordering::Release);
+            self.head = position + 1;
+        }
+    }
+}
+
+impl<T: Copy> BufferWriter<T> for SeqWriter<T> {
+    /// Writes as much of `buffer` as currently fits and returns the number
+    /// of elements written, like the lock-free ring.
+    #[inline(always)]
+    fn write(&mut self, buffer: &[T]) -> usize {
+        let len = self.free_run(buffer.len());
+        let index = self.head as usize;
+
+        // The slots sit between their stamps, so the run is copied one slot
+        // at a time. They are free, and published only once every one has
+        // been written.
+        unsafe {
+            for (offset, value) in buffer[..len].iter().enumerate() {
+                *self.get_mut(index.wrapping_add(offset)) = *value;
+            }
+            self.advance_index(len);
+        }
+
+        len
+    }
+}
+
+pub struct SeqReader<T: Copy> {
+    shared: Arc<Shared<T>>,
+    tail: u64,
+}
+
+impl<T: Copy> SeqReader<T> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    // Number of consecutive published slots from the read position, up to
+    // `max`. The writer publishes in order, so the run ends at the first
+    // slot not yet published; when the last slot asked for is published,
+    // so are all before it.
+    #[inline(always)]
+    fn published_run(&self, max: usize) -> usize {
+        let max = max.min(self.capacity());
+        if self.filled(max).0 == max {
+            return max;
+        }
+
+        (0..max)
+            .take_while(|&offset| {
+                let position = self.tail + offset as u64;
+                self.shared.slot(position).stamp.load(Ordering::Acquire) == position + 1
+            })
+            .count()
+    }
+}
+
+impl<T: Copy> RawBufferReader<T> for SeqReader<T> {
+    // The writer publishes slots in order, so the run of `size` slots holds
+    // data exactly when its last slot does.
+    #[inline(always)]
+    fn filled(&self, size: usize) -> (usize, usize) {
+        let tail = self.tail as usize;
+        if size == 0 || size > self.capacity() {
+            return (0, tail);
+        }
+
+        let last = self.tail + (size - 1) as u64;
+        if self.shared.slot(last).stamp.load(Ordering::Acquire) == last + 1 {
+            (size, tail)
+        } else {
+            (0, tail)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get(&self, index: usize) -> &T {
+        let position = position(self.tail, index);
+
+        unsafe { &*self.shared.slot(position).value.get().cast() }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        let capacity = self.capacity() as u64;
+        for _ in 0..offset {
+            let position = self.tail;
+            self.shared
+                .slot(position)
+                .stamp
+                .store(position + capacity, Ordering::Release);
+            self.tail = position + 1;
+        }
+    }
+}
+
+impl<T: Copy> BufferReader<T> for SeqReader<T> {
+    /// Reads as many queued elements as fit in `buffer`.
+    #[inline(always)]
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        let len = self.published_run(buffer.remaining());
+        let index = self.tail as usize;
+
+        // The slots sit between their stamps, so the run is copied one slot
+        // at a time. They are published, and released only once every one
+        // has been copied out.
+        unsafe {
+            for offset in 0..len {
+                let _ = buffer.push(*self.get(index.wrapping_add(offset)));
+            }
+            self.advance_index(len);
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_start_out_uninitialized() {
+        // References may not be null, so the slots could not start out as
+        // all-zero `&str`s.
+        let (mut writer, mut reader) = ring_buffer::<&'static str>(3).unwrap();
+        let mut buffer = [""; 2];
+
+        for lap in 0..4 {
+            assert_eq!(writer.write(&["a", "b"]), 2);
+            assert_eq!(reader.read(&mut buffer), 2, "lap {lap}");
+            assert_eq!(buffer, ["a", "b"]);
+        }
+    }
+
+    #[test]
+    fn needs_two_slots() {
+        assert!(ring_buffer::<u64>(1).is_err());
+        assert!(ring_buffer::<u64>(2).is_ok());
+    }
+
+    #[test]
+    fn transfers_take_what_fits_across_the_wrap() {
+        let (mut writer, mut reader) = ring_buffer::<u64>(5).unwrap();
+        let mut buffer = [0; 8];
+
+        assert_eq!(writer.write(&[1, 2, 3]), 3);
+        assert_eq!(reader.read(&mut buffer[..2]), 2);
+        // The slot holding 3 is still taken, so the run of free slots ends
+        // just before it, after wrapping to the front.
+        assert_eq!(writer.write(&[4, 5, 6, 7, 8, 9]), 4);
+        assert_eq!(writer.write(&[9]), 0);
+
+        assert_eq!(reader.read(&mut buffer), 5);
+        assert_eq!(buffer[..5], [3, 4, 5, 6, 7]);
+        assert_eq!(reader.read(&mut buffer), 0);
+    }
+}
@@ -0,0 +1,212 @@
+use std::{io, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::ring_buffer::{
+    RawBufferReader, RawBufferWriter, Reader, RingBuffer, RingBufferError, Writer,
+};
+
+const HEADER_SIZE: usize = 4;
+
+pub fn serde_channel<T: Serialize + DeserializeOwned>(
+    byte_capacity: usize,
+) -> Result<(MessageSender<T>, MessageReceiver<T>), RingBufferError> {
+    let (writer, reader) = RingBuffer::<u8>::new(byte_capacity)?;
+
+    Ok((
+        MessageSender {
+            writer,
+            _marker: PhantomData,
+        },
+        MessageReceiver {
+            reader,
+            _marker: PhantomData,
+        },
+    ))
+}
+
+#[derive(Debug)]
+pub enum SendError {
+    /// The encoded frame is larger than the ring and can never be sent.
+    FrameTooLarge,
+    /// The ring does not have room for the frame right now.
+    Full,
+    Encode(bincode::Error),
+}
+
+pub struct MessageSender<T> {
+    writer: Writer<u8>,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> MessageSender<T> {
+    pub fn send(&mut self, message: &T) -> Result<(), SendError> {
+        let size = bincode::serialized_size(message).map_err(SendError::Encode)?;
+        let frame_size = u32::try_from(size)
+            .ok()
+            .and_then(|size| (size as usize).checked_add(HEADER_SIZE))
+            .filter(|&frame_size| frame_size <= self.writer.capacity())
+            .ok_or(SendError::FrameTooLarge)?;
+
+        let (available, index) = self.writer.available(frame_size);
+        if available == 0 {
+            return Err(SendError::Full);
+        }
+
+        let mut slots = SlotWriter {
+            writer: &mut self.writer,
+            index,
+        };
+        io::Write::write_all(&mut slots, &(size as u32).to_le_bytes())
+            .map_err(|error| SendError::Encode(error.into()))?;
+        bincode::serialize_into(&mut slots, message).map_err(SendError::Encode)?;
+        // The header and the `size` bytes after it fill the frame exactly.
+        unsafe { self.writer.advance_index(frame_size) };
+
+        Ok(())
+    }
+}
+
+pub struct MessageReceiver<T> {
+    reader: Reader<u8>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> MessageReceiver<T> {
+    /// Returns the next message, or `Ok(None)` when no complete frame has
+    /// been published yet. A decoding error consumes the offending frame.
+    pub fn recv(&mut self) -> Result<Option<T>, bincode::Error> {
+        let (filled, index) = self.reader.filled(HEADER_SIZE);
+        if filled == 0 {
+            return Ok(None);
+        }
+
+        let header = std::array::from_fn(|offset| unsafe { *self.reader.get(index + offset) });
+        let frame_size = u32::from_le_bytes(header) as usize + HEADER_SIZE;
+        let (filled, _) = self.reader.filled(frame_size);
+        if filled == 0 {
+            return Ok(None);
+        }
+
+        let slots = SlotReader {
+            reader: &self.reader,
+            index: index + HEADER_SIZE,
+            end: index + frame_size,
+        };
+        let message = bincode::deserialize_from(slots);
+        // `filled` showed the whole frame, and nothing borrows it any more.
+        unsafe { self.reader.advance_index(frame_size) };
+
+        message.map(Some)
+    }
+}
+
+struct SlotWriter<'a> {
+    writer: &'a mut Writer<u8>,
+    index: usize,
+}
+
+impl io::Write for SlotWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // bincode writes exactly the `size` it measured up front, all of
+        // which `available` reported free.
+        for byte in buf {
+            *unsafe { self.writer.get_mut(self.index) } = *byte;
+            self.index = self.index.wrapping_add(1);
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct SlotReader<'a> {
+    reader: &'a Reader<u8>,
+    index: usize,
+    end: usize,
+}
+
+impl io::Read for SlotReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.end.wrapping_sub(self.index));
+        // `end` is never past the published frame.
+        for byte in &mut buf[..n] {
+            *byte = unsafe { *self.reader.get(self.index) };
+            self.index = self.index.wrapping_add(1);
+        }
+
+        Ok(n)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Event {
+        Started,
+        Progress { done: u32, total: u32 },
+        Log(String),
+        Samples(Vec<i16>),
+    }
+
+    #[test]
+    fn enums_and_strings_round_trip_across_the_wrap() {
+        let (mut sender, mut receiver) = serde_channel::<Event>(64).unwrap();
+        let events = [
+            Event::Started,
+            Event::Progress { done: 3, total: 7 },
+            Event::Log("half way there".to_string()),
+            Event::Log(String::new()),
+            Event::Samples(vec![-1, 0, i16::MAX]),
+        ];
+
+        // Enough rounds that frames straddle the end of the storage.
+        for round in 0..20 {
+            for event in &events {
+                sender.send(event).unwrap();
+                assert_eq!(
+                    receiver.recv().unwrap().as_ref(),
+                    Some(event),
+                    "round {round}"
+                );
+            }
+        }
+        assert_eq!(receiver.recv().unwrap(), None);
+    }
+
+    #[test]
+    fn a_message_larger_than_half_the_ring_round_trips() {
+        let (mut sender, mut receiver) = serde_channel::<String>(100).unwrap();
+        let message = "x".repeat(80);
+
+        for _ in 0..5 {
+            sender.send(&message).unwrap();
+            // A second one cannot fit until the first is read.
+            assert!(matches!(sender.send(&message), Err(SendError::Full)));
+            assert_eq!(receiver.recv().unwrap(), Some(message.clone()));
+        }
+    }
+
+    #[test]
+    fn a_frame_too_large_for_the_ring_is_not_mistaken_for_a_full_one() {
+        let (mut sender, mut receiver) = serde_channel::<String>(32).unwrap();
+
+        // On an empty ring, so the error cannot be transient.
+        assert!(matches!(
+            sender.send(&"y".repeat(40)),
+            Err(SendError::FrameTooLarge)
+        ));
+
+        sender.send(&"z".repeat(10)).unwrap();
+        assert!(matches!(sender.send(&"z".repeat(10)), Err(SendError::Full)));
+        assert_eq!(receiver.recv().unwrap(), Some("z".repeat(10)));
+        sender.send(&"z".repeat(10)).unwrap();
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    os::fd::AsRawFd,
+    path::Path,
+    ptr::NonNull,
+};
+
+use crate::{
+    region::{self, SharedReader, SharedWriter},
+    ring_buffer::RingBufferError,
+};
+
+#[derive(Debug)]
+pub enum ShmError {
+    Io(io::Error),
+    /// The capacity cannot be laid out, or the mapped header does not
+    /// describe a ring of this element type: wrong magic, version, capacity
+    /// or element size.
+    InvalidMapping,
+}
+
+impl From<io::Error> for ShmError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<RingBufferError> for ShmError {
+    fn from(_: RingBufferError) -> Self {
+        Self::InvalidMapping
+    }
+}
+
+// A shared mapping of a whole file. The file itself can be closed once it
+// is mapped; the mapping keeps the pages alive.
+struct Mapping {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// The mapping is owned and only unmapped on drop, so the pointer may move to
+// another thread with it.
+unsafe impl Send for Mapping {}
+
+impl Mapping {
+    fn map(file: &File, len: usize) -> io::Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            ptr: NonNull::new(ptr.cast()).ok_or_else(io::Error::last_os_error)?,
+            len,
+        })
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr.as_ptr().cast(), self.len) };
+    }
+}
+
+/// Writer half of a ring in a shared file mapping, e.g. a file under
+/// `/dev/shm` or a `memfd`, for handing data to another process.
+///
+/// The mapping uses the layout of [`crate::region`]: a header with a magic
+/// number, a layout version, the capacity, the element size and the two
+/// indices, followed by the slots. The indices are published with the same
+/// acquire/release pairs as the in-process rings, which work across
+/// processes sharing the pages.
+///
+/// Elements are copied as raw bytes, so `T` must be plain data without
+/// pointers or references, and both processes must agree on its layout,
+/// e.g. a primitive or a `#[repr(C)]` struct built for the same target.
+pub struct ShmWriter<T: Copy> {
+    writer: SharedWriter<T>,
+    // Declared after the handle, so it is unmapped only once the handle is
+    // gone.
+    _mapping: Mapping,
+}
+
+impl<T: Copy> ShmWriter<T> {
+    /// Creates (or truncates) the file at `path` and sets up an empty ring
+    /// with room for `capacity` elements in it.
+    pub fn create(path: impl AsRef<Path>, capacity: usize) -> Result<Self, ShmError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+
+        Self::create_in(file, capacity)
+    }
+
+    /// Like [`ShmWriter::create`], for a file that is already open, such as
+    /// a `memfd`. Its contents are replaced.
+    pub fn create_in(file: File, capacity: usize) -> Result<Self, ShmError> {
+        let len = region::region_len::<T>(capacity).ok_or(ShmError::InvalidMapping)?;
+        file.set_len(0)?;
+        file.set_len(len as u64)?;
+
+        let mapping = Mapping::map(&file, len)?;
+        // Safety: the mapping was just made for this handle and outlives it,
+        // and no reader can attach before `init` publishes the header.
+        let writer = unsafe {
+            region::init::<T>(mapping.ptr.as_ptr(), len)?;
+            SharedWriter::attach(mapping.ptr.as_ptr(), len)?
+        };
+        if writer.capacity() as usize != capacity {
+            return Err(ShmError::InvalidMapping);
+        }
+
+        Ok(Self {
+            writer,
+            _mapping: mapping,
+        })
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> u32 {
+        self.writer.capacity()
+    }
+
+    /// Writes all of `buffer` if it fits and returns its length, or returns
+    /// zero without writing anything.
+    #[inline(always)]
+    pub fn write(&mut self, buffer: &[T]) -> u32 {
+        self.writer.write(buffer)
+    }
+
+    /// Waits until all of `buffer` fits and writes it, yielding the thread
+    /// in between. Returns zero if `buffer` is empty or larger than the
+    /// ring.
+    pub fn write_blocking(&mut self, buffer: &[T]) -> u32 {
+        self.writer.write_blocking(buffer)
+    }
+}
+
+/// Reader half of a ring set up by [`ShmWriter`], usually in another
+/// process.
+pub struct ShmReader<T: Copy> {
+    reader: SharedReader<T>,
+    _mapping: Mapping,
+}
+
+impl<T: Copy> ShmReader<T> {
+    /// Maps the ring in the file at `path` after checking its header.
+    ///
+    /// # Safety
+    ///
+    /// The ring must have been created by [`ShmWriter`] for a `T` with the
+    /// same layout; the header only records the element size. At most one
+    /// reader may be attached at a time.
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, ShmError> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+
+        Self::open_in(file)
+    }
+
+    /// Like [`ShmReader::open`], for a file that is already open, such as a
+    /// `memfd` passed down from the writer's process.
+    ///
+    /// # Safety
+    ///
+    /// As for [`ShmReader::open`].
+    pub unsafe fn open_in(file: File) -> Result<Self, ShmError> {
+        let len = usize::try_from(file.metadata()?.len()).map_err(|_| ShmError::InvalidMapping)?;
+        if len == 0 {
+            return Err(ShmError::InvalidMapping);
+        }
+
+        let mapping = Mapping::map(&file, len)?;
+        let reader = SharedReader::attach(mapping.ptr.as_ptr(), len)?;
+
+        Ok(Self {
+            reader,
+            _mapping: mapping,
+        })
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> u32 {
+        self.reader.capacity()
+    }
+
+    /// Fills all of `buffer` if that many elements are queued and returns
+    /// its length, or returns zero without reading anything.
+    #[inline(always)]
+    pub fn read(&mut self, buffer: &mut [T]) -> u32 {
+        self.reader.read(buffer)
+    }
+
+    /// Waits until `buffer` can be filled and reads into it, yielding the
+    /// thread in between. Returns zero if `buffer` is empty or larger than
+    /// the ring.
+    pub fn read_blocking(&mut self, buffer: &mut [T]) -> u32 {
+        self.reader.read_blocking(buffer)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::{
+        fs,
+        io::{Seek, SeekFrom, Write},
+        path::PathBuf,
+    };
+
+    use super::*;
+
+    // A ring file in the temp directory, removed once the test is done.
+    struct RingFile(PathBuf);
+
+    impl RingFile {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("spsc-shm-{}-{name}", std::process::id())))
+        }
+
+        // Overwrites the header word at `offset` with `value`.
+        fn patch(&self, offset: u64, value: u32) {
+            let mut file = OpenOptions::new().write(true).open(&self.0).unwrap();
+            file.seek(SeekFrom::Start(offset)).unwrap();
+            file.write_all(&value.to_le_bytes()).unwrap();
+        }
+
+        fn open<T: Copy>(&self) -> Result<ShmReader<T>, ShmError> {
+            unsafe { ShmReader::open(&self.0) }
+        }
+    }
+
+    impl Drop for RingFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    // Offsets of the header words, as laid out by `crate::region`.
+    const MAGIC: u64 = 0;
+    const VERSION: u64 = 4;
+    const CAPACITY: u64 = 8;
+
+    #[test]
+    fn a_reader_sees_what_the_writer_wrote() {
+        let file = RingFile::new("roundtrip");
+        let mut writer = ShmWriter::<u64>::create(&file.0, 4).unwrap();
+        let mut reader = file.open::<u64>().unwrap();
+        assert_eq!(reader.capacity(), 4);
+
+        for round in 0..3 {
+            let values = [round * 3, round * 3 + 1, round * 3 + 2];
+            assert_eq!(writer.write(&values), 3);
+            let mut buffer = [0; 3];
+            assert_eq!(reader.read(&mut buffer), 3);
+            assert_eq!(buffer, values);
+        }
+    }
+
+    #[test]
+    fn a_bad_magic_is_rejected() {
+        let file = RingFile::new("magic");
+        let _writer = ShmWriter::<u64>::create(&file.0, 4).unwrap();
+        file.patch(MAGIC, u32::from_le_bytes(*b"CSPS"));
+
+        assert!(matches!(file.open::<u64>(), Err(ShmError::InvalidMapping)));
+    }
+
+    #[test]
+    fn another_layout_version_is_rejected() {
+        let file = RingFile::new("version");
+        let _writer = ShmWriter::<u64>::create(&file.0, 4).unwrap();
+        file.patch(VERSION, 2);
+
+        assert!(matches!(file.open::<u64>(), Err(ShmError::InvalidMapping)));
+    }
+
+    #[test]
+    fn a_capacity_the_file_cannot_hold_is_rejected() {
+        let file = RingFile::new("capacity");
+        let _writer = ShmWriter::<u64>::create(&file.0, 4).unwrap();
+        file.patch(CAPACITY, 5);
+
+        assert!(matches!(file.open::<u64>(), Err(ShmError::InvalidMapping)));
+    }
+
+    #[test]
+    fn another_element_type_is_rejected() {
+        let file = RingFile::new("element");
+        let _writer = ShmWriter::<u64>::create(&file.0, 4).unwrap();
+
+        assert!(matches!(file.open::<u32>(), Err(ShmError::InvalidMapping)));
+    }
+
+    #[test]
+    fn an_empty_file_is_rejected() {
+        let file = RingFile::new("empty");
+        File::create(&file.0).unwrap();
+
+        assert!(matches!(file.open::<u64>(), Err(ShmError::InvalidMapping)));
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+
+use crate::ring_buffer::{RawBufferWriter, Writer};
+
+/// A writer that never blocks and never drops data.
+///
+/// Elements that do not fit in the ring are kept in an unbounded local
+/// queue and moved into the ring, ahead of any newer data, on subsequent
+/// writes. A steadily growing [`SpillWriter::spilled_len`] means the consumer
+/// cannot keep up.
+pub struct SpillWriter<T: Copy> {
+    writer: Writer<T>,
+    spilled: VecDeque<T>,
+}
+
+impl<T: Copy> SpillWriter<T> {
+    pub fn new(writer: Writer<T>) -> Self {
+        Self {
+            writer,
+            spilled: VecDeque::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn spilled_len(&self) -> usize {
+        self.spilled.len()
+    }
+
+    pub fn write(&mut self, buffer: &[T]) {
+        let (free, index) = self.writer.free();
+
+        let from_spill = free.min(self.spilled.len());
+        let from_buffer = if self.spilled.len() == from_spill {
+            (free - from_spill).min(buffer.len())
+        } else {
+            0
+        };
+
+        // Both runs together stay within the `free` slots from `index`, and
+        // they are published only once every one has been written.
+        unsafe {
+            for (offset, value) in self.spilled.drain(..from_spill).enumerate() {
+                *self.writer.get_mut(index.wrapping_add(offset)) = value;
+            }
+            for (offset, value) in buffer[..from_buffer].iter().enumerate() {
+                *self.writer.get_mut(index.wrapping_add(from_spill + offset)) = *value;
+            }
+
+            if from_spill + from_buffer > 0 {
+                self.writer.advance_index(from_spill + from_buffer);
+            }
+        }
+        self.spilled.extend(&buffer[from_buffer..]);
+    }
+
+    /// Moves as much spilled data into the ring as currently fits.
+    pub fn flush(&mut self) {
+        self.write(&[]);
+    }
+
+    /// Returns the inner writer and whatever is still spilled.
+    pub fn into_parts(self) -> (Writer<T>, VecDeque<T>) {
+        (self.writer, self.spilled)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+    use crate::ring_buffer::{BufferReader, RingBuffer};
+
+    #[test]
+    fn stalled_consumer_gets_everything_in_order_after_recovering() {
+        let (writer, mut reader) = RingBuffer::<u32>::new(8).unwrap();
+        let mut writer = SpillWriter::new(writer);
+
+        let source: Vec<u32> = (0..100).collect();
+        for chunk in source.chunks(3) {
+            writer.write(chunk);
+        }
+        assert_eq!(writer.spilled_len(), 92);
+
+        let mut received = Vec::new();
+        let mut buffer = [0; 5];
+        while received.len() < source.len() {
+            writer.flush();
+            let n = reader.read(&mut buffer);
+            received.extend_from_slice(&buffer[..n]);
+        }
+
+        assert_eq!(received, source);
+        assert_eq!(writer.spilled_len(), 0);
+    }
+
+    #[test]
+    fn spilled_data_goes_out_before_newer_writes() {
+        let (writer, mut reader) = RingBuffer::<u32>::new(4).unwrap();
+        let mut writer = SpillWriter::new(writer);
+        writer.write(&[1, 2, 3, 4, 5, 6]);
+
+        let mut buffer = [0; 4];
+        assert_eq!(reader.read(&mut buffer[..3]), 3);
+        // Three slots are free, but two of them go to the spilled elements.
+        writer.write(&[7, 8]);
+        assert_eq!(writer.spilled_len(), 1);
+
+        let mut received = vec![buffer[0], buffer[1], buffer[2]];
+        while received.len() < 8 {
+            writer.flush();
+            let n = reader.read(&mut buffer[..1]);
+            received.extend_from_slice(&buffer[..n]);
+        }
+        assert_eq!(received, [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn writer_never_blocks_while_the_consumer_thread_stalls() {
+        const TOTAL: u32 = 50_000;
+
+        let (writer, mut reader) = RingBuffer::<u32>::new(16).unwrap();
+        let mut writer = SpillWriter::new(writer);
+        let consumer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+
+            let mut received = Vec::new();
+            let mut buffer = [0; 16];
+            while received.len() < TOTAL as usize {
+                match reader.read(&mut buffer) {
+                    0 => thread::yield_now(),
+                    n => received.extend_from_slice(&buffer[..n]),
+                }
+            }
+            received
+        });
+
+        for value in 0..TOTAL {
+            writer.write(&[value]);
+        }
+        let peak = writer.spilled_len();
+        while writer.spilled_len() > 0 {
+            writer.flush();
+            thread::yield_now();
+        }
+
+        assert!(peak > 0);
+        assert_eq!(consumer.join().unwrap(), (0..TOTAL).collect::<Vec<_>>());
+    }
+}
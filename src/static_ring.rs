@@ -0,0 +1,307 @@
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use crate::{
+    index,
+    ring_buffer::{
+        BufferReader, BufferWriter, CachePadded, RawBufferReader, RawBufferWriter, ReadBuf,
+        RingBufferError,
+    },
+};
+
+/// A ring with its storage inline, for use without an allocator. Since
+/// [`StaticRingBuffer::new`] is a `const fn`, the ring can be placed in a
+/// `static` and split into its two halves at runtime with
+/// [`StaticRingBuffer::split`].
+///
+/// The indices work exactly as in [`crate::ring_buffer::RingBuffer`], so
+/// `N` need not be a power of two. It is checked when the ring is built:
+/// zero, or an odd size above a third of the index range, fails to compile.
+pub struct StaticRingBuffer<T, const N: usize> {
+    buffer: UnsafeCell<[MaybeUninit<T>; N]>,
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    split: AtomicBool,
+}
+
+// The halves only ever touch slots their indices hand them, like the heap
+// ring, and `split` makes sure there is only one of each.
+unsafe impl<T: Send, const N: usize> Sync for StaticRingBuffer<T, N> {}
+
+impl<T: Copy, const N: usize> Default for StaticRingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy, const N: usize> StaticRingBuffer<T, N> {
+    const WRAP: usize = match index::index_wrap(N) {
+        Some(wrap) => wrap,
+        None => panic!("unsupported StaticRingBuffer capacity"),
+    };
+
+    pub const fn new() -> Self {
+        // Evaluating the wrap here rejects a bad `N` at compile time.
+        let _wrap = Self::WRAP;
+
+        Self {
+            buffer: UnsafeCell::new([const { MaybeUninit::uninit() }; N]),
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Hands out the writer and reader halves. Only the first call
+    /// succeeds; any later one fails with [`RingBufferError::AlreadySplit`],
+    /// even after the halves are dropped.
+    pub fn split(
+        &'static self,
+    ) -> Result<(StaticWriter<T, N>, StaticReader<T, N>), RingBufferError> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return Err(RingBufferError::AlreadySplit);
+        }
+
+        Ok((StaticWriter { ring: self }, StaticReader { ring: self }))
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    #[inline(always)]
+    fn slot(&self, index: usize) -> *mut T {
+        // Safety: the remainder is below `N`, the array length.
+        unsafe { self.buffer.get().cast::<T>().add(index % N) }
+    }
+
+    // The read index is loaded first, as in the heap ring, so the distance
+    // can only overstate and the clamp covers it.
+    #[inline(always)]
+    fn occupied(&self) -> usize {
+        let tail_index = self.tail.0.load(Ordering::Acquire);
+        let head_index = self.head.0.load(Ordering::Acquire);
+
+        index::distance(Self::WRAP, tail_index, head_index).min(N)
+    }
+}
+
+pub struct StaticWriter<T: Copy + 'static, const N: usize> {
+    ring: &'static StaticRingBuffer<T, N>,
+}
+
+impl<T: Copy + 'static, const N: usize> StaticWriter<T, N> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of queued elements, as a snapshot.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ring.occupied()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline(always)]
+    fn free(&self) -> (usize, usize) {
+        let head_index = self.ring.head.0.load(Ordering::Relaxed);
+        let tail_index = self.ring.tail.0.load(Ordering::Acquire);
+        let filled = index::distance(StaticRingBuffer::<T, N>::WRAP, tail_index, head_index);
+
+        (N - filled, head_index)
+    }
+}
+
+impl<T: Copy + 'static, const N: usize> RawBufferWriter<T> for StaticWriter<T, N> {
+    #[inline(always)]
+    fn available(&self, size: usize) -> (usize, usize) {
+        let (free, head_index) = self.free();
+        if free >= size {
+            (size, head_index)
+        } else {
+            (0, head_index)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get_mut(&mut self, index: usize) -> &mut T {
+        // The slot may not hold a value yet; callers only store through the
+        // reference, which for a `Copy` type never reads the old contents.
+        unsafe { &mut *self.ring.slot(index) }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        let head_index = self.ring.head.0.load(Ordering::Relaxed);
+        self.ring.head.0.store(
+            index::offset_index(StaticRingBuffer::<T, N>::WRAP, head_index, offset),
+            Ordering::Release,
+        );
+    }
+}
+
+impl<T: Copy + 'static, const N: usize> BufferWriter<T> for StaticWriter<T, N> {
+    /// Writes as much of `buffer` as currently fits and returns the number
+    /// of elements written.
+    #[inline(always)]
+    fn write(&mut self, buffer: &[T]) -> usize {
+        let (free, index) = self.free();
+        let n = free.min(buffer.len());
+
+        // `free` reported at least `n` free slots from `index`, and they are
+        // published only once every one has been written.
+        unsafe {
+            for (offset, value) in buffer[..n].iter().enumerate() {
+                *self.get_mut(index.wrapping_add(offset)) = *value;
+            }
+            if n > 0 {
+                self.advance_index(n);
+            }
+        }
+
+        n
+    }
+}
+
+pub struct StaticReader<T: Copy + 'static, const N: usize> {
+    ring: &'static StaticRingBuffer<T, N>,
+}
+
+impl<T: Copy + 'static, const N: usize> StaticReader<T, N> {
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of queued elements, as a snapshot.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ring.occupied()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline(always)]
+    fn pending(&self) -> (usize, usize) {
+        let tail_index = self.ring.tail.0.load(Ordering::Relaxed);
+        let head_index = self.ring.head.0.load(Ordering::Acquire);
+
+        (
+            index::distance(StaticRingBuffer::<T, N>::WRAP, tail_index, head_index),
+            tail_index,
+        )
+    }
+}
+
+impl<T: Copy + 'static, const N: usize> RawBufferReader<T> for StaticReader<T, N> {
+    #[inline(always)]
+    fn filled(&self, size: usize) -> (usize, usize) {
+        let (pending, tail_index) = self.pending();
+        if pending >= size {
+            (size, tail_index)
+        } else {
+            (0, tail_index)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get(&self, index: usize) -> &T {
+        unsafe { &*self.ring.slot(index) }
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        let tail_index = self.ring.tail.0.load(Ordering::Relaxed);
+        self.ring.tail.0.store(
+            index::offset_index(StaticRingBuffer::<T, N>::WRAP, tail_index, offset),
+            Ordering::Release,
+        );
+    }
+}
+
+impl<T: Copy + 'static, const N: usize> BufferReader<T> for StaticReader<T, N> {
+    /// Reads as many queued elements as fit in `buffer`.
+    #[inline(always)]
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        let (pending, index) = self.pending();
+        let n = pending.min(buffer.remaining());
+
+        // `pending` reported at least `n` queued slots from `index`, and they
+        // are released only after every one has been copied out.
+        unsafe {
+            for offset in 0..n {
+                let _ = buffer.push(*self.get(index.wrapping_add(offset)));
+            }
+            if n > 0 {
+                self.advance_index(n);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn a_ring_is_split_only_once() {
+        static RING: StaticRingBuffer<u32, 4> = StaticRingBuffer::new();
+
+        {
+            let _halves = RING.split().unwrap();
+            assert!(matches!(RING.split(), Err(RingBufferError::AlreadySplit)));
+        }
+
+        // Letting go of the halves does not make the ring splittable again.
+        assert!(matches!(RING.split(), Err(RingBufferError::AlreadySplit)));
+    }
+
+    #[test]
+    fn halves_of_a_static_ring_move_data_between_threads() {
+        const ITEMS: u32 = 100_000;
+        static RING: StaticRingBuffer<u32, 7> = StaticRingBuffer::new();
+
+        let (mut writer, mut reader) = RING.split().unwrap();
+        let producer = thread::spawn(move || {
+            let mut next = 0;
+            while next < ITEMS {
+                let burst: Vec<_> = (next..ITEMS.min(next + 5)).collect();
+                match writer.write(&burst) {
+                    0 => thread::yield_now(),
+                    n => next += n as u32,
+                }
+            }
+        });
+
+        let mut expected = 0;
+        let mut buffer = [0; 4];
+        while expected < ITEMS {
+            match reader.read(&mut buffer) {
+                0 => thread::yield_now(),
+                n => {
+                    for &value in &buffer[..n] {
+                        assert_eq!(value, expected);
+                        expected += 1;
+                    }
+                }
+            }
+        }
+
+        producer.join().unwrap();
+        assert!(reader.is_empty());
+    }
+}
@@ -0,0 +1,20 @@
+//! The atomics and shared pointer the lock-free rings are built on. Building
+//! with `--cfg loom` swaps them for loom's model-checked ones, so a
+//! weakened ordering shows up as a failing interleaving rather than as a
+//! rare bug in the field.
+
+#[cfg(not(loom))]
+pub(crate) use alloc::sync::Arc;
+#[cfg(all(not(loom), any(feature = "stats", feature = "std")))]
+pub(crate) use core::sync::atomic::AtomicU64;
+#[cfg(not(loom))]
+pub(crate) use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+#[cfg(all(loom, any(feature = "stats", feature = "std")))]
+pub(crate) use loom::sync::atomic::AtomicU64;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+#[cfg(loom)]
+pub(crate) use loom::sync::Arc;
+#[cfg(loom)]
+pub(crate) use loom::thread::yield_now;
@@ -0,0 +1,455 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Barrier,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::ring_buffer::{
+    BufferReader, BufferWriter, RawBufferReader, RawBufferWriter, Reader, RingBuffer, Writer,
+};
+
+/// How long [`run_transfer`] waits before declaring the transfer hung.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransferReport {
+    pub elements: usize,
+    /// From both threads starting on the data to both being done, leaving
+    /// out thread start-up.
+    pub elapsed: Duration,
+    /// Writes that found no room and had to be retried.
+    pub write_retries: u64,
+    /// Reads that found nothing queued and had to be retried.
+    pub read_retries: u64,
+}
+
+/// Moves `data` from `writer` to `reader` on two fresh threads, `burst`
+/// elements per call, and checks that the reader saw exactly `data` in
+/// order. Panics with the progress made so far if the transfer does not
+/// finish within [`DEFAULT_TIMEOUT`], and with the first differing index if
+/// the contents do not match.
+pub fn run_transfer<T, W, R>(writer: W, reader: R, data: Vec<T>, burst: usize) -> TransferReport
+where
+    T: Copy + Default + PartialEq + Debug + Send + Sync + 'static,
+    W: BufferWriter<T> + Send + 'static,
+    R: BufferReader<T> + Send + 'static,
+{
+    run_transfer_timeout(writer, reader, data, burst, DEFAULT_TIMEOUT)
+}
+
+pub fn run_transfer_timeout<T, W, R>(
+    writer: W,
+    reader: R,
+    data: Vec<T>,
+    burst: usize,
+    timeout: Duration,
+) -> TransferReport
+where
+    T: Copy + Default + PartialEq + Debug + Send + Sync + 'static,
+    W: BufferWriter<T> + Send + 'static,
+    R: BufferReader<T> + Send + 'static,
+{
+    run_transfer_bursts(writer, reader, data, &[burst], &[burst], timeout)
+}
+
+/// Like [`run_transfer_timeout`], but the writer cycles through
+/// `write_bursts` and the reader through `read_bursts` for the sizes of
+/// their calls, so that partial transfers and wrap-arounds land at varying
+/// offsets. Zero-sized bursts are skipped.
+pub fn run_transfer_bursts<T, W, R>(
+    mut writer: W,
+    mut reader: R,
+    data: Vec<T>,
+    write_bursts: &[usize],
+    read_bursts: &[usize],
+    timeout: Duration,
+) -> TransferReport
+where
+    T: Copy + Default + PartialEq + Debug + Send + Sync + 'static,
+    W: BufferWriter<T> + Send + 'static,
+    R: BufferReader<T> + Send + 'static,
+{
+    let write_bursts: Vec<usize> = write_bursts.iter().copied().filter(|&n| n > 0).collect();
+    let read_bursts: Vec<usize> = read_bursts.iter().copied().filter(|&n| n > 0).collect();
+    assert!(
+        !write_bursts.is_empty() && !read_bursts.is_empty(),
+        "bursts must not all be zero",
+    );
+
+    let data: Arc<[T]> = data.into();
+    let elements = data.len();
+    let stop = Arc::new(AtomicBool::new(false));
+    let written = Arc::new(AtomicUsize::new(0));
+    let read = Arc::new(AtomicUsize::new(0));
+    let (done, finished) = mpsc::channel();
+    // Both threads start moving data together, once they are up, so that
+    // thread start-up stays out of the reported time.
+    let ready = Arc::new(Barrier::new(3));
+
+    let writer_thread = thread::spawn({
+        let (data, stop, written, done, ready) = (
+            data.clone(),
+            stop.clone(),
+            written.clone(),
+            done.clone(),
+            ready.clone(),
+        );
+        move || {
+            ready.wait();
+            let mut bursts = write_bursts.iter().cycle();
+            let mut retries = 0;
+            let mut index = 0;
+
+            while index != elements && !stop.load(Ordering::Relaxed) {
+                let end = (index + bursts.next().unwrap()).min(elements);
+                match writer.write(&data[index..end]) {
+                    0 => {
+                        retries += 1;
+                        thread::yield_now();
+                    }
+                    n => {
+                        index += n;
+                        written.store(index, Ordering::Relaxed);
+                    }
+                }
+            }
+            let _ = done.send(());
+
+            retries
+        }
+    });
+
+    let reader_thread = thread::spawn({
+        let (stop, read, ready) = (stop.clone(), read.clone(), ready.clone());
+        move || {
+            let mut received = vec![T::default(); elements];
+            ready.wait();
+            let mut bursts = read_bursts.iter().cycle();
+            let mut retries = 0;
+            let mut index = 0;
+
+            while index != elements && !stop.load(Ordering::Relaxed) {
+                let end = (index + bursts.next().unwrap()).min(elements);
+                match reader.read(&mut received[index..end]) {
+                    0 => {
+                        retries += 1;
+                        thread::yield_now();
+                    }
+                    n => {
+                        index += n;
+                        read.store(index, Ordering::Relaxed);
+                    }
+                }
+            }
+            let _ = done.send(());
+
+            (received, retries)
+        }
+    });
+
+    ready.wait();
+    let start = Instant::now();
+    let deadline = start + timeout;
+    for _ in 0..2 {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if finished.recv_timeout(remaining).is_err() {
+            stop.store(true, Ordering::Relaxed);
+            panic!(
+                "transfer of {elements} elements did not finish within {timeout:?}: \
+                 {} written, {} read",
+                written.load(Ordering::Relaxed),
+                read.load(Ordering::Relaxed),
+            );
+        }
+    }
+    let elapsed = start.elapsed();
+
+    let write_retries = writer_thread.join().expect("writer thread panicked");
+    let (received, read_retries) = reader_thread.join().expect("reader thread panicked");
+    if let Some(index) = (0..elements).find(|&index| received[index] != data[index]) {
+        panic!(
+            "element {index} differs: sent {:?}, received {:?}",
+            data[index], received[index],
+        );
+    }
+
+    TransferReport {
+        elements,
+        elapsed,
+        write_retries,
+        read_retries,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Write(usize),
+    Read(usize),
+    /// Drops up to `n` elements through [`Reader::skip`].
+    Skip(usize),
+}
+
+/// Drives both halves of a `u64` ring from the calling thread according to
+/// an explicit schedule, so that an ordering problem reproduces exactly.
+///
+/// Writes push consecutive sequence numbers and reads check that they come
+/// back in order. After every step the pump checks that the transfer
+/// counts are consistent with its own model of the queue: a write of `n`
+/// elements with at least `n` slots free, or a read of `n` with at least `n`
+/// elements queued, must move all `n`, and both sides must agree on how much
+/// is queued. A violation panics with the steps taken so far.
+pub struct Pump {
+    writer: Writer<u64>,
+    reader: Reader<u64>,
+    written: u64,
+    read: u64,
+    history: Vec<Step>,
+}
+
+impl Pump {
+    pub fn new(writer: Writer<u64>, reader: Reader<u64>) -> Self {
+        Self {
+            writer,
+            reader,
+            written: 0,
+            read: 0,
+            history: Vec::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn queued(&self) -> usize {
+        (self.written - self.read) as usize
+    }
+
+    pub fn history(&self) -> &[Step] {
+        &self.history
+    }
+
+    pub fn step_write(&mut self, n: usize) -> usize {
+        self.history.push(Step::Write(n));
+        let free = self.writer.capacity() - self.queued();
+
+        let values: Vec<u64> = (self.written..self.written + n as u64).collect();
+        let written = self.writer.write(&values);
+        self.check(
+            written == n.min(free),
+            "write did not move min(n, free) elements",
+        );
+        self.written += written as u64;
+        self.check_queue();
+
+        written
+    }
+
+    pub fn step_read(&mut self, n: usize) -> usize {
+        self.history.push(Step::Read(n));
+        let queued = self.queued();
+
+        let mut values = vec![u64::MAX; n];
+        let read = self.reader.read(&mut values);
+        self.check(
+            read == n.min(queued),
+            "read did not move min(n, queued) elements",
+        );
+        for (offset, value) in values[..read].iter().enumerate() {
+            self.check(
+                *value == self.read + offset as u64,
+                "elements came back out of order",
+            );
+        }
+        self.read += read as u64;
+        self.check_queue();
+
+        read
+    }
+
+    pub fn step_skip(&mut self, n: usize) -> usize {
+        self.history.push(Step::Skip(n));
+        let queued = self.queued();
+
+        let expected = self.reader.peek_at(0);
+        let skipped = self.reader.skip(n);
+        self.check(
+            skipped == n.min(queued),
+            "skip did not drop min(n, queued) elements",
+        );
+        self.check(
+            skipped == 0 || expected == Some(self.read),
+            "peek did not show the oldest element",
+        );
+        self.read += skipped as u64;
+        self.check_queue();
+
+        skipped
+    }
+
+    /// Runs `script` step by step, returning the number of elements each
+    /// step moved.
+    pub fn run(&mut self, script: &[Step]) -> Vec<usize> {
+        script
+            .iter()
+            .map(|step| match *step {
+                Step::Write(n) => self.step_write(n),
+                Step::Read(n) => self.step_read(n),
+                Step::Skip(n) => self.step_skip(n),
+            })
+            .collect()
+    }
+
+    fn check_queue(&self) {
+        let queued = self.queued();
+
+        self.check(
+            queued <= self.writer.capacity(),
+            "more queued than the ring holds",
+        );
+        self.check(
+            self.reader.filled(queued).0 == queued && self.reader.filled(queued + 1).0 == 0,
+            "reader disagrees about how much is queued",
+        );
+        let free = self.writer.capacity() - queued;
+        self.check(
+            self.writer.available(free).0 == free && self.writer.available(free + 1).0 == 0,
+            "writer disagrees about how much is free",
+        );
+    }
+
+    #[track_caller]
+    fn check(&self, condition: bool, message: &str) {
+        if !condition {
+            panic!("{message} after steps {:?}", self.history);
+        }
+    }
+}
+
+/// Runs `script` on any pair of halves from the calling thread, writing
+/// consecutive `u64`s, and panics unless every element comes out exactly
+/// once and in order. Unlike [`Pump`] it knows nothing about the capacity,
+/// so rings that refuse a write they cannot take whole pass as well; a skip
+/// is a read whose elements are checked and dropped. Returns the number of
+/// elements left queued.
+pub fn run_fifo<W, R>(writer: &mut W, reader: &mut R, script: &[Step]) -> u64
+where
+    W: BufferWriter<u64> + ?Sized,
+    R: BufferReader<u64> + ?Sized,
+{
+    let (mut written, mut read) = (0u64, 0u64);
+
+    for (step, &op) in script.iter().enumerate() {
+        match op {
+            Step::Write(n) => {
+                let values: Vec<u64> = (written..written + n as u64).collect();
+                let moved = writer.write(&values);
+                assert!(
+                    moved <= n,
+                    "write of {n} moved {moved} at step {step} of {:?}",
+                    &script[..=step],
+                );
+                written += moved as u64;
+            }
+            Step::Read(n) | Step::Skip(n) => {
+                let mut values = vec![u64::MAX; n];
+                let moved = reader.read(&mut values);
+                assert!(
+                    moved <= n && read + moved as u64 <= written,
+                    "read of {n} moved {moved} with {} queued at step {step} of {:?}",
+                    written - read,
+                    &script[..=step],
+                );
+                for (offset, value) in values[..moved].iter().enumerate() {
+                    assert_eq!(
+                        *value,
+                        read + offset as u64,
+                        "element out of order at step {step} of {:?}",
+                        &script[..=step],
+                    );
+                }
+                read += moved as u64;
+            }
+        }
+    }
+
+    written - read
+}
+
+/// One operation in a differential run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Write(Vec<u8>),
+    Read(usize),
+    /// Discards everything queued.
+    Clear,
+    /// Closes the writer; later writes must be turned away.
+    Close,
+}
+
+/// Applies `ops` to a lock-free ring and to the mutex/`VecDeque` ring, both
+/// with room for `capacity` bytes, and panics as soon as they disagree on a
+/// return count or on the bytes read. The `VecDeque` backend is simple
+/// enough to act as the specification; it has no notion of closing, so a
+/// closed model simply takes no more writes, and the lock-free reader must
+/// report the end of the stream exactly when a closed model runs empty.
+pub fn run_differential(capacity: usize, ops: &[Op]) {
+    let (mut writer, mut reader) = RingBuffer::<u8>::new(capacity).unwrap();
+    let model = crate::vecdeque::RingBuffer::<u8>::new(capacity);
+    let mut closed = false;
+
+    for (step, op) in ops.iter().enumerate() {
+        match op {
+            Op::Write(data) => {
+                let actual = writer.write(data);
+                let expected = if closed { 0 } else { model.write(data) };
+                assert_eq!(
+                    actual,
+                    expected,
+                    "write count diverged at step {step} of {:?}",
+                    &ops[..=step],
+                );
+            }
+            Op::Read(n) => {
+                let mut actual = vec![0; *n];
+                let mut expected = vec![0; *n];
+                let actual_count = reader.read(&mut actual);
+                let expected_count = model.read(&mut expected);
+                assert_eq!(
+                    actual_count,
+                    expected_count,
+                    "read count diverged at step {step} of {:?}",
+                    &ops[..=step],
+                );
+                assert_eq!(
+                    actual[..actual_count],
+                    expected[..expected_count],
+                    "read bytes diverged at step {step} of {:?}",
+                    &ops[..=step],
+                );
+            }
+            Op::Clear => {
+                let actual = reader.skip(usize::MAX);
+                let expected = model.read(&mut vec![0; model.len()]);
+                assert_eq!(
+                    actual,
+                    expected,
+                    "clear count diverged at step {step} of {:?}",
+                    &ops[..=step],
+                );
+            }
+            Op::Close => {
+                writer.close();
+                closed = true;
+            }
+        }
+
+        assert_eq!(
+            reader.is_closed(),
+            closed && model.is_empty(),
+            "end of stream diverged at step {step} of {:?}",
+            &ops[..=step],
+        );
+    }
+}
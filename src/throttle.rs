@@ -0,0 +1,222 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    clock::{Clock, MonotonicClock},
+    ring_buffer::{BufferReader, RawBufferReader, ReadBuf, Reader},
+};
+
+/// A reader that releases at most `rate` elements per second, with bursts
+/// of up to `burst` elements after an idle period (a token bucket).
+///
+/// Every element consumed through the wrapper costs one token, whether it
+/// is copied out by [`BufferReader::read`] or skipped with
+/// [`RawBufferReader::advance_index`].
+pub struct Throttled<T: Copy, C: Clock = MonotonicClock> {
+    reader: Reader<T>,
+    clock: C,
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl<T: Copy> Throttled<T> {
+    /// The bucket starts full. Panics if `rate` is not positive or `burst`
+    /// is zero.
+    pub fn new(reader: Reader<T>, rate: f64, burst: usize) -> Self {
+        Self::with_clock(reader, rate, burst, MonotonicClock)
+    }
+}
+
+impl<T: Copy, C: Clock> Throttled<T, C> {
+    pub fn with_clock(reader: Reader<T>, rate: f64, burst: usize, clock: C) -> Self {
+        assert!(rate > 0.0, "rate must be positive");
+        assert!(burst > 0, "burst must not be zero");
+
+        Self {
+            reader,
+            rate,
+            burst: burst as f64,
+            tokens: burst as f64,
+            refilled_at: clock.now(),
+            clock,
+        }
+    }
+
+    fn tokens_at(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+
+        (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.burst)
+    }
+
+    fn refill(&mut self) {
+        let now = self.clock.now();
+        self.tokens = self.tokens_at(now);
+        self.refilled_at = now;
+    }
+
+    /// Number of elements that may be consumed right now.
+    #[inline(always)]
+    pub fn tokens(&self) -> u32 {
+        self.tokens_at(self.clock.now()) as u32
+    }
+
+    /// Reads all of `buffer`, sleeping whenever the bucket runs dry, and
+    /// returns the number of elements read. Returns early with fewer if the
+    /// writer disconnects or closes the ring and everything it published
+    /// has been read.
+    pub fn read_paced(&mut self, buffer: &mut [T]) -> usize {
+        let mut read = 0;
+
+        while read < buffer.len() {
+            // Nothing more can arrive, so there is no token worth waiting for.
+            if self.reader.is_finished() && self.reader.is_empty() {
+                break;
+            }
+
+            self.refill();
+            if self.tokens < 1.0 {
+                self.clock
+                    .sleep(Duration::from_secs_f64((1.0 - self.tokens) / self.rate));
+                continue;
+            }
+
+            let allowed = (self.tokens as usize).min(buffer.len() - read);
+            let finished = self.reader.is_finished();
+            match self.reader.read(&mut buffer[read..read + allowed]) {
+                0 if finished => break,
+                0 => thread::yield_now(),
+                n => {
+                    self.tokens -= n as f64;
+                    read += n;
+                }
+            }
+        }
+
+        read
+    }
+
+    pub fn into_inner(self) -> Reader<T> {
+        self.reader
+    }
+}
+
+impl<T: Copy, C: Clock> RawBufferReader<T> for Throttled<T, C> {
+    #[inline(always)]
+    fn filled(&self, size: usize) -> (usize, usize) {
+        let (filled, index) = self.reader.filled(size);
+
+        if self.tokens_at(self.clock.now()) >= size as f64 {
+            (filled, index)
+        } else {
+            (0, index)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn get(&self, index: usize) -> &T {
+        self.reader.get(index)
+    }
+
+    #[inline(always)]
+    unsafe fn advance_index(&mut self, offset: usize) {
+        self.refill();
+        self.tokens -= offset as f64;
+        self.reader.advance_index(offset);
+    }
+}
+
+impl<T: Copy, C: Clock> BufferReader<T> for Throttled<T, C> {
+    /// Reads as many elements as fit in `buffer`, limited by the tokens
+    /// currently in the bucket.
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        self.refill();
+        let allowed = (self.tokens as usize).min(buffer.remaining());
+
+        // Only the allowed slots are handed on, and whatever the inner
+        // reader fills there counts as filled here.
+        let n = unsafe {
+            let mut allowed = ReadBuf::new(&mut buffer.unfilled_mut()[..allowed]);
+            self.reader.read_buf(&mut allowed);
+            let n = allowed.filled();
+            buffer.advance(n);
+            n
+        };
+        self.tokens -= n as f64;
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::MockClock,
+        ring_buffer::{BufferWriter, RingBuffer},
+    };
+
+    fn throttled(rate: f64, burst: usize) -> (Throttled<u32, MockClock>, MockClock) {
+        let (mut writer, reader) = RingBuffer::<u32>::new(64).unwrap();
+        writer.write(&(0..64).collect::<Vec<_>>());
+        let clock = MockClock::new();
+
+        (
+            Throttled::with_clock(reader, rate, burst, clock.clone()),
+            clock,
+        )
+    }
+
+    #[test]
+    fn read_is_limited_to_the_tokens_in_the_bucket() {
+        let (mut throttled, clock) = throttled(10.0, 5);
+        let mut buffer = [0; 20];
+
+        assert_eq!(throttled.read(&mut buffer), 5);
+        assert_eq!(buffer[..5], [0, 1, 2, 3, 4]);
+        assert_eq!(throttled.read(&mut buffer), 0);
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(throttled.tokens(), 1);
+        assert_eq!(throttled.read(&mut buffer), 1);
+        assert_eq!(buffer[0], 5);
+
+        // An idle period refills no more than the burst.
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(throttled.tokens(), 5);
+        assert_eq!(throttled.read(&mut buffer), 5);
+        assert_eq!(buffer[..5], [6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn read_paced_sleeps_exactly_as_long_as_the_rate_demands() {
+        let (mut throttled, clock) = throttled(20.0, 4);
+        let start = clock.now();
+        let mut buffer = [0; 24];
+
+        assert_eq!(throttled.read_paced(&mut buffer), 24);
+        assert_eq!(buffer.to_vec(), (0..24).collect::<Vec<_>>());
+
+        // The first four come from the full bucket, the other twenty take
+        // one second at twenty per second.
+        let elapsed = clock.now() - start;
+        let drift = elapsed.abs_diff(Duration::from_secs(1));
+        assert!(drift < Duration::from_micros(1), "{elapsed:?}");
+    }
+
+    #[test]
+    fn read_paced_stops_once_the_writer_is_gone() {
+        let (mut writer, reader) = RingBuffer::<u32>::new(8).unwrap();
+        writer.write(&[1, 2, 3]);
+        drop(writer);
+        let clock = MockClock::new();
+        let mut throttled = Throttled::with_clock(reader, 1.0, 1, clock.clone());
+        let start = clock.now();
+
+        let mut buffer = [0; 8];
+        assert_eq!(throttled.read_paced(&mut buffer), 3);
+        assert_eq!(buffer[..3], [1, 2, 3]);
+        assert_eq!(clock.now() - start, Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    clock::{Clock, MonotonicClock},
+    ring_buffer::{RawBufferReader, RawBufferWriter},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Timed<T: Copy> {
+    pub value: T,
+    pub stamp: Instant,
+}
+
+impl<T: Copy> Timed<T> {
+    #[inline(always)]
+    pub fn age(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.stamp)
+    }
+}
+
+pub struct TimedWriter<T: Copy, W: RawBufferWriter<Timed<T>>, C: Clock = MonotonicClock> {
+    writer: W,
+    clock: C,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, W: RawBufferWriter<Timed<T>>> TimedWriter<T, W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_clock(writer, MonotonicClock)
+    }
+}
+
+impl<T: Copy, W: RawBufferWriter<Timed<T>>, C: Clock> TimedWriter<T, W, C> {
+    pub fn with_clock(writer: W, clock: C) -> Self {
+        Self {
+            writer,
+            clock,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn write(&mut self, buffer: &[T]) -> usize {
+        let (available, index) = self.writer.available(buffer.len());
+
+        if available > 0 {
+            let stamp = self.clock.now();
+            // `available` free slots start at `index`, and they are published
+            // only once every one has been written.
+            unsafe {
+                for (offset, value) in buffer.iter().enumerate() {
+                    *self.writer.get_mut(index + offset) = Timed {
+                        value: *value,
+                        stamp,
+                    };
+                }
+                self.writer.advance_index(available);
+            }
+
+            available
+        } else {
+            0
+        }
+    }
+}
+
+pub struct TimedReader<T: Copy, R: RawBufferReader<Timed<T>>, C: Clock = MonotonicClock> {
+    reader: R,
+    clock: C,
+    window: AgeWindow,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, R: RawBufferReader<Timed<T>>> TimedReader<T, R> {
+    /// Wraps `reader`, keeping rolling statistics over the ages of the last
+    /// `window` elements read.
+    pub fn new(reader: R, window: usize) -> Self {
+        Self::with_clock(reader, window, MonotonicClock)
+    }
+}
+
+impl<T: Copy, R: RawBufferReader<Timed<T>>, C: Clock> TimedReader<T, R, C> {
+    pub fn with_clock(reader: R, window: usize, clock: C) -> Self {
+        Self {
+            reader,
+            clock,
+            window: AgeWindow::new(window),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    pub fn read(&mut self, buffer: &mut [T]) -> usize {
+        let (filled, index) = self.reader.filled(buffer.len());
+
+        if filled > 0 {
+            let now = self.clock.now();
+            for (offset, value) in buffer.iter_mut().enumerate() {
+                // One of the `filled` published slots from `index`.
+                let timed = unsafe { self.reader.get(index + offset) };
+                self.window.record(timed.age(now));
+                *value = timed.value;
+            }
+            // Every one of them has been copied out above.
+            unsafe { self.reader.advance_index(filled) };
+
+            filled
+        } else {
+            0
+        }
+    }
+
+    pub fn read_timed(&mut self, buffer: &mut [Timed<T>]) -> usize {
+        let (filled, index) = self.reader.filled(buffer.len());
+
+        if filled > 0 {
+            let now = self.clock.now();
+            for (offset, value) in buffer.iter_mut().enumerate() {
+                // One of the `filled` published slots from `index`.
+                let timed = *unsafe { self.reader.get(index + offset) };
+                self.window.record(timed.age(now));
+                *value = timed;
+            }
+            // Every one of them has been copied out above.
+            unsafe { self.reader.advance_index(filled) };
+
+            filled
+        } else {
+            0
+        }
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.window.max()
+    }
+
+    pub fn mean_age(&self) -> Option<Duration> {
+        self.window.mean()
+    }
+}
+
+struct AgeWindow {
+    ages: Vec<Duration>,
+    size: usize,
+    next: usize,
+}
+
+impl AgeWindow {
+    fn new(size: usize) -> Self {
+        Self {
+            ages: Vec::with_capacity(size),
+            size,
+            next: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn record(&mut self, age: Duration) {
+        if self.size == 0 {
+            return;
+        }
+
+        if self.ages.len() < self.size {
+            self.ages.push(age);
+        } else {
+            self.ages[self.next] = age;
+        }
+        self.next = (self.next + 1) % self.size;
+    }
+
+    fn max(&self) -> Option<Duration> {
+        self.ages.iter().max().copied()
+    }
+
+    fn mean(&self) -> Option<Duration> {
+        if self.ages.is_empty() {
+            None
+        } else {
+            Some(self.ages.iter().sum::<Duration>() / self.ages.len() as u32)
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use crate::{clock::MockClock, ring_buffer::RingBuffer};
+
+    #[test]
+    fn ages_follow_the_clock_exactly() {
+        let clock = MockClock::new();
+        let (writer, reader) = RingBuffer::<Timed<u32>>::new(8).unwrap();
+        let mut writer = TimedWriter::with_clock(writer, clock.clone());
+        let mut reader = TimedReader::with_clock(reader, 4, clock.clone());
+
+        assert_eq!(writer.write(&[1, 2]), 2);
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(writer.write(&[3]), 1);
+        clock.advance(Duration::from_millis(5));
+
+        let mut timed = [Timed {
+            value: 0,
+            stamp: clock.now(),
+        }; 3];
+        assert_eq!(reader.read_timed(&mut timed), 3);
+        let now = clock.now();
+        let ages: Vec<_> = timed.iter().map(|timed| timed.age(now)).collect();
+        assert_eq!(
+            ages,
+            [
+                Duration::from_millis(15),
+                Duration::from_millis(15),
+                Duration::from_millis(5)
+            ]
+        );
+        assert_eq!(timed.map(|timed| timed.value), [1, 2, 3]);
+        assert_eq!(reader.max_age(), Some(Duration::from_millis(15)));
+        assert_eq!(reader.mean_age(), Some(Duration::from_nanos(11_666_666)));
+    }
+
+    #[test]
+    fn statistics_cover_only_the_last_window() {
+        let clock = MockClock::new();
+        let (writer, reader) = RingBuffer::<Timed<u32>>::new(8).unwrap();
+        let mut writer = TimedWriter::with_clock(writer, clock.clone());
+        let mut reader = TimedReader::with_clock(reader, 2, clock.clone());
+        assert_eq!(reader.max_age(), None);
+        assert_eq!(reader.mean_age(), None);
+
+        let mut buffer = [0];
+        for age in [30, 10, 20] {
+            writer.write(&[age]);
+            clock.advance(Duration::from_millis(age as u64));
+            assert_eq!(reader.read(&mut buffer), 1);
+        }
+
+        // The 30ms age has rolled out of the window of two.
+        assert_eq!(reader.max_age(), Some(Duration::from_millis(20)));
+        assert_eq!(reader.mean_age(), Some(Duration::from_millis(15)));
+    }
+}
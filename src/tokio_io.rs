@@ -0,0 +1,90 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::{
+    futures::{self, AsyncReader, AsyncWriter},
+    ring_buffer::RingBufferError,
+};
+
+/// Creates a byte ring whose halves implement tokio's [`AsyncWrite`] and
+/// [`AsyncRead`], for use as an in-process pipe with `tokio::io::copy` or
+/// codec framing. Built on [`futures::ring_buffer`], so the same wake-up
+/// costs apply.
+pub fn pipe(capacity: usize) -> Result<(PipeWriter, PipeReader), RingBufferError> {
+    let (writer, reader) = futures::ring_buffer(capacity)?;
+
+    Ok((writer.into(), reader.into()))
+}
+
+/// The writing end of a [`pipe`]. Shutting it down closes the pipe like
+/// [`AsyncWriter::close`]: the reader gets whatever is still queued and then
+/// end of file, while writes fail with [`io::ErrorKind::BrokenPipe`].
+/// Dropping the writer ends the stream the same way.
+pub struct PipeWriter {
+    writer: AsyncWriter<u8>,
+}
+
+impl From<AsyncWriter<u8>> for PipeWriter {
+    fn from(writer: AsyncWriter<u8>) -> Self {
+        Self { writer }
+    }
+}
+
+impl AsyncWrite for PipeWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        // Zero for a non-empty buffer means the reader is gone or the pipe
+        // was shut down.
+        self.get_mut().writer.poll_write(cx, buf).map(|n| match n {
+            0 if !buf.is_empty() => Err(io::ErrorKind::BrokenPipe.into()),
+            n => Ok(n),
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Written bytes are visible to the reader right away.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.writer.close();
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The reading end of a [`pipe`].
+pub struct PipeReader {
+    reader: AsyncReader<u8>,
+}
+
+impl From<AsyncReader<u8>> for PipeReader {
+    fn from(reader: AsyncReader<u8>) -> Self {
+        Self { reader }
+    }
+}
+
+impl AsyncRead for PipeReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let reader = &mut self.get_mut().reader;
+
+        // Reading nothing into a non-empty buffer is end of file, which
+        // leaves `buf` unfilled as tokio expects.
+        reader.poll_read(cx, buf.initialize_unfilled()).map(|n| {
+            buf.advance(n);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,285 @@
+use std::{marker::PhantomData, mem::size_of, ops::Deref};
+
+use bytemuck::Pod;
+
+use crate::ring_buffer::{BufferWriter, RawBufferReader, RawBufferWriter, Reader, Writer};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TypedFrameError {
+    /// `T` has no bytes, so its frames could not be told apart.
+    ZeroSized,
+    /// A single `T` does not fit in the ring.
+    FrameTooLarge,
+}
+
+// Every frame is exactly `size_of::<T>()` bytes, so no length prefix is
+// needed; both halves only have to agree on `T`.
+fn frame_size<T: Pod>(capacity: usize) -> Result<usize, TypedFrameError> {
+    match size_of::<T>() {
+        0 => Err(TypedFrameError::ZeroSized),
+        size if size > capacity => Err(TypedFrameError::FrameTooLarge),
+        size => Ok(size),
+    }
+}
+
+/// Writes plain-old-data values into a byte ring as fixed-size frames.
+pub struct TypedFrameWriter<T: Pod> {
+    writer: Writer<u8>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> TypedFrameWriter<T> {
+    pub fn new(writer: Writer<u8>) -> Result<Self, TypedFrameError> {
+        frame_size::<T>(writer.capacity())?;
+
+        Ok(Self {
+            writer,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn into_inner(self) -> Writer<u8> {
+        self.writer
+    }
+
+    /// Publishes the bytes of `value` as one frame, or hands the value back
+    /// if the ring does not have room for it right now.
+    pub fn write(&mut self, value: &T) -> Result<(), T> {
+        let bytes = bytemuck::bytes_of(value);
+        if self.writer.available(bytes.len()).0 == 0 {
+            return Err(*value);
+        }
+
+        self.writer.write(bytes);
+
+        Ok(())
+    }
+}
+
+/// Reads the frames written by [`TypedFrameWriter`] back as values.
+pub struct TypedFrameReader<T: Pod> {
+    reader: Reader<u8>,
+    frame_size: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> TypedFrameReader<T> {
+    pub fn new(reader: Reader<u8>) -> Result<Self, TypedFrameError> {
+        let frame_size = frame_size::<T>(reader.capacity())?;
+
+        Ok(Self {
+            reader,
+            frame_size,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn into_inner(self) -> Reader<u8> {
+        self.reader
+    }
+
+    fn peek(&self) -> Option<Frame<T>> {
+        let (filled, index) = self.reader.filled(self.frame_size);
+        if filled == 0 {
+            return None;
+        }
+
+        let (first, second) = self.reader.segments(index, self.frame_size);
+        if second.is_empty() {
+            if let Ok(value) = bytemuck::try_from_bytes::<T>(first) {
+                return Some(Frame::Borrowed(value));
+            }
+        }
+
+        // The frame wraps around the end of the storage or is misaligned.
+        let mut value = T::zeroed();
+        let bytes = bytemuck::bytes_of_mut(&mut value);
+        bytes[..first.len()].copy_from_slice(first);
+        bytes[first.len()..].copy_from_slice(second);
+
+        Some(Frame::Copied(value))
+    }
+
+    /// Copies the next frame out and consumes it.
+    pub fn read(&mut self) -> Option<T> {
+        let value = match self.peek()? {
+            Frame::Borrowed(value) => unsafe { *value },
+            Frame::Copied(value) => value,
+        };
+        // `peek` only returns frames that are published whole.
+        unsafe { self.reader.advance_index(self.frame_size) };
+
+        Some(value)
+    }
+
+    /// Returns a guard over the next frame without consuming it. The guard
+    /// borrows the ring's storage directly when the frame is contiguous and
+    /// suitably aligned for `T`, and holds a copy otherwise. Call
+    /// [`FrameRef::consume`] to release the frame.
+    pub fn read_ref(&mut self) -> Option<FrameRef<'_, T>> {
+        let frame = self.peek()?;
+
+        Some(FrameRef {
+            reader: self,
+            frame,
+        })
+    }
+}
+
+// A borrowed frame points into the ring's storage. The pointer stays valid
+// until the frame is consumed, because the writer cannot reuse the slots
+// before then.
+enum Frame<T> {
+    Borrowed(*const T),
+    Copied(T),
+}
+
+pub struct FrameRef<'a, T: Pod> {
+    reader: &'a mut TypedFrameReader<T>,
+    frame: Frame<T>,
+}
+
+impl<T: Pod> FrameRef<'_, T> {
+    /// Returns `true` if the guard points into the ring instead of a copy.
+    #[inline(always)]
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.frame, Frame::Borrowed(_))
+    }
+
+    pub fn consume(self) {
+        let frame_size = self.reader.frame_size;
+        // The guard was made from a published frame, which this releases
+        // along with the guard.
+        unsafe { self.reader.reader.advance_index(frame_size) };
+    }
+}
+
+impl<T: Pod> Deref for FrameRef<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        match &self.frame {
+            Frame::Borrowed(value) => unsafe { &**value },
+            Frame::Copied(value) => value,
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use bytemuck::Zeroable;
+
+    use super::*;
+    use crate::ring_buffer::RingBuffer;
+
+    // Twelve bytes with four-byte alignment, where the last three bytes are
+    // padding spelled out as a field so that the type is plain old data.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[repr(C)]
+    struct Sample {
+        id: u32,
+        value: i32,
+        flag: u8,
+        _padding: [u8; 3],
+    }
+
+    unsafe impl Zeroable for Sample {}
+    unsafe impl Pod for Sample {}
+
+    fn sample(id: u32) -> Sample {
+        Sample {
+            id,
+            value: -(id as i32),
+            flag: id as u8,
+            _padding: [0; 3],
+        }
+    }
+
+    // Room for two and a half frames, aligned so that a frame at the start
+    // of the storage can be borrowed in place.
+    fn halves() -> (TypedFrameWriter<Sample>, TypedFrameReader<Sample>) {
+        let (writer, reader) = RingBuffer::<u8>::builder()
+            .capacity(30)
+            .align(16)
+            .build()
+            .unwrap();
+
+        (
+            TypedFrameWriter::new(writer).unwrap(),
+            TypedFrameReader::new(reader).unwrap(),
+        )
+    }
+
+    #[test]
+    fn round_trips_a_struct_with_padding() {
+        let (mut writer, mut reader) = halves();
+
+        for id in 0..100 {
+            writer.write(&sample(id)).unwrap();
+            writer.write(&sample(id + 1000)).unwrap();
+            assert_eq!(writer.write(&sample(id)), Err(sample(id)));
+
+            assert_eq!(reader.read(), Some(sample(id)));
+            assert_eq!(reader.read(), Some(sample(id + 1000)));
+            assert_eq!(reader.read(), None);
+        }
+    }
+
+    #[test]
+    fn read_ref_borrows_aligned_frames_and_copies_the_rest() {
+        let (mut writer, mut reader) = halves();
+
+        // The first frame starts at the aligned front of the storage.
+        writer.write(&sample(1)).unwrap();
+        writer.write(&sample(2)).unwrap();
+        let frame = reader.read_ref().unwrap();
+        assert!(frame.is_borrowed());
+        assert_eq!(*frame, sample(1));
+        frame.consume();
+
+        // The third frame covers bytes 24 to 36 of 30, so it straddles the
+        // end of the storage and has to be copied.
+        writer.write(&sample(3)).unwrap();
+        let frame = reader.read_ref().unwrap();
+        assert!(frame.is_borrowed());
+        assert_eq!(*frame, sample(2));
+        frame.consume();
+
+        {
+            let frame = reader.read_ref().unwrap();
+            assert!(!frame.is_borrowed());
+            assert_eq!(*frame, sample(3));
+        }
+        // The guard went away without consuming, so the frame is still
+        // queued.
+        assert_eq!(reader.read(), Some(sample(3)));
+
+        // The frame after it starts at byte 6, which is misaligned.
+        writer.write(&sample(4)).unwrap();
+        let frame = reader.read_ref().unwrap();
+        assert!(!frame.is_borrowed());
+        assert_eq!(*frame, sample(4));
+        frame.consume();
+        assert!(reader.read_ref().is_none());
+    }
+
+    #[test]
+    fn rejects_frames_that_cannot_fit() {
+        let (writer, reader) = RingBuffer::<u8>::new(11).unwrap();
+        assert_eq!(
+            TypedFrameWriter::<Sample>::new(writer).err(),
+            Some(TypedFrameError::FrameTooLarge)
+        );
+        assert_eq!(
+            TypedFrameReader::<Sample>::new(reader).err(),
+            Some(TypedFrameError::FrameTooLarge)
+        );
+
+        let (writer, _reader) = RingBuffer::<u8>::new(11).unwrap();
+        assert_eq!(
+            TypedFrameWriter::<()>::new(writer).err(),
+            Some(TypedFrameError::ZeroSized)
+        );
+    }
+}
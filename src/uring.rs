@@ -0,0 +1,184 @@
+use crate::ring_buffer::{RawBufferWriter, Writer};
+
+/// One contiguous piece of a grant, addressed the way a fixed-buffer SQE
+/// (`IORING_OP_READ_FIXED`) wants it: the registered buffer's index plus a
+/// byte offset and length within it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UringSegment {
+    pub buf_index: u16,
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Free slots set aside for the kernel to write into. The region may wrap,
+/// in which case it is split into two segments.
+#[derive(Clone, Copy, Debug)]
+pub struct UringGrant {
+    base: *mut u8,
+    segments: [UringSegment; 2],
+    count: usize,
+}
+
+impl UringGrant {
+    pub fn segments(&self) -> &[UringSegment] {
+        &self.segments[..self.count]
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments().iter().map(|segment| segment.len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The same region as absolute iovecs, e.g. for `IORING_OP_READV`.
+    pub fn iovecs(&self) -> Vec<libc::iovec> {
+        self.segments()
+            .iter()
+            .map(|segment| libc::iovec {
+                iov_base: unsafe { self.base.add(segment.offset) }.cast(),
+                iov_len: segment.len,
+            })
+            .collect()
+    }
+}
+
+impl Writer<u8> {
+    /// The slot storage as one iovec for `io_uring_register_buffers`. The
+    /// pointer stays valid and fixed for as long as any handle to the ring
+    /// is alive; unregister the buffer before dropping the last one.
+    pub fn registered_buffer(&self) -> libc::iovec {
+        libc::iovec {
+            iov_base: self.storage().cast(),
+            iov_len: self.capacity(),
+        }
+    }
+
+    /// Sets aside the next `n` free bytes for a fixed-buffer read into the
+    /// buffer registered at `buf_index`, or returns `None` if fewer than `n`
+    /// are free. Only one grant may be in flight at a time, and nothing else
+    /// may write to the ring until it is committed.
+    pub fn uring_grant(&self, buf_index: u16, n: usize) -> Option<UringGrant> {
+        let (free, index) = self.free();
+        if n == 0 || n > free {
+            return None;
+        }
+
+        let capacity = self.capacity();
+        let start = index % capacity;
+        let first = n.min(capacity - start);
+        let segment = |offset, len| UringSegment {
+            buf_index,
+            offset,
+            len,
+        };
+
+        Some(UringGrant {
+            base: self.storage(),
+            segments: [segment(start, first), segment(0, n - first)],
+            count: if first < n { 2 } else { 1 },
+        })
+    }
+
+    /// Publishes `n` bytes written by the kernel into the current grant.
+    ///
+    /// # Safety
+    ///
+    /// The completion for the grant must have been reaped, and `n` must not
+    /// exceed both the grant's length and the byte count the CQE reported.
+    pub unsafe fn uring_commit(&mut self, n: usize) {
+        if n > 0 {
+            unsafe { self.advance_index(n) };
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::ring_buffer::{BufferReader, BufferWriter, RingBuffer};
+
+    // Stands in for the kernel completing a read: fills the iovecs from
+    // another thread, the way the kernel writes into registered memory
+    // behind the process's back, and reports how many bytes it wrote.
+    fn complete(iovecs: Vec<libc::iovec>, data: &'static [u8]) -> usize {
+        let iovecs: Vec<(usize, usize)> = iovecs
+            .iter()
+            .map(|iovec| (iovec.iov_base as usize, iovec.iov_len))
+            .collect();
+
+        thread::spawn(move || {
+            let mut written = 0;
+            for (base, len) in iovecs {
+                let len = len.min(data.len() - written);
+                let data = data[written..].as_ptr();
+                unsafe { std::ptr::copy_nonoverlapping(data, base as *mut u8, len) };
+                written += len;
+            }
+            written
+        })
+        .join()
+        .unwrap()
+    }
+
+    #[test]
+    fn a_grant_across_the_wrap_is_filled_and_committed() {
+        let (mut writer, mut reader) = RingBuffer::<u8>::new(8).unwrap();
+        assert_eq!(writer.write(&[0; 5]), 5);
+        assert_eq!(reader.read(&mut [0; 5]), 5);
+
+        let grant = writer.uring_grant(3, 6).unwrap();
+        assert_eq!(
+            grant.segments(),
+            [
+                UringSegment {
+                    buf_index: 3,
+                    offset: 5,
+                    len: 3,
+                },
+                UringSegment {
+                    buf_index: 3,
+                    offset: 0,
+                    len: 3,
+                },
+            ]
+        );
+
+        // The iovecs are the same two pieces of the registered buffer.
+        let base = writer.registered_buffer().iov_base as usize;
+        let iovecs = grant.iovecs();
+        assert_eq!(iovecs[0].iov_base as usize, base + 5);
+        assert_eq!(iovecs[1].iov_base as usize, base);
+
+        // Nothing is visible until the commit.
+        let written = complete(iovecs, b"kernel");
+        assert_eq!(reader.pending().0, 0);
+        unsafe { writer.uring_commit(written) };
+
+        let mut buffer = [0; 6];
+        assert_eq!(reader.read(&mut buffer), 6);
+        assert_eq!(&buffer, b"kernel");
+    }
+
+    #[test]
+    fn a_short_completion_publishes_only_what_was_written() {
+        let (mut writer, mut reader) = RingBuffer::<u8>::new(8).unwrap();
+
+        let grant = writer.uring_grant(0, 8).unwrap();
+        assert_eq!(grant.len(), 8);
+        let written = complete(grant.iovecs(), b"abc");
+        unsafe { writer.uring_commit(written) };
+
+        let mut buffer = [0; 3];
+        assert_eq!(reader.read(&mut buffer), 3);
+        assert_eq!(&buffer, b"abc");
+
+        // The rest of the grant is free again for the next one.
+        assert_eq!(writer.uring_grant(0, 8).map(|grant| grant.len()), Some(8));
+        assert!(writer.uring_grant(0, 9).is_none());
+        assert!(writer.uring_grant(0, 0).is_none());
+    }
+}
@@ -1,58 +1,574 @@
 use std::{
     collections::VecDeque,
-    sync::{Arc, Mutex},
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
 };
 
+use crate::ring_buffer::{self, BufferReader, BufferWriter, ReadBuf, Reader, Writer};
+
+/// Creates a mutex-guarded ring already split into a writing and a reading
+/// handle, so it can stand in for the lock-free halves in code generic over
+/// [`BufferWriter`] and [`BufferReader`].
+pub fn ring_buffer<T: Copy>(capacity: usize) -> (DequeWriter<T>, DequeReader<T>) {
+    RingBuffer::new(capacity).split()
+}
+
+struct Shared<T> {
+    buffer: Mutex<VecDeque<T>>,
+    // Signalled after elements are removed, for writers waiting on a full
+    // buffer.
+    not_full: Condvar,
+    // Signalled after elements are added, for readers waiting on an empty
+    // buffer.
+    not_empty: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn new(buffer: VecDeque<T>) -> Self {
+        Self {
+            buffer: Mutex::new(buffer),
+            not_full: Condvar::new(),
+            not_empty: Condvar::new(),
+        }
+    }
+}
+
 pub struct RingBuffer<T: Clone + Copy> {
-    inner: Arc<Mutex<VecDeque<T>>>,
+    inner: Arc<Shared<T>>,
+    // The bound asked for. `VecDeque::with_capacity` may allocate more, so
+    // its own capacity is never used as the limit.
+    capacity: usize,
 }
 
 impl<T: Clone + Copy> Clone for RingBuffer<T> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            capacity: self.capacity,
         }
     }
 }
 
+// Takes the lock for the length but never formats the elements, so `T`
+// need not be `Debug`.
+impl<T: Clone + Copy> std::fmt::Debug for RingBuffer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RingBuffer")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .field("handles", &Arc::strong_count(&self.inner))
+            .finish()
+    }
+}
+
 impl<T: Clone + Copy> RingBuffer<T> {
     pub fn new(capacity: usize) -> Self {
         let buffer = VecDeque::<T>::with_capacity(capacity);
 
         Self {
-            inner: Arc::new(Mutex::new(buffer)),
+            inner: Arc::new(Shared::new(buffer)),
+            capacity,
+        }
+    }
+
+    pub fn into_inner(self) -> Result<VecDeque<T>, Self> {
+        let capacity = self.capacity;
+
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => Ok(inner
+                .buffer
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner)),
+            Err(inner) => Err(Self { inner, capacity }),
+        }
+    }
+
+    /// Moves the queued elements, in order, into a new lock-free ring with
+    /// the same capacity and returns its two halves. Fails and hands `self`
+    /// back if other handles to this ring still exist.
+    pub fn upgrade(self) -> Result<(Writer<T>, Reader<T>), Self> {
+        let capacity = self.capacity;
+        let buffer = self.into_inner()?;
+
+        match ring_buffer::RingBuffer::new(capacity) {
+            Ok((mut writer, reader)) => {
+                let (front, back) = buffer.as_slices();
+                writer.write(front);
+                writer.write(back);
+
+                Ok((writer, reader))
+            }
+            Err(_) => Err(Self {
+                inner: Arc::new(Shared::new(buffer)),
+                capacity,
+            }),
         }
     }
 
+    /// Hands this ring out as a writing and a reading handle. Other clones
+    /// of it keep working alongside them.
+    pub fn split(self) -> (DequeWriter<T>, DequeReader<T>) {
+        (
+            DequeWriter {
+                ring_buffer: self.clone(),
+            },
+            DequeReader { ring_buffer: self },
+        )
+    }
+
+    /// The bound given to [`RingBuffer::new`], which the storage never
+    /// grows past.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of queued elements. Another handle may change it right after
+    /// the lock is released, so this is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.free_len() == 0
+    }
+
+    pub fn free_len(&self) -> usize {
+        self.capacity - self.len()
+    }
+
+    /// Writes as much of `buffer` as currently fits and returns the number
+    /// of elements written, like the lock-free ring.
     pub fn write(&self, buffer: &[T]) -> usize {
-        let mut ring_buffer = self.inner.lock().unwrap();
-        let available = ring_buffer.capacity() - ring_buffer.len();
-        let burst_size = buffer.len();
+        let mut ring_buffer = self.lock();
 
-        if available >= burst_size {
-            for value in buffer {
-                ring_buffer.push_back(*value);
+        self.write_locked(&mut ring_buffer, buffer)
+    }
+
+    /// Reads as many queued elements as fit in `buffer` and returns the
+    /// number of elements read.
+    pub fn read(&self, buffer: &mut [T]) -> usize {
+        let mut ring_buffer = self.lock();
+
+        self.read_locked(&mut ring_buffer, buffer)
+    }
+
+    /// Appends up to `max` queued elements to `vec` and returns how many
+    /// were appended, like the lock-free
+    /// [`Reader::read_into_vec`](ring_buffer::Reader::read_into_vec). Room is
+    /// reserved once and the elements are copied a contiguous run at a time.
+    pub fn read_into_vec(&self, vec: &mut Vec<T>, max: usize) -> usize {
+        let mut ring_buffer = self.lock();
+        let burst_size = max.min(ring_buffer.len());
+
+        let (front, back) = ring_buffer.as_slices();
+        let first = burst_size.min(front.len());
+        vec.reserve(burst_size);
+        vec.extend_from_slice(&front[..first]);
+        vec.extend_from_slice(&back[..burst_size - first]);
+
+        ring_buffer.drain(..burst_size);
+        if burst_size > 0 {
+            self.inner.not_full.notify_all();
+        }
+
+        burst_size
+    }
+
+    /// Writes all of `buffer`, sleeping on a condition variable whenever the
+    /// ring is full until a read makes room.
+    ///
+    /// Every handle is also a reader, so nothing reports a missing peer: with
+    /// no one reading, or a zero capacity, this waits forever.
+    pub fn write_blocking(&self, buffer: &[T]) {
+        self.write_deadline(buffer, None);
+    }
+
+    /// Fills all of `buffer`, sleeping on a condition variable whenever the
+    /// ring is empty until a write adds elements.
+    ///
+    /// As with [`RingBuffer::write_blocking`], this waits forever if no one
+    /// writes.
+    pub fn read_blocking(&self, buffer: &mut [T]) {
+        self.read_deadline(buffer, None);
+    }
+
+    /// Like [`RingBuffer::write_blocking`], but gives up once `timeout` has
+    /// passed. Returns the number of elements written, which is less than
+    /// `buffer.len()` only on timeout.
+    pub fn write_timeout(&self, buffer: &[T], timeout: Duration) -> usize {
+        self.write_deadline(buffer, Instant::now().checked_add(timeout))
+    }
+
+    /// Like [`RingBuffer::read_blocking`], but gives up once `timeout` has
+    /// passed. Returns the number of elements read, which is less than
+    /// `buffer.len()` only on timeout.
+    pub fn read_timeout(&self, buffer: &mut [T], timeout: Duration) -> usize {
+        self.read_deadline(buffer, Instant::now().checked_add(timeout))
+    }
+
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        let mut ring_buffer = self.lock();
+        if ring_buffer.len() == self.capacity {
+            return Err(value);
+        }
+
+        ring_buffer.push_back(value);
+        self.inner.not_empty.notify_all();
+
+        Ok(())
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        let value = self.lock().pop_front()?;
+        self.inner.not_full.notify_all();
+
+        Some(value)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<T>> {
+        self.inner.buffer.lock().unwrap()
+    }
+
+    // Both sides notify while still holding the lock, so a waiter that has
+    // just seen the old length is already parked on the condvar and cannot
+    // miss the signal. Every waiter is woken because clones may be blocked
+    // on either side at once.
+    fn write_locked(&self, ring_buffer: &mut VecDeque<T>, buffer: &[T]) -> usize {
+        let available = self.capacity - ring_buffer.len();
+        let burst_size = buffer.len().min(available);
+
+        ring_buffer.extend(&buffer[..burst_size]);
+        if burst_size > 0 {
+            self.inner.not_empty.notify_all();
+        }
+
+        burst_size
+    }
+
+    fn read_locked(&self, ring_buffer: &mut VecDeque<T>, buffer: &mut [T]) -> usize {
+        let burst_size = buffer.len().min(ring_buffer.len());
+
+        // At most two copies, one per contiguous run of the deque.
+        let (front, back) = ring_buffer.as_slices();
+        let first = burst_size.min(front.len());
+        buffer[..first].copy_from_slice(&front[..first]);
+        buffer[first..burst_size].copy_from_slice(&back[..burst_size - first]);
+
+        ring_buffer.drain(..burst_size);
+        if burst_size > 0 {
+            self.inner.not_full.notify_all();
+        }
+
+        burst_size
+    }
+
+    fn write_deadline(&self, buffer: &[T], deadline: Option<Instant>) -> usize {
+        let mut ring_buffer = self.lock();
+        let mut written = 0;
+
+        loop {
+            written += self.write_locked(&mut ring_buffer, &buffer[written..]);
+            if written == buffer.len() {
+                return written;
             }
 
-            burst_size
-        } else {
-            0
+            ring_buffer = match wait(&self.inner.not_full, ring_buffer, deadline) {
+                Some(ring_buffer) => ring_buffer,
+                None => return written,
+            };
         }
     }
 
-    pub fn read(&self, buffer: &mut [T]) -> usize {
-        let mut ring_buffer = self.inner.lock().unwrap();
-        let filled = ring_buffer.len();
-        let burst_size = buffer.len();
+    fn read_deadline(&self, buffer: &mut [T], deadline: Option<Instant>) -> usize {
+        let mut ring_buffer = self.lock();
+        let mut read = 0;
 
-        if filled >= burst_size {
-            for index in 0..burst_size {
-                buffer[index] = ring_buffer.pop_front().unwrap();
+        loop {
+            read += self.read_locked(&mut ring_buffer, &mut buffer[read..]);
+            if read == buffer.len() {
+                return read;
             }
 
-            burst_size
-        } else {
-            0
+            ring_buffer = match wait(&self.inner.not_empty, ring_buffer, deadline) {
+                Some(ring_buffer) => ring_buffer,
+                None => return read,
+            };
+        }
+    }
+}
+
+/// The writing half of a split [`RingBuffer`].
+pub struct DequeWriter<T: Clone + Copy> {
+    ring_buffer: RingBuffer<T>,
+}
+
+impl<T: Clone + Copy> DequeWriter<T> {
+    pub fn capacity(&self) -> usize {
+        self.ring_buffer.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring_buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring_buffer.is_empty()
+    }
+}
+
+impl<T: Clone + Copy> BufferWriter<T> for DequeWriter<T> {
+    fn write(&mut self, buffer: &[T]) -> usize {
+        self.ring_buffer.write(buffer)
+    }
+}
+
+/// The reading half of a split [`RingBuffer`].
+pub struct DequeReader<T: Clone + Copy> {
+    ring_buffer: RingBuffer<T>,
+}
+
+impl<T: Clone + Copy> DequeReader<T> {
+    pub fn capacity(&self) -> usize {
+        self.ring_buffer.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring_buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring_buffer.is_empty()
+    }
+}
+
+impl<T: Clone + Copy> BufferReader<T> for DequeReader<T> {
+    fn read_buf(&mut self, buffer: &mut ReadBuf<'_, T>) {
+        let mut ring_buffer = self.ring_buffer.lock();
+
+        let (front, back) = ring_buffer.as_slices();
+        let n = buffer.fill(front) + buffer.fill(back);
+
+        ring_buffer.drain(..n);
+        if n > 0 {
+            self.ring_buffer.inner.not_full.notify_all();
         }
     }
+
+    fn read(&mut self, buffer: &mut [T]) -> usize {
+        self.ring_buffer.read(buffer)
+    }
+}
+
+// Sleeps on `condvar` until notified, or returns `None` once `deadline` has
+// passed. Without a deadline this waits indefinitely.
+fn wait<'a, T>(
+    condvar: &Condvar,
+    guard: MutexGuard<'a, VecDeque<T>>,
+    deadline: Option<Instant>,
+) -> Option<MutexGuard<'a, VecDeque<T>>> {
+    let Some(deadline) = deadline else {
+        return Some(condvar.wait(guard).unwrap());
+    };
+
+    let left = deadline.checked_duration_since(Instant::now())?;
+    if left.is_zero() {
+        return None;
+    }
+
+    Some(condvar.wait_timeout(guard, left).unwrap().0)
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_inner_returns_the_queued_elements_from_the_last_handle() {
+        let ring_buffer = RingBuffer::<u32>::new(4);
+        ring_buffer.write(&[1, 2, 3]);
+        ring_buffer.read(&mut [0]);
+
+        let Ok(buffer) = ring_buffer.into_inner() else {
+            panic!("the last handle must get the queue back");
+        };
+        assert_eq!(buffer, [2, 3]);
+    }
+
+    #[test]
+    fn into_inner_hands_the_ring_back_while_a_clone_is_alive() {
+        let ring_buffer = RingBuffer::<u32>::new(4);
+        let clone = ring_buffer.clone();
+        ring_buffer.write(&[1, 2]);
+
+        let ring_buffer = ring_buffer.into_inner().unwrap_err();
+
+        drop(clone);
+        let Ok(buffer) = ring_buffer.into_inner() else {
+            panic!("the last handle must get the queue back");
+        };
+        assert_eq!(buffer, [1, 2]);
+    }
+
+    #[test]
+    fn into_inner_recovers_a_poisoned_lock() {
+        let ring_buffer = RingBuffer::<u32>::new(4);
+        ring_buffer.write(&[7]);
+
+        let clone = ring_buffer.clone();
+        std::thread::spawn(move || {
+            let _guard = clone.inner.buffer.lock();
+            panic!("poison the lock");
+        })
+        .join()
+        .unwrap_err();
+
+        let Ok(buffer) = ring_buffer.into_inner() else {
+            panic!("the last handle must get the queue back");
+        };
+        assert_eq!(buffer, [7]);
+    }
+
+    #[test]
+    fn upgrade_moves_the_queue_in_order() {
+        let ring_buffer = RingBuffer::<u32>::new(5);
+        // Leave the deque's contents split across the end of its storage.
+        ring_buffer.write(&[0, 0, 0, 1, 2]);
+        ring_buffer.read(&mut [0; 3]);
+        ring_buffer.write(&[3, 4, 5]);
+
+        let Ok((mut writer, mut reader)) = ring_buffer.upgrade() else {
+            panic!("the last handle must be able to upgrade");
+        };
+        assert!(writer.capacity() >= 5);
+        assert_eq!(reader.read_array::<5>(), Some([1, 2, 3, 4, 5]));
+        assert_eq!(writer.write(&[6]), 1);
+        assert_eq!(reader.read_array::<1>(), Some([6]));
+    }
+
+    #[test]
+    fn upgrade_hands_the_ring_back_while_a_clone_is_alive() {
+        let ring_buffer = RingBuffer::<u32>::new(4);
+        let clone = ring_buffer.clone();
+        ring_buffer.write(&[1, 2]);
+
+        let Err(ring_buffer) = ring_buffer.upgrade() else {
+            panic!("a clone is still alive");
+        };
+
+        drop(clone);
+        let Ok((_writer, mut reader)) = ring_buffer.upgrade() else {
+            panic!("the last handle must be able to upgrade");
+        };
+        assert_eq!(reader.read_array::<2>(), Some([1, 2]));
+    }
+
+    #[test]
+    fn writes_stop_at_the_requested_capacity() {
+        // Odd capacities are where `VecDeque::with_capacity` tends to round
+        // its allocation up.
+        for capacity in [1, 3, 5, 7, 100] {
+            let ring_buffer = RingBuffer::<u32>::new(capacity);
+            let allocated = ring_buffer.lock().capacity();
+
+            let items: Vec<u32> = (0..capacity as u32).collect();
+            assert_eq!(ring_buffer.write(&items), capacity);
+            assert!(ring_buffer.is_full());
+            assert_eq!(ring_buffer.len(), capacity);
+            assert_eq!(ring_buffer.capacity(), capacity);
+
+            assert_eq!(ring_buffer.write(&[99]), 0);
+            assert_eq!(ring_buffer.try_push(99), Err(99));
+            assert_eq!(ring_buffer.write_timeout(&[99], Duration::ZERO), 0);
+            assert_eq!(ring_buffer.len(), capacity);
+
+            // Draining and refilling never grows the storage either.
+            assert_eq!(ring_buffer.read(&mut [0; 2]), 2.min(capacity));
+            assert_eq!(ring_buffer.write(&[1, 2, 3]), 2.min(capacity));
+            assert_eq!(ring_buffer.lock().capacity(), allocated);
+        }
+    }
+
+    #[test]
+    fn blocking_calls_ping_pong_through_one_slot() {
+        const ITEMS: u32 = 10_000;
+        let ring_buffer = RingBuffer::<u32>::new(1);
+        let clone = ring_buffer.clone();
+
+        let producer = std::thread::spawn(move || {
+            for value in 0..ITEMS {
+                clone.write_blocking(&[value]);
+            }
+        });
+
+        // Each item needs the other side to wake, so a lost wakeup hangs
+        // here instead of passing.
+        let mut buffer = [0];
+        for expected in 0..ITEMS {
+            ring_buffer.read_blocking(&mut buffer);
+            assert_eq!(buffer, [expected]);
+        }
+        producer.join().unwrap();
+        assert!(ring_buffer.is_empty());
+    }
+
+    #[test]
+    fn blocked_writer_wakes_when_the_reader_drains() {
+        let ring_buffer = RingBuffer::<u32>::new(2);
+        ring_buffer.write(&[1, 2]);
+        let clone = ring_buffer.clone();
+
+        let writer =
+            std::thread::spawn(move || clone.write_timeout(&[3, 4], Duration::from_secs(10)));
+        std::thread::sleep(Duration::from_millis(20));
+
+        let mut buffer = [0; 2];
+        assert_eq!(ring_buffer.read(&mut buffer), 2);
+        assert_eq!(writer.join().unwrap(), 2);
+        assert_eq!(ring_buffer.read(&mut buffer), 2);
+        assert_eq!(buffer, [3, 4]);
+    }
+
+    #[test]
+    fn timed_calls_return_what_moved_before_the_deadline() {
+        let ring_buffer = RingBuffer::<u32>::new(2);
+
+        assert_eq!(
+            ring_buffer.write_timeout(&[1, 2, 3], Duration::from_millis(10)),
+            2
+        );
+        let mut buffer = [0; 3];
+        assert_eq!(
+            ring_buffer.read_timeout(&mut buffer, Duration::from_millis(10)),
+            2
+        );
+        assert_eq!(buffer[..2], [1, 2]);
+        assert_eq!(
+            ring_buffer.read_timeout(&mut buffer, Duration::from_millis(10)),
+            0
+        );
+    }
+
+    #[test]
+    fn debug_shows_the_occupancy_without_the_elements() {
+        // Not `Debug` itself.
+        #[derive(Clone, Copy)]
+        struct Opaque;
+
+        let ring_buffer = RingBuffer::<Opaque>::new(4);
+        ring_buffer.write(&[Opaque; 3]);
+        assert_eq!(
+            format!("{ring_buffer:?}"),
+            "RingBuffer { capacity: 4, len: 3, handles: 1 }"
+        );
+
+        let clone = ring_buffer.clone();
+        assert_eq!(
+            format!("{clone:?}"),
+            "RingBuffer { capacity: 4, len: 3, handles: 2 }"
+        );
+    }
 }
@@ -0,0 +1,87 @@
+use std::alloc::{alloc_zeroed, Layout};
+
+use wasm_bindgen::prelude::*;
+
+use crate::region::{self, SharedReader, SharedWriter};
+
+// Bindings for moving bytes between workers through a ring placed in
+// `SharedArrayBuffer`-backed linear memory. Regions are addressed by their
+// offset into that memory, which is a pointer on wasm32.
+
+fn js_error(error: crate::ring_buffer::RingBufferError) -> JsError {
+    JsError::new(&format!("{error:?}"))
+}
+
+/// Allocates a zeroed region suitable for a ring and returns its offset.
+/// The region is never freed.
+#[wasm_bindgen]
+pub fn alloc_region(len: usize) -> Result<usize, JsError> {
+    let layout =
+        Layout::from_size_align(len, 8).map_err(|error| JsError::new(&error.to_string()))?;
+    if layout.size() == 0 {
+        return Err(JsError::new("region length must not be zero"));
+    }
+
+    Ok(unsafe { alloc_zeroed(layout) } as usize)
+}
+
+/// Turns the region at `offset` into an empty byte ring. Call this once,
+/// before either side attaches.
+#[wasm_bindgen]
+pub fn init_byte_ring(offset: usize, len: usize) -> Result<(), JsError> {
+    unsafe { region::init::<u8>(offset as *mut u8, len) }.map_err(js_error)
+}
+
+#[wasm_bindgen]
+pub struct ByteRingWriter {
+    writer: SharedWriter<u8>,
+}
+
+#[wasm_bindgen]
+impl ByteRingWriter {
+    pub fn attach(offset: usize, len: usize) -> Result<ByteRingWriter, JsError> {
+        let writer = unsafe { SharedWriter::attach(offset as *mut u8, len) }.map_err(js_error)?;
+
+        Ok(Self { writer })
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.writer.capacity()
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> u32 {
+        self.writer.write(data)
+    }
+
+    /// Blocks with `Atomics.wait` semantics; only call this from a worker.
+    pub fn write_blocking(&mut self, data: &[u8]) -> u32 {
+        self.writer.write_blocking(data)
+    }
+}
+
+#[wasm_bindgen]
+pub struct ByteRingReader {
+    reader: SharedReader<u8>,
+}
+
+#[wasm_bindgen]
+impl ByteRingReader {
+    pub fn attach(offset: usize, len: usize) -> Result<ByteRingReader, JsError> {
+        let reader = unsafe { SharedReader::attach(offset as *mut u8, len) }.map_err(js_error)?;
+
+        Ok(Self { reader })
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.reader.capacity()
+    }
+
+    pub fn read(&mut self, out: &mut [u8]) -> u32 {
+        self.reader.read(out)
+    }
+
+    /// Blocks with `Atomics.wait` semantics; only call this from a worker.
+    pub fn read_blocking(&mut self, out: &mut [u8]) -> u32 {
+        self.reader.read_blocking(out)
+    }
+}
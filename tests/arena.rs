@@ -0,0 +1,70 @@
+// Churns channels through a ring arena under a counting allocator, to check
+// that creating and dropping rings never touches the allocator once the
+// arena exists.
+
+#![cfg(not(loom))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use spsc::{
+    arena::RingArena,
+    ring_buffer::{BufferReader, BufferWriter},
+};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Only the churning thread is counted; the test harness allocates on
+    // its own threads at any time.
+    static COUNTED: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTED.with(Cell::get) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[test]
+fn channel_churn_does_not_allocate_after_warm_up() {
+    let arena = RingArena::new(16, 256).unwrap();
+    let mut buffer = [0u64; 8];
+
+    // Every slot is used once, so anything lazily set up is in place.
+    let mut churn = |rounds: u64| {
+        for round in 0..rounds {
+            let mut rings: Vec<_> = (0..16).map(|_| arena.create::<u64>().unwrap()).collect();
+            assert!(arena.create::<u64>().is_none());
+
+            for (writer, reader) in &mut rings {
+                assert_eq!(writer.write(&[round; 8]), 8);
+                assert_eq!(reader.read(&mut buffer), 8);
+                assert_eq!(buffer, [round; 8]);
+            }
+        }
+    };
+    churn(1);
+
+    COUNTED.with(|counted| counted.set(true));
+    churn(1000);
+    COUNTED.with(|counted| counted.set(false));
+    // The `Vec` holding each round's rings is the only allocation left.
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), 1000);
+    assert_eq!(arena.occupancy().rings_in_use, 0);
+}
@@ -0,0 +1,9 @@
+// Checks that code which must not compile is rejected, such as moving the
+// same-thread ring's handles to another thread.
+
+#![cfg(not(loom))]
+
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
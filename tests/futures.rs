@@ -0,0 +1,160 @@
+// Drives the async halves from a minimal executor: each task runs on its
+// own thread, which parks until the task's waker unparks it, so a lost
+// wake-up shows up as a hang rather than as a busy loop that happens to
+// recover.
+
+#![cfg(not(loom))]
+
+use std::{
+    future::Future,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+use spsc::futures::ring_buffer;
+
+const ITEMS: u64 = 100_000;
+
+struct Unpark {
+    thread: Thread,
+    wakes: AtomicUsize,
+}
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.wakes.fetch_add(1, Ordering::Relaxed);
+        self.thread.unpark();
+    }
+}
+
+fn unpark_current() -> Arc<Unpark> {
+    Arc::new(Unpark {
+        thread: thread::current(),
+        wakes: AtomicUsize::new(0),
+    })
+}
+
+// Polls `future` on the calling thread, parking between polls.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(unpark_current());
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+// Parks until `unpark` has been woken, failing after a generous deadline
+// instead of hanging.
+fn until_woken(unpark: &Unpark) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while unpark.wakes.load(Ordering::Relaxed) == 0 {
+        assert!(Instant::now() < deadline, "the waker never fired");
+        thread::park_timeout(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn tasks_move_every_item_in_order() {
+    let (mut writer, mut reader) = ring_buffer::<u64>(64).unwrap();
+
+    let producer = thread::spawn(move || {
+        block_on(async {
+            let values: Vec<u64> = (0..ITEMS).collect();
+            let mut rest = &values[..];
+            while !rest.is_empty() {
+                let n = writer.write(&rest[..rest.len().min(37)]).await;
+                assert!(n > 0, "the reader went away");
+                rest = &rest[n..];
+            }
+        })
+    });
+
+    let received = block_on(async {
+        let mut received = Vec::with_capacity(ITEMS as usize);
+        let mut buffer = [0; 23];
+        loop {
+            match reader.read(&mut buffer).await {
+                0 => break received,
+                n => received.extend_from_slice(&buffer[..n]),
+            }
+        }
+    });
+
+    producer.join().unwrap();
+    assert!(received.iter().copied().eq(0..ITEMS));
+}
+
+// Polls a read on an empty ring once, so its waker is registered, then lets
+// `end` run on another thread and checks that the waker fired and the read
+// now sees the end of the stream.
+fn pending_read_is_woken_by<F>(end: F)
+where
+    F: FnOnce(spsc::futures::AsyncWriter<u64>) + Send + 'static,
+{
+    let (writer, mut reader) = ring_buffer::<u64>(4).unwrap();
+    let unpark = unpark_current();
+    let waker = Waker::from(unpark.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut buffer = [0; 4];
+
+    assert_eq!(reader.poll_read(&mut cx, &mut buffer), Poll::Pending);
+
+    let ender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        end(writer);
+    });
+    until_woken(&unpark);
+    ender.join().unwrap();
+
+    assert_eq!(reader.poll_read(&mut cx, &mut buffer), Poll::Ready(0));
+}
+
+#[test]
+fn dropping_the_writer_wakes_a_pending_read() {
+    pending_read_is_woken_by(drop);
+}
+
+#[test]
+fn closing_the_ring_wakes_a_pending_read() {
+    pending_read_is_woken_by(|writer| {
+        writer.close();
+        // Still alive: the close alone has to wake the reader.
+        thread::sleep(Duration::from_millis(20));
+        drop(writer);
+    });
+}
+
+#[test]
+fn dropping_the_reader_wakes_a_pending_write() {
+    let (mut writer, reader) = ring_buffer::<u64>(2).unwrap();
+    let unpark = unpark_current();
+    let waker = Waker::from(unpark.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    assert_eq!(writer.poll_write(&mut cx, &[1, 2]), Poll::Ready(2));
+    assert_eq!(writer.poll_write(&mut cx, &[3]), Poll::Pending);
+
+    let dropper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        drop(reader);
+    });
+    until_woken(&unpark);
+    dropper.join().unwrap();
+
+    assert_eq!(writer.poll_write(&mut cx, &[3]), Poll::Ready(0));
+}
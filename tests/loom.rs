@@ -0,0 +1,254 @@
+// Model-checks the index protocol of the lock-free ring under every
+// interleaving loom can find. Run with
+//
+//     RUSTFLAGS="--cfg loom" cargo test --release
+//
+// The ring takes its atomics from loom in that build, so a weakened
+// ordering shows up here as a failing interleaving. The other tests use
+// the ring outside of a model and are left out of that build.
+
+#![cfg(loom)]
+
+use loom::thread;
+use spsc::ring_buffer::{BufferReader, BufferWriter, RingBuffer};
+
+#[test]
+fn ping_pong_through_a_ring_of_two() {
+    loom::model(|| {
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(2).unwrap();
+
+        let producer = thread::spawn(move || {
+            for value in 1..=3 {
+                let mut value = value;
+                while let Err(rejected) = writer.try_push(value) {
+                    value = rejected;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        // The third element only fits once the reader has handed a slot
+        // back, so the indices wrap under the model as well.
+        for expected in 1..=3 {
+            loop {
+                match reader.try_pop() {
+                    Some(value) => {
+                        assert_eq!(value, expected);
+                        break;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+        }
+
+        producer.join().unwrap();
+    });
+}
+
+#[test]
+fn publish_is_seen_whole_at_the_empty_boundary() {
+    loom::model(|| {
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(4).unwrap();
+
+        let producer = thread::spawn(move || {
+            assert_eq!(writer.write(&[10, 20, 30]), 3);
+        });
+
+        // Whatever the reader catches of the burst must be a prefix of it
+        // with every value already stored, never an unwritten slot.
+        let mut buffer = [0; 3];
+        let read = reader.read(&mut buffer);
+        assert_eq!(buffer[..read], [10, 20, 30][..read]);
+
+        producer.join().unwrap();
+        let rest = reader.read(&mut buffer[read..]);
+        assert_eq!(read + rest, 3);
+        assert_eq!(buffer, [10, 20, 30]);
+    });
+}
+
+#[test]
+fn disconnect_is_seen_after_the_last_publish() {
+    loom::model(|| {
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(2).unwrap();
+
+        let producer = thread::spawn(move || {
+            assert_eq!(writer.write(&[7]), 1);
+            drop(writer);
+        });
+
+        // Once the writer is seen gone, its element must be visible too.
+        let mut received = Vec::new();
+        loop {
+            let disconnected = reader.is_disconnected();
+            received.extend(reader.try_pop());
+            if disconnected {
+                break;
+            }
+            thread::yield_now();
+        }
+        assert_eq!(received, [7]);
+
+        producer.join().unwrap();
+    });
+}
+
+#[test]
+fn close_is_seen_after_the_last_publish() {
+    loom::model(|| {
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(2).unwrap();
+
+        let producer = thread::spawn(move || {
+            assert_eq!(writer.write(&[7]), 1);
+            writer.close();
+            writer
+        });
+
+        // The writer stays alive, so only the close ends the stream, and
+        // not before its element has been read.
+        let mut received = Vec::new();
+        while !reader.is_closed() {
+            received.extend(reader.try_pop());
+            thread::yield_now();
+        }
+        assert_eq!(received, [7]);
+        assert!(!reader.is_disconnected());
+
+        drop(producer.join().unwrap());
+    });
+}
+
+#[test]
+fn freed_slot_is_seen_by_the_writer() {
+    loom::model(|| {
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(1).unwrap();
+        assert_eq!(writer.write(&[1]), 1);
+
+        let consumer = thread::spawn(move || {
+            assert_eq!(reader.try_pop(), Some(1));
+            reader
+        });
+
+        // The writer either still sees the ring full or gets the slot the
+        // reader has finished with; it never overwrites an unread element.
+        let second = writer.try_push(2).is_ok();
+
+        let mut reader = consumer.join().unwrap();
+        assert_eq!(reader.try_pop(), second.then_some(2));
+    });
+}
+
+// The slot-stamp ring keeps no shared index pair, so these check its stamp
+// protocol the same way: each slot goes free, published, released.
+
+#[test]
+fn slot_stamps_ping_pong_through_a_ring_of_two() {
+    loom::model(|| {
+        let (mut writer, mut reader) = spsc::seqring::ring_buffer::<u64>(2).unwrap();
+
+        let producer = thread::spawn(move || {
+            for value in 1..=3 {
+                while writer.write(&[value]) == 0 {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        // The third element reuses the first slot, once the reader has
+        // stamped it free for the next lap.
+        let mut buffer = [0];
+        for expected in 1..=3 {
+            while reader.read(&mut buffer) == 0 {
+                thread::yield_now();
+            }
+            assert_eq!(buffer, [expected]);
+        }
+
+        producer.join().unwrap();
+    });
+}
+
+#[test]
+fn slot_stamps_publish_a_burst_in_order() {
+    loom::model(|| {
+        let (mut writer, mut reader) = spsc::seqring::ring_buffer::<u64>(2).unwrap();
+        assert_eq!(writer.write(&[1]), 1);
+        let mut buffer = [0];
+        assert_eq!(reader.read(&mut buffer), 1);
+
+        // The burst starts at the second slot and wraps onto the first,
+        // which the reader has stamped free for the next lap.
+        let producer = thread::spawn(move || {
+            assert_eq!(writer.write(&[10, 20]), 2);
+        });
+
+        let mut buffer = [0; 2];
+        let read = reader.read(&mut buffer);
+        assert_eq!(buffer[..read], [10, 20][..read]);
+
+        producer.join().unwrap();
+        let rest = reader.read(&mut buffer[read..]);
+        assert_eq!(buffer, [10, 20]);
+        assert_eq!(read + rest, 2);
+    });
+}
+
+#[test]
+fn two_senders_race_for_the_last_free_slot() {
+    loom::model(|| {
+        let (sender, mut reader) = spsc::mpsc::channel::<u64>(2).unwrap();
+        assert_eq!(sender.write(&[10]), 1);
+
+        let racers: Vec<_> = [20, 30]
+            .into_iter()
+            .map(|value| {
+                let sender = sender.clone();
+                thread::spawn(move || sender.try_push(value).is_ok())
+            })
+            .collect();
+        let won: Vec<bool> = racers
+            .into_iter()
+            .map(|racer| racer.join().unwrap())
+            .collect();
+
+        // Exactly one claim gets the slot, and its value is published
+        // behind the one already queued.
+        assert_eq!(won.iter().filter(|&&won| won).count(), 1);
+        let winner = if won[0] { 20 } else { 30 };
+        let mut buffer = [0; 3];
+        assert_eq!(reader.read(&mut buffer), 2);
+        assert_eq!(buffer[..2], [10, winner]);
+    });
+}
+
+#[test]
+fn senders_publish_in_claim_order() {
+    loom::model(|| {
+        let (sender, mut reader) = spsc::mpsc::channel::<u64>(2).unwrap();
+        // Leaves zeros behind in both slots, so a slot read before its
+        // sender stored into it shows up as a zero.
+        assert_eq!(sender.write(&[0, 0]), 2);
+        assert_eq!(reader.read(&mut [0; 2]), 2);
+
+        let senders: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|value| {
+                let sender = sender.clone();
+                thread::spawn(move || assert_eq!(sender.write(&[value]), 1))
+            })
+            .collect();
+
+        // Whichever claim came second waits for the first to publish, so
+        // the reader never sees a slot past an unfilled one.
+        let mut buffer = [0; 2];
+        let read = reader.read(&mut buffer);
+        assert!(buffer[..read].iter().all(|&value| value != 0), "{buffer:?}");
+
+        for sender in senders {
+            sender.join().unwrap();
+        }
+        let rest = reader.read(&mut buffer[read..]);
+        buffer.sort_unstable();
+        assert_eq!((read + rest, buffer), (2, [1, 2]));
+    });
+}
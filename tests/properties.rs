@@ -0,0 +1,332 @@
+// Random schedules of partial writes, reads, peeks and skips over a stream
+// of consecutive `u64`s, run against every backend through the shared
+// traits, on one thread and with the two sides on separate threads. Each
+// run checks that the reader sees the stream exactly once and in order, and
+// proptest shrinks a failure down to a minimal schedule.
+
+#![cfg(not(loom))]
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use proptest::{prelude::*, test_runner::TestCaseError};
+use spsc::{
+    arena::{ArenaReader, RingArena},
+    backend::AnyReader,
+    local::LocalReader,
+    mailbox::MailboxReader,
+    ring_buffer::{BufferReader, BufferWriter, RawBufferReader, Reader, RingBuffer},
+    seqring::SeqReader,
+    vecdeque::DequeReader,
+    AnyRingBuffer, Backend,
+};
+
+#[derive(Clone, Copy, Debug)]
+enum Action {
+    Write(usize),
+    Read(usize),
+    Peek(usize),
+    Skip(usize),
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        4 => (0..12usize).prop_map(Action::Write),
+        4 => (0..12usize).prop_map(Action::Read),
+        1 => (0..12usize).prop_map(Action::Peek),
+        1 => (0..12usize).prop_map(Action::Skip),
+    ]
+}
+
+// Small capacities, so that nearly every schedule wraps.
+fn capacity() -> impl Strategy<Value = usize> {
+    prop::sample::select(vec![2, 3, 7, 8])
+}
+
+// What a schedule does to a reader besides `BufferReader::read`.
+trait Inspect: BufferReader<u64> {
+    /// Copies up to `n` of the oldest queued elements without consuming
+    /// them, or returns `None` if the reader cannot look at them in place.
+    fn peek(&self, n: usize) -> Option<Vec<u64>>;
+
+    /// Drops up to `n` of the oldest queued elements and returns how many.
+    fn skip(&mut self, n: usize) -> usize;
+}
+
+// `filled` is all-or-nothing, so this finds the longest run of at most `n`
+// queued elements, and where it starts.
+fn queued_run<R: RawBufferReader<u64>>(reader: &R, n: usize) -> (usize, usize) {
+    (0..=n)
+        .rev()
+        .map(|len| (len, reader.filled(len)))
+        .find(|&(len, (filled, _))| filled == len)
+        .map(|(len, (_, index))| (len, index))
+        .unwrap()
+}
+
+macro_rules! inspect_in_place {
+    ($($reader:ty),* $(,)?) => {$(
+        impl Inspect for $reader {
+            fn peek(&self, n: usize) -> Option<Vec<u64>> {
+                let (len, index) = queued_run(self, n);
+
+                Some(
+                    (0..len)
+                        .map(|offset| unsafe { *self.get(index.wrapping_add(offset)) })
+                        .collect(),
+                )
+            }
+
+            fn skip(&mut self, n: usize) -> usize {
+                let (len, _) = queued_run(self, n);
+                unsafe { self.advance_index(len) };
+
+                len
+            }
+        }
+    )*};
+}
+
+inspect_in_place!(
+    Reader<u64>,
+    LocalReader<u64>,
+    SeqReader<u64>,
+    ArenaReader<u64>,
+    MailboxReader<u64>,
+);
+
+// These readers sit behind a lock, so they cannot hand out references into
+// the queue; a skip is a read whose elements are dropped.
+macro_rules! inspect_by_reading {
+    ($($reader:ty),* $(,)?) => {$(
+        impl Inspect for $reader {
+            fn peek(&self, _n: usize) -> Option<Vec<u64>> {
+                None
+            }
+
+            fn skip(&mut self, n: usize) -> usize {
+                self.read(&mut vec![0; n])
+            }
+        }
+    )*};
+}
+
+inspect_by_reading!(DequeReader<u64>, AnyReader<u64>);
+
+fn stream(from: u64, len: usize) -> Vec<u64> {
+    (from..from + len as u64).collect()
+}
+
+// Runs `actions` from the calling thread. With both sides on one thread the
+// queue length is known, so every call must move exactly as much as fits.
+fn run_single<W, R>(
+    name: &str,
+    writer: &mut W,
+    reader: &mut R,
+    capacity: usize,
+    actions: &[Action],
+) -> Result<(), TestCaseError>
+where
+    W: BufferWriter<u64> + ?Sized,
+    R: Inspect + ?Sized,
+{
+    let (mut written, mut consumed) = (0u64, 0u64);
+
+    for &action in actions {
+        let queued = (written - consumed) as usize;
+        match action {
+            Action::Write(n) => {
+                let moved = writer.write(&stream(written, n));
+                prop_assert_eq!(moved, n.min(capacity - queued), "{}: {:?}", name, action);
+                written += moved as u64;
+            }
+            Action::Read(n) => {
+                let mut buffer = vec![u64::MAX; n];
+                let moved = reader.read(&mut buffer);
+                prop_assert_eq!(moved, n.min(queued), "{}: {:?}", name, action);
+                prop_assert_eq!(&buffer[..moved], &stream(consumed, moved)[..], "{}", name);
+                consumed += moved as u64;
+            }
+            Action::Peek(n) => {
+                if let Some(peeked) = reader.peek(n) {
+                    prop_assert_eq!(peeked, stream(consumed, n.min(queued)), "{}", name);
+                }
+            }
+            Action::Skip(n) => {
+                let moved = reader.skip(n);
+                prop_assert_eq!(moved, n.min(queued), "{}: {:?}", name, action);
+                consumed += moved as u64;
+            }
+        }
+    }
+
+    let mut rest = vec![u64::MAX; capacity + 1];
+    let moved = reader.read(&mut rest);
+    prop_assert_eq!(&rest[..moved], &stream(consumed, moved)[..], "{}", name);
+    prop_assert_eq!(consumed + moved as u64, written, "{}: left queued", name);
+
+    Ok(())
+}
+
+// Writes the bursts from a producer thread, retrying whatever did not fit,
+// while the calling thread cycles through the consuming actions until it
+// has seen the whole stream. Only upper bounds hold here, since the other
+// side moves concurrently.
+fn run_threaded<W, R>(
+    name: &str,
+    mut writer: W,
+    mut reader: R,
+    bursts: &[usize],
+    actions: &[Action],
+) -> Result<(), TestCaseError>
+where
+    W: BufferWriter<u64> + Send + 'static,
+    R: Inspect,
+{
+    let total: u64 = bursts.iter().map(|&n| n as u64).sum();
+    // Set when the consumer gives up, so the producer does not wait on a
+    // full ring forever.
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let producer = {
+        let bursts = bursts.to_vec();
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let mut written = 0;
+            for n in bursts {
+                let values = stream(written, n);
+                let mut rest = &values[..];
+                while !rest.is_empty() && !stop.load(Ordering::Relaxed) {
+                    match writer.write(rest) {
+                        0 => thread::yield_now(),
+                        moved => rest = &rest[moved..],
+                    }
+                }
+                written += n as u64;
+            }
+        })
+    };
+
+    let result = consume(name, &mut reader, total, actions);
+    stop.store(true, Ordering::Relaxed);
+    producer.join().unwrap();
+
+    result
+}
+
+fn consume<R: Inspect>(
+    name: &str,
+    reader: &mut R,
+    total: u64,
+    actions: &[Action],
+) -> Result<(), TestCaseError> {
+    let mut consumed = 0u64;
+    // A read of one closes every round, so the stream always drains.
+    let round: Vec<Action> = actions.iter().copied().chain([Action::Read(1)]).collect();
+
+    while consumed < total {
+        for &action in &round {
+            match action {
+                Action::Write(_) => thread::yield_now(),
+                Action::Read(n) => {
+                    let mut buffer = vec![u64::MAX; n];
+                    let moved = reader.read(&mut buffer);
+                    prop_assert!(moved <= n, "{}: {:?} moved {}", name, action, moved);
+                    prop_assert_eq!(&buffer[..moved], &stream(consumed, moved)[..], "{}", name);
+                    consumed += moved as u64;
+                }
+                Action::Peek(n) => {
+                    if let Some(peeked) = reader.peek(n) {
+                        prop_assert!(peeked.len() <= n, "{}: {:?}", name, action);
+                        prop_assert_eq!(&peeked, &stream(consumed, peeked.len()), "{}", name);
+                    }
+                }
+                Action::Skip(n) => {
+                    let moved = reader.skip(n);
+                    prop_assert!(moved <= n, "{}: {:?} moved {}", name, action, moved);
+                    consumed += moved as u64;
+                }
+            }
+            prop_assert!(consumed <= total, "{}: read past the stream", name);
+        }
+    }
+
+    Ok(())
+}
+
+// Arena slots are whole cache lines, so its rings hold eight `u64`s
+// whatever capacity the case asks for.
+fn arena_halves() -> (spsc::arena::ArenaWriter<u64>, ArenaReader<u64>) {
+    let arena = RingArena::new(1, 64).unwrap();
+    arena.create::<u64>().unwrap()
+}
+
+proptest! {
+    #[test]
+    fn single_threaded_schedules_keep_the_stream_in_order(
+        capacity in capacity(),
+        actions in prop::collection::vec(action(), 0..64),
+    ) {
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(capacity).unwrap();
+        run_single("ring_buffer", &mut writer, &mut reader, capacity, &actions)?;
+
+        let (mut writer, mut reader) = spsc::vecdeque::ring_buffer::<u64>(capacity);
+        run_single("vecdeque", &mut writer, &mut reader, capacity, &actions)?;
+
+        let (mut writer, mut reader) = spsc::local::ring_buffer::<u64>(capacity).unwrap();
+        run_single("local", &mut writer, &mut reader, capacity, &actions)?;
+
+        let (mut writer, mut reader) = spsc::seqring::ring_buffer::<u64>(capacity).unwrap();
+        run_single("seqring", &mut writer, &mut reader, capacity, &actions)?;
+
+        let (mut writer, mut reader) = arena_halves();
+        let arena_capacity = writer.capacity();
+        run_single("arena", &mut writer, &mut reader, arena_capacity, &actions)?;
+
+        for backend in [Backend::LockFree, Backend::Mutex] {
+            let (mut writer, mut reader) = AnyRingBuffer::<u64>::new(capacity, backend).unwrap();
+            let name = format!("{backend:?}");
+            run_single(&name, &mut writer, &mut reader, capacity, &actions)?;
+        }
+
+        let (mut writer, mut reader) = spsc::mailbox::<u64>();
+        run_single("mailbox", &mut writer, &mut reader, 1, &actions)?;
+    }
+}
+
+proptest! {
+    // Every case spawns a producer per backend, so fewer of them.
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn threaded_schedules_keep_the_stream_in_order(
+        capacity in capacity(),
+        bursts in prop::collection::vec(0..12usize, 0..32),
+        actions in prop::collection::vec(action(), 0..16),
+    ) {
+        let (writer, reader) = RingBuffer::<u64>::new(capacity).unwrap();
+        run_threaded("ring_buffer", writer, reader, &bursts, &actions)?;
+
+        let (writer, reader) = spsc::vecdeque::ring_buffer::<u64>(capacity);
+        run_threaded("vecdeque", writer, reader, &bursts, &actions)?;
+
+        let (writer, reader) = spsc::seqring::ring_buffer::<u64>(capacity).unwrap();
+        run_threaded("seqring", writer, reader, &bursts, &actions)?;
+
+        let (writer, reader) = arena_halves();
+        run_threaded("arena", writer, reader, &bursts, &actions)?;
+
+        for backend in [Backend::LockFree, Backend::Mutex] {
+            let (writer, reader) = AnyRingBuffer::<u64>::new(capacity, backend).unwrap();
+            run_threaded(&format!("{backend:?}"), writer, reader, &bursts, &actions)?;
+        }
+
+        let (writer, reader) = spsc::mailbox::<u64>();
+        run_threaded("mailbox", writer, reader, &bursts, &actions)?;
+    }
+}
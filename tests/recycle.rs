@@ -0,0 +1,90 @@
+// Runs buffers around a recycling pair under a counting allocator, to check
+// that the steady state allocates nothing and shutdown hands every buffer
+// back.
+
+#![cfg(not(loom))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+// Buffers get a size nothing else in the test allocates, so their
+// allocations can be told apart from the test harness's.
+const BUFFER_SIZE: usize = 4099;
+const BUFFERS: usize = 4;
+const CYCLES: usize = 1_000_000;
+
+struct Counting;
+
+static BUFFER_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() == BUFFER_SIZE {
+            BUFFER_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[test]
+fn a_million_cycles_allocate_only_the_initial_buffers() {
+    let (mut producer, mut consumer) =
+        spsc::recycle(BUFFERS, || Vec::<u8>::with_capacity(BUFFER_SIZE));
+    assert_eq!(BUFFER_ALLOCATIONS.load(Ordering::Relaxed), BUFFERS);
+
+    let worker = thread::spawn(move || {
+        let mut sum = 0u64;
+        while let Some(mut buffer) = consumer.receive() {
+            sum += buffer.iter().map(|&byte| byte as u64).sum::<u64>();
+            buffer.clear();
+            consumer.recycle(buffer).unwrap();
+        }
+        (sum, consumer.shutdown())
+    });
+
+    let mut expected = 0u64;
+    for cycle in 0..CYCLES {
+        let mut buffer = producer.obtain().unwrap();
+        assert!(buffer.is_empty());
+        let byte = cycle as u8;
+        buffer.extend_from_slice(&[byte; 16]);
+        expected += 16 * byte as u64;
+        producer.submit(buffer).unwrap();
+    }
+    let mut returned = producer.shutdown();
+
+    let (sum, rest) = worker.join().unwrap();
+    returned.extend(rest);
+    assert_eq!(sum, expected);
+    assert_eq!(returned.len(), BUFFERS);
+    assert!(returned
+        .iter()
+        .all(|buffer| buffer.capacity() == BUFFER_SIZE));
+    assert_eq!(BUFFER_ALLOCATIONS.load(Ordering::Relaxed), BUFFERS);
+}
+
+#[test]
+fn shutdown_returns_buffers_held_on_both_paths() {
+    let (mut producer, consumer) = spsc::recycle(3, || vec![0u8; 8]);
+
+    // One buffer in each direction, and one held by user code.
+    let first = producer.obtain().unwrap();
+    producer.submit(first).unwrap();
+    let held = producer.obtain().unwrap();
+
+    // The consumer leaves first with the filled buffer, and the producer
+    // then collects the one still on the return path.
+    assert_eq!(consumer.shutdown().len(), 1);
+    assert_eq!(producer.shutdown().len(), 1);
+    drop(held);
+}
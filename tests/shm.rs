@@ -0,0 +1,60 @@
+// Moves timestamps from this process to a child through a ring in a shared
+// file mapping. The child is this test binary again, started with the
+// ring's path in `READER_ENV`, so it runs the reader side of the same test
+// and reports through its exit status.
+
+#![cfg(all(target_os = "linux", feature = "shm", not(loom)))]
+
+use std::{env, fs, process::Command, thread};
+
+use spsc::shm::{ShmReader, ShmWriter};
+
+const READER_ENV: &str = "SPSC_SHM_READER";
+const TEST: &str = "a_million_items_cross_processes_in_order";
+const ITEMS: u64 = 1_000_000;
+const BURST: usize = 100;
+
+#[test]
+fn a_million_items_cross_processes_in_order() {
+    if let Ok(path) = env::var(READER_ENV) {
+        let mut reader = unsafe { ShmReader::<u64>::open(path) }.unwrap();
+        let mut next = 0;
+        let mut buffer = [0; BURST];
+        while next < ITEMS {
+            assert_eq!(reader.read_blocking(&mut buffer), BURST as u32);
+            for &value in &buffer {
+                assert_eq!(value, next);
+                next += 1;
+            }
+        }
+        return;
+    }
+
+    let path = env::temp_dir().join(format!("spsc-shm-transfer-{}", std::process::id()));
+    let mut writer = ShmWriter::<u64>::create(&path, 1024).unwrap();
+    let mut child = Command::new(env::current_exe().unwrap())
+        .args([TEST, "--exact"])
+        .env(READER_ENV, &path)
+        .spawn()
+        .unwrap();
+
+    let mut burst = [0; BURST];
+    for start in (0..ITEMS).step_by(BURST) {
+        for (offset, value) in burst.iter_mut().enumerate() {
+            *value = start + offset as u64;
+        }
+        // Checks on the child while the ring is full, so a reader that
+        // failed does not leave this side waiting forever.
+        while writer.write(&burst) == 0 {
+            if let Some(status) = child.try_wait().unwrap() {
+                panic!("the reader exited early: {status}");
+            }
+            thread::yield_now();
+        }
+    }
+
+    let status = child.wait().unwrap();
+    drop(writer);
+    let _ = fs::remove_file(&path);
+    assert!(status.success(), "the reader failed: {status}");
+}
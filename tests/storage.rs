@@ -0,0 +1,64 @@
+// Tracks the ring storage under a counting allocator, to check that it is
+// handed back exactly once whichever thread drops the last handle.
+
+#![cfg(not(loom))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicIsize, Ordering},
+        Arc, Barrier,
+    },
+    thread,
+};
+
+use spsc::ring_buffer::{BufferWriter, RingBuffer};
+
+struct Counting;
+
+// An odd capacity, so no other allocation in the test binary is likely to
+// have the storage's size.
+const CAPACITY: usize = 1237;
+const STORAGE_BYTES: usize = CAPACITY * std::mem::size_of::<u64>();
+
+static LIVE_STORAGE: AtomicIsize = AtomicIsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() == STORAGE_BYTES {
+            LIVE_STORAGE.fetch_add(1, Ordering::Relaxed);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if layout.size() == STORAGE_BYTES {
+            LIVE_STORAGE.fetch_sub(1, Ordering::Relaxed);
+        }
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[test]
+fn handles_dropped_at_once_on_two_threads_free_the_storage_once() {
+    for _ in 0..200 {
+        let (mut writer, reader) = RingBuffer::<u64>::new(CAPACITY).unwrap();
+        assert_eq!(writer.write(&[7; 3]), 3);
+        assert_eq!(LIVE_STORAGE.load(Ordering::Relaxed), 1);
+
+        let barrier = Arc::new(Barrier::new(2));
+        let other = barrier.clone();
+        let thread = thread::spawn(move || {
+            other.wait();
+            drop(reader);
+        });
+        barrier.wait();
+        drop(writer);
+        thread.join().unwrap();
+
+        assert_eq!(LIVE_STORAGE.load(Ordering::Relaxed), 0);
+    }
+}
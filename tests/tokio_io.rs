@@ -0,0 +1,63 @@
+// Pipes bytes between tokio tasks through the ring's `AsyncWrite` and
+// `AsyncRead` halves.
+
+#![cfg(not(loom))]
+
+use spsc::tokio_io::pipe;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const LEN: usize = 10 << 20;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+// FNV-1a, so a dropped, repeated or reordered byte changes the result.
+fn checksum(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn copy_pipes_ten_megabytes_through_a_small_ring() {
+    let data: Vec<u8> = (0..LEN).map(|i| (i * 31 + i / 4099) as u8).collect();
+    let expected = checksum(FNV_OFFSET, &data);
+    let (mut writer, mut reader) = pipe(4096).unwrap();
+
+    let producer = tokio::spawn(async move {
+        let copied = tokio::io::copy(&mut &data[..], &mut writer).await.unwrap();
+        writer.shutdown().await.unwrap();
+        copied
+    });
+
+    let consumer = tokio::spawn(async move {
+        let (mut hash, mut len) = (FNV_OFFSET, 0);
+        let mut buffer = [0; 1500];
+        loop {
+            match reader.read(&mut buffer).await.unwrap() {
+                0 => break (hash, len),
+                n => {
+                    hash = checksum(hash, &buffer[..n]);
+                    len += n;
+                }
+            }
+        }
+    });
+
+    assert_eq!(producer.await.unwrap(), LEN as u64);
+    assert_eq!(consumer.await.unwrap(), (expected, LEN));
+}
+
+#[tokio::test]
+async fn shutdown_closes_the_pipe_without_dropping_the_writer() {
+    let (mut writer, mut reader) = pipe(16).unwrap();
+
+    writer.write_all(b"tail").await.unwrap();
+    writer.shutdown().await.unwrap();
+
+    // The writer is still alive, yet the reader drains and sees the end.
+    let mut received = Vec::new();
+    reader.read_to_end(&mut received).await.unwrap();
+    assert_eq!(received, b"tail");
+
+    let error = writer.write_all(b"more").await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe);
+}
@@ -0,0 +1,195 @@
+// Moves the same data through every backend with the `testing` harness, so
+// they all get identical coverage: two threads with mismatched burst sizes,
+// scripted single-threaded schedules, and the differential run against the
+// `VecDeque` ring.
+
+#![cfg(not(loom))]
+
+use std::time::Duration;
+
+use proptest::prelude::*;
+use spsc::{
+    ring_buffer::{BufferReader, BufferWriter, RingBuffer},
+    static_ring::StaticRingBuffer,
+    testing::{run_differential, run_fifo, run_transfer, run_transfer_bursts, Op, Pump, Step},
+    AnyRingBuffer, Backend,
+};
+
+const ELEMENTS: u64 = 50_000;
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+fn data() -> Vec<u64> {
+    (0..ELEMENTS).collect()
+}
+
+// Bursts that differ between the sides and never divide each other, so
+// partial transfers and wraps land at varying offsets.
+fn transfer<W, R>(writer: W, reader: R)
+where
+    W: BufferWriter<u64> + Send + 'static,
+    R: BufferReader<u64> + Send + 'static,
+{
+    let report = run_transfer_bursts(writer, reader, data(), &[1, 3, 5], &[2, 7, 1], TIMEOUT);
+    assert_eq!(report.elements, ELEMENTS as usize);
+}
+
+#[test]
+fn lock_free_ring() {
+    for capacity in [7, 8, 4096] {
+        let (writer, reader) = RingBuffer::<u64>::new(capacity).unwrap();
+        transfer(writer, reader);
+    }
+}
+
+#[test]
+fn lock_free_ring_of_one() {
+    let (writer, reader) = RingBuffer::<u64>::new(1).unwrap();
+    run_transfer(writer, reader, data(), 1);
+}
+
+#[test]
+fn vecdeque_ring() {
+    let (writer, reader) = spsc::vecdeque::ring_buffer::<u64>(7);
+    transfer(writer, reader);
+}
+
+#[test]
+fn mailbox() {
+    let (writer, reader) = spsc::mailbox::<u64>();
+    run_transfer(writer, reader, data(), 1);
+}
+
+#[test]
+fn slot_stamp_ring() {
+    let (writer, reader) = spsc::seqring::ring_buffer::<u64>(7).unwrap();
+    transfer(writer, reader);
+}
+
+#[test]
+fn arena_ring() {
+    let arena = spsc::arena::RingArena::new(2, 7 * 8).unwrap();
+    let (writer, reader) = arena.create::<u64>().unwrap();
+    transfer(writer, reader);
+}
+
+#[test]
+fn static_ring() {
+    static RING: StaticRingBuffer<u64, 7> = StaticRingBuffer::new();
+
+    let (writer, reader) = RING.split().unwrap();
+    transfer(writer, reader);
+}
+
+#[test]
+fn runtime_selected_backends() {
+    for backend in [Backend::LockFree, Backend::Mutex] {
+        let (writer, reader) = AnyRingBuffer::<u64>::new(7, backend).unwrap();
+        assert_eq!(writer.backend(), backend);
+        transfer(writer, reader);
+    }
+}
+
+#[test]
+fn instrumented_ring() {
+    let (writer, reader) = spsc::latency::instrumented::<u64>(7).unwrap();
+    transfer(writer, reader);
+}
+
+// Fills the ring, drains it in odd steps and refills it so that every
+// index crosses the end of the storage more than once.
+fn wrapping_script(capacity: usize) -> Vec<Step> {
+    let mut script = Vec::new();
+    for round in 1..=3 * capacity {
+        script.push(Step::Write(round % capacity + 1));
+        script.push(Step::Read(round % 3 + 1));
+        if round % 4 == 0 {
+            script.push(Step::Skip(2));
+        }
+    }
+    script.push(Step::Write(2 * capacity));
+    script.push(Step::Read(2 * capacity));
+    script
+}
+
+#[test]
+fn pump_schedules_across_the_wrap() {
+    for capacity in [2, 3, 7, 8] {
+        let (writer, reader) = RingBuffer::<u64>::new(capacity).unwrap();
+        let mut pump = Pump::new(writer, reader);
+
+        pump.run(&wrapping_script(capacity));
+        assert_eq!(pump.queued(), 0);
+    }
+}
+
+#[test]
+fn fifo_schedules_on_the_same_thread() {
+    for capacity in [2, 3, 7, 8] {
+        let script = wrapping_script(capacity);
+
+        let (mut writer, mut reader) = RingBuffer::<u64>::new(capacity).unwrap();
+        assert_eq!(run_fifo(&mut writer, &mut reader, &script), 0);
+
+        let (mut writer, mut reader) = spsc::local::ring_buffer::<u64>(capacity).unwrap();
+        assert_eq!(run_fifo(&mut writer, &mut reader, &script), 0);
+
+        let (mut writer, mut reader) = spsc::seqring::ring_buffer::<u64>(capacity).unwrap();
+        assert_eq!(run_fifo(&mut writer, &mut reader, &script), 0);
+
+        // Arena slots are whole cache lines, so this ring can be larger.
+        let arena = spsc::arena::RingArena::new(1, capacity * 8).unwrap();
+        let (mut writer, mut reader) = arena.create::<u64>().unwrap();
+        assert_eq!(run_fifo(&mut writer, &mut reader, &script), 0);
+
+        for backend in [Backend::LockFree, Backend::Mutex] {
+            let (mut writer, mut reader) = AnyRingBuffer::<u64>::new(capacity, backend).unwrap();
+            assert_eq!(run_fifo(&mut writer, &mut reader, &script), 0);
+        }
+    }
+}
+
+#[test]
+fn differential_against_the_vecdeque_ring() {
+    let ops = [
+        Op::Write(vec![1, 2, 3, 4, 5]),
+        Op::Read(2),
+        Op::Write(vec![6, 7, 8, 9]),
+        Op::Read(0),
+        Op::Write(vec![10]),
+        Op::Read(7),
+        Op::Read(1),
+        Op::Write((0..20).collect()),
+        Op::Read(3),
+        Op::Clear,
+        Op::Write(vec![11, 12, 13]),
+        Op::Read(1),
+        Op::Close,
+        Op::Write(vec![14]),
+        Op::Read(1),
+        Op::Clear,
+        Op::Read(1),
+    ];
+
+    for capacity in [1, 3, 7, 8] {
+        run_differential(capacity, &ops);
+    }
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        8 => prop::collection::vec(any::<u8>(), 0..20).prop_map(Op::Write),
+        8 => (0..20usize).prop_map(Op::Read),
+        2 => Just(Op::Clear),
+        1 => Just(Op::Close),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn differential_against_the_vecdeque_ring_on_random_ops(
+        capacity in 1..17usize,
+        ops in prop::collection::vec(op(), 0..64),
+    ) {
+        run_differential(capacity, &ops);
+    }
+}
@@ -0,0 +1,4 @@
+fn main() {
+    let (_writer, reader) = spsc::local::ring_buffer::<u8>(4).unwrap();
+    std::thread::spawn(move || drop(reader));
+}
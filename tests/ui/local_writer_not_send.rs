@@ -0,0 +1,4 @@
+fn main() {
+    let (writer, _reader) = spsc::local::ring_buffer::<u8>(4).unwrap();
+    std::thread::spawn(move || drop(writer));
+}
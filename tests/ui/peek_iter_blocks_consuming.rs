@@ -0,0 +1,8 @@
+fn main() {
+    let (mut writer, mut reader) = spsc::ring_buffer::RingBuffer::<u64>::new(4).unwrap();
+    writer.write_array([1]).unwrap();
+
+    let mut iter = reader.peek_iter();
+    let _ = reader.pop_ref();
+    iter.next();
+}
@@ -0,0 +1,8 @@
+use std::rc::Rc;
+
+use spsc::ring_buffer::RingBuffer;
+
+fn main() {
+    let (_writer, reader) = RingBuffer::<Rc<u8>>::new(4).unwrap();
+    std::thread::spawn(move || drop(reader));
+}
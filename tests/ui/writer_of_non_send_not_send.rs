@@ -0,0 +1,10 @@
+use std::rc::Rc;
+
+use spsc::ring_buffer::RingBuffer;
+
+fn main() {
+    // An `Rc` pushed on another thread could be popped here while its
+    // clones stay behind, racing on the count.
+    let (writer, _reader) = RingBuffer::<Rc<u8>>::new(4).unwrap();
+    std::thread::spawn(move || drop(writer));
+}